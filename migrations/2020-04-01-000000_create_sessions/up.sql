@@ -0,0 +1,8 @@
+create table sessions (
+    token uuid default uuid_generate_v4() primary key,
+    user_id uuid not null,
+    expires_at timestamp not null default current_timestamp + interval '30 days',
+    created_at timestamp not null default current_timestamp,
+
+    foreign key (user_id) references users(id) on delete cascade
+);
@@ -0,0 +1 @@
+drop table if exists review_states;
\ No newline at end of file
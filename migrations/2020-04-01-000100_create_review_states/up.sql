@@ -0,0 +1,17 @@
+create table review_states (
+    user_id uuid not null,
+    question_id uuid not null,
+    repetitions integer not null default 0,
+    interval_days integer not null default 0,
+    ease_factor double precision not null default 2.5,
+    due_at timestamp not null default current_timestamp,
+    last_grade integer null,
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp,
+
+    primary key (user_id, question_id),
+    foreign key (user_id) references users(id) on delete cascade,
+    foreign key (question_id) references questions(id) on delete cascade
+);
+
+create index review_states_due_idx on review_states (user_id, due_at);
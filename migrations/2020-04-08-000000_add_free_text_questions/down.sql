@@ -0,0 +1,7 @@
+drop table if exists answer_aliases;
+
+alter table questions
+    drop constraint if exists questions_max_edit_distance_check,
+    drop constraint if exists questions_kind_check,
+    drop column if exists max_edit_distance,
+    drop column if exists kind;
\ No newline at end of file
@@ -0,0 +1,16 @@
+alter table questions
+    add column kind varchar not null default 'choice',
+    add column max_edit_distance integer not null default 1,
+    add constraint questions_kind_check check (kind in ('choice', 'free_text')),
+    add constraint questions_max_edit_distance_check check (max_edit_distance >= 0);
+
+create table answer_aliases (
+    id uuid default uuid_generate_v4() primary key,
+    answer_id uuid not null,
+    content varchar not null,
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp,
+
+    unique (answer_id, content),
+    foreign key (answer_id) references answers(id) on delete cascade
+);
@@ -0,0 +1,14 @@
+delete from questions where kind = 'numeric';
+
+alter table questions
+    drop constraint if exists questions_answer_check,
+    drop constraint if exists questions_numeric_curve_check,
+    drop constraint if exists questions_numeric_tolerance_check,
+    drop constraint if exists questions_kind_check,
+    drop column if exists numeric_curve,
+    drop column if exists numeric_tolerance,
+    drop column if exists numeric_value,
+    alter column correct_answer_id set not null;
+
+alter table questions
+    add constraint questions_kind_check check (kind in ('choice', 'free_text'));
\ No newline at end of file
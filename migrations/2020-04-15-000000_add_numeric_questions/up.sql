@@ -0,0 +1,16 @@
+alter table questions drop constraint questions_kind_check;
+
+alter table questions
+    alter column correct_answer_id drop not null,
+    add column numeric_value double precision null,
+    add column numeric_tolerance double precision not null default 0,
+    add column numeric_curve varchar not null default 'linear',
+    add constraint questions_kind_check check (kind in ('choice', 'free_text', 'numeric')),
+    add constraint questions_numeric_tolerance_check check (numeric_tolerance >= 0),
+    add constraint questions_numeric_curve_check check (numeric_curve in ('linear', 'step')),
+    add constraint questions_answer_check check (
+        case kind
+            when 'numeric' then numeric_value is not null
+            else correct_answer_id is not null
+        end
+    );
@@ -0,0 +1,18 @@
+drop table if exists question_choices;
+
+delete from questions where kind in ('ordering', 'matching');
+
+alter table questions
+    drop constraint if exists questions_answer_check,
+    drop constraint if exists questions_scoring_rule_check,
+    drop constraint if exists questions_kind_check,
+    drop column if exists scoring_rule;
+
+alter table questions
+    add constraint questions_kind_check check (kind in ('choice', 'free_text', 'numeric')),
+    add constraint questions_answer_check check (
+        case kind
+            when 'numeric' then numeric_value is not null
+            else correct_answer_id is not null
+        end
+    );
\ No newline at end of file
@@ -0,0 +1,32 @@
+alter table questions
+    drop constraint questions_kind_check,
+    drop constraint questions_answer_check;
+
+alter table questions
+    add column scoring_rule varchar not null default 'all_or_nothing',
+    add constraint questions_kind_check check (kind in ('choice', 'free_text', 'numeric', 'ordering', 'matching')),
+    add constraint questions_scoring_rule_check check (scoring_rule in ('all_or_nothing', 'per_item', 'pairwise')),
+    add constraint questions_answer_check check (
+        case kind
+            when 'numeric' then numeric_value is not null
+            when 'ordering' then correct_answer_id is null
+            when 'matching' then correct_answer_id is null
+            else correct_answer_id is not null
+        end
+    );
+
+create table question_choices (
+    id uuid default uuid_generate_v4() primary key,
+    question_id uuid not null,
+    answer_id uuid not null,
+    position integer not null,
+    match_answer_id uuid null,
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp,
+
+    unique (question_id, position),
+    unique (question_id, answer_id),
+    foreign key (question_id) references questions(id) on delete cascade,
+    foreign key (answer_id) references answers(id),
+    foreign key (match_answer_id) references answers(id)
+);
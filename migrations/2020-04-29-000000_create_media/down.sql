@@ -0,0 +1,8 @@
+alter table questions
+    drop constraint if exists questions_clip_check,
+    drop constraint if exists questions_media_id_fkey,
+    drop column if exists clip_end_ms,
+    drop column if exists clip_start_ms,
+    drop column if exists media_id;
+
+drop table if exists media;
\ No newline at end of file
@@ -0,0 +1,21 @@
+create table media (
+    id uuid default uuid_generate_v4() primary key,
+    key varchar not null unique,
+    content_type varchar not null,
+    size_bytes integer not null,
+    uploaded_by uuid not null,
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp,
+
+    foreign key (uploaded_by) references users(id)
+);
+
+alter table questions
+    add column media_id uuid null,
+    add column clip_start_ms integer null,
+    add column clip_end_ms integer null,
+    add constraint questions_media_id_fkey foreign key (media_id) references media(id),
+    add constraint questions_clip_check check (
+        (clip_start_ms is null or clip_start_ms >= 0)
+        and (clip_end_ms is null or clip_end_ms > coalesce(clip_start_ms, 0))
+    );
@@ -0,0 +1,16 @@
+create table tags (
+    id uuid default uuid_generate_v4() primary key,
+    name varchar not null unique,
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp
+);
+
+create table question_tags (
+    question_id uuid not null,
+    tag_id uuid not null,
+    created_at timestamp not null default current_timestamp,
+
+    primary key (question_id, tag_id),
+    foreign key (question_id) references questions(id) on delete cascade,
+    foreign key (tag_id) references tags(id) on delete cascade
+);
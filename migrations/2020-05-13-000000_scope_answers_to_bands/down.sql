@@ -0,0 +1,36 @@
+-- Merge answers with the same content back into the oldest row
+create temporary table answer_merges as
+select a.id as answer_id, keep.id as keep_id
+from answers a
+join lateral (
+    select k.id from answers k where k.content = a.content order by k.created_at, k.id limit 1
+) keep on keep.id <> a.id;
+
+insert into answer_aliases (answer_id, content, created_at, updated_at)
+select m.keep_id, al.content, al.created_at, al.updated_at
+from answer_merges m join answer_aliases al on al.answer_id = m.answer_id
+on conflict (answer_id, content) do nothing;
+
+update questions
+set correct_answer_id = m.keep_id
+from answer_merges m
+where questions.correct_answer_id = m.answer_id;
+
+update question_choices
+set answer_id = m.keep_id
+from answer_merges m
+where question_choices.answer_id = m.answer_id;
+
+update question_choices
+set match_answer_id = m.keep_id
+from answer_merges m
+where question_choices.match_answer_id = m.answer_id;
+
+delete from answers where id in (select answer_id from answer_merges);
+
+drop table answer_merges;
+
+alter table answers
+    drop constraint answers_band_id_content_key,
+    drop column band_id,
+    add constraint answers_content_key unique (content);
\ No newline at end of file
@@ -0,0 +1,62 @@
+alter table answers add column band_id uuid null;
+
+-- Every band that uses each answer, through a question or one of its choices
+create temporary table answer_bands as
+select distinct used.answer_id, used.band_id
+from (
+    select correct_answer_id as answer_id, band_id from questions where correct_answer_id is not null
+    union
+    select c.answer_id, q.band_id from question_choices c join questions q on q.id = c.question_id
+    union
+    select c.match_answer_id, q.band_id from question_choices c join questions q on q.id = c.question_id
+    where c.match_answer_id is not null
+) used;
+
+-- The band that was created first keeps the original row
+update answers
+set band_id = owner.band_id
+from (
+    select distinct on (ab.answer_id) ab.answer_id, ab.band_id
+    from answer_bands ab join bands b on b.id = ab.band_id
+    order by ab.answer_id, b.created_at, b.id
+) owner
+where answers.id = owner.answer_id;
+
+-- Answers no question uses keep a null band_id, nothing new is created without one.
+-- Content is only unique per band from here on, so the copies below can share it.
+alter table answers
+    drop constraint answers_content_key,
+    add constraint answers_band_id_content_key unique (band_id, content),
+    add foreign key (band_id) references bands(id);
+
+-- Every other band gets its own copy, aliases included
+create temporary table answer_copies as
+select ab.answer_id, ab.band_id, uuid_generate_v4() as copy_id
+from answer_bands ab join answers a on a.id = ab.answer_id
+where a.band_id <> ab.band_id;
+
+insert into answers (id, content, band_id, created_at, updated_at)
+select c.copy_id, a.content, c.band_id, a.created_at, a.updated_at
+from answer_copies c join answers a on a.id = c.answer_id;
+
+insert into answer_aliases (answer_id, content, created_at, updated_at)
+select c.copy_id, al.content, al.created_at, al.updated_at
+from answer_copies c join answer_aliases al on al.answer_id = c.answer_id;
+
+update questions
+set correct_answer_id = c.copy_id
+from answer_copies c
+where questions.correct_answer_id = c.answer_id and questions.band_id = c.band_id;
+
+update question_choices
+set answer_id = c.copy_id
+from answer_copies c, questions q
+where q.id = question_choices.question_id and question_choices.answer_id = c.answer_id and q.band_id = c.band_id;
+
+update question_choices
+set match_answer_id = c.copy_id
+from answer_copies c, questions q
+where q.id = question_choices.question_id and question_choices.match_answer_id = c.answer_id and q.band_id = c.band_id;
+
+drop table answer_copies;
+drop table answer_bands;
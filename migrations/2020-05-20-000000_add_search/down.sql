@@ -0,0 +1,13 @@
+drop trigger users_search_vector_update on users;
+drop trigger questions_search_vector_update on questions;
+drop trigger bands_search_vector_update on bands;
+
+drop index users_username_trgm_idx;
+drop index questions_content_trgm_idx;
+drop index bands_name_trgm_idx;
+
+alter table users drop column search_vector;
+alter table questions drop column search_vector;
+alter table bands drop column search_vector;
+
+drop extension if exists pg_trgm;
\ No newline at end of file
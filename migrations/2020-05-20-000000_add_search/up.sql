@@ -0,0 +1,24 @@
+create extension if not exists pg_trgm;
+
+alter table bands add column search_vector tsvector;
+alter table questions add column search_vector tsvector;
+alter table users add column search_vector tsvector;
+
+update bands set search_vector = to_tsvector('pg_catalog.simple', name);
+update questions set search_vector = to_tsvector('pg_catalog.simple', content);
+update users set search_vector = to_tsvector('pg_catalog.simple', username);
+
+create trigger bands_search_vector_update before insert or update on bands
+    for each row execute procedure tsvector_update_trigger(search_vector, 'pg_catalog.simple', name);
+create trigger questions_search_vector_update before insert or update on questions
+    for each row execute procedure tsvector_update_trigger(search_vector, 'pg_catalog.simple', content);
+create trigger users_search_vector_update before insert or update on users
+    for each row execute procedure tsvector_update_trigger(search_vector, 'pg_catalog.simple', username);
+
+create index bands_search_idx on bands using gin (search_vector);
+create index questions_search_idx on questions using gin (search_vector);
+create index users_search_idx on users using gin (search_vector);
+
+create index bands_name_trgm_idx on bands using gin (name gin_trgm_ops);
+create index questions_content_trgm_idx on questions using gin (content gin_trgm_ops);
+create index users_username_trgm_idx on users using gin (username gin_trgm_ops);
@@ -0,0 +1,3 @@
+drop index question_tags_tag_id_idx;
+
+alter table tags drop column kind;
\ No newline at end of file
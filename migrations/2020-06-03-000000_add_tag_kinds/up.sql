@@ -0,0 +1,7 @@
+alter table tags
+    add column kind varchar not null default 'topic',
+    add constraint tags_kind_check check (kind in ('topic', 'era', 'album'));
+
+update tags set kind = 'era' where name ~ '^''?[0-9]{2}s?$|^[0-9]{4}s?$';
+
+create index question_tags_tag_id_idx on question_tags (tag_id);
@@ -0,0 +1,5 @@
+drop table attempt_questions;
+drop table attempts;
+drop table quiz_questions;
+drop table quizzes;
+drop table band_authors;
\ No newline at end of file
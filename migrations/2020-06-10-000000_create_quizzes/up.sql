@@ -0,0 +1,84 @@
+create table band_authors (
+    band_id uuid not null,
+    user_id uuid not null,
+    created_at timestamp not null default current_timestamp,
+
+    primary key (band_id, user_id),
+    foreign key (band_id) references bands(id) on delete cascade,
+    foreign key (user_id) references users(id) on delete cascade
+);
+
+-- Owners are authors of their own bands
+insert into band_authors (band_id, user_id)
+select id, owner_id from bands;
+
+create table quizzes (
+    id uuid default uuid_generate_v4() primary key,
+    band_id uuid not null,
+    title varchar not null,
+    description varchar null,
+    status varchar not null default 'draft',
+    time_limit_seconds integer null,
+    points_per_question integer not null default 10,
+    created_by uuid not null,
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp,
+
+    constraint quizzes_status_check check (status in ('draft', 'published', 'archived')),
+    constraint quizzes_time_limit_seconds_check check (time_limit_seconds > 0),
+    constraint quizzes_points_per_question_check check (points_per_question >= 0),
+    foreign key (band_id) references bands(id),
+    foreign key (created_by) references users(id)
+);
+
+create index quizzes_band_id_idx on quizzes (band_id, status);
+
+create table quiz_questions (
+    quiz_id uuid not null,
+    question_id uuid not null,
+    position integer not null,
+
+    primary key (quiz_id, question_id),
+    unique (quiz_id, position),
+    foreign key (quiz_id) references quizzes(id) on delete cascade,
+    foreign key (question_id) references questions(id)
+);
+
+create table attempts (
+    id uuid default uuid_generate_v4() primary key,
+    quiz_id uuid null,
+    band_id uuid not null,
+    user_id uuid not null,
+    started_at timestamp not null default current_timestamp,
+    finished_at timestamp null,
+    score double precision not null default 0,
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp,
+
+    foreign key (quiz_id) references quizzes(id),
+    foreign key (band_id) references bands(id),
+    foreign key (user_id) references users(id) on delete cascade
+);
+
+create index attempts_user_id_idx on attempts (user_id, started_at);
+
+-- The questions served in an attempt, and the player's response once answered
+create table attempt_questions (
+    attempt_id uuid not null,
+    question_id uuid not null,
+    position integer not null,
+    answer_id uuid null,
+    answer_text varchar null,
+    answer_number double precision null,
+    answer_ordering uuid[] null,
+    answer_pair_ids uuid[] null,
+    answer_pair_match_ids uuid[] null,
+    accepted boolean null,
+    score double precision null,
+    answered_at timestamp null,
+
+    primary key (attempt_id, question_id),
+    unique (attempt_id, position),
+    foreign key (attempt_id) references attempts(id) on delete cascade,
+    foreign key (question_id) references questions(id)
+);
@@ -0,0 +1,13 @@
+alter table quizzes
+    add column points_per_question integer not null default 10,
+    add constraint quizzes_points_per_question_check check (points_per_question >= 0);
+
+update quizzes
+set points_per_question = scoring_rules.points_per_question
+from scoring_rules
+where scoring_rules.id = quizzes.scoring_rules_id;
+
+alter table attempts drop column scoring_rules_id;
+alter table quizzes drop column scoring_rules_id;
+
+drop table scoring_rules;
\ No newline at end of file
@@ -0,0 +1,39 @@
+create table scoring_rules (
+    id uuid default uuid_generate_v4() primary key,
+    points_per_question integer not null default 10,
+    speed_bonus_points integer not null default 0,
+    speed_bonus_seconds integer not null default 0,
+    wrong_answer_penalty integer not null default 0,
+    streak_bonus double precision not null default 0,
+    max_streak_multiplier double precision not null default 1,
+    partial_credit boolean not null default true,
+    created_at timestamp not null default current_timestamp,
+
+    constraint scoring_rules_points_check check (
+        points_per_question >= 0 and speed_bonus_points >= 0 and wrong_answer_penalty >= 0
+    ),
+    constraint scoring_rules_speed_bonus_check check (
+        speed_bonus_seconds >= 0 and (speed_bonus_points = 0 or speed_bonus_seconds > 0)
+    ),
+    constraint scoring_rules_streak_check check (streak_bonus >= 0 and max_streak_multiplier >= 1)
+);
+
+alter table quizzes add column scoring_rules_id uuid null;
+alter table attempts add column scoring_rules_id uuid null;
+
+-- Quizzes that didn't use the default points get rules of their own
+update quizzes set scoring_rules_id = uuid_generate_v4() where points_per_question <> 10;
+
+insert into scoring_rules (id, points_per_question)
+select scoring_rules_id, points_per_question from quizzes where scoring_rules_id is not null;
+
+update attempts
+set scoring_rules_id = quizzes.scoring_rules_id
+from quizzes
+where quizzes.id = attempts.quiz_id;
+
+alter table quizzes
+    drop column points_per_question,
+    add foreign key (scoring_rules_id) references scoring_rules(id);
+
+alter table attempts add foreign key (scoring_rules_id) references scoring_rules(id);
@@ -0,0 +1,11 @@
+drop table attempt_choices;
+
+alter table attempt_questions drop column served_at;
+
+drop index attempts_flagged_at_idx;
+
+alter table attempts
+    drop column flagged_at,
+    drop column flag_reasons;
+
+alter table quizzes drop column seconds_per_question;
\ No newline at end of file
@@ -0,0 +1,26 @@
+alter table quizzes
+    add column seconds_per_question integer null,
+    add constraint quizzes_seconds_per_question_check check (seconds_per_question > 0);
+
+alter table attempts
+    add column flag_reasons text[] not null default '{}',
+    add column flagged_at timestamp null;
+
+create index attempts_flagged_at_idx on attempts (band_id, flagged_at) where flagged_at is not null;
+
+alter table attempt_questions add column served_at timestamp null;
+
+-- Choices in the order shown to one player, each with a token only valid in that attempt
+create table attempt_choices (
+    token uuid default uuid_generate_v4() primary key,
+    attempt_id uuid not null,
+    question_id uuid not null,
+    answer_id uuid not null,
+    side varchar not null default 'answer',
+    position integer not null,
+
+    constraint attempt_choices_side_check check (side in ('answer', 'match')),
+    unique (attempt_id, question_id, side, answer_id),
+    foreign key (attempt_id, question_id) references attempt_questions(attempt_id, question_id) on delete cascade,
+    foreign key (answer_id) references answers(id)
+);
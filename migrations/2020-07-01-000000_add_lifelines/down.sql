@@ -0,0 +1,19 @@
+drop table attempt_lifelines;
+
+alter table attempt_choices drop column eliminated;
+
+alter table scoring_rules
+    drop constraint scoring_rules_lifeline_cost_check,
+    drop column hint_cost,
+    drop column fifty_fifty_cost;
+
+alter table attempts
+    drop column hint_lifelines,
+    drop column fifty_fifty_lifelines;
+
+alter table quizzes
+    drop constraint quizzes_lifelines_check,
+    drop column hint_lifelines,
+    drop column fifty_fifty_lifelines;
+
+alter table questions drop column hint;
\ No newline at end of file
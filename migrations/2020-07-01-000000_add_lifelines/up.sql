@@ -0,0 +1,31 @@
+alter table questions add column hint varchar null;
+
+alter table quizzes
+    add column fifty_fifty_lifelines integer not null default 1,
+    add column hint_lifelines integer not null default 1,
+    add constraint quizzes_lifelines_check check (fifty_fifty_lifelines >= 0 and hint_lifelines >= 0);
+
+-- Budgets are copied when the attempt starts, so editing the quiz doesn't change running attempts
+alter table attempts
+    add column fifty_fifty_lifelines integer not null default 1,
+    add column hint_lifelines integer not null default 1;
+
+alter table scoring_rules
+    add column fifty_fifty_cost double precision not null default 0.5,
+    add column hint_cost double precision not null default 0.25,
+    add constraint scoring_rules_lifeline_cost_check check (
+        fifty_fifty_cost between 0 and 1 and hint_cost between 0 and 1
+    );
+
+alter table attempt_choices add column eliminated boolean not null default false;
+
+create table attempt_lifelines (
+    attempt_id uuid not null,
+    question_id uuid not null,
+    kind varchar not null,
+    used_at timestamp not null default current_timestamp,
+
+    constraint attempt_lifelines_kind_check check (kind in ('fifty_fifty', 'hint')),
+    primary key (attempt_id, question_id, kind),
+    foreign key (attempt_id, question_id) references attempt_questions(attempt_id, question_id) on delete cascade
+);
@@ -0,0 +1,3 @@
+alter table questions
+    drop column source,
+    drop column explanation;
\ No newline at end of file
@@ -0,0 +1,3 @@
+alter table questions
+    add column explanation varchar null,
+    add column source varchar null;
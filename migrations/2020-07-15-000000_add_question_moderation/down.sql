@@ -0,0 +1,12 @@
+drop table notifications;
+
+drop index questions_band_id_status_idx;
+
+alter table questions
+    drop column moderated_at,
+    drop column moderated_by,
+    drop column moderation_reason,
+    drop column submitted_by,
+    drop column status;
+
+alter table band_authors drop column role;
\ No newline at end of file
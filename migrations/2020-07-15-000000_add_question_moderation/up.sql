@@ -0,0 +1,32 @@
+-- Moderators review fans' submissions on top of what authors can do
+alter table band_authors
+    add column role varchar not null default 'author',
+    add constraint band_authors_role_check check (role in ('author', 'moderator'));
+
+-- Existing questions were written by authors and count as approved
+alter table questions
+    add column status varchar not null default 'approved',
+    add column submitted_by uuid null,
+    add column moderation_reason varchar null,
+    add column moderated_by uuid null,
+    add column moderated_at timestamp null,
+    add constraint questions_status_check check (status in ('pending', 'approved', 'rejected', 'changes_requested')),
+    add foreign key (submitted_by) references users(id) on delete set null,
+    add foreign key (moderated_by) references users(id) on delete set null;
+
+create index questions_band_id_status_idx on questions (band_id, status);
+
+create table notifications (
+    id uuid default uuid_generate_v4() primary key,
+    user_id uuid not null,
+    kind varchar not null,
+    message varchar not null,
+    question_id uuid null,
+    read_at timestamp null,
+    created_at timestamp not null default current_timestamp,
+
+    foreign key (user_id) references users(id) on delete cascade,
+    foreign key (question_id) references questions(id) on delete cascade
+);
+
+create index notifications_user_id_idx on notifications (user_id, created_at);
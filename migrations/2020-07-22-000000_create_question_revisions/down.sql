@@ -0,0 +1,3 @@
+drop table question_edit_suggestions;
+
+drop table question_revisions;
\ No newline at end of file
@@ -0,0 +1,38 @@
+-- Every saved version of a question, never updated once written
+create table question_revisions (
+    id uuid default uuid_generate_v4() primary key,
+    question_id uuid not null,
+    number integer not null,
+    author_id uuid null,
+    -- JSON of the question's fields and choices after the change
+    snapshot text not null,
+    -- Set when the revision restored an earlier one
+    reverted_from integer null,
+    created_at timestamp not null default current_timestamp,
+
+    unique (question_id, number),
+    foreign key (question_id) references questions(id) on delete cascade,
+    foreign key (author_id) references users(id) on delete set null
+);
+
+create table question_edit_suggestions (
+    id uuid default uuid_generate_v4() primary key,
+    question_id uuid not null,
+    suggested_by uuid not null,
+    snapshot text not null,
+    -- Revision the edit was made against, accepting fails once the question moved on
+    base_revision integer not null,
+    note varchar null,
+    status varchar not null default 'pending',
+    reason varchar null,
+    decided_by uuid null,
+    decided_at timestamp null,
+    created_at timestamp not null default current_timestamp,
+
+    constraint question_edit_suggestions_status_check check (status in ('pending', 'accepted', 'rejected')),
+    foreign key (question_id) references questions(id) on delete cascade,
+    foreign key (suggested_by) references users(id) on delete cascade,
+    foreign key (decided_by) references users(id) on delete set null
+);
+
+create index question_edit_suggestions_question_id_idx on question_edit_suggestions (question_id, status);
@@ -0,0 +1,5 @@
+drop table reports;
+
+alter table users drop column hidden_at;
+alter table bands drop column hidden_at;
+alter table questions drop column hidden_at;
\ No newline at end of file
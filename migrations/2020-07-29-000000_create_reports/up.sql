@@ -0,0 +1,27 @@
+-- Set while enough reports are open, or when a report was upheld
+alter table questions add column hidden_at timestamp null;
+alter table bands add column hidden_at timestamp null;
+alter table users add column hidden_at timestamp null;
+
+create table reports (
+    id uuid default uuid_generate_v4() primary key,
+    reporter_id uuid not null,
+    target_type varchar not null,
+    target_id uuid not null,
+    reason varchar not null,
+    note varchar null,
+    status varchar not null default 'open',
+    resolved_by uuid null,
+    resolved_at timestamp null,
+    created_at timestamp not null default current_timestamp,
+
+    constraint reports_target_type_check check (target_type in ('question', 'band', 'user')),
+    constraint reports_reason_check check (reason in ('wrong_answer', 'offensive', 'spam', 'other')),
+    constraint reports_status_check check (status in ('open', 'dismissed', 'upheld')),
+    foreign key (reporter_id) references users(id) on delete cascade,
+    foreign key (resolved_by) references users(id) on delete set null
+);
+
+-- A reporter has at most one open report per target, reporting again updates it
+create unique index reports_open_reporter_target_idx on reports (reporter_id, target_type, target_id) where status = 'open';
+create index reports_target_idx on reports (target_type, target_id, status);
@@ -0,0 +1,29 @@
+-- Who changed what. Actors and targets aren't foreign keys so entries
+-- outlive the rows they describe.
+create table audit_log (
+    id uuid default uuid_generate_v4() primary key,
+    actor_id uuid null,
+    action varchar not null,
+    target_type varchar not null,
+    target_id uuid not null,
+    -- JSON of the target before and after the change
+    before text null,
+    after text null,
+    request_id uuid not null,
+    ip varchar null,
+    created_at timestamp not null default current_timestamp
+);
+
+create index audit_log_created_at_idx on audit_log (created_at);
+create index audit_log_actor_id_idx on audit_log (actor_id, created_at);
+create index audit_log_target_idx on audit_log (target_type, target_id, created_at);
+
+create function audit_log_append_only() returns trigger as $$
+begin
+    raise exception 'audit_log is append-only';
+end;
+$$ language plpgsql;
+
+create trigger audit_log_append_only
+    before update or delete on audit_log
+    for each row execute procedure audit_log_append_only();
@@ -0,0 +1,7 @@
+drop index questions_deleted_at_idx;
+drop index bands_deleted_at_idx;
+drop index users_deleted_at_idx;
+
+alter table questions drop column deleted_at;
+alter table bands drop column deleted_at;
+alter table users drop column deleted_at;
\ No newline at end of file
@@ -0,0 +1,8 @@
+-- Deleted rows stay restorable until purged after the retention period
+alter table users add column deleted_at timestamp null;
+alter table bands add column deleted_at timestamp null;
+alter table questions add column deleted_at timestamp null;
+
+create index users_deleted_at_idx on users (deleted_at) where deleted_at is not null;
+create index bands_deleted_at_idx on bands (deleted_at) where deleted_at is not null;
+create index questions_deleted_at_idx on questions (deleted_at) where deleted_at is not null;
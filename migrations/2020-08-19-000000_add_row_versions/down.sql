@@ -0,0 +1,18 @@
+drop trigger quizzes_increment_version on quizzes;
+drop trigger questions_increment_version on questions;
+drop function increment_version();
+
+alter table quizzes drop column version;
+alter table questions drop column version;
+
+drop trigger users_set_updated_at on users;
+drop trigger tags_set_updated_at on tags;
+drop trigger quizzes_set_updated_at on quizzes;
+drop trigger questions_set_updated_at on questions;
+drop trigger question_choices_set_updated_at on question_choices;
+drop trigger media_set_updated_at on media;
+drop trigger bands_set_updated_at on bands;
+drop trigger attempts_set_updated_at on attempts;
+drop trigger answers_set_updated_at on answers;
+drop trigger answer_aliases_set_updated_at on answer_aliases;
+drop function set_updated_at();
\ No newline at end of file
@@ -0,0 +1,58 @@
+-- Keeps updated_at current whatever the update sets. Review states keep the
+-- time they were graded at, which the scheduler sets itself.
+create function set_updated_at() returns trigger as $$
+begin
+    new.updated_at = current_timestamp;
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger answer_aliases_set_updated_at
+    before update on answer_aliases
+    for each row when (old.* is distinct from new.*) execute procedure set_updated_at();
+create trigger answers_set_updated_at
+    before update on answers
+    for each row when (old.* is distinct from new.*) execute procedure set_updated_at();
+create trigger attempts_set_updated_at
+    before update on attempts
+    for each row when (old.* is distinct from new.*) execute procedure set_updated_at();
+create trigger bands_set_updated_at
+    before update on bands
+    for each row when (old.* is distinct from new.*) execute procedure set_updated_at();
+create trigger media_set_updated_at
+    before update on media
+    for each row when (old.* is distinct from new.*) execute procedure set_updated_at();
+create trigger question_choices_set_updated_at
+    before update on question_choices
+    for each row when (old.* is distinct from new.*) execute procedure set_updated_at();
+create trigger questions_set_updated_at
+    before update on questions
+    for each row when (old.* is distinct from new.*) execute procedure set_updated_at();
+create trigger quizzes_set_updated_at
+    before update on quizzes
+    for each row when (old.* is distinct from new.*) execute procedure set_updated_at();
+create trigger tags_set_updated_at
+    before update on tags
+    for each row when (old.* is distinct from new.*) execute procedure set_updated_at();
+create trigger users_set_updated_at
+    before update on users
+    for each row when (old.* is distinct from new.*) execute procedure set_updated_at();
+
+-- Editors send back the version they read so concurrent edits fail instead of
+-- overwriting each other
+alter table questions add column version integer not null default 1;
+alter table quizzes add column version integer not null default 1;
+
+create function increment_version() returns trigger as $$
+begin
+    new.version = old.version + 1;
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger questions_increment_version
+    before update on questions
+    for each row when (old.* is distinct from new.*) execute procedure increment_version();
+create trigger quizzes_increment_version
+    before update on quizzes
+    for each row when (old.* is distinct from new.*) execute procedure increment_version();
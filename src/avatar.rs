@@ -0,0 +1,204 @@
+/// Avatars
+/// Re-encodes uploaded avatars into fixed-size thumbnails and draws
+/// identicons for users without one
+
+use crate::errors::{AppError, AppErrorType};
+use image::{imageops::FilterType, io::Reader, DynamicImage, ImageOutputFormat, Rgb, RgbImage};
+use sha2::{Digest, Sha256};
+use std::io::Cursor;
+use uuid::Uuid;
+
+/// Content types accepted for avatar uploads, with their file extensions
+pub const AVATAR_TYPES: &[(&str, &str)] = &[
+    ("image/jpeg", "jpg"),
+    ("image/png", "png"),
+    ("image/gif", "gif"),
+];
+
+/// Edge length of the stored avatar
+pub const AVATAR_SIZE: u32 = 256;
+/// Edge length of the small thumbnail
+pub const THUMBNAIL_SIZE: u32 = 64;
+/// Largest accepted source image edge, in pixels
+const MAX_SOURCE_SIZE: u32 = 4096;
+/// Largest accepted source image area, so a long thin image can't use up memory either
+const MAX_SOURCE_PIXELS: u64 = 16_000_000;
+const IDENTICON_CELLS: u32 = 5;
+
+#[derive(Clone)]
+pub struct AvatarService {
+    base_url: String
+}
+
+impl AvatarService {
+    pub fn new(base_url: String) -> AvatarService {
+        AvatarService { base_url }
+    }
+
+    pub fn identicon_url(&self, user_id: Uuid, size: u32) -> String {
+        format!("{}/identicons/{}.png?size={}", self.base_url.trim_end_matches('/'), user_id, size)
+    }
+}
+
+/// Storage key of an avatar image of `size` pixels
+pub fn avatar_key(user_id: Uuid, upload_id: Uuid, size: u32) -> String {
+    format!("avatars/{}/{}/{}.png", user_id, upload_id, size)
+}
+
+/// The thumbnail of an uploaded avatar sits next to it. Images set any other
+/// way are returned unchanged.
+pub fn thumbnail_url(image: &str) -> String {
+    let avatar_suffix = format!("/{}.png", AVATAR_SIZE);
+
+    if image.contains("/avatars/") && image.ends_with(&avatar_suffix) {
+        format!("{}/{}.png", &image[..image.len() - avatar_suffix.len()], THUMBNAIL_SIZE)
+    } else {
+        image.to_string()
+    }
+}
+
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, AppError> {
+    let mut bytes = Vec::new();
+    image.write_to(&mut bytes, ImageOutputFormat::Png)
+        .map_err(|err| AppError {
+            cause: Some(err.to_string()),
+            message: None,
+            error_type: AppErrorType::DbError
+        })?;
+
+    Ok(bytes)
+}
+
+fn unreadable(err: impl ToString) -> AppError {
+    AppError {
+        cause: Some(err.to_string()),
+        message: Some("image could not be read.".to_string()),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+fn reader(bytes: &[u8]) -> Result<Reader<Cursor<&[u8]>>, AppError> {
+    Reader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(unreadable)
+}
+
+/// Decodes an uploaded image and re-encodes it as square PNGs of
+/// `AVATAR_SIZE` and `THUMBNAIL_SIZE`, cropped to the center. The size is
+/// read from the image header and checked before anything is decoded.
+pub fn thumbnails(bytes: &[u8]) -> Result<Vec<(u32, Vec<u8>)>, AppError> {
+    let (width, height) = reader(bytes)?
+        .into_dimensions()
+        .map_err(unreadable)?;
+
+    if width > MAX_SOURCE_SIZE || height > MAX_SOURCE_SIZE || u64::from(width) * u64::from(height) > MAX_SOURCE_PIXELS {
+        return Err(AppError {
+            cause: None,
+            message: Some(format!("image must be at most {0}x{0} pixels.", MAX_SOURCE_SIZE)),
+            error_type: AppErrorType::InvalidField
+        });
+    }
+
+    let source = reader(bytes)?
+        .decode()
+        .map_err(unreadable)?;
+
+    [AVATAR_SIZE, THUMBNAIL_SIZE]
+        .iter()
+        .map(|size| {
+            let thumbnail = source.resize_to_fill(*size, *size, FilterType::Lanczos3);
+            encode_png(&thumbnail).map(|png| (*size, png))
+        })
+        .collect()
+}
+
+/// Draws a mirrored 5x5 identicon whose pattern and color come from `seed`
+pub fn identicon(seed: &[u8], size: u32) -> Result<Vec<u8>, AppError> {
+    let hash = Sha256::digest(seed);
+    let color = Rgb([hash[0] / 2 + 64, hash[1] / 2 + 64, hash[2] / 2 + 64]);
+    let background = Rgb([240, 240, 240]);
+
+    let cell = (size / (IDENTICON_CELLS + 1)).max(1);
+    let margin = size.saturating_sub(cell * IDENTICON_CELLS) / 2;
+    let mut image = RgbImage::from_pixel(size, size, background);
+
+    for row in 0..IDENTICON_CELLS {
+        for column in 0..(IDENTICON_CELLS + 1) / 2 {
+            if hash[(3 + row * 3 + column) as usize] % 2 != 0 {
+                continue;
+            }
+
+            for mirrored in &[column, IDENTICON_CELLS - 1 - column] {
+                for y in 0..cell {
+                    for x in 0..cell {
+                        let px = margin + mirrored * cell + x;
+                        let py = margin + row * cell + y;
+                        if px < size && py < size {
+                            image.put_pixel(px, py, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    encode_png(&DynamicImage::ImageRgb8(image))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{identicon, thumbnail_url, thumbnails, AVATAR_SIZE, MAX_SOURCE_SIZE, THUMBNAIL_SIZE};
+    use image::{DynamicImage, GenericImageView, ImageOutputFormat, RgbImage};
+
+    #[test]
+    fn test_thumbnails_are_square() {
+        let mut source = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(300, 120))
+            .write_to(&mut source, ImageOutputFormat::Png)
+            .unwrap();
+
+        let sizes: Vec<(u32, u32)> = thumbnails(&source)
+            .unwrap()
+            .iter()
+            .map(|(_, png)| image::load_from_memory(png).unwrap().dimensions())
+            .collect();
+
+        assert_eq!(sizes, vec![(AVATAR_SIZE, AVATAR_SIZE), (THUMBNAIL_SIZE, THUMBNAIL_SIZE)]);
+    }
+
+    #[test]
+    fn test_thumbnails_reject_large_images() {
+        let mut source = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(MAX_SOURCE_SIZE + 1, 1))
+            .write_to(&mut source, ImageOutputFormat::Png)
+            .unwrap();
+
+        let err = thumbnails(&source).unwrap_err();
+        assert_eq!(err.message.as_deref(), Some("image must be at most 4096x4096 pixels."));
+    }
+
+    #[test]
+    fn test_thumbnails_reject_garbage() {
+        assert!(thumbnails(b"not an image").is_err());
+    }
+
+    #[test]
+    fn test_identicon_is_deterministic() {
+        let first = identicon(b"user-1", 64).unwrap();
+
+        assert_eq!(first, identicon(b"user-1", 64).unwrap());
+        assert_ne!(first, identicon(b"user-2", 64).unwrap());
+        assert_eq!(image::load_from_memory(&first).unwrap().dimensions(), (64, 64));
+    }
+
+    #[test]
+    fn test_thumbnail_url() {
+        assert_eq!(
+            thumbnail_url("http://host/files/avatars/u/a/256.png"),
+            "http://host/files/avatars/u/a/64.png"
+        );
+        assert_eq!(thumbnail_url("https://example.com/me.jpg"), "https://example.com/me.jpg");
+    }
+
+}
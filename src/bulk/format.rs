@@ -0,0 +1,231 @@
+/// Bulk question formats
+/// Reads and writes question records as JSON, CSV or YAML
+
+use crate::errors::{AppError, AppErrorType};
+use juniper::GraphQLEnum;
+use serde::{Deserialize, Serialize};
+
+/// Separator for list columns in CSV files
+const CSV_LIST_SEPARATOR: char = '|';
+
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum BulkFormat {
+    Json,
+    Csv,
+    Yaml,
+}
+
+impl BulkFormat {
+    /// Picks the format from a file name's extension
+    pub fn from_path(path: &str) -> Option<BulkFormat> {
+        let extension = path.rsplit('.').next()?.to_lowercase();
+        match extension.as_str() {
+            "json" => Some(BulkFormat::Json),
+            "csv" => Some(BulkFormat::Csv),
+            "yaml" | "yml" => Some(BulkFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// One question as band owners keep it in their spreadsheets
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QuestionRecord {
+    pub prompt: String,
+    pub correct_answer: String,
+    #[serde(default)]
+    pub distractors: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// CSV can't hold lists, so they are joined with `|`
+#[derive(Serialize, Deserialize)]
+struct CsvRecord {
+    prompt: String,
+    correct_answer: String,
+    #[serde(default)]
+    distractors: String,
+    #[serde(default)]
+    tags: String,
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(CSV_LIST_SEPARATOR)
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn join_list(items: &[String]) -> String {
+    items.join(&CSV_LIST_SEPARATOR.to_string())
+}
+
+impl From<CsvRecord> for QuestionRecord {
+    fn from(record: CsvRecord) -> QuestionRecord {
+        QuestionRecord {
+            prompt: record.prompt,
+            correct_answer: record.correct_answer,
+            distractors: split_list(&record.distractors),
+            tags: split_list(&record.tags),
+        }
+    }
+}
+
+impl From<&QuestionRecord> for CsvRecord {
+    fn from(record: &QuestionRecord) -> CsvRecord {
+        CsvRecord {
+            prompt: record.prompt.clone(),
+            correct_answer: record.correct_answer.clone(),
+            distractors: join_list(&record.distractors),
+            tags: join_list(&record.tags),
+        }
+    }
+}
+
+fn unreadable(format: BulkFormat, err: String) -> AppError {
+    AppError {
+        cause: Some(err),
+        message: Some(format!("file is not valid {:?}.", format)),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+/// Trims fields and checks a record can become a question
+pub fn validate(record: QuestionRecord) -> Result<QuestionRecord, String> {
+    let record = QuestionRecord {
+        prompt: record.prompt.trim().to_string(),
+        correct_answer: record.correct_answer.trim().to_string(),
+        distractors: record.distractors.iter().map(|item| item.trim().to_string()).collect(),
+        tags: record.tags.iter().map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect(),
+    };
+
+    if record.prompt.is_empty() {
+        return Err("prompt is empty".to_string());
+    }
+    if record.correct_answer.is_empty() {
+        return Err("correct_answer is empty".to_string());
+    }
+    if record.distractors.iter().any(|distractor| distractor.is_empty()) {
+        return Err("distractors can't be empty".to_string());
+    }
+
+    let mut options = record.distractors.clone();
+    options.push(record.correct_answer.clone());
+    options.sort();
+    options.dedup();
+    if options.len() != record.distractors.len() + 1 {
+        return Err("answers must be distinct".to_string());
+    }
+
+    Ok(record)
+}
+
+/// Parses a file into records, keeping per-row errors instead of failing
+/// the whole file. Fails only when the file itself can't be read.
+pub fn parse(format: BulkFormat, input: &str) -> Result<Vec<Result<QuestionRecord, String>>, AppError> {
+    let rows = match format {
+        BulkFormat::Json => serde_json::from_str::<Vec<serde_json::Value>>(input)
+            .map_err(|err| unreadable(format, err.to_string()))?
+            .into_iter()
+            .map(|row| serde_json::from_value::<QuestionRecord>(row).map_err(|err| err.to_string()))
+            .collect::<Vec<_>>(),
+        BulkFormat::Yaml => serde_yaml::from_str::<Vec<serde_yaml::Value>>(input)
+            .map_err(|err| unreadable(format, err.to_string()))?
+            .into_iter()
+            .map(|row| serde_yaml::from_value::<QuestionRecord>(row).map_err(|err| err.to_string()))
+            .collect::<Vec<_>>(),
+        BulkFormat::Csv => csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(input.as_bytes())
+            .deserialize::<CsvRecord>()
+            .map(|row| row.map(QuestionRecord::from).map_err(|err| err.to_string()))
+            .collect::<Vec<_>>(),
+    };
+
+    Ok(rows.into_iter().map(|row| row.and_then(validate)).collect())
+}
+
+pub fn serialize(format: BulkFormat, records: &[QuestionRecord]) -> Result<String, AppError> {
+    let failed = |err: String| AppError {
+        cause: Some(err),
+        message: None,
+        error_type: AppErrorType::DbError
+    };
+
+    match format {
+        BulkFormat::Json => serde_json::to_string_pretty(records).map_err(|err| failed(err.to_string())),
+        BulkFormat::Yaml => serde_yaml::to_string(records).map_err(|err| failed(err.to_string())),
+        BulkFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            for record in records {
+                writer.serialize(CsvRecord::from(record)).map_err(|err| failed(err.to_string()))?;
+            }
+            let bytes = writer.into_inner().map_err(|err| failed(err.to_string()))?;
+            String::from_utf8(bytes).map_err(|err| failed(err.to_string()))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{parse, serialize, BulkFormat, QuestionRecord};
+
+    fn records() -> Vec<QuestionRecord> {
+        vec![
+            QuestionRecord {
+                prompt: "Which album opens with \"Come Together\"?".to_string(),
+                correct_answer: "Abbey Road".to_string(),
+                distractors: vec!["Let It Be".to_string(), "Revolver".to_string()],
+                tags: vec!["albums".to_string(), "1960s".to_string()],
+            },
+            QuestionRecord {
+                prompt: "Who played drums?".to_string(),
+                correct_answer: "Ringo Starr".to_string(),
+                distractors: vec![],
+                tags: vec![],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for format in &[BulkFormat::Json, BulkFormat::Csv, BulkFormat::Yaml] {
+            let exported = serialize(*format, &records()).unwrap();
+            let imported: Vec<QuestionRecord> = parse(*format, &exported)
+                .unwrap()
+                .into_iter()
+                .map(|row| row.unwrap())
+                .collect();
+
+            assert_eq!(imported, records(), "{:?} should round-trip", format);
+        }
+    }
+
+    #[test]
+    fn test_row_errors_are_reported_per_row() {
+        let csv = "prompt,correct_answer,distractors,tags\n\
+                   Good?,Yes,No,\n\
+                   ,Missing prompt,,\n\
+                   Same?,A,A,\n";
+
+        let rows = parse(BulkFormat::Csv, csv).unwrap();
+
+        assert!(rows[0].is_ok());
+        assert_eq!(rows[1], Err("prompt is empty".to_string()));
+        assert_eq!(rows[2], Err("answers must be distinct".to_string()));
+    }
+
+    #[test]
+    fn test_unreadable_file_fails() {
+        assert!(parse(BulkFormat::Json, "{ not json").is_err());
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(BulkFormat::from_path("trivia.YML"), Some(BulkFormat::Yaml));
+        assert_eq!(BulkFormat::from_path("trivia.xlsx"), None);
+    }
+
+}
@@ -0,0 +1,203 @@
+pub mod format;
+pub mod open_trivia;
+
+use crate::errors::{AppError, AppErrorType};
+use crate::models::question::{CreateQuestion, QuestionKind};
+use crate::grading::free_text::normalize;
+use crate::repositories::stores::{AnswerStore, QuestionStore, TagStore};
+use format::{BulkFormat, QuestionRecord};
+use juniper::{GraphQLEnum, GraphQLObject};
+use std::{collections::HashSet, sync::Arc};
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum ImportStatus {
+    Created,
+    /// Would be created, reported by dry runs
+    Valid,
+    /// A question with the same prompt already exists for the band
+    Duplicate,
+    /// Left out on purpose, e.g. filtered by category
+    Skipped,
+    Failed,
+}
+
+/// Why a row was not turned into a record
+#[derive(Debug, PartialEq)]
+pub enum Rejection {
+    Invalid(String),
+    Skipped(String),
+}
+
+#[derive(Clone, GraphQLObject)]
+pub struct ImportRow {
+    /// 1-based position of the record in the file
+    pub row: i32,
+    pub status: ImportStatus,
+    pub prompt: Option<String>,
+    pub message: Option<String>,
+    pub question_id: Option<Uuid>,
+}
+
+#[derive(Clone, GraphQLObject)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: i32,
+    pub duplicates: i32,
+    pub skipped: i32,
+    pub failed: i32,
+    pub rows: Vec<ImportRow>,
+}
+
+/// Prompts differing only in case, punctuation or accents count as duplicates
+fn prompt_key(prompt: &str) -> String {
+    normalize(prompt)
+}
+
+/// Imports questions for a band. Answers are matched by content so rows
+/// reuse existing answers instead of failing on the unique constraint; rows
+/// whose prompt already exists for the band are skipped, and so are ones that
+/// look like its questions unless forced. Records without distractors become
+/// free-text questions. A row that can't be saved fails the import, which
+/// callers run in a transaction so nothing of it is kept.
+pub struct QuestionImporter {
+    pub questions: Arc<dyn QuestionStore>,
+    pub answers: Arc<dyn AnswerStore>,
+    pub tags: Arc<dyn TagStore>,
+}
+
+impl QuestionImporter {
+    pub async fn import(&self, band_id: Uuid, format: BulkFormat, input: &str, dry_run: bool, force: bool) -> Result<ImportReport, AppError> {
+        let rows = format::parse(format, input)?
+            .into_iter()
+            .map(|row| row.map_err(Rejection::Invalid))
+            .collect();
+
+        self.import_records(band_id, rows, dry_run, force).await
+    }
+
+    pub async fn import_records(&self, band_id: Uuid, rows: Vec<Result<QuestionRecord, Rejection>>, dry_run: bool, force: bool) -> Result<ImportReport, AppError> {
+        let mut seen: HashSet<String> = self.questions
+            .get_for_band(band_id)
+            .await?
+            .iter()
+            .map(|question| prompt_key(&question.content))
+            .collect();
+
+        let mut report = ImportReport { dry_run, created: 0, duplicates: 0, skipped: 0, failed: 0, rows: vec![] };
+
+        for (index, row) in rows.into_iter().enumerate() {
+            let row_number = index as i32 + 1;
+
+            let record = match row {
+                Ok(record) => record,
+                Err(Rejection::Invalid(message)) => {
+                    report.failed += 1;
+                    report.rows.push(ImportRow { row: row_number, status: ImportStatus::Failed, prompt: None, message: Some(message), question_id: None });
+                    continue;
+                },
+                Err(Rejection::Skipped(message)) => {
+                    report.skipped += 1;
+                    report.rows.push(ImportRow { row: row_number, status: ImportStatus::Skipped, prompt: None, message: Some(message), question_id: None });
+                    continue;
+                },
+            };
+
+            if !seen.insert(prompt_key(&record.prompt)) {
+                report.duplicates += 1;
+                report.rows.push(ImportRow { row: row_number, status: ImportStatus::Duplicate, prompt: Some(record.prompt), message: None, question_id: None });
+                continue;
+            }
+
+            if dry_run {
+                report.rows.push(ImportRow { row: row_number, status: ImportStatus::Valid, prompt: Some(record.prompt), message: None, question_id: None });
+                continue;
+            }
+
+            match self.create(band_id, &record, force).await {
+                Ok(question_id) => {
+                    report.created += 1;
+                    report.rows.push(ImportRow { row: row_number, status: ImportStatus::Created, prompt: Some(record.prompt), message: None, question_id: Some(question_id) });
+                },
+                Err(AppError { error_type: AppErrorType::DuplicateError(_), message, .. }) => {
+                    report.duplicates += 1;
+                    report.rows.push(ImportRow { row: row_number, status: ImportStatus::Duplicate, prompt: Some(record.prompt), message, question_id: None });
+                },
+                Err(err) => return Err(AppError {
+                    message: Some(format!("row {}: {}", row_number, err.message())),
+                    ..err
+                }),
+            }
+        }
+
+        Ok(report)
+    }
+
+    async fn create(&self, band_id: Uuid, record: &QuestionRecord, force: bool) -> Result<Uuid, AppError> {
+        let (correct_answer, _) = self.answers.find_or_create(band_id, &record.correct_answer).await?;
+
+        let mut distractor_ids = Vec::new();
+        for distractor in &record.distractors {
+            distractor_ids.push(self.answers.find_or_create(band_id, distractor).await?.0.id);
+        }
+
+        let kind = if distractor_ids.is_empty() { QuestionKind::FreeText } else { QuestionKind::Choice };
+
+        let question = self.questions.create(CreateQuestion {
+            content: record.prompt.clone(),
+            correct_answer_id: Some(correct_answer.id),
+            distractor_ids: if distractor_ids.is_empty() { None } else { Some(distractor_ids) },
+            band_id,
+            kind: Some(kind),
+            max_edit_distance: None,
+            numeric_value: None,
+            numeric_tolerance: None,
+            numeric_curve: None,
+            media_id: None,
+            clip_start_ms: None,
+            clip_end_ms: None,
+            hint: None,
+            explanation: None,
+            source: None,
+            tags: None,
+            force: Some(force),
+        }, None).await?;
+
+        self.tags.tag_question(question.id, &record.tags, None).await?;
+
+        Ok(question.id)
+    }
+
+    /// Exports a band's choice and free-text questions; other kinds have no
+    /// spreadsheet representation and are left out.
+    pub async fn export(&self, band_id: Uuid, format: BulkFormat) -> Result<String, AppError> {
+        let mut records = Vec::new();
+
+        for question in self.questions.get_for_band(band_id).await? {
+            let key = self.questions.answer_key(&question).await?;
+            let correct_answer = match key.correct_answer {
+                Some(answer) => answer,
+                None => continue,
+            };
+
+            let mut distractors = Vec::new();
+            for choice in key.choices.iter().filter(|choice| choice.answer_id != correct_answer.id) {
+                distractors.push(self.answers.get(choice.answer_id).await?.content);
+            }
+
+            let tags = self.tags.for_question(question.id).await?
+                .into_iter()
+                .map(|tag| tag.name)
+                .collect();
+
+            records.push(QuestionRecord {
+                prompt: question.content,
+                correct_answer: correct_answer.content,
+                distractors,
+                tags,
+            });
+        }
+
+        format::serialize(format, &records)
+    }
+}
@@ -0,0 +1,188 @@
+/// Open Trivia DB import
+/// Maps Open Trivia DB style JSON (the API response or a bare list of its
+/// results) onto question records, decoding the HTML entities it uses
+
+use crate::bulk::{format::{validate, QuestionRecord}, Rejection};
+use crate::errors::{AppError, AppErrorType};
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Deserialize)]
+struct OpenTriviaQuestion {
+    category: String,
+    #[serde(rename = "type")]
+    kind: String,
+    difficulty: String,
+    question: String,
+    correct_answer: String,
+    incorrect_answers: Vec<String>,
+}
+
+const NAMED_ENTITIES: &[(&str, char)] = &[
+    ("quot", '"'), ("amp", '&'), ("apos", '\''), ("lt", '<'), ("gt", '>'),
+    ("nbsp", ' '), ("shy", '\u{ad}'), ("deg", '°'), ("hellip", '…'),
+    ("ndash", '–'), ("mdash", '—'), ("lsquo", '‘'), ("rsquo", '’'),
+    ("ldquo", '“'), ("rdquo", '”'), ("laquo", '«'), ("raquo", '»'),
+    ("aacute", 'á'), ("Aacute", 'Á'), ("agrave", 'à'), ("acirc", 'â'),
+    ("auml", 'ä'), ("Auml", 'Ä'), ("aring", 'å'), ("Aring", 'Å'),
+    ("ccedil", 'ç'), ("eacute", 'é'), ("Eacute", 'É'), ("egrave", 'è'),
+    ("ecirc", 'ê'), ("euml", 'ë'), ("iacute", 'í'), ("iuml", 'ï'),
+    ("ntilde", 'ñ'), ("oacute", 'ó'), ("ocirc", 'ô'), ("ouml", 'ö'),
+    ("Ouml", 'Ö'), ("oslash", 'ø'), ("Oslash", 'Ø'), ("uacute", 'ú'),
+    ("uuml", 'ü'), ("Uuml", 'Ü'), ("szlig", 'ß'), ("eth", 'ð'),
+    ("pi", 'π'), ("times", '×'), ("divide", '÷'), ("sup2", '²'),
+];
+
+fn decode_entity(entity: &str) -> Option<char> {
+    if entity.starts_with("#x") || entity.starts_with("#X") {
+        u32::from_str_radix(&entity[2..], 16).ok().and_then(std::char::from_u32)
+    } else if entity.starts_with('#') {
+        entity[1..].parse::<u32>().ok().and_then(std::char::from_u32)
+    } else {
+        NAMED_ENTITIES.iter()
+            .find(|(name, _)| *name == entity)
+            .map(|(_, decoded)| *decoded)
+    }
+}
+
+/// Decodes named and numeric HTML entities, leaving unknown ones as they are
+pub fn decode_html_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        let entity = &rest[start..];
+
+        let replacement = entity.find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| decode_entity(&entity[1..end]).map(|decoded| (decoded, end)));
+
+        match replacement {
+            Some((character, end)) => {
+                decoded.push(character);
+                rest = &entity[end + 1..];
+            },
+            None => {
+                decoded.push('&');
+                rest = &entity[1..];
+            },
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+fn to_record(item: Value, category_filter: Option<&str>) -> Result<QuestionRecord, Rejection> {
+    let item: OpenTriviaQuestion = serde_json::from_value(item)
+        .map_err(|err| Rejection::Invalid(err.to_string()))?;
+
+    let category = decode_html_entities(&item.category);
+    if let Some(filter) = category_filter {
+        if !category.to_lowercase().contains(&filter.to_lowercase()) {
+            return Err(Rejection::Skipped(format!("category {} doesn't match {}", category, filter)));
+        }
+    }
+
+    if item.kind != "multiple" && item.kind != "boolean" {
+        return Err(Rejection::Skipped(format!("unsupported question type {}", item.kind)));
+    }
+
+    validate(QuestionRecord {
+        prompt: decode_html_entities(&item.question),
+        correct_answer: decode_html_entities(&item.correct_answer),
+        distractors: item.incorrect_answers.iter().map(|answer| decode_html_entities(answer)).collect(),
+        tags: vec![category, decode_html_entities(&item.difficulty)],
+    })
+    .map_err(Rejection::Invalid)
+}
+
+/// Parses a dump into records, keeping only categories containing
+/// `category_filter` when one is given
+pub fn parse(input: &str, category_filter: Option<&str>) -> Result<Vec<Result<QuestionRecord, Rejection>>, AppError> {
+    let unreadable = |cause: String| AppError {
+        cause: Some(cause),
+        message: Some("file is not an Open Trivia DB dump.".to_string()),
+        error_type: AppErrorType::InvalidField
+    };
+
+    let items = match serde_json::from_str::<Value>(input).map_err(|err| unreadable(err.to_string()))? {
+        Value::Array(items) => items,
+        Value::Object(mut dump) => match dump.remove("results") {
+            Some(Value::Array(items)) => items,
+            _ => return Err(unreadable("missing results list".to_string())),
+        },
+        _ => return Err(unreadable("expected an object or a list".to_string())),
+    };
+
+    Ok(items.into_iter().map(|item| to_record(item, category_filter)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{decode_html_entities, parse};
+    use crate::bulk::Rejection;
+
+    #[test]
+    fn test_decode_html_entities() {
+        assert_eq!(
+            decode_html_entities("&quot;Hey Jude&quot; &amp; &#039;Let It Be&#039;"),
+            "\"Hey Jude\" & 'Let It Be'"
+        );
+        assert_eq!(decode_html_entities("Mot&ouml;rhead &#x41;C/DC"), "Motörhead AC/DC");
+        assert_eq!(decode_html_entities("AT&T &unknown; &"), "AT&T &unknown; &");
+    }
+
+    #[test]
+    fn test_parse_dump() {
+        let dump = r#"{
+            "response_code": 0,
+            "results": [
+                {
+                    "category": "Entertainment: Music",
+                    "type": "multiple",
+                    "difficulty": "easy",
+                    "question": "Which band recorded &quot;Paranoid&quot;?",
+                    "correct_answer": "Black Sabbath",
+                    "incorrect_answers": ["Deep Purple", "Led Zeppelin", "Mot&ouml;rhead"]
+                },
+                {
+                    "category": "Entertainment: Music",
+                    "type": "boolean",
+                    "difficulty": "medium",
+                    "question": "Queen&#039;s drummer was Roger Taylor.",
+                    "correct_answer": "True",
+                    "incorrect_answers": ["False"]
+                },
+                {
+                    "category": "Science: Computers",
+                    "type": "multiple",
+                    "difficulty": "hard",
+                    "question": "What does CPU stand for?",
+                    "correct_answer": "Central Processing Unit",
+                    "incorrect_answers": ["Computer Personal Unit"]
+                },
+                { "category": "Entertainment: Music" }
+            ]
+        }"#;
+
+        let rows = parse(dump, Some("music")).unwrap();
+
+        let first = rows[0].as_ref().unwrap();
+        assert_eq!(first.prompt, "Which band recorded \"Paranoid\"?");
+        assert_eq!(first.distractors[2], "Motörhead");
+        assert_eq!(first.tags, vec!["Entertainment: Music".to_string(), "easy".to_string()]);
+        assert_eq!(rows[1].as_ref().unwrap().correct_answer, "True");
+        assert!(matches!(rows[2], Err(Rejection::Skipped(_))), "Other categories should be skipped");
+        assert!(matches!(rows[3], Err(Rejection::Invalid(_))), "Incomplete items should be reported");
+    }
+
+    #[test]
+    fn test_parse_bare_list() {
+        assert_eq!(parse("[]", None).unwrap().len(), 0);
+        assert!(parse("{\"response_code\": 1}", None).is_err());
+    }
+
+}
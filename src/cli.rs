@@ -0,0 +1,125 @@
+/// Command line tasks
+/// Run with `fan-quiz-juniper <command> ...` instead of starting the server
+
+use crate::bulk::{ImportReport, QuestionImporter, format::BulkFormat, open_trivia};
+use crate::config::Config;
+use crate::errors::AppError;
+use crate::purge;
+use crate::repositories::{db::{self, Db}, answer::AnswerRepository, question::QuestionRepository, tag::TagRepository};
+use std::{fs, io, sync::Arc};
+use uuid::Uuid;
+
+const USAGE: &str = "usage:
+    fan-quiz-juniper import-questions <band-id> <file.json|csv|yaml> [--dry-run] [--force]
+    fan-quiz-juniper export-questions <band-id> <file.json|csv|yaml>
+    fan-quiz-juniper import-opentdb <band-id> <file.json> [--dry-run] [--force] [--category <name>]
+    fan-quiz-juniper purge-deleted [--days <n>]";
+
+fn usage_error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("{}\n{}", message, USAGE))
+}
+
+fn app_error(err: AppError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{} ({})", err.message(), err.cause.unwrap_or_default()))
+}
+
+fn band_and_format(args: &[String]) -> io::Result<(Uuid, BulkFormat, String)> {
+    let band_id = args.get(1)
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .ok_or_else(|| usage_error("missing or invalid band id"))?;
+    let path = args.get(2)
+        .ok_or_else(|| usage_error("missing file"))?;
+    let format = BulkFormat::from_path(path)
+        .ok_or_else(|| usage_error("file must end in .json, .csv, .yaml or .yml"))?;
+
+    Ok((band_id, format, path.clone()))
+}
+
+fn print_report(report: &ImportReport) {
+    for row in &report.rows {
+        println!(
+            "row {}: {:?} {} {}",
+            row.row,
+            row.status,
+            row.prompt.as_deref().unwrap_or(""),
+            row.message.as_deref().unwrap_or("")
+        );
+    }
+    println!(
+        "{}{} created, {} duplicates, {} skipped, {} failed",
+        if report.dry_run { "dry run: " } else { "" },
+        report.created,
+        report.duplicates,
+        report.skipped,
+        report.failed
+    );
+}
+
+fn importer(db: Db) -> QuestionImporter {
+    QuestionImporter {
+        questions: Arc::new(QuestionRepository::new(db.clone())),
+        answers: Arc::new(AnswerRepository::new(db.clone())),
+        tags: Arc::new(TagRepository::new(db)),
+    }
+}
+
+pub async fn run(config: &Config, args: &[String]) -> io::Result<()> {
+    let pool = Arc::new(config.configure_pool());
+    let db = Db::from(pool.clone());
+
+    match args.first().map(|command| command.as_str()) {
+        Some("import-questions") => {
+            let (band_id, format, path) = band_and_format(args)?;
+            let dry_run = args.iter().any(|arg| arg == "--dry-run");
+            let input = fs::read_to_string(&path)?;
+
+            let force = args.iter().any(|arg| arg == "--force");
+
+            let report = db::transaction(&db, |db| async move {
+                importer(db).import(band_id, format, &input, dry_run, force).await
+            }).await.map_err(app_error)?;
+
+            print_report(&report);
+            Ok(())
+        },
+        Some("import-opentdb") => {
+            let (band_id, _, path) = band_and_format(args)?;
+            let dry_run = args.iter().any(|arg| arg == "--dry-run");
+            let category = args.iter()
+                .position(|arg| arg == "--category")
+                .map(|index| args.get(index + 1).ok_or_else(|| usage_error("missing category")))
+                .transpose()?;
+            let input = fs::read_to_string(&path)?;
+
+            let rows = open_trivia::parse(&input, category.map(|name| name.as_str())).map_err(app_error)?;
+            let force = args.iter().any(|arg| arg == "--force");
+
+            let report = db::transaction(&db, |db| async move {
+                importer(db).import_records(band_id, rows, dry_run, force).await
+            }).await.map_err(app_error)?;
+
+            print_report(&report);
+            Ok(())
+        },
+        Some("export-questions") => {
+            let (band_id, format, path) = band_and_format(args)?;
+            let output = importer(db).export(band_id, format).await.map_err(app_error)?;
+            fs::write(path, output)
+        },
+        Some("purge-deleted") => {
+            let days = match args.iter().position(|arg| arg == "--days") {
+                Some(index) => args.get(index + 1)
+                    .and_then(|days| days.parse::<i32>().ok())
+                    .filter(|days| *days >= 0)
+                    .ok_or_else(|| usage_error("missing or invalid number of days"))?,
+                None => config.retention_settings().deleted_days,
+            };
+
+            let report = purge::purge_deleted(pool, days).await.map_err(app_error)?;
+
+            println!("{} questions, {} bands and {} users purged", report.questions, report.bands, report.users);
+            Ok(())
+        },
+        _ => Err(usage_error("unknown command")),
+    }
+}
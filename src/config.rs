@@ -8,21 +8,77 @@ use slog_async;
 use slog_envlogger;
 use slog_term;
 use tokio_postgres::NoTls;
-use argonautica::Hasher;
+use argonautica::{Hasher, Verifier};
 use futures::compat::Future01CompatExt;
+use crate::storage::{Storage, local::LocalStorage, s3::S3Storage};
+use crate::avatar::AvatarService;
+use std::{path::PathBuf, sync::Arc};
 
 #[derive(Deserialize)]
 pub struct ServerConfig {
     pub host: String,
     pub port: i32,
     pub url: String,
-    pub secret_key: String
+    pub secret_key: String,
+    /// Set when a proxy in front of the server sets the forwarding and request id headers
+    #[serde(default)]
+    pub trust_proxy: bool,
+}
+
+#[derive(Deserialize)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+    pub public_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Directory for uploads when no S3 backend is configured
+    pub root: String,
+    pub max_upload_bytes: usize,
+    pub s3: Option<S3Config>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            root: "./uploads".to_string(),
+            max_upload_bytes: 10 * 1024 * 1024,
+            s3: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// Days deleted bands, questions and users are kept before being purged
+    pub deleted_days: i32,
+    pub purge_interval_minutes: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        RetentionConfig {
+            deleted_days: 30,
+            purge_interval_minutes: 60,
+        }
+    }
 }
 
 #[derive(Deserialize)]
 pub struct Config {
     pub server: ServerConfig,
     pub pg: deadpool_postgres::Config,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
 }
 
 impl Config {
@@ -44,8 +100,46 @@ impl Config {
     }
 
     pub fn hashing_service(&self) -> HashingService {
-        HashingService {
-            secret_key: self.server.secret_key.clone()
+        HashingService::new(self.server.secret_key.clone())
+    }
+
+    pub fn avatar_service(&self) -> AvatarService {
+        AvatarService::new(self.server.url.clone())
+    }
+
+    pub fn upload_settings(&self) -> UploadSettings {
+        UploadSettings {
+            max_bytes: self.storage.max_upload_bytes
+        }
+    }
+
+    pub fn proxy_settings(&self) -> ProxySettings {
+        ProxySettings {
+            trusted: self.server.trust_proxy
+        }
+    }
+
+    pub fn retention_settings(&self) -> RetentionSettings {
+        RetentionSettings {
+            deleted_days: self.retention.deleted_days,
+            interval_minutes: self.retention.purge_interval_minutes.max(1),
+        }
+    }
+
+    pub fn storage(&self) -> Arc<dyn Storage> {
+        match &self.storage.s3 {
+            Some(s3) => Arc::new(S3Storage::new(
+                s3.endpoint.clone(),
+                s3.bucket.clone(),
+                s3.region.clone(),
+                s3.access_key.clone(),
+                s3.secret_key.clone(),
+                s3.public_url.clone(),
+            )),
+            None => Arc::new(LocalStorage::new(
+                PathBuf::from(&self.storage.root),
+                format!("{}/files", self.server.url.trim_end_matches('/')),
+            )),
         }
     }
 
@@ -60,12 +154,32 @@ impl Config {
     }
 }
 
+#[derive(Clone)]
+pub struct UploadSettings {
+    pub max_bytes: usize
+}
+
+#[derive(Clone)]
+pub struct ProxySettings {
+    pub trusted: bool
+}
+
+#[derive(Clone)]
+pub struct RetentionSettings {
+    pub deleted_days: i32,
+    pub interval_minutes: u64,
+}
+
 #[derive(Clone)]
 pub struct HashingService {
     secret_key: String
 }
 
 impl HashingService {
+    pub fn new(secret_key: String) -> HashingService {
+        HashingService { secret_key }
+    }
+
     pub async fn hash(&self, password: String) -> Result<String, AppError> { // ~300ms
         Hasher::default()
             .with_password(&password)
@@ -81,4 +195,21 @@ impl HashingService {
                 }
             })
     }
+
+    pub async fn verify(&self, password: String, hash: String) -> Result<bool, AppError> {
+        Verifier::default()
+            .with_hash(&hash)
+            .with_password(&password)
+            .with_secret_key(&self.secret_key)
+            .verify_non_blocking()
+            .compat()
+            .await
+            .map_err(|err| {
+                AppError {
+                    message: None,
+                    cause: Some(err.to_string()),
+                    error_type: AppErrorType::DbError
+                }
+            })
+    }
 }
\ No newline at end of file
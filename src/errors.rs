@@ -2,14 +2,23 @@ use deadpool_postgres::PoolError;
 use std::fmt;
 use tokio_postgres::error::Error;
 use tokio_pg_mapper;
-use juniper::{IntoFieldError, FieldError, Value};
+use juniper::{IntoFieldError, FieldError, Object, Value};
+use actix_web::{error::BlockingError, http::StatusCode, HttpResponse, ResponseError};
+use serde::Serialize;
+use crate::models::duplicate::SimilarQuestion;
 
 #[derive(Debug, Clone)]
 pub enum AppErrorType {
     DbError,
     #[allow(dead_code)]
     NotFoundError,
-    InvalidField
+    InvalidField,
+    UnauthorizedError,
+    ForbiddenError,
+    /// Someone else changed the item since the client read it
+    ConflictError,
+    /// The item looks like the existing ones listed
+    DuplicateError(Vec<SimilarQuestion>)
 }
 
 #[derive(Debug, Clone)]
@@ -34,14 +43,48 @@ impl AppError {
                 error_type: AppErrorType::InvalidField,
                 ..
             } => "Invalid field value provided".to_string(),
+            AppError {
+                error_type: AppErrorType::UnauthorizedError,
+                ..
+            } => "You must be logged in to do that".to_string(),
+            AppError {
+                error_type: AppErrorType::ForbiddenError,
+                ..
+            } => "You are not allowed to do that".to_string(),
+            AppError {
+                error_type: AppErrorType::ConflictError,
+                ..
+            } => "The item was changed since you last read it, reload it and try again".to_string(),
             _ => "An unexpected error has occurred".to_string(),
         }
     }
 }
 
+/// Lists the look-alikes in the error's `extensions`, for clients to show
+fn duplicates_value(similar: &[SimilarQuestion]) -> Value {
+    let duplicates = similar.iter()
+        .map(|question| {
+            let mut duplicate = Object::with_capacity(3);
+            duplicate.add_field("id", Value::scalar(question.id.to_string()));
+            duplicate.add_field("content", Value::scalar(question.content.clone()));
+            duplicate.add_field("similarity", Value::scalar(question.similarity));
+            Value::object(duplicate)
+        })
+        .collect();
+
+    let mut extensions = Object::with_capacity(1);
+    extensions.add_field("duplicates", Value::list(duplicates));
+    Value::object(extensions)
+}
+
 impl IntoFieldError for AppError {
     fn into_field_error(self) -> FieldError { 
-        FieldError::new(self.message(), Value::null())
+        let extensions = match &self.error_type {
+            AppErrorType::DuplicateError(similar) => duplicates_value(similar),
+            _ => Value::null(),
+        };
+
+        FieldError::new(self.message(), extensions)
     }
 }
 
@@ -75,6 +118,43 @@ impl From<tokio_pg_mapper::Error> for AppError {
     }
 }
 
+impl From<BlockingError<AppError>> for AppError {
+    fn from(error: BlockingError<AppError>) -> AppError {
+        match error {
+            BlockingError::Error(error) => error,
+            BlockingError::Canceled => AppError {
+                message: None,
+                cause: Some("Blocking operation was canceled".to_string()),
+                error_type: AppErrorType::DbError,
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AppErrorResponse {
+    error: String,
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self.error_type {
+            AppErrorType::DbError => StatusCode::INTERNAL_SERVER_ERROR,
+            AppErrorType::NotFoundError => StatusCode::NOT_FOUND,
+            AppErrorType::InvalidField => StatusCode::BAD_REQUEST,
+            AppErrorType::UnauthorizedError => StatusCode::UNAUTHORIZED,
+            AppErrorType::ForbiddenError => StatusCode::FORBIDDEN,
+            AppErrorType::ConflictError => StatusCode::CONFLICT,
+            AppErrorType::DuplicateError(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .json(AppErrorResponse { error: self.message() })
+    }
+}
+
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "{}", self.message())
@@ -116,6 +196,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_default_forbidden_error() {
+        let forbidden_error = AppError {
+            message: None,
+            cause: None,
+            error_type: AppErrorType::ForbiddenError,
+        };
+
+        assert_eq!(
+            forbidden_error.message(),
+            "You are not allowed to do that".to_string(),
+            "Default message should be shown"
+        );
+    }
+
+    #[test]
+    fn test_default_conflict_error() {
+        let conflict_error = AppError {
+            message: None,
+            cause: None,
+            error_type: AppErrorType::ConflictError,
+        };
+
+        assert_eq!(
+            conflict_error.message(),
+            "The item was changed since you last read it, reload it and try again".to_string(),
+            "Default message should be shown"
+        );
+    }
+
+    #[test]
+    fn test_duplicates_in_extensions() {
+        use crate::models::duplicate::SimilarQuestion;
+        use juniper::{IntoFieldError, Value};
+        use uuid::Uuid;
+
+        let id = Uuid::new_v4();
+        let duplicate_error = AppError {
+            message: Some("question looks like a duplicate.".to_string()),
+            cause: None,
+            error_type: AppErrorType::DuplicateError(vec![SimilarQuestion { id, content: "Who sang Wonderwall?".to_string(), similarity: 0.75 }]),
+        };
+
+        let field_error = duplicate_error.into_field_error();
+        let duplicates = field_error.extensions()
+            .as_object_value()
+            .and_then(|extensions| extensions.get_field_value("duplicates"))
+            .and_then(Value::as_list_value)
+            .expect("duplicates should be listed");
+
+        assert_eq!(duplicates.len(), 1);
+        let duplicate = duplicates[0].as_object_value().unwrap();
+        assert_eq!(duplicate.get_field_value("id").and_then(Value::as_string_value), Some(id.to_string().as_str()));
+        assert_eq!(duplicate.get_field_value("similarity").and_then(|value| value.as_scalar_value::<f64>()), Some(&0.75));
+    }
+
     #[test]
     fn test_user_db_error() {
         let user_message = "User-facing message".to_string();
@@ -0,0 +1,112 @@
+/// Free-text matching
+/// Normalizes typed answers and compares them by edit distance
+
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Best candidate a submission was matched against
+#[derive(Debug, PartialEq)]
+pub struct TextMatch<'a> {
+    pub candidate: &'a str,
+    pub distance: usize,
+}
+
+/// Lowercases, strips diacritics and punctuation, collapses whitespace
+/// and drops a leading "the"
+pub fn normalize(text: &str) -> String {
+    let folded: String = text
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(|c| c.to_lowercase())
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+
+    let words: Vec<&str> = folded.split_whitespace().collect();
+
+    match words.split_first() {
+        Some((&"the", rest)) if !rest.is_empty() => rest.join(" "),
+        _ => words.join(" "),
+    }
+}
+
+/// Levenshtein distance counted in chars
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + if a_char == *b_char { 0 } else { 1 };
+            current[j + 1] = substitution
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Finds the closest candidate within `max_distance` edits of the submission
+pub fn best_match<'a>(submission: &str, candidates: &[&'a str], max_distance: usize) -> Option<TextMatch<'a>> {
+    let submission = normalize(submission);
+    if submission.is_empty() {
+        return None;
+    }
+
+    candidates
+        .iter()
+        .filter_map(|candidate| {
+            let normalized = normalize(candidate);
+            let distance = edit_distance(&submission, &normalized);
+
+            if distance <= max_distance {
+                Some(TextMatch { candidate, distance })
+            } else {
+                None
+            }
+        })
+        .min_by_key(|text_match| text_match.distance)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{best_match, edit_distance, normalize, TextMatch};
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("The Beatles"), "beatles");
+        assert_eq!(normalize("  Mötley   Crüe! "), "motley crue");
+        assert_eq!(normalize("AC/DC"), "ac dc");
+        assert_eq!(normalize("The The"), "the", "A lone \"the\" should be kept");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+
+    #[test]
+    fn test_best_match_prefers_closest_alias() {
+        let candidates = ["Sgt. Pepper's Lonely Hearts Club Band", "Sgt Pepper"];
+
+        assert_eq!(
+            best_match("sgt peper", &candidates, 2),
+            Some(TextMatch { candidate: "Sgt Pepper", distance: 1 }),
+        );
+        assert_eq!(best_match("Revolver", &candidates, 2), None);
+    }
+
+    #[test]
+    fn test_configured_tolerance() {
+        assert_eq!(best_match("U3", &["U2"], 0), None, "Zero tolerance should need an exact match");
+        assert_eq!(best_match("U3", &["U2"], 1), Some(TextMatch { candidate: "U2", distance: 1 }), "Short answers should get the configured tolerance too");
+        assert_eq!(best_match("u2", &["U2"], 0), Some(TextMatch { candidate: "U2", distance: 0 }));
+        assert_eq!(best_match("Metalika", &["Metallica"], 2), Some(TextMatch { candidate: "Metallica", distance: 2 }));
+        assert_eq!(best_match("Metalika", &["Metallica"], 1), None);
+    }
+
+}
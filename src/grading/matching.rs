@@ -0,0 +1,63 @@
+/// Matching scoring
+/// Credit for pairing each item with its match
+
+use crate::models::question::ScoringRule;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// Credit between 0 and 1 for the `submitted` pairs. Each left item counts
+/// once, so repeating it can't earn extra credit.
+pub fn score<T: Eq + Hash>(expected: &[(T, T)], submitted: &[(T, T)], rule: ScoringRule) -> f64 {
+    if expected.is_empty() {
+        return 0.0;
+    }
+
+    let answers: HashMap<&T, &T> = expected.iter()
+        .map(|(left, right)| (left, right))
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut correct = 0;
+    for (left, right) in submitted {
+        if seen.insert(left) && answers.get(left) == Some(&right) {
+            correct += 1;
+        }
+    }
+
+    match rule {
+        ScoringRule::AllOrNothing => if correct == expected.len() { 1.0 } else { 0.0 },
+        ScoringRule::PerItem | ScoringRule::Pairwise => correct as f64 / expected.len() as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::score;
+    use crate::models::question::ScoringRule;
+
+    #[test]
+    fn test_per_item() {
+        let expected = [(1, 10), (2, 20), (3, 30), (4, 40)];
+
+        assert_eq!(score(&expected, &[(1, 10), (2, 20), (3, 40), (4, 30)], ScoringRule::PerItem), 0.5);
+        assert_eq!(score(&expected, &[(4, 40), (3, 30), (2, 20), (1, 10)], ScoringRule::PerItem), 1.0, "Pair order shouldn't matter");
+    }
+
+    #[test]
+    fn test_all_or_nothing() {
+        let expected = [(1, 10), (2, 20)];
+
+        assert_eq!(score(&expected, &[(1, 10), (2, 20)], ScoringRule::AllOrNothing), 1.0);
+        assert_eq!(score(&expected, &[(1, 10)], ScoringRule::AllOrNothing), 0.0);
+    }
+
+    #[test]
+    fn test_repeated_items_count_once() {
+        let expected = [(1, 10), (2, 20)];
+
+        assert_eq!(score(&expected, &[(1, 10), (1, 10), (1, 10)], ScoringRule::PerItem), 0.5);
+        assert_eq!(score(&expected, &[(1, 20), (1, 10)], ScoringRule::PerItem), 0.0, "Only the first pairing of an item counts");
+    }
+
+}
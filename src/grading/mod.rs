@@ -0,0 +1,143 @@
+pub mod free_text;
+pub mod matching;
+pub mod numeric;
+pub mod ordering;
+pub mod scoring;
+
+use crate::errors::{AppError, AppErrorType};
+use uuid::Uuid;
+use crate::models::{
+    answer::Answer,
+    grade::{AnswerSubmission, Grade},
+    question::{Question, QuestionKind, NumericCurve, ScoringRule},
+    question_choice::QuestionChoice,
+};
+
+/// Everything needed to grade submissions for one question
+pub struct AnswerKey {
+    pub correct_answer: Option<Answer>,
+    pub aliases: Vec<String>,
+    /// Choices of ordering and matching questions, by position
+    pub choices: Vec<QuestionChoice>,
+}
+
+fn missing_field(field: &str) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(format!("{} is required for this question.", field)),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+fn corrupt_question(question: &Question, cause: String) -> AppError {
+    AppError {
+        cause: Some(format!("question {}: {}", question.id, cause)),
+        message: None,
+        error_type: AppErrorType::DbError
+    }
+}
+
+fn all_or_nothing(question: &Question, matched: Option<String>) -> Grade {
+    Grade {
+        question_id: question.id,
+        accepted: matched.is_some(),
+        score: if matched.is_some() { 1.0 } else { 0.0 },
+        matched,
+        distance: None,
+    }
+}
+
+pub fn grade(question: &Question, key: &AnswerKey, submission: &AnswerSubmission) -> Result<Grade, AppError> {
+    let kind = question.kind.parse::<QuestionKind>()
+        .map_err(|err| corrupt_question(question, err))?;
+
+    match kind {
+        QuestionKind::Choice => {
+            let correct_answer = key.correct_answer.as_ref()
+                .ok_or_else(|| corrupt_question(question, "missing correct answer".to_string()))?;
+            let answer_id = submission.answer_id.ok_or_else(|| missing_field("answerId"))?;
+
+            let matched = if answer_id == correct_answer.id {
+                Some(correct_answer.content.clone())
+            } else {
+                None
+            };
+
+            Ok(all_or_nothing(question, matched))
+        },
+        QuestionKind::FreeText => {
+            let correct_answer = key.correct_answer.as_ref()
+                .ok_or_else(|| corrupt_question(question, "missing correct answer".to_string()))?;
+            let text = submission.text.as_ref().ok_or_else(|| missing_field("text"))?;
+
+            let mut candidates = vec![correct_answer.content.as_str()];
+            candidates.extend(key.aliases.iter().map(|alias| alias.as_str()));
+
+            let text_match = free_text::best_match(text, &candidates, question.max_edit_distance.max(0) as usize);
+
+            Ok(Grade {
+                distance: text_match.as_ref().map(|text_match| text_match.distance as i32),
+                ..all_or_nothing(question, text_match.map(|text_match| text_match.candidate.to_string()))
+            })
+        },
+        QuestionKind::Numeric => {
+            let expected = question.numeric_value
+                .ok_or_else(|| corrupt_question(question, "missing numeric value".to_string()))?;
+            let curve = question.numeric_curve.parse::<NumericCurve>()
+                .map_err(|err| corrupt_question(question, err))?;
+            let submitted = submission.number.ok_or_else(|| missing_field("number"))?;
+
+            let score = numeric::score(expected, submitted, question.numeric_tolerance, curve);
+
+            Ok(Grade {
+                question_id: question.id,
+                accepted: score >= 1.0,
+                score,
+                matched: if score > 0.0 { Some(expected.to_string()) } else { None },
+                distance: None,
+            })
+        },
+        QuestionKind::Ordering => {
+            let rule = question.scoring_rule.parse::<ScoringRule>()
+                .map_err(|err| corrupt_question(question, err))?;
+            let submitted = submission.ordering.as_ref().ok_or_else(|| missing_field("ordering"))?;
+            let expected: Vec<Uuid> = key.choices.iter().map(|choice| choice.answer_id).collect();
+
+            if !ordering::is_permutation(&expected, submitted) {
+                return Err(AppError {
+                    cause: None,
+                    message: Some("ordering must contain each of the question's answers once.".to_string()),
+                    error_type: AppErrorType::InvalidField
+                });
+            }
+
+            Ok(partial_credit(question, ordering::score(&expected, submitted, rule)))
+        },
+        QuestionKind::Matching => {
+            let rule = question.scoring_rule.parse::<ScoringRule>()
+                .map_err(|err| corrupt_question(question, err))?;
+            let submitted: Vec<(Uuid, Uuid)> = submission.pairs.as_ref()
+                .ok_or_else(|| missing_field("pairs"))?
+                .iter()
+                .map(|pair| (pair.answer_id, pair.match_answer_id))
+                .collect();
+            let expected = key.choices.iter()
+                .map(|choice| choice.match_answer_id
+                    .map(|match_answer_id| (choice.answer_id, match_answer_id))
+                    .ok_or_else(|| corrupt_question(question, format!("choice {} has no match", choice.id))))
+                .collect::<Result<Vec<(Uuid, Uuid)>, AppError>>()?;
+
+            Ok(partial_credit(question, matching::score(&expected, &submitted, rule)))
+        },
+    }
+}
+
+fn partial_credit(question: &Question, score: f64) -> Grade {
+    Grade {
+        question_id: question.id,
+        accepted: score >= 1.0,
+        score,
+        matched: None,
+        distance: None,
+    }
+}
@@ -0,0 +1,65 @@
+/// Numeric scoring
+/// Partial credit for near misses on number and year questions
+
+use crate::models::question::NumericCurve;
+
+/// Credit between 0 and 1 for `submitted` given the `expected` value.
+/// `Linear` falls from full credit at the exact value to nothing at
+/// `tolerance`; `Step` gives full credit anywhere within `tolerance`.
+pub fn score(expected: f64, submitted: f64, tolerance: f64, curve: NumericCurve) -> f64 {
+    let miss = (submitted - expected).abs();
+
+    if miss == 0.0 {
+        return 1.0;
+    }
+
+    if !miss.is_finite() || miss > tolerance {
+        return 0.0;
+    }
+
+    match curve {
+        NumericCurve::Step => 1.0,
+        NumericCurve::Linear => 1.0 - miss / tolerance,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::score;
+    use crate::models::question::NumericCurve;
+
+    #[test]
+    fn test_exact_value_gets_full_credit() {
+        assert_eq!(score(1999.0, 1999.0, 0.0, NumericCurve::Linear), 1.0);
+        assert_eq!(score(1999.0, 1999.0, 0.0, NumericCurve::Step), 1.0);
+    }
+
+    #[test]
+    fn test_linear_partial_credit() {
+        assert_eq!(score(1999.0, 1998.0, 4.0, NumericCurve::Linear), 0.75);
+        assert_eq!(score(1999.0, 2002.0, 4.0, NumericCurve::Linear), 0.25);
+        assert_eq!(score(1999.0, 2001.0, 4.0, NumericCurve::Linear), 0.5);
+    }
+
+    #[test]
+    fn test_linear_boundaries() {
+        assert_eq!(score(1999.0, 2002.0, 3.0, NumericCurve::Linear), 0.0, "A miss of exactly the tolerance gets nothing");
+        assert_eq!(score(1999.0, 2003.0, 3.0, NumericCurve::Linear), 0.0, "Misses past the tolerance get nothing");
+        assert_eq!(score(10.0, 10.5, 1.0, NumericCurve::Linear), 0.5);
+        assert_eq!(score(1999.0, 1999.0, 3.0, NumericCurve::Linear), 1.0);
+    }
+
+    #[test]
+    fn test_step_within_tolerance() {
+        assert_eq!(score(1999.0, 2001.0, 2.0, NumericCurve::Step), 1.0);
+        assert_eq!(score(1999.0, 2002.0, 2.0, NumericCurve::Step), 0.0);
+    }
+
+    #[test]
+    fn test_zero_tolerance_requires_exact_value() {
+        assert_eq!(score(12.0, 12.5, 0.0, NumericCurve::Linear), 0.0);
+        assert_eq!(score(12.0, std::f64::NAN, 5.0, NumericCurve::Step), 0.0);
+    }
+
+}
@@ -0,0 +1,99 @@
+/// Ordering scoring
+/// Credit for putting items in order, with optional partial credit
+
+use crate::models::question::ScoringRule;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// True when `submitted` contains exactly the items of `expected`, each once
+pub fn is_permutation<T: Eq + Hash>(expected: &[T], submitted: &[T]) -> bool {
+    let mut counts: HashMap<&T, i32> = HashMap::new();
+    for item in expected {
+        *counts.entry(item).or_insert(0) += 1;
+    }
+    for item in submitted {
+        *counts.entry(item).or_insert(0) -= 1;
+    }
+
+    expected.len() == submitted.len() && counts.values().all(|count| *count == 0)
+}
+
+/// Credit between 0 and 1 for a permutation of `expected`
+pub fn score<T: Eq + Hash>(expected: &[T], submitted: &[T], rule: ScoringRule) -> f64 {
+    if expected.is_empty() || !is_permutation(expected, submitted) {
+        return 0.0;
+    }
+
+    match rule {
+        ScoringRule::AllOrNothing => if expected == submitted { 1.0 } else { 0.0 },
+        ScoringRule::PerItem => {
+            let in_place = expected.iter()
+                .zip(submitted.iter())
+                .filter(|(expected, submitted)| expected == submitted)
+                .count();
+
+            in_place as f64 / expected.len() as f64
+        },
+        ScoringRule::Pairwise => {
+            if expected.len() < 2 {
+                return 1.0;
+            }
+
+            let positions: HashMap<&T, usize> = submitted.iter()
+                .enumerate()
+                .map(|(position, item)| (item, position))
+                .collect();
+
+            let mut pairs = 0;
+            let mut in_order = 0;
+            for (i, earlier) in expected.iter().enumerate() {
+                for later in &expected[i + 1..] {
+                    pairs += 1;
+                    if positions[earlier] < positions[later] {
+                        in_order += 1;
+                    }
+                }
+            }
+
+            in_order as f64 / pairs as f64
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{is_permutation, score};
+    use crate::models::question::ScoringRule;
+
+    #[test]
+    fn test_is_permutation() {
+        assert!(is_permutation(&[1, 2, 3], &[3, 1, 2]));
+        assert!(!is_permutation(&[1, 2, 3], &[1, 2]));
+        assert!(!is_permutation(&[1, 2, 3], &[1, 1, 3]));
+    }
+
+    #[test]
+    fn test_all_or_nothing() {
+        assert_eq!(score(&[1, 2, 3], &[1, 2, 3], ScoringRule::AllOrNothing), 1.0);
+        assert_eq!(score(&[1, 2, 3], &[1, 3, 2], ScoringRule::AllOrNothing), 0.0);
+    }
+
+    #[test]
+    fn test_per_item() {
+        assert_eq!(score(&[1, 2, 3, 4], &[1, 2, 4, 3], ScoringRule::PerItem), 0.5);
+        assert_eq!(score(&[1, 2, 3, 4], &[2, 3, 4, 1], ScoringRule::PerItem), 0.0, "A shifted run earns nothing per item");
+    }
+
+    #[test]
+    fn test_pairwise() {
+        assert_eq!(score(&[1, 2, 3, 4], &[2, 3, 4, 1], ScoringRule::Pairwise), 0.5, "A shifted run keeps most pairs in order");
+        assert_eq!(score(&[1, 2, 3], &[3, 2, 1], ScoringRule::Pairwise), 0.0);
+    }
+
+    #[test]
+    fn test_invalid_submission_scores_zero() {
+        assert_eq!(score(&[1, 2, 3], &[1, 2, 5], ScoringRule::PerItem), 0.0);
+    }
+
+}
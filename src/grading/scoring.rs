@@ -0,0 +1,262 @@
+/// Quiz scoring
+/// Turns the graded responses of an attempt into points, so a score can always
+/// be recomputed from what was stored
+
+use crate::models::scoring_rules::ScoringRules;
+use juniper::GraphQLObject;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rules {
+    pub points_per_question: i32,
+    /// Extra points for an instant correct answer, decaying linearly to nothing
+    /// at `speed_bonus_seconds`
+    pub speed_bonus_points: i32,
+    pub speed_bonus_seconds: i32,
+    /// Points taken off for a wrong answer, unanswered questions cost nothing
+    pub wrong_answer_penalty: i32,
+    /// Multiplier added for every correct answer in a row after the first
+    pub streak_bonus: f64,
+    pub max_streak_multiplier: f64,
+    /// Without partial credit, anything short of a fully correct answer is wrong
+    pub partial_credit: bool,
+    /// Fraction of a question's points given up by using a lifeline on it
+    pub fifty_fifty_cost: f64,
+    pub hint_cost: f64,
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            points_per_question: 10,
+            speed_bonus_points: 0,
+            speed_bonus_seconds: 0,
+            wrong_answer_penalty: 0,
+            streak_bonus: 0.0,
+            max_streak_multiplier: 1.0,
+            partial_credit: true,
+            fifty_fifty_cost: 0.5,
+            hint_cost: 0.25,
+        }
+    }
+}
+
+impl From<&ScoringRules> for Rules {
+    fn from(rules: &ScoringRules) -> Rules {
+        Rules {
+            points_per_question: rules.points_per_question,
+            speed_bonus_points: rules.speed_bonus_points,
+            speed_bonus_seconds: rules.speed_bonus_seconds,
+            wrong_answer_penalty: rules.wrong_answer_penalty,
+            streak_bonus: rules.streak_bonus,
+            max_streak_multiplier: rules.max_streak_multiplier,
+            partial_credit: rules.partial_credit,
+            fifty_fifty_cost: rules.fifty_fifty_cost,
+            hint_cost: rules.hint_cost,
+        }
+    }
+}
+
+/// A served question as it was answered
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Response {
+    pub question_id: Uuid,
+    /// Credit from grading, from 0 to 1, or None if it wasn't answered
+    pub credit: Option<f64>,
+    /// Time taken to answer
+    pub seconds: Option<f64>,
+    /// Lifelines used on the question
+    pub fifty_fifty: bool,
+    pub hint: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, GraphQLObject)]
+pub struct QuestionScore {
+    pub question_id: Uuid,
+    /// Points for the credit earned, before bonuses
+    pub base_points: f64,
+    pub speed_bonus: f64,
+    pub streak_multiplier: f64,
+    /// What's left of the points after the lifelines used on the question
+    pub lifeline_multiplier: f64,
+    /// Negative for penalized wrong answers
+    pub points: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, GraphQLObject)]
+pub struct ScoreBreakdown {
+    pub total: f64,
+    pub correct_count: i32,
+    pub best_streak: i32,
+    pub questions: Vec<QuestionScore>,
+}
+
+/// Scores responses in play order
+pub fn score(rules: &Rules, responses: &[Response]) -> ScoreBreakdown {
+    let mut streak = 0;
+    let mut best_streak = 0;
+    let mut correct_count = 0;
+    let mut questions = Vec::with_capacity(responses.len());
+
+    for response in responses {
+        let credit = match response.credit {
+            Some(credit) if rules.partial_credit => credit.max(0.0).min(1.0),
+            Some(credit) if credit >= 1.0 => 1.0,
+            Some(_) => 0.0,
+            None => {
+                streak = 0;
+                questions.push(QuestionScore {
+                    question_id: response.question_id,
+                    base_points: 0.0,
+                    speed_bonus: 0.0,
+                    streak_multiplier: 1.0,
+                    lifeline_multiplier: 1.0,
+                    points: 0.0,
+                });
+                continue;
+            },
+        };
+
+        if credit >= 1.0 {
+            streak += 1;
+            correct_count += 1;
+            best_streak = best_streak.max(streak);
+        } else {
+            streak = 0;
+        }
+
+        let base_points = rules.points_per_question as f64 * credit;
+        let speed_bonus = match response.seconds {
+            Some(seconds) if rules.speed_bonus_seconds > 0 && credit > 0.0 => {
+                let remaining = 1.0 - seconds.max(0.0) / rules.speed_bonus_seconds as f64;
+                rules.speed_bonus_points as f64 * remaining.max(0.0) * credit
+            },
+            _ => 0.0,
+        };
+        let streak_multiplier = if streak > 1 {
+            (1.0 + rules.streak_bonus * (streak - 1) as f64).min(rules.max_streak_multiplier.max(1.0))
+        } else {
+            1.0
+        };
+        let lifeline_multiplier = [
+            (response.fifty_fifty, rules.fifty_fifty_cost),
+            (response.hint, rules.hint_cost),
+        ].iter()
+            .filter(|(used, _)| *used)
+            .map(|(_, cost)| 1.0 - cost.max(0.0).min(1.0))
+            .product::<f64>();
+        let points = if credit > 0.0 {
+            (base_points + speed_bonus) * streak_multiplier * lifeline_multiplier
+        } else {
+            -(rules.wrong_answer_penalty as f64)
+        };
+
+        questions.push(QuestionScore {
+            question_id: response.question_id,
+            base_points,
+            speed_bonus,
+            streak_multiplier,
+            lifeline_multiplier,
+            points,
+        });
+    }
+
+    ScoreBreakdown {
+        total: questions.iter().map(|question| question.points).sum(),
+        correct_count,
+        best_streak,
+        questions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{score, Response, Rules};
+    use uuid::Uuid;
+
+    fn response(credit: Option<f64>, seconds: Option<f64>) -> Response {
+        Response { question_id: Uuid::new_v4(), credit, seconds, fifty_fifty: false, hint: false }
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let breakdown = score(&Rules::default(), &[
+            response(Some(1.0), Some(3.0)),
+            response(Some(0.5), None),
+            response(Some(0.0), Some(1.0)),
+            response(None, None),
+        ]);
+
+        assert_eq!(breakdown.total, 15.0);
+        assert_eq!(breakdown.correct_count, 1);
+        assert_eq!(breakdown.best_streak, 1);
+        assert_eq!(breakdown.questions[1].points, 5.0, "Partial credit should earn partial points");
+    }
+
+    #[test]
+    fn test_speed_bonus_decays() {
+        let rules = Rules { speed_bonus_points: 10, speed_bonus_seconds: 20, ..Rules::default() };
+
+        let breakdown = score(&rules, &[
+            response(Some(1.0), Some(0.0)),
+            response(Some(1.0), Some(5.0)),
+            response(Some(1.0), Some(30.0)),
+            response(Some(1.0), None),
+        ]);
+
+        let bonuses: Vec<f64> = breakdown.questions.iter().map(|question| question.speed_bonus).collect();
+        assert_eq!(bonuses, vec![10.0, 7.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_negative_marking() {
+        let rules = Rules { wrong_answer_penalty: 5, partial_credit: false, ..Rules::default() };
+
+        let breakdown = score(&rules, &[
+            response(Some(0.0), None),
+            response(Some(0.9), None),
+            response(None, None),
+        ]);
+
+        let points: Vec<f64> = breakdown.questions.iter().map(|question| question.points).collect();
+        assert_eq!(points, vec![-5.0, -5.0, 0.0], "Unanswered questions shouldn't be penalized");
+        assert_eq!(breakdown.total, -10.0);
+    }
+
+    #[test]
+    fn test_streak_multiplier() {
+        let rules = Rules { streak_bonus: 0.5, max_streak_multiplier: 2.0, ..Rules::default() };
+
+        let breakdown = score(&rules, &[
+            response(Some(1.0), None),
+            response(Some(1.0), None),
+            response(Some(1.0), None),
+            response(Some(1.0), None),
+            response(Some(0.5), None),
+            response(Some(1.0), None),
+        ]);
+
+        let multipliers: Vec<f64> = breakdown.questions.iter().map(|question| question.streak_multiplier).collect();
+        assert_eq!(multipliers, vec![1.0, 1.5, 2.0, 2.0, 1.0, 1.0]);
+        assert_eq!(breakdown.best_streak, 4);
+        assert_eq!(breakdown.total, 10.0 + 15.0 + 20.0 + 20.0 + 5.0 + 10.0);
+    }
+
+    #[test]
+    fn test_lifeline_costs() {
+        let rules = Rules { wrong_answer_penalty: 2, ..Rules::default() };
+
+        let breakdown = score(&rules, &[
+            Response { fifty_fifty: true, ..response(Some(1.0), None) },
+            Response { hint: true, ..response(Some(1.0), None) },
+            Response { fifty_fifty: true, hint: true, ..response(Some(1.0), None) },
+            Response { fifty_fifty: true, ..response(Some(0.0), None) },
+        ]);
+
+        let points: Vec<f64> = breakdown.questions.iter().map(|question| question.points).collect();
+        assert_eq!(points, vec![5.0, 7.5, 3.75, -2.0], "Lifelines shouldn't change the penalty for wrong answers");
+        assert_eq!(breakdown.correct_count, 3);
+    }
+
+}
@@ -1,38 +1,329 @@
-use deadpool_postgres::Pool;
 use juniper::RootNode;
-use crate::errors::AppError;
+use crate::errors::{AppError, AppErrorType};
 use crate::repositories::{
-    user::UserRepository, 
-    answer::AnswerRepository,
-    question::{QuestionRepository, QuestionLoader},
+    db::{self, Db},
+    stores::{
+        Stores, UserStore, AnswerStore, QuestionStore, BandStore, SessionStore, ReviewStateStore,
+        MediaStore, TagStore, SearchStore, QuizStore, AttemptStore, ScoringRulesStore,
+        NotificationStore, RevisionStore, ReportStore, AuditStore,
+    },
+    question::QuestionLoader,
+    search::DEFAULT_PAGE_SIZE,
 };
 use crate::config::HashingService;
+use crate::avatar::{self, AvatarService, AVATAR_SIZE, THUMBNAIL_SIZE};
+use crate::storage::Storage;
+use crate::grading::scoring::ScoreBreakdown;
+use crate::bulk::{QuestionImporter, ImportReport, format::BulkFormat};
+use crate::play::AttemptService;
+use crate::moderation::ModerationService;
+use crate::revisions::RevisionService;
+use crate::reports::ReportService;
 use crate::models::{
     user::{User, CreateUser},
-    answer::{Answer, CreateAnswer},
-    question::{Question, CreateQuestion},
+    answer::{Answer, CreateAnswer, AnswerAlias, CreateAnswerAlias},
+    question::{
+        Question, CreateQuestion, CreateOrderingQuestion, CreateMatchingQuestion,
+        QuestionKind, QuestionStatus, NumericCurve, ScoringRule, UpdateQuestion,
+    },
+    question_choice::{self, QuestionChoice},
+    grade::Grade,
+    session::{Session, Login},
+    review_state::ReviewState,
+    media::{Media, MediaKind},
+    search::{SearchConnection, SearchType},
+    tag::{Tag, TagKind, TagStats},
+    band::{Band, BandRole, CreateBand},
+    quiz::{Quiz, QuizStatus, CreateQuiz, UpdateQuiz},
+    attempt::{Attempt, AttemptSubmission, ReviewedQuestion, ServedChoice, ServedQuestion, StartRandomQuiz},
+    scoring_rules::ScoringRules,
+    lifeline::{LifelineBudget, LifelineKind},
+    notification::{Notification, NotificationKind},
+    report::{Report, ReportReason, ReportStatus, ReportTargetType},
+    audit::{self, AuditAction, AuditEntry, AuditFilter, AuditTargetType, NewAuditEntry},
+    revision::{self, EditSuggestion, FieldChange, QuestionSnapshot, Revision, SuggestionStatus},
+    duplicate::{self, DuplicateCluster, SimilarQuestion, DUPLICATE_THRESHOLD},
 };
-use std::sync::Arc;
+use std::{future::Future, sync::Arc};
 use uuid::Uuid;
 use chrono::NaiveDateTime;
 
+fn not_found() -> AppError {
+    AppError {
+        cause: None,
+        message: None,
+        error_type: AppErrorType::NotFoundError
+    }
+}
+
 #[derive(Clone)]
 pub struct Context {
-    pub pool: Arc<Pool>,
+    /// The pool, or the transaction the request's repositories share
+    pub db: Db,
+    /// The repositories, in Postgres or in memory for tests
+    pub stores: Arc<dyn Stores>,
     pub hashing: Arc<HashingService>,
+    pub storage: Arc<dyn Storage>,
+    pub avatars: Arc<AvatarService>,
+    pub user_id: Option<Uuid>,
+    /// Ties audit log entries to the request that made them
+    pub request_id: Uuid,
+    pub ip: Option<String>,
 }
 
 impl Context {
-    pub fn user_repository(&self) -> UserRepository {
-        UserRepository::new(self.pool.clone())
+    /// Id of the logged in user, or an error for anonymous requests
+    pub fn current_user_id(&self) -> Result<Uuid, AppError> {
+        self.user_id.ok_or(AppError {
+            cause: None,
+            message: None,
+            error_type: AppErrorType::UnauthorizedError
+        })
+    }
+
+    /// Id of the logged in user if they are an admin
+    pub async fn current_admin_id(&self) -> Result<Uuid, AppError> {
+        let user = self.user_repository().get(self.current_user_id()?).await?;
+
+        if user.is_admin {
+            Ok(user.id)
+        } else {
+            Err(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::ForbiddenError
+            })
+        }
+    }
+
+    /// Runs `work` with a copy of the context whose repositories and services share one
+    /// transaction, committed when it returns Ok and rolled back on an AppError
+    pub async fn transaction<T, F, Fut>(&self, work: F) -> Result<T, AppError>
+    where
+        F: FnOnce(Context) -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        if !self.stores.uses_db() {
+            return work(self.clone()).await;
+        }
+
+        let context = self.clone();
+        db::transaction(&self.db, move |db| work(Context { db, ..context })).await
+    }
+
+    pub fn user_repository(&self) -> Arc<dyn UserStore> {
+        self.stores.users(&self.db)
+    }
+
+    pub fn answer_repository(&self) -> Arc<dyn AnswerStore> {
+        self.stores.answers(&self.db)
+    }
+
+    pub fn question_repository(&self) -> Arc<dyn QuestionStore> {
+        self.stores.questions(&self.db)
+    }
+
+    pub fn session_repository(&self) -> Arc<dyn SessionStore> {
+        self.stores.sessions(&self.db)
+    }
+
+    pub fn review_state_repository(&self) -> Arc<dyn ReviewStateStore> {
+        self.stores.review_states(&self.db)
+    }
+
+    pub fn media_repository(&self) -> Arc<dyn MediaStore> {
+        self.stores.media(&self.db)
+    }
+
+    pub fn tag_repository(&self) -> Arc<dyn TagStore> {
+        self.stores.tags(&self.db)
+    }
+
+    pub fn search_repository(&self) -> Arc<dyn SearchStore> {
+        self.stores.search(&self.db)
+    }
+
+    pub fn band_repository(&self) -> Arc<dyn BandStore> {
+        self.stores.bands(&self.db)
+    }
+
+    pub fn quiz_repository(&self) -> Arc<dyn QuizStore> {
+        self.stores.quizzes(&self.db)
+    }
+
+    pub fn attempt_repository(&self) -> Arc<dyn AttemptStore> {
+        self.stores.attempts(&self.db)
+    }
+
+    pub fn scoring_rules_repository(&self) -> Arc<dyn ScoringRulesStore> {
+        self.stores.scoring_rules(&self.db)
+    }
+
+    pub fn notification_repository(&self) -> Arc<dyn NotificationStore> {
+        self.stores.notifications(&self.db)
+    }
+
+    pub fn revision_repository(&self) -> Arc<dyn RevisionStore> {
+        self.stores.revisions(&self.db)
+    }
+
+    pub fn report_repository(&self) -> Arc<dyn ReportStore> {
+        self.stores.reports(&self.db)
+    }
+
+    pub fn audit_repository(&self) -> Arc<dyn AuditStore> {
+        self.stores.audit_log(&self.db)
+    }
+
+    /// Records a change made by the logged in user. `before` and `after` come from `audit::snapshot`.
+    pub async fn audit(&self, action: AuditAction, target_type: AuditTargetType, target_id: Uuid, before: Option<String>, after: Option<String>) -> Result<(), AppError> {
+        self.audit_repository().create(NewAuditEntry {
+            actor_id: self.user_id,
+            action,
+            target_type,
+            target_id,
+            before,
+            after,
+            request_id: self.request_id,
+            ip: self.ip.clone(),
+        }).await?;
+        Ok(())
+    }
+
+    pub fn moderation_service(&self) -> ModerationService {
+        ModerationService {
+            questions: self.question_repository(),
+            notifications: self.notification_repository(),
+        }
+    }
+
+    pub fn revision_service(&self) -> RevisionService {
+        RevisionService {
+            answers: self.answer_repository(),
+            questions: self.question_repository(),
+            revisions: self.revision_repository(),
+            notifications: self.notification_repository(),
+        }
+    }
+
+    pub fn report_service(&self) -> ReportService {
+        ReportService {
+            reports: self.report_repository(),
+            questions: self.question_repository(),
+            bands: self.band_repository(),
+            users: self.user_repository(),
+        }
+    }
+
+    /// Id of the logged in user if they can resolve reports on the target:
+    /// the band's owner and moderators for questions and bands, admins for everything
+    pub async fn current_report_moderator_id(&self, target_type: ReportTargetType, target_id: Uuid) -> Result<Uuid, AppError> {
+        match self.report_service().band_of(target_type, target_id).await? {
+            Some(band_id) => self.current_moderator_id(band_id).await,
+            None => self.current_admin_id().await,
+        }
+    }
+
+    pub fn attempt_service(&self) -> AttemptService {
+        AttemptService {
+            answers: self.answer_repository(),
+            attempts: self.attempt_repository(),
+            quizzes: self.quiz_repository(),
+            questions: self.question_repository(),
+            scoring_rules: self.scoring_rules_repository(),
+        }
+    }
+
+    /// Whether the logged in user can edit the band's content
+    pub async fn is_band_author(&self, band_id: Uuid) -> Result<bool, AppError> {
+        match self.user_id {
+            Some(user_id) => Ok(
+                self.band_repository().is_author(band_id, user_id).await?
+                    || self.user_repository().get(user_id).await?.is_admin
+            ),
+            None => Ok(false),
+        }
+    }
+
+    /// Id of the logged in user if they are one of the band's authors, or an admin
+    pub async fn current_author_id(&self, band_id: Uuid) -> Result<Uuid, AppError> {
+        let user_id = self.current_user_id()?;
+
+        if self.is_band_author(band_id).await? {
+            Ok(user_id)
+        } else {
+            Err(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::ForbiddenError
+            })
+        }
+    }
+
+    /// Id of the logged in user if they are the band's owner or one of its moderators, or an admin
+    pub async fn current_moderator_id(&self, band_id: Uuid) -> Result<Uuid, AppError> {
+        let user_id = self.current_user_id()?;
+
+        if self.band_repository().is_moderator(band_id, user_id).await? || self.user_repository().get(user_id).await?.is_admin {
+            Ok(user_id)
+        } else {
+            Err(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::ForbiddenError
+            })
+        }
+    }
+
+    /// The question if it's approved and not hidden after being reported. The band's
+    /// authors see all of its questions, submitters their own until they're approved.
+    pub async fn visible_question(&self, question: Question) -> Result<Question, AppError> {
+        let public = question.question_status() == QuestionStatus::Approved && question.hidden_at.is_none();
+        let own_submission = question.submitted_by.is_some() && question.submitted_by == self.user_id && question.hidden_at.is_none();
+
+        if public || own_submission || self.is_band_author(question.band_id).await? {
+            Ok(question)
+        } else {
+            Err(not_found())
+        }
     }
 
-    pub fn answer_repository(&self) -> AnswerRepository {
-        AnswerRepository::new(self.pool.clone())
+    /// The band, unless it's hidden and the logged in user isn't one of its authors
+    pub async fn visible_band(&self, band: Band) -> Result<Band, AppError> {
+        if band.hidden_at.is_none() || self.is_band_author(band.id).await? {
+            Ok(band)
+        } else {
+            Err(not_found())
+        }
     }
 
-    pub fn question_repository(&self) -> QuestionRepository {
-        QuestionRepository::new(self.pool.clone())
+    /// The user, unless their profile is hidden and the logged in user is neither them nor an admin
+    pub async fn visible_user(&self, user: User) -> Result<User, AppError> {
+        if user.hidden_at.is_none() || self.user_id == Some(user.id) || self.current_admin_id().await.is_ok() {
+            Ok(user)
+        } else {
+            Err(not_found())
+        }
+    }
+
+    /// The logged in user when their questions for the band need moderation,
+    /// None for the band's authors
+    pub async fn question_submitter(&self, band_id: Uuid) -> Result<Option<Uuid>, AppError> {
+        let user_id = self.current_user_id()?;
+
+        if self.is_band_author(band_id).await? {
+            Ok(None)
+        } else {
+            Ok(Some(user_id))
+        }
+    }
+
+    pub fn question_importer(&self) -> QuestionImporter {
+        QuestionImporter {
+            questions: self.question_repository(),
+            answers: self.answer_repository(),
+            tags: self.tag_repository(),
+        }
     }
 }
 
@@ -54,23 +345,162 @@ impl Query {
     }
 
     pub async fn user(id: Uuid, context: &Context) -> Result<User, AppError> {
-        context.user_repository().get(id).await
+        let user = context.user_repository().get(id).await?;
+        context.visible_user(user).await
     }
 
-    pub async fn answers(context: &Context) -> Result<Vec<Answer>, AppError> {
-        context.answer_repository().all().await
+    pub async fn answers(band_id: Option<Uuid>, context: &Context) -> Result<Vec<Answer>, AppError> {
+        match band_id {
+            Some(band_id) => context.answer_repository().get_for_band(band_id).await,
+            None => context.answer_repository().all().await,
+        }
     }
 
     pub async fn answer(id: Uuid, context: &Context) -> Result<Answer, AppError> {
         context.answer_repository().get(id).await
     }
 
-    pub async fn questions(context: &Context) -> Result<Vec<Question>, AppError> {
-        context.question_repository().all().await
+    /// Questions of a band, or of every band, carrying all of `tags`
+    pub async fn questions(band_id: Option<Uuid>, tags: Option<Vec<String>>, context: &Context) -> Result<Vec<Question>, AppError> {
+        match (band_id, tags) {
+            (None, None) => context.question_repository().all().await,
+            (band_id, tags) => context.question_repository().find(band_id, &tags.unwrap_or_default()).await,
+        }
+    }
+
+    pub async fn tags(band_id: Option<Uuid>, context: &Context) -> Result<Vec<Tag>, AppError> {
+        context.tag_repository().all(band_id).await
+    }
+
+    /// Question counts per tag, with the logged in user's review progress
+    pub async fn tag_stats(band_id: Option<Uuid>, context: &Context) -> Result<Vec<TagStats>, AppError> {
+        context.tag_repository().stats(band_id, context.user_id).await
     }
 
     pub async fn question(id: Uuid, context: &Context) -> Result<Question, AppError> {
-        context.question_repository().get(id).await
+        let question = context.question_repository().get(id).await?;
+        context.visible_question(question).await
+    }
+
+    /// Searches bands, questions and users, all of them unless `types` is given
+    pub async fn search(query: String, types: Option<Vec<SearchType>>, first: Option<i32>, after: Option<String>, context: &Context) -> Result<SearchConnection, AppError> {
+        context.search_repository()
+            .search(&query, types.unwrap_or_else(SearchType::all), first.unwrap_or(DEFAULT_PAGE_SIZE), after)
+            .await
+    }
+
+    /// The band's questions that look like `content`, to warn its authors before creating a duplicate
+    pub async fn similar_questions(band_id: Uuid, content: String, context: &Context) -> Result<Vec<SimilarQuestion>, AppError> {
+        context.current_author_id(band_id).await?;
+        context.question_repository().similar(band_id, &content, DUPLICATE_THRESHOLD, false).await
+    }
+
+    /// Groups of near-duplicate questions to clean up, admins only
+    pub async fn duplicate_clusters(band_id: Option<Uuid>, threshold: Option<f64>, context: &Context) -> Result<Vec<DuplicateCluster>, AppError> {
+        context.current_admin_id().await?;
+        let pairs = context.question_repository()
+            .duplicate_pairs(band_id, threshold.unwrap_or(DUPLICATE_THRESHOLD))
+            .await?;
+
+        Ok(duplicate::clusters(&pairs))
+    }
+
+    pub async fn bands(context: &Context) -> Result<Vec<Band>, AppError> {
+        context.band_repository().all().await
+    }
+
+    pub async fn band(id: Uuid, context: &Context) -> Result<Band, AppError> {
+        let band = context.band_repository().get(id).await?;
+        context.visible_band(band).await
+    }
+
+    /// Drafts and archived quizzes are only visible to the band's authors
+    pub async fn quiz(id: Uuid, context: &Context) -> Result<Quiz, AppError> {
+        let quiz = context.quiz_repository().get(id).await?;
+
+        if quiz.quiz_status() == QuizStatus::Published || context.is_band_author(quiz.band_id).await? {
+            Ok(quiz)
+        } else {
+            Err(not_found())
+        }
+    }
+
+    pub async fn quizzes(band_id: Uuid, status: Option<QuizStatus>, context: &Context) -> Result<Vec<Quiz>, AppError> {
+        let statuses = match status {
+            _ if !context.is_band_author(band_id).await? => vec![QuizStatus::Published],
+            Some(status) => vec![status],
+            None => vec![QuizStatus::Draft, QuizStatus::Published, QuizStatus::Archived],
+        };
+
+        context.quiz_repository().get_for_band(band_id, &statuses).await
+    }
+
+    pub async fn attempt(id: Uuid, context: &Context) -> Result<Attempt, AppError> {
+        let user_id = context.current_user_id()?;
+        context.attempt_service().owned(id, user_id).await
+    }
+
+    /// Questions fans submitted to the band, for its owner and moderators
+    pub async fn moderation_queue(band_id: Uuid, context: &Context) -> Result<Vec<Question>, AppError> {
+        context.current_moderator_id(band_id).await?;
+        context.question_repository().moderation_queue(band_id).await
+    }
+
+    /// Edits fans suggested for the band's questions, for its authors
+    pub async fn edit_suggestions(band_id: Uuid, context: &Context) -> Result<Vec<EditSuggestion>, AppError> {
+        context.current_author_id(band_id).await?;
+        context.revision_repository().pending_suggestions(band_id).await
+    }
+
+    /// Open reports on the band and its questions for its owner and moderators,
+    /// or all open reports for admins
+    pub async fn report_queue(band_id: Option<Uuid>, context: &Context) -> Result<Vec<Report>, AppError> {
+        match band_id {
+            Some(band_id) => context.current_moderator_id(band_id).await?,
+            None => context.current_admin_id().await?,
+        };
+        context.report_repository().queue(band_id).await
+    }
+
+    /// Who changed what, newest first. Admins only.
+    pub async fn audit_log(filter: Option<AuditFilter>, first: Option<i32>, context: &Context) -> Result<Vec<AuditEntry>, AppError> {
+        context.current_admin_id().await?;
+        let first = first.unwrap_or(100).max(1).min(500);
+        context.audit_repository().search(&filter.unwrap_or_default(), first as i64).await
+    }
+
+    pub async fn notifications(unread_only: Option<bool>, context: &Context) -> Result<Vec<Notification>, AppError> {
+        let user_id = context.current_user_id()?;
+        context.notification_repository().for_user(user_id, unread_only.unwrap_or(false)).await
+    }
+
+    /// What the player got right and wrong, once the attempt is finished
+    pub async fn attempt_review(attempt_id: Uuid, context: &Context) -> Result<Vec<ReviewedQuestion>, AppError> {
+        let user_id = context.current_user_id()?;
+        context.attempt_service().review(attempt_id, user_id).await
+    }
+
+    /// Attempts flagged for implausible timing, for the band's authors to review
+    pub async fn flagged_attempts(band_id: Uuid, context: &Context) -> Result<Vec<Attempt>, AppError> {
+        context.current_author_id(band_id).await?;
+        context.attempt_repository().flagged(band_id).await
+    }
+
+    pub async fn practice_queue(band_id: Uuid, limit: i32, context: &Context) -> Result<Vec<Question>, AppError> {
+        let user_id = context.current_user_id()?;
+        context.review_state_repository().practice_queue(user_id, band_id, limit).await
+    }
+
+    /// Grades another answer to a question of your finished attempt, without changing its score
+    pub async fn check_answer(attempt_id: Uuid, input: AttemptSubmission, context: &Context) -> Result<Grade, AppError> {
+        let user_id = context.current_user_id()?;
+        context.attempt_service().check(attempt_id, user_id, input).await
+    }
+
+    /// Exports the band's questions, authors only
+    pub async fn export_questions(band_id: Uuid, format: BulkFormat, context: &Context) -> Result<String, AppError> {
+        context.current_author_id(band_id).await?;
+        context.question_importer().export(band_id, format).await
     }
 
 }
@@ -91,12 +521,35 @@ impl User {
         self.email.as_str()
     }
 
+    /// Left out while the profile is hidden after being reported
     pub fn bio(&self) -> Option<&str> {
-        self.bio.as_deref()
+        self.bio.as_deref().filter(|_| self.hidden_at.is_none())
     }
 
-    pub fn image(&self) -> Option<&str> {
-        self.image.as_deref()
+    pub fn is_admin(&self) -> bool {
+        self.is_admin
+    }
+
+    /// Avatar URL, or a generated identicon when none is set or the profile is hidden
+    pub fn image(&self, context: &Context) -> String {
+        self.image.clone()
+            .filter(|_| self.hidden_at.is_none())
+            .unwrap_or_else(|| context.avatars.identicon_url(self.id, AVATAR_SIZE))
+    }
+
+    pub fn thumbnail(&self, context: &Context) -> String {
+        match self.image.as_ref().filter(|_| self.hidden_at.is_none()) {
+            Some(image) => avatar::thumbnail_url(image),
+            None => context.avatars.identicon_url(self.id, THUMBNAIL_SIZE),
+        }
+    }
+
+    pub fn hidden_at(&self) -> Option<NaiveDateTime> {
+        self.hidden_at
+    }
+
+    pub fn deleted_at(&self) -> Option<NaiveDateTime> {
+        self.deleted_at
     }
 
     pub fn created_at(&self) -> NaiveDateTime {
@@ -109,18 +562,1097 @@ impl User {
 
 }
 
-pub struct Mutation {}
+#[juniper::graphql_object(
+    Context = Context
+)]
+impl Answer {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn content(&self) -> &str {
+        self.content.as_str()
+    }
+
+    pub fn band_id(&self) -> Option<Uuid> {
+        self.band_id
+    }
+
+    pub async fn aliases(&self, context: &Context) -> Result<Vec<AnswerAlias>, AppError> {
+        context.answer_repository().aliases(self.id).await
+    }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+
+}
 
 #[juniper::graphql_object(
-    Context = Context,
+    Context = Context
 )]
-impl Mutation {
-    pub async fn create_user(input: CreateUser, context: &Context) -> Result<User, AppError> {
-        context.user_repository().create(input, context.hashing.clone()).await
+impl Question {
+    pub fn id(&self) -> Uuid {
+        self.id
     }
 
-    pub async fn create_answer(input: CreateAnswer, context: &Context) -> Result<Answer, AppError> {
-        context.answer_repository().create(input).await
+    pub fn content(&self) -> &str {
+        self.content.as_str()
+    }
+
+    pub fn kind(&self) -> QuestionKind {
+        self.kind.parse().unwrap_or(QuestionKind::Choice)
+    }
+
+    /// Only shown to the band's authors
+    pub async fn correct_answer_id(&self, context: &Context) -> Result<Option<Uuid>, AppError> {
+        if context.is_band_author(self.band_id).await? {
+            Ok(self.correct_answer_id)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn max_edit_distance(&self) -> i32 {
+        self.max_edit_distance
+    }
+
+    /// The correct value of numeric questions, only shown to the band's authors
+    pub async fn numeric_value(&self, context: &Context) -> Result<Option<f64>, AppError> {
+        if context.is_band_author(self.band_id).await? {
+            Ok(self.numeric_value)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn numeric_tolerance(&self) -> f64 {
+        self.numeric_tolerance
+    }
+
+    pub fn numeric_curve(&self) -> NumericCurve {
+        self.numeric_curve.parse().unwrap_or(NumericCurve::Linear)
+    }
+
+    pub fn scoring_rule(&self) -> ScoringRule {
+        self.scoring_rule.parse().unwrap_or(ScoringRule::AllOrNothing)
+    }
+
+    /// Anyone but the band's authors gets the choices in an order that doesn't
+    /// give the answer away, without the pairs of matching questions
+    pub async fn choices(&self, context: &Context) -> Result<Vec<QuestionChoice>, AppError> {
+        let choices = context.question_repository().choices(self.id).await?;
+
+        if context.is_band_author(self.band_id).await? {
+            Ok(choices)
+        } else {
+            Ok(question_choice::without_answers(choices))
+        }
+    }
+
+    pub async fn tags(&self, context: &Context) -> Result<Vec<Tag>, AppError> {
+        context.tag_repository().for_question(self.id).await
+    }
+
+    pub async fn media(&self, context: &Context) -> Result<Option<Media>, AppError> {
+        match self.media_id {
+            Some(media_id) => context.media_repository().get(media_id).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Start of the audio clip to play, in milliseconds
+    pub fn clip_start_ms(&self) -> Option<i32> {
+        self.clip_start_ms
+    }
+
+    pub fn clip_end_ms(&self) -> Option<i32> {
+        self.clip_end_ms
+    }
+
+    /// Only shown to the band's authors, players get it through the hint lifeline
+    pub async fn hint(&self, context: &Context) -> Result<Option<String>, AppError> {
+        if context.is_band_author(self.band_id).await? {
+            Ok(self.hint.clone())
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Only shown to the band's authors, players get it in attemptReview
+    pub async fn explanation(&self, context: &Context) -> Result<Option<String>, AppError> {
+        if context.is_band_author(self.band_id).await? {
+            Ok(self.explanation.clone())
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Only shown to the band's authors, players get it in attemptReview
+    pub async fn source(&self, context: &Context) -> Result<Option<String>, AppError> {
+        if context.is_band_author(self.band_id).await? {
+            Ok(self.source.clone())
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn status(&self) -> QuestionStatus {
+        self.question_status()
+    }
+
+    /// Fan who submitted the question, None for questions written by authors
+    pub fn submitted_by(&self) -> Option<Uuid> {
+        self.submitted_by
+    }
+
+    /// Why the question was rejected or needs changes
+    pub fn moderation_reason(&self) -> Option<&str> {
+        self.moderation_reason.as_deref()
+    }
+
+    pub fn moderated_at(&self) -> Option<NaiveDateTime> {
+        self.moderated_at
+    }
+
+    /// Set while the question is hidden from players after being reported
+    pub fn hidden_at(&self) -> Option<NaiveDateTime> {
+        self.hidden_at
+    }
+
+    /// Every saved version of the question and what it changed, oldest first. Authors only.
+    pub async fn revisions(&self, context: &Context) -> Result<Vec<Revision>, AppError> {
+        context.current_author_id(self.band_id).await?;
+        let revisions = context.revision_repository().for_question(self.id).await?;
+        revision::revision_history(&revisions)
+    }
+
+    /// Set once the question is deleted, it's kept for played attempts until purged
+    pub fn deleted_at(&self) -> Option<NaiveDateTime> {
+        self.deleted_at
+    }
+
+    /// Send it back with `updateQuestion` so concurrent edits don't overwrite each other
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+
+    pub fn band_id(&self) -> Uuid {
+        self.band_id
+    }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+
+}
+
+#[juniper::graphql_object(
+    Context = Context
+)]
+impl Media {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn kind(&self) -> MediaKind {
+        self.media_kind()
+    }
+
+    pub fn url(&self, context: &Context) -> String {
+        context.storage.url(&self.key)
+    }
+
+    pub fn content_type(&self) -> &str {
+        self.content_type.as_str()
+    }
+
+    pub fn size_bytes(&self) -> i32 {
+        self.size_bytes
+    }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+}
+
+#[juniper::graphql_object(
+    Context = Context
+)]
+impl Tag {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn kind(&self) -> TagKind {
+        self.kind.parse().unwrap_or(TagKind::Topic)
+    }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+}
+
+#[juniper::graphql_object(
+    Context = Context
+)]
+impl Quiz {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub async fn band(&self, context: &Context) -> Result<Band, AppError> {
+        let band = context.band_repository().get(self.band_id).await?;
+        context.visible_band(band).await
+    }
+
+    pub fn title(&self) -> &str {
+        self.title.as_str()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn status(&self) -> QuizStatus {
+        self.quiz_status()
+    }
+
+    pub fn time_limit_seconds(&self) -> Option<i32> {
+        self.time_limit_seconds
+    }
+
+    pub fn seconds_per_question(&self) -> Option<i32> {
+        self.seconds_per_question
+    }
+
+    /// 50/50 lifelines each attempt gets
+    pub fn fifty_fifty_lifelines(&self) -> i32 {
+        self.fifty_fifty_lifelines
+    }
+
+    /// Hint lifelines each attempt gets
+    pub fn hint_lifelines(&self) -> i32 {
+        self.hint_lifelines
+    }
+
+    /// None when the quiz uses the default rules
+    pub async fn scoring_rules(&self, context: &Context) -> Result<Option<ScoringRules>, AppError> {
+        match self.scoring_rules_id {
+            Some(scoring_rules_id) => Ok(Some(context.scoring_rules_repository().get(scoring_rules_id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Questions in the order they are played, for the band's authors.
+    /// Players get them one at a time through nextQuestion.
+    pub async fn questions(&self, context: &Context) -> Result<Vec<Question>, AppError> {
+        context.current_author_id(self.band_id).await?;
+        context.quiz_repository().questions(self.id).await
+    }
+
+    /// Send it back with `updateQuiz` so concurrent edits don't overwrite each other
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+
+}
+
+#[juniper::graphql_object(
+    Context = Context
+)]
+impl Attempt {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// None when playing random questions
+    pub async fn quiz(&self, context: &Context) -> Result<Option<Quiz>, AppError> {
+        context.attempt_service().quiz(self).await
+    }
+
+    pub fn band_id(&self) -> Uuid {
+        self.band_id
+    }
+
+    pub fn started_at(&self) -> NaiveDateTime {
+        self.started_at
+    }
+
+    pub fn finished_at(&self) -> Option<NaiveDateTime> {
+        self.finished_at
+    }
+
+    /// When answers stop being accepted, for quizzes with a time limit
+    pub async fn deadline(&self, context: &Context) -> Result<Option<NaiveDateTime>, AppError> {
+        context.attempt_service().deadline(self).await
+    }
+
+    /// Final score, set once the attempt is finished
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    /// Points per question, recomputed from the stored responses
+    pub async fn score_breakdown(&self, context: &Context) -> Result<ScoreBreakdown, AppError> {
+        context.attempt_service().breakdown(self).await
+    }
+
+    /// Questions are only shown one at a time, through nextQuestion
+    pub async fn question_count(&self, context: &Context) -> Result<i32, AppError> {
+        Ok(context.attempt_repository().questions(self.id).await?.len() as i32)
+    }
+
+    pub async fn answered_count(&self, context: &Context) -> Result<i32, AppError> {
+        let served = context.attempt_repository().questions(self.id).await?;
+        Ok(served.iter().filter(|question| question.answered_at.is_some()).count() as i32)
+    }
+
+    pub async fn lifelines(&self, context: &Context) -> Result<Vec<LifelineBudget>, AppError> {
+        context.attempt_service().lifeline_budgets(self).await
+    }
+
+    /// Why the attempt was flagged for review
+    pub fn flag_reasons(&self) -> &[String] {
+        &self.flag_reasons
+    }
+
+    pub fn flagged_at(&self) -> Option<NaiveDateTime> {
+        self.flagged_at
+    }
+
+}
+
+#[juniper::graphql_object(
+    Context = Context
+)]
+impl ServedQuestion {
+    /// Where the question is in the attempt, to refer to it when answering
+    pub fn position(&self) -> i32 {
+        self.position
+    }
+
+    pub fn content(&self) -> &str {
+        &self.question.content
+    }
+
+    pub fn kind(&self) -> QuestionKind {
+        self.question.kind.parse().unwrap_or(QuestionKind::Choice)
+    }
+
+    pub async fn media(&self, context: &Context) -> Result<Option<Media>, AppError> {
+        match self.question.media_id {
+            Some(media_id) => context.media_repository().get(media_id).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn clip_start_ms(&self) -> Option<i32> {
+        self.question.clip_start_ms
+    }
+
+    pub fn clip_end_ms(&self) -> Option<i32> {
+        self.question.clip_end_ms
+    }
+
+    /// Options, ordering items or the left-hand side of matching questions, shuffled for this attempt
+    pub fn choices(&self) -> &[ServedChoice] {
+        &self.choices
+    }
+
+    /// Right-hand side of matching questions, shuffled for this attempt
+    pub fn match_choices(&self) -> &[ServedChoice] {
+        &self.match_choices
+    }
+
+    pub fn served_at(&self) -> NaiveDateTime {
+        self.served_at
+    }
+
+    /// When answers to this question stop being accepted
+    pub fn deadline(&self) -> Option<NaiveDateTime> {
+        self.deadline
+    }
+
+    /// Revealed by the hint lifeline
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+
+    pub fn lifelines_used(&self) -> &[LifelineKind] {
+        &self.lifelines_used
+    }
+
+}
+
+#[juniper::graphql_object(
+    Context = Context
+)]
+impl Notification {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn kind(&self) -> NotificationKind {
+        self.kind.parse().unwrap_or(NotificationKind::QuestionApproved)
+    }
+
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+
+    pub async fn question(&self, context: &Context) -> Result<Option<Question>, AppError> {
+        match self.question_id {
+            Some(question_id) => {
+                let question = context.question_repository().get(question_id).await?;
+                context.visible_question(question).await.map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub fn read_at(&self) -> Option<NaiveDateTime> {
+        self.read_at
+    }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+}
+
+#[juniper::graphql_object(
+    Context = Context
+)]
+impl EditSuggestion {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub async fn question(&self, context: &Context) -> Result<Question, AppError> {
+        let question = context.question_repository().get(self.question_id).await?;
+        context.visible_question(question).await
+    }
+
+    pub fn suggested_by(&self) -> Uuid {
+        self.suggested_by
+    }
+
+    /// What the edit changes from the revision it was made against
+    pub async fn changes(&self, context: &Context) -> Result<Vec<FieldChange>, AppError> {
+        let base = context.revision_repository().get(self.question_id, self.base_revision).await?;
+        let before = QuestionSnapshot::from_json(&base.snapshot)?;
+        let after = QuestionSnapshot::from_json(&self.snapshot)?;
+        Ok(revision::diff(Some(&before), &after))
+    }
+
+    pub fn base_revision(&self) -> i32 {
+        self.base_revision
+    }
+
+    pub fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    pub fn status(&self) -> SuggestionStatus {
+        self.suggestion_status()
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+
+    pub fn decided_at(&self) -> Option<NaiveDateTime> {
+        self.decided_at
+    }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+}
+
+#[juniper::graphql_object(
+    Context = Context
+)]
+impl Report {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn target_type(&self) -> ReportTargetType {
+        self.report_target_type()
+    }
+
+    pub fn target_id(&self) -> Uuid {
+        self.target_id
+    }
+
+    pub fn reason(&self) -> ReportReason {
+        self.reason.parse().unwrap_or(ReportReason::Other)
+    }
+
+    pub fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    pub fn status(&self) -> ReportStatus {
+        self.status.parse().unwrap_or(ReportStatus::Open)
+    }
+
+    pub fn resolved_at(&self) -> Option<NaiveDateTime> {
+        self.resolved_at
+    }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+}
+
+#[juniper::graphql_object(
+    Context = Context
+)]
+impl AuditEntry {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn actor_id(&self) -> Option<Uuid> {
+        self.actor_id
+    }
+
+    pub fn action(&self) -> AuditAction {
+        self.action.parse().unwrap_or(AuditAction::Update)
+    }
+
+    pub fn target_type(&self) -> AuditTargetType {
+        self.target_type.parse().unwrap_or(AuditTargetType::Question)
+    }
+
+    pub fn target_id(&self) -> Uuid {
+        self.target_id
+    }
+
+    /// JSON of the target before the change
+    pub fn before(&self) -> Option<&str> {
+        self.before.as_deref()
+    }
+
+    /// JSON of the target after the change
+    pub fn after(&self) -> Option<&str> {
+        self.after.as_deref()
+    }
+
+    pub fn request_id(&self) -> Uuid {
+        self.request_id
+    }
+
+    pub fn ip(&self) -> Option<&str> {
+        self.ip.as_deref()
+    }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+}
+
+pub struct Mutation {}
+
+#[juniper::graphql_object(
+    Context = Context,
+)]
+impl Mutation {
+    pub async fn create_user(input: CreateUser, context: &Context) -> Result<User, AppError> {
+        context.transaction(|context| async move {
+            let user = context.user_repository().create(input, context.hashing.clone()).await?;
+            context.audit(AuditAction::Create, AuditTargetType::User, user.id, None, audit::snapshot(&user)).await?;
+            Ok(user)
+        }).await
+    }
+
+    /// Band authors only
+    pub async fn create_answer(input: CreateAnswer, context: &Context) -> Result<Answer, AppError> {
+        context.current_author_id(input.band_id).await?;
+        context.transaction(|context| async move {
+            let answer = context.answer_repository().create(input).await?;
+            context.audit(AuditAction::Create, AuditTargetType::Answer, answer.id, None, audit::snapshot(&answer)).await?;
+            Ok(answer)
+        }).await
+    }
+
+    /// Returns the band's answer with this exact content, creating it if there's none. Band authors only.
+    pub async fn find_or_create_answer(band_id: Uuid, content: String, context: &Context) -> Result<Answer, AppError> {
+        context.current_author_id(band_id).await?;
+        context.transaction(|context| async move {
+            let (answer, created) = context.answer_repository().find_or_create(band_id, &content).await?;
+            if created {
+                context.audit(AuditAction::Create, AuditTargetType::Answer, answer.id, None, audit::snapshot(&answer)).await?;
+            }
+            Ok(answer)
+        }).await
+    }
+
+    /// Also records a revision of the questions the answer is the correct answer of
+    pub async fn create_answer_alias(input: CreateAnswerAlias, context: &Context) -> Result<AnswerAlias, AppError> {
+        context.transaction(|context| async move {
+            let answer = context.answer_repository().get(input.answer_id).await?;
+            // Answers from before they were scoped to bands are left to admins
+            match answer.band_id {
+                Some(band_id) => context.current_author_id(band_id).await?,
+                None => context.current_admin_id().await?,
+            };
+            let alias = context.answer_repository().create_alias(input).await?;
+            context.revision_service().record_aliases(alias.answer_id, context.user_id).await?;
+            context.audit(AuditAction::Update, AuditTargetType::Answer, alias.answer_id, None, audit::snapshot(&alias)).await?;
+            Ok(alias)
+        }).await
+    }
+
+    /// Questions from fans wait in the band's moderation queue
+    pub async fn create_question(input: CreateQuestion, context: &Context) -> Result<Question, AppError> {
+        context.transaction(|context| async move {
+            let submitted_by = context.question_submitter(input.band_id).await?;
+            let tags = input.tags.clone().unwrap_or_default();
+            let question = context.question_repository().create(input, submitted_by).await?;
+            context.tag_repository().tag_question(question.id, &tags, None).await?;
+            context.revision_service().record(&question, context.user_id).await?;
+            context.audit(AuditAction::Create, AuditTargetType::Question, question.id, None, audit::snapshot(&question)).await?;
+            Ok(question)
+        }).await
+    }
+
+    /// Authors edit any of the band's questions, submitters their own once changes were requested.
+    /// Every edit is kept as a revision.
+    pub async fn update_question(input: UpdateQuestion, context: &Context) -> Result<Question, AppError> {
+        context.transaction(|context| async move {
+            let user_id = context.current_user_id()?;
+            let question = context.question_repository().get(input.id).await?;
+
+            let own_resubmission = question.submitted_by == Some(user_id)
+                && question.question_status() == QuestionStatus::ChangesRequested;
+            if !own_resubmission {
+                context.current_author_id(question.band_id).await?;
+            }
+
+            let updated = context.revision_service().update(input, user_id).await?;
+            context.audit(AuditAction::Update, AuditTargetType::Question, question.id, audit::snapshot(&question), audit::snapshot(&updated)).await?;
+            Ok(updated)
+        }).await
+    }
+
+    /// Proposes an edit for the band's authors to accept or reject
+    pub async fn suggest_question_edit(input: UpdateQuestion, note: Option<String>, context: &Context) -> Result<EditSuggestion, AppError> {
+        let user_id = context.current_user_id()?;
+        context.transaction(|context| async move {
+            let suggestion = context.revision_service().suggest(input, user_id, note).await?;
+            context.audit(AuditAction::SuggestEdit, AuditTargetType::Question, suggestion.question_id, None, audit::snapshot(&suggestion)).await?;
+            Ok(suggestion)
+        }).await
+    }
+
+    pub async fn accept_question_edit(suggestion_id: Uuid, context: &Context) -> Result<Question, AppError> {
+        context.transaction(|context| async move {
+            let suggestion = context.revision_repository().get_suggestion(suggestion_id).await?;
+            let question = context.question_repository().get(suggestion.question_id).await?;
+            let author_id = context.current_author_id(question.band_id).await?;
+            let updated = context.revision_service().accept(suggestion.id, author_id).await?;
+            context.audit(AuditAction::AcceptEdit, AuditTargetType::Question, question.id, audit::snapshot(&question), audit::snapshot(&updated)).await?;
+            Ok(updated)
+        }).await
+    }
+
+    pub async fn reject_question_edit(suggestion_id: Uuid, reason: String, context: &Context) -> Result<EditSuggestion, AppError> {
+        context.transaction(|context| async move {
+            let suggestion = context.revision_repository().get_suggestion(suggestion_id).await?;
+            let question = context.question_repository().get(suggestion.question_id).await?;
+            let author_id = context.current_author_id(question.band_id).await?;
+            let rejected = context.revision_service().reject(suggestion.id, author_id, reason).await?;
+            context.audit(AuditAction::RejectEdit, AuditTargetType::Question, question.id, audit::snapshot(&suggestion), audit::snapshot(&rejected)).await?;
+            Ok(rejected)
+        }).await
+    }
+
+    /// Restores the question to an earlier revision, recorded as a new revision
+    pub async fn revert_question(question_id: Uuid, revision: i32, context: &Context) -> Result<Question, AppError> {
+        context.transaction(|context| async move {
+            let question = context.question_repository().get(question_id).await?;
+            let author_id = context.current_author_id(question.band_id).await?;
+            let updated = context.revision_service().revert(question.id, revision, author_id).await?;
+            context.audit(AuditAction::Revert, AuditTargetType::Question, question.id, audit::snapshot(&question), audit::snapshot(&updated)).await?;
+            Ok(updated)
+        }).await
+    }
+
+    /// Adds tags to a question, `kind` defaulting to a guess for new tags. Band authors only.
+    pub async fn tag_question(question_id: Uuid, tags: Vec<String>, kind: Option<TagKind>, context: &Context) -> Result<Vec<Tag>, AppError> {
+        context.transaction(|context| async move {
+            let question = context.question_repository().get(question_id).await?;
+            context.current_author_id(question.band_id).await?;
+            let before = context.tag_repository().for_question(question_id).await?;
+            context.tag_repository().tag_question(question_id, &tags, kind).await?;
+            let after = context.tag_repository().for_question(question_id).await?;
+            context.audit(AuditAction::Update, AuditTargetType::Question, question_id, audit::snapshot(&before), audit::snapshot(&after)).await?;
+            Ok(after)
+        }).await
+    }
+
+    pub async fn untag_question(question_id: Uuid, tags: Vec<String>, context: &Context) -> Result<Vec<Tag>, AppError> {
+        context.transaction(|context| async move {
+            let question = context.question_repository().get(question_id).await?;
+            context.current_author_id(question.band_id).await?;
+            let before = context.tag_repository().for_question(question_id).await?;
+            context.tag_repository().untag_question(question_id, &tags).await?;
+            let after = context.tag_repository().for_question(question_id).await?;
+            context.audit(AuditAction::Update, AuditTargetType::Question, question_id, audit::snapshot(&before), audit::snapshot(&after)).await?;
+            Ok(after)
+        }).await
+    }
+
+    /// Imports questions from the contents of a JSON, CSV or YAML file, authors only
+    /// Rows that look like the band's questions are reported as duplicates unless `force` is set.
+    /// Runs in one transaction, so a row that can't be saved fails the whole import.
+    pub async fn import_questions(band_id: Uuid, format: BulkFormat, file: String, dry_run: Option<bool>, force: Option<bool>, context: &Context) -> Result<ImportReport, AppError> {
+        context.current_author_id(band_id).await?;
+        let dry_run = dry_run.unwrap_or(false);
+
+        context.transaction(|context| async move {
+            let report = context.question_importer().import(band_id, format, &file, dry_run, force.unwrap_or(false)).await?;
+
+            if !dry_run && report.created > 0 {
+                let question_ids: Vec<Uuid> = report.rows.iter().filter_map(|row| row.question_id).collect();
+                let after = audit::snapshot(&serde_json::json!({ "created": report.created, "question_ids": question_ids }));
+                context.audit(AuditAction::Import, AuditTargetType::Band, band_id, None, after).await?;
+            }
+
+            Ok(report)
+        }).await
+    }
+
+    pub async fn create_ordering_question(input: CreateOrderingQuestion, context: &Context) -> Result<Question, AppError> {
+        context.transaction(|context| async move {
+            let submitted_by = context.question_submitter(input.band_id).await?;
+            let question = context.question_repository().create_ordering(input, submitted_by).await?;
+            context.revision_service().record(&question, context.user_id).await?;
+            context.audit(AuditAction::Create, AuditTargetType::Question, question.id, None, audit::snapshot(&question)).await?;
+            Ok(question)
+        }).await
+    }
+
+    pub async fn create_matching_question(input: CreateMatchingQuestion, context: &Context) -> Result<Question, AppError> {
+        context.transaction(|context| async move {
+            let submitted_by = context.question_submitter(input.band_id).await?;
+            let question = context.question_repository().create_matching(input, submitted_by).await?;
+            context.revision_service().record(&question, context.user_id).await?;
+            context.audit(AuditAction::Create, AuditTargetType::Question, question.id, None, audit::snapshot(&question)).await?;
+            Ok(question)
+        }).await
+    }
+
+    pub async fn create_band(input: CreateBand, context: &Context) -> Result<Band, AppError> {
+        context.transaction(|context| async move {
+            let user_id = context.current_user_id()?;
+            let band = context.band_repository().create(input, user_id).await?;
+            context.audit(AuditAction::Create, AuditTargetType::Band, band.id, None, audit::snapshot(&band)).await?;
+            Ok(band)
+        }).await
+    }
+
+    /// Lets another user write questions and quizzes for the band, and
+    /// moderate fans' questions with the moderator role. Owners only.
+    pub async fn add_band_author(band_id: Uuid, user_id: Uuid, role: Option<BandRole>, context: &Context) -> Result<Band, AppError> {
+        context.transaction(|context| async move {
+            let band = context.band_repository().get(band_id).await?;
+            if context.current_user_id()? != band.owner_id {
+                context.current_admin_id().await?;
+            }
+
+            let role = role.unwrap_or(BandRole::Author);
+            context.band_repository().add_author(band_id, user_id, role).await?;
+
+            let after = audit::snapshot(&serde_json::json!({ "user_id": user_id, "role": role.as_str() }));
+            context.audit(AuditAction::AddAuthor, AuditTargetType::Band, band_id, None, after).await?;
+            Ok(band)
+        }).await
+    }
+
+    pub async fn approve_question(question_id: Uuid, context: &Context) -> Result<Question, AppError> {
+        context.transaction(|context| async move {
+            let question = context.question_repository().get(question_id).await?;
+            let moderator_id = context.current_moderator_id(question.band_id).await?;
+            let moderated = context.moderation_service().moderate(question.id, moderator_id, QuestionStatus::Approved, None).await?;
+            context.audit(AuditAction::Approve, AuditTargetType::Question, question.id, audit::snapshot(&question), audit::snapshot(&moderated)).await?;
+            Ok(moderated)
+        }).await
+    }
+
+    pub async fn reject_question(question_id: Uuid, reason: String, context: &Context) -> Result<Question, AppError> {
+        context.transaction(|context| async move {
+            let question = context.question_repository().get(question_id).await?;
+            let moderator_id = context.current_moderator_id(question.band_id).await?;
+            let moderated = context.moderation_service().moderate(question.id, moderator_id, QuestionStatus::Rejected, Some(reason)).await?;
+            context.audit(AuditAction::Reject, AuditTargetType::Question, question.id, audit::snapshot(&question), audit::snapshot(&moderated)).await?;
+            Ok(moderated)
+        }).await
+    }
+
+    /// Sends the question back to its submitter with the changes to make
+    pub async fn request_question_changes(question_id: Uuid, reason: String, context: &Context) -> Result<Question, AppError> {
+        context.transaction(|context| async move {
+            let question = context.question_repository().get(question_id).await?;
+            let moderator_id = context.current_moderator_id(question.band_id).await?;
+            let moderated = context.moderation_service().moderate(question.id, moderator_id, QuestionStatus::ChangesRequested, Some(reason)).await?;
+            context.audit(AuditAction::RequestChanges, AuditTargetType::Question, question.id, audit::snapshot(&question), audit::snapshot(&moderated)).await?;
+            Ok(moderated)
+        }).await
+    }
+
+    /// Marks the given notifications as read, or all of them. Returns how many were unread.
+    pub async fn mark_notifications_read(ids: Option<Vec<Uuid>>, context: &Context) -> Result<i32, AppError> {
+        let user_id = context.current_user_id()?;
+        context.notification_repository().mark_read(user_id, ids.as_deref()).await
+    }
+
+    /// Flags a question, band or user as wrong or offensive. Reporting the same
+    /// target again updates the report, enough reports hide it pending review.
+    pub async fn report_content(target_type: ReportTargetType, target_id: Uuid, reason: ReportReason, note: Option<String>, context: &Context) -> Result<Report, AppError> {
+        let user_id = context.current_user_id()?;
+        context.transaction(|context| async move {
+            let (report, hidden) = context.report_service().report(user_id, target_type, target_id, reason, note).await?;
+            if hidden {
+                context.audit(AuditAction::Hide, target_type.into(), target_id, None, audit::snapshot(&report)).await?;
+            }
+            Ok(report)
+        }).await
+    }
+
+    /// Closes the open reports on the report's target and shows it again
+    pub async fn dismiss_report(report_id: Uuid, context: &Context) -> Result<Vec<Report>, AppError> {
+        context.transaction(|context| async move {
+            let report = context.report_repository().get(report_id).await?;
+            let moderator_id = context.current_report_moderator_id(report.report_target_type(), report.target_id).await?;
+            let reports = context.report_service().resolve(report.id, moderator_id, ReportStatus::Dismissed).await?;
+            context.audit(AuditAction::Show, report.report_target_type().into(), report.target_id, None, audit::snapshot(&reports)).await?;
+            Ok(reports)
+        }).await
+    }
+
+    /// Closes the open reports on the report's target and keeps it hidden
+    pub async fn uphold_report(report_id: Uuid, context: &Context) -> Result<Vec<Report>, AppError> {
+        context.transaction(|context| async move {
+            let report = context.report_repository().get(report_id).await?;
+            let moderator_id = context.current_report_moderator_id(report.report_target_type(), report.target_id).await?;
+            let reports = context.report_service().resolve(report.id, moderator_id, ReportStatus::Upheld).await?;
+            context.audit(AuditAction::Hide, report.report_target_type().into(), report.target_id, None, audit::snapshot(&reports)).await?;
+            Ok(reports)
+        }).await
+    }
+
+    /// Hides the question from players and listings. Admins can restore it
+    /// until it's purged after the retention period.
+    pub async fn delete_question(id: Uuid, context: &Context) -> Result<Question, AppError> {
+        context.transaction(|context| async move {
+            let question = context.question_repository().get(id).await?;
+            context.current_author_id(question.band_id).await?;
+            let deleted = context.question_repository().soft_delete(id).await?;
+            context.audit(AuditAction::Delete, AuditTargetType::Question, id, audit::snapshot(&question), audit::snapshot(&deleted)).await?;
+            Ok(deleted)
+        }).await
+    }
+
+    /// Deletes the band along with its questions. Owners and admins only.
+    pub async fn delete_band(id: Uuid, context: &Context) -> Result<Band, AppError> {
+        context.transaction(|context| async move {
+            let band = context.band_repository().get(id).await?;
+            if context.current_user_id()? != band.owner_id {
+                context.current_admin_id().await?;
+            }
+
+            let deleted = context.band_repository().soft_delete(id).await?;
+            context.audit(AuditAction::Delete, AuditTargetType::Band, id, audit::snapshot(&band), audit::snapshot(&deleted)).await?;
+            Ok(deleted)
+        }).await
+    }
+
+    /// Deletes your own account, or any account as an admin, and ends its sessions
+    pub async fn delete_user(id: Uuid, context: &Context) -> Result<User, AppError> {
+        context.transaction(|context| async move {
+            let user = context.user_repository().get(id).await?;
+            if context.current_user_id()? != user.id {
+                context.current_admin_id().await?;
+            }
+
+            let deleted = context.user_repository().soft_delete(id).await?;
+            context.audit(AuditAction::Delete, AuditTargetType::User, id, audit::snapshot(&user), audit::snapshot(&deleted)).await?;
+            Ok(deleted)
+        }).await
+    }
+
+    /// Admins only
+    pub async fn restore_question(id: Uuid, context: &Context) -> Result<Question, AppError> {
+        context.current_admin_id().await?;
+        context.transaction(|context| async move {
+            let restored = context.question_repository().restore(id).await?;
+            context.audit(AuditAction::Restore, AuditTargetType::Question, id, None, audit::snapshot(&restored)).await?;
+            Ok(restored)
+        }).await
+    }
+
+    /// Also restores the questions deleted along with the band. Admins only.
+    pub async fn restore_band(id: Uuid, context: &Context) -> Result<Band, AppError> {
+        context.current_admin_id().await?;
+        context.transaction(|context| async move {
+            let restored = context.band_repository().restore(id).await?;
+            context.audit(AuditAction::Restore, AuditTargetType::Band, id, None, audit::snapshot(&restored)).await?;
+            Ok(restored)
+        }).await
+    }
+
+    /// Admins only
+    pub async fn restore_user(id: Uuid, context: &Context) -> Result<User, AppError> {
+        context.current_admin_id().await?;
+        context.transaction(|context| async move {
+            let restored = context.user_repository().restore(id).await?;
+            context.audit(AuditAction::Restore, AuditTargetType::User, id, None, audit::snapshot(&restored)).await?;
+            Ok(restored)
+        }).await
+    }
+
+    pub async fn create_quiz(input: CreateQuiz, context: &Context) -> Result<Quiz, AppError> {
+        let user_id = context.current_author_id(input.band_id).await?;
+        context.transaction(|context| async move {
+            let scoring_rules_id = match &input.scoring_rules {
+                Some(rules) => Some(context.scoring_rules_repository().create(rules).await?.id),
+                None => None,
+            };
+
+            let quiz = context.quiz_repository().create(input, user_id, scoring_rules_id).await?;
+            context.audit(AuditAction::Create, AuditTargetType::Quiz, quiz.id, None, audit::snapshot(&quiz)).await?;
+            Ok(quiz)
+        }).await
+    }
+
+    pub async fn update_quiz(input: UpdateQuiz, context: &Context) -> Result<Quiz, AppError> {
+        context.transaction(|context| async move {
+            let quiz = context.quiz_repository().get(input.id).await?;
+            context.current_author_id(quiz.band_id).await?;
+            // Rewriting the quiz's rules in place leaves no unused ones behind
+            let scoring_rules_id = match (&input.scoring_rules, quiz.scoring_rules_id) {
+                (Some(rules), Some(id)) => Some(context.scoring_rules_repository().replace(id, rules).await?.id),
+                (Some(rules), None) => Some(context.scoring_rules_repository().create(rules).await?.id),
+                (None, _) => None,
+            };
+
+            let updated = context.quiz_repository().update(input, scoring_rules_id).await?;
+            context.audit(AuditAction::Update, AuditTargetType::Quiz, quiz.id, audit::snapshot(&quiz), audit::snapshot(&updated)).await?;
+            Ok(updated)
+        }).await
+    }
+
+    /// Replaces the quiz's questions, `questionIds` being the order they are played in
+    pub async fn set_quiz_questions(quiz_id: Uuid, question_ids: Vec<Uuid>, context: &Context) -> Result<Quiz, AppError> {
+        context.transaction(|context| async move {
+            let quiz = context.quiz_repository().get(quiz_id).await?;
+            context.current_author_id(quiz.band_id).await?;
+            context.quiz_repository().set_questions(&quiz, &question_ids).await?;
+            Ok(quiz)
+        }).await
+    }
+
+    /// Publishes, archives or returns the quiz to draft
+    pub async fn set_quiz_status(quiz_id: Uuid, status: QuizStatus, context: &Context) -> Result<Quiz, AppError> {
+        context.transaction(|context| async move {
+            let quiz = context.quiz_repository().get(quiz_id).await?;
+            context.current_author_id(quiz.band_id).await?;
+            let updated = context.quiz_repository().set_status(&quiz, status).await?;
+            context.audit(AuditAction::Update, AuditTargetType::Quiz, quiz.id, audit::snapshot(&quiz), audit::snapshot(&updated)).await?;
+            Ok(updated)
+        }).await
+    }
+
+    pub async fn delete_quiz(quiz_id: Uuid, context: &Context) -> Result<bool, AppError> {
+        context.transaction(|context| async move {
+            let quiz = context.quiz_repository().get(quiz_id).await?;
+            context.current_author_id(quiz.band_id).await?;
+            context.quiz_repository().delete(quiz.id).await?;
+            context.audit(AuditAction::Delete, AuditTargetType::Quiz, quiz.id, audit::snapshot(&quiz), None).await?;
+            Ok(true)
+        }).await
+    }
+
+    pub async fn start_quiz(quiz_id: Uuid, context: &Context) -> Result<Attempt, AppError> {
+        let user_id = context.current_user_id()?;
+        context.transaction(|context| async move {
+            context.attempt_service().start_quiz(quiz_id, user_id).await
+        }).await
+    }
+
+    pub async fn start_random_quiz(input: StartRandomQuiz, context: &Context) -> Result<Attempt, AppError> {
+        let user_id = context.current_user_id()?;
+        context.transaction(|context| async move {
+            context.attempt_service().start_random(input, user_id).await
+        }).await
+    }
+
+    /// Serves the attempt's current question, None once all were answered or timed out
+    pub async fn next_question(attempt_id: Uuid, context: &Context) -> Result<Option<ServedQuestion>, AppError> {
+        let user_id = context.current_user_id()?;
+        context.attempt_service().next_question(attempt_id, user_id).await
+    }
+
+    pub async fn submit_attempt_answer(attempt_id: Uuid, input: AttemptSubmission, context: &Context) -> Result<Grade, AppError> {
+        let user_id = context.current_user_id()?;
+        context.attempt_service().submit(attempt_id, user_id, input).await
+    }
+
+    /// `position` is the served question's position in the attempt
+    pub async fn use_lifeline(attempt_id: Uuid, position: i32, kind: LifelineKind, context: &Context) -> Result<ServedQuestion, AppError> {
+        let user_id = context.current_user_id()?;
+        context.transaction(|context| async move {
+            context.attempt_service().use_lifeline(attempt_id, user_id, position, kind).await
+        }).await
+    }
+
+    pub async fn finish_attempt(attempt_id: Uuid, context: &Context) -> Result<Attempt, AppError> {
+        let user_id = context.current_user_id()?;
+        context.transaction(|context| async move {
+            context.attempt_service().finish(attempt_id, user_id).await
+        }).await
+    }
+
+    pub async fn clear_attempt_flag(attempt_id: Uuid, context: &Context) -> Result<Attempt, AppError> {
+        let attempt = context.attempt_repository().get(attempt_id).await?;
+        context.current_author_id(attempt.band_id).await?;
+        context.attempt_repository().clear_flag(attempt.id).await
+    }
+
+    pub async fn login(input: Login, context: &Context) -> Result<Session, AppError> {
+        context.session_repository().create(input, context.hashing.clone()).await
+    }
+
+    pub async fn review_question(question_id: Uuid, grade: i32, context: &Context) -> Result<ReviewState, AppError> {
+        let user_id = context.current_user_id()?;
+        context.review_state_repository().review(user_id, question_id, grade).await
     }
 }
 
@@ -1,11 +1,32 @@
-mod graphql;
+pub(crate) mod graphql;
+#[cfg(test)]
+mod schema_tests;
 
-use actix_web::{web, HttpResponse};
+use actix_multipart::Multipart;
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
 use deadpool_postgres::Pool;
+use futures::StreamExt;
 use juniper::http::{graphiql::graphiql_source, GraphQLRequest};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use graphql::{create_schema, Schema, Context};
-use crate::{config::HashingService};
+use crate::{
+    avatar::{self, AvatarService, AVATAR_SIZE, AVATAR_TYPES},
+    config::{HashingService, ProxySettings, UploadSettings},
+    errors::{AppError, AppErrorType},
+    models::{audit::{self, AuditAction, AuditTargetType, NewAuditEntry}, media::CreateMedia},
+    repositories::{
+        audit::AuditRepository,
+        db,
+        media::MediaRepository,
+        session::SessionRepository,
+        stores::{AuditStore, MediaStore, PgStores, SessionStore, UserStore},
+        user::UserRepository,
+    },
+    storage::{Storage, validation::{validate_upload, MEDIA_TYPES}},
+};
+use slog_scope::error;
+use uuid::Uuid;
 
 async fn health() -> HttpResponse {
     HttpResponse::Ok().finish()
@@ -17,6 +38,10 @@ pub fn app_config(config: &mut web::ServiceConfig) {
         .data(schema)
         .service(web::resource("/graphql").route(web::post().to(graphql)))
         .service(web::resource("/graphiql").route(web::get().to(graphiql)))
+        .service(web::resource("/media").route(web::post().to(upload_media)))
+        .service(web::resource("/files/{key:.*}").route(web::get().to(file)))
+        .service(web::resource("/avatar").route(web::post().to(upload_avatar)))
+        .service(web::resource("/identicons/{name}").route(web::get().to(identicon)))
         .service(web::resource("/").route(web::get().to(health)));
 }
 
@@ -27,16 +52,268 @@ async fn graphiql() -> HttpResponse {
         .body(html)
 }
 
+/// Reads the session token from an `Authorization: Bearer <token>` header
+fn session_token(req: &HttpRequest) -> Option<Uuid> {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| value.starts_with("Bearer "))
+        .and_then(|value| Uuid::parse_str(value["Bearer ".len()..].trim()).ok())
+}
+
+/// Id from the `X-Request-Id` header set by a trusted proxy, or a new one.
+/// Clients could otherwise pick the id their entries are logged under.
+fn request_id(req: &HttpRequest, proxy: &ProxySettings) -> Uuid {
+    req.headers()
+        .get("x-request-id")
+        .filter(|_| proxy.trusted)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| Uuid::parse_str(value.trim()).ok())
+        .unwrap_or_else(Uuid::new_v4)
+}
+
+/// The client's address. `Forwarded` and `X-Forwarded-For` can be set by
+/// anyone, so they're only read behind a trusted proxy.
+fn client_ip(req: &HttpRequest, proxy: &ProxySettings) -> Option<String> {
+    if proxy.trusted {
+        req.connection_info().realip_remote_addr().map(String::from)
+    } else {
+        req.peer_addr().map(|addr| addr.ip().to_string())
+    }
+}
+
+async fn current_user_id(req: &HttpRequest, pool: Arc<Pool>) -> Option<Uuid> {
+    match session_token(req) {
+        Some(token) => SessionRepository::new(pool)
+            .find_user_id(token)
+            .await
+            .unwrap_or_else(|err| {
+                error!("Error resolving session {}", err);
+                None
+            }),
+        None => None,
+    }
+}
+
 async fn graphql(
+    req: HttpRequest,
     data: web::Json<GraphQLRequest>,
     schema: web::Data<Schema>,
     pool: web::Data<Pool>,
-    hashing_service: web::Data<HashingService>
+    hashing_service: web::Data<HashingService>,
+    storage: web::Data<Arc<dyn Storage>>,
+    avatar_service: web::Data<AvatarService>,
+    proxy: web::Data<ProxySettings>,
 ) -> HttpResponse {
     let pool: Arc<Pool> = pool.into_inner();
     let hashing: Arc<HashingService> = hashing_service.into_inner();
-    let context = Context { pool, hashing };
+    let storage: Arc<dyn Storage> = storage.get_ref().clone();
+    let avatars: Arc<AvatarService> = avatar_service.into_inner();
+    let user_id = current_user_id(&req, pool.clone()).await;
+    let request_id = request_id(&req, &proxy);
+    let ip = client_ip(&req, &proxy);
+    let context = Context { db: pool.into(), stores: Arc::new(PgStores), hashing, storage, avatars, user_id, request_id, ip };
     let res = data.execute(&schema, &context).await;
 
     HttpResponse::Ok().json(res)
 }
+
+struct Upload {
+    content_type: String,
+    bytes: Vec<u8>,
+}
+
+fn invalid_upload(message: &str) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(message.to_string()),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+/// Reads the first file of a multipart body, failing once it exceeds `max_bytes`
+async fn read_upload(mut payload: Multipart, max_bytes: usize) -> Result<Upload, AppError> {
+    let mut field = payload
+        .next()
+        .await
+        .ok_or_else(|| invalid_upload("no file was uploaded."))?
+        .map_err(|err| AppError {
+            cause: Some(err.to_string()),
+            message: Some("invalid multipart body.".to_string()),
+            error_type: AppErrorType::InvalidField
+        })?;
+
+    let content_type = format!("{}/{}", field.content_type().type_(), field.content_type().subtype());
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field.next().await {
+        let chunk = chunk.map_err(|err| AppError {
+            cause: Some(err.to_string()),
+            message: Some("upload was interrupted.".to_string()),
+            error_type: AppErrorType::InvalidField
+        })?;
+
+        if bytes.len() + chunk.len() > max_bytes {
+            return Err(invalid_upload(&format!("file is larger than {} bytes.", max_bytes)));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(Upload { content_type, bytes })
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MediaResponse {
+    id: Uuid,
+    url: String,
+    content_type: String,
+    size_bytes: i32,
+}
+
+async fn require_user_id(req: &HttpRequest, pool: Arc<Pool>) -> Result<Uuid, AppError> {
+    current_user_id(req, pool)
+        .await
+        .ok_or(AppError {
+            cause: None,
+            message: None,
+            error_type: AppErrorType::UnauthorizedError
+        })
+}
+
+/// Stores an image or audio clip for use in questions
+async fn upload_media(
+    req: HttpRequest,
+    payload: Multipart,
+    pool: web::Data<Pool>,
+    storage: web::Data<Arc<dyn Storage>>,
+    upload_settings: web::Data<UploadSettings>,
+) -> Result<HttpResponse, AppError> {
+    let pool: Arc<Pool> = pool.into_inner();
+    let uploaded_by = require_user_id(&req, pool.clone()).await?;
+
+    let upload = read_upload(payload, upload_settings.max_bytes).await?;
+    let extension = validate_upload(&upload.content_type, &upload.bytes, MEDIA_TYPES, upload_settings.max_bytes)?;
+
+    let key = format!("media/{}.{}", Uuid::new_v4(), extension);
+    let size_bytes = upload.bytes.len() as i32;
+
+    // The row is inserted first and only committed once the file is stored,
+    // so a failed upload leaves neither behind
+    let storage = storage.get_ref();
+    let media = db::transaction(&pool.into(), |db| async move {
+        let media = MediaRepository::new(db)
+            .create(CreateMedia {
+                key,
+                content_type: upload.content_type,
+                size_bytes,
+                uploaded_by,
+            })
+            .await?;
+
+        storage.put(&media.key, &media.content_type, upload.bytes).await?;
+        Ok(media)
+    }).await?;
+
+    Ok(HttpResponse::Created().json(MediaResponse {
+        id: media.id,
+        url: storage.url(&media.key),
+        content_type: media.content_type,
+        size_bytes: media.size_bytes,
+    }))
+}
+
+/// Serves files from the storage backend
+async fn file(key: web::Path<String>, storage: web::Data<Arc<dyn Storage>>) -> Result<HttpResponse, AppError> {
+    let bytes = storage.get(&key).await?;
+
+    let content_type = MEDIA_TYPES.iter()
+        .find(|(_, extension)| key.ends_with(&format!(".{}", extension)))
+        .map(|(content_type, _)| *content_type)
+        .unwrap_or("application/octet-stream");
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .body(bytes))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AvatarResponse {
+    image: String,
+    thumbnail: String,
+}
+
+/// Replaces the logged in user's avatar with a re-encoded, thumbnailed copy of the upload
+async fn upload_avatar(
+    req: HttpRequest,
+    payload: Multipart,
+    pool: web::Data<Pool>,
+    storage: web::Data<Arc<dyn Storage>>,
+    upload_settings: web::Data<UploadSettings>,
+    proxy: web::Data<ProxySettings>,
+) -> Result<HttpResponse, AppError> {
+    let pool: Arc<Pool> = pool.into_inner();
+    let user_id = require_user_id(&req, pool.clone()).await?;
+
+    let upload = read_upload(payload, upload_settings.max_bytes).await?;
+    validate_upload(&upload.content_type, &upload.bytes, AVATAR_TYPES, upload_settings.max_bytes)?;
+
+    let bytes = upload.bytes;
+    let thumbnails = web::block(move || avatar::thumbnails(&bytes))
+        .await?;
+
+    let upload_id = Uuid::new_v4();
+    for (size, png) in thumbnails {
+        storage.put(&avatar::avatar_key(user_id, upload_id, size), "image/png", png).await?;
+    }
+
+    let image = storage.url(&avatar::avatar_key(user_id, upload_id, AVATAR_SIZE));
+    let request_id = request_id(&req, &proxy);
+    let ip = client_ip(&req, &proxy);
+    let user = db::transaction(&pool.into(), |db| async move {
+        let users = UserRepository::new(db.clone());
+        let before = users.get(user_id).await?;
+        let user = users.update_image(user_id, Some(image)).await?;
+
+        AuditRepository::new(db)
+            .create(NewAuditEntry {
+                actor_id: Some(user_id),
+                action: AuditAction::Update,
+                target_type: AuditTargetType::User,
+                target_id: user_id,
+                before: audit::snapshot(&before),
+                after: audit::snapshot(&user),
+                request_id,
+                ip,
+            })
+            .await?;
+
+        Ok(user)
+    }).await?;
+
+    let image = user.image.unwrap_or_default();
+    Ok(HttpResponse::Ok().json(AvatarResponse {
+        thumbnail: avatar::thumbnail_url(&image),
+        image,
+    }))
+}
+
+#[derive(Deserialize)]
+struct IdenticonQuery {
+    size: Option<u32>,
+}
+
+/// Draws the fallback avatar for `/identicons/<user id>.png`
+async fn identicon(name: web::Path<String>, query: web::Query<IdenticonQuery>) -> Result<HttpResponse, AppError> {
+    let seed = name.trim_end_matches(".png").to_string();
+    let size = query.size.unwrap_or(AVATAR_SIZE).max(16).min(512);
+
+    let png = web::block(move || avatar::identicon(seed.as_bytes(), size))
+        .await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("image/png")
+        .header(header::CACHE_CONTROL, "public, max-age=86400")
+        .body(png))
+}
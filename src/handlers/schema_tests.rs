@@ -0,0 +1,604 @@
+/// Schema Tests
+/// Runs GraphQL queries against the in-memory stores, without Postgres
+
+use super::graphql::{create_schema, Context};
+use crate::avatar::AvatarService;
+use crate::config::HashingService;
+use crate::models::{
+    answer::{Answer, CreateAnswer},
+    band::{Band, CreateBand},
+    media::CreateMedia,
+    question::{CreateQuestion, Question},
+    quiz::{CreateQuiz, QuizStatus},
+    report::HIDE_THRESHOLD,
+    user::User,
+};
+use crate::repositories::{
+    db::Db,
+    memory::MemoryStore,
+    stores::{AnswerStore, BandStore, MediaStore, QuestionStore, QuizStore},
+};
+use crate::storage::local::LocalStorage;
+use deadpool_postgres::{Manager, Pool};
+use juniper::http::GraphQLRequest;
+use serde_json::{json, Value};
+use std::{env, sync::Arc};
+use tokio_postgres::NoTls;
+use uuid::Uuid;
+
+/// A context whose tables all live in `store`. Its pool never connects, so
+/// only handlers that bypass the stores would reach Postgres.
+fn context(store: &Arc<MemoryStore>, user_id: Option<Uuid>) -> Context {
+    let pool = Pool::new(Manager::new(tokio_postgres::Config::new(), NoTls), 1);
+
+    Context {
+        db: Db::Pool(Arc::new(pool)),
+        stores: Arc::new(store.clone()),
+        hashing: Arc::new(HashingService::new("secret".to_string())),
+        storage: Arc::new(LocalStorage::new(env::temp_dir(), "http://localhost:8080/files".to_string())),
+        avatars: Arc::new(AvatarService::new("http://localhost:8080".to_string())),
+        user_id,
+        request_id: Uuid::new_v4(),
+        ip: None,
+    }
+}
+
+async fn execute(query: &str, context: &Context) -> Value {
+    let schema = create_schema();
+    let request = GraphQLRequest::new(query.to_string(), None, None);
+    let response = request.execute(&schema, context).await;
+
+    serde_json::to_value(&response).unwrap()
+}
+
+fn error_message(response: &Value) -> &str {
+    response["errors"][0]["message"].as_str().unwrap_or_default()
+}
+
+async fn band(store: &MemoryStore, name: &str) -> (User, Band) {
+    let owner = store.add_user(&format!("{} owner", name), false);
+    let band = BandStore::create(store, CreateBand { name: name.to_string() }, owner.id).await.unwrap();
+
+    (owner, band)
+}
+
+async fn answer(store: &MemoryStore, band: &Band, content: &str) -> Answer {
+    AnswerStore::create(store, CreateAnswer { band_id: band.id, content: content.to_string(), aliases: None }).await.unwrap()
+}
+
+/// Leaving out `distractors` creates a choice question without any
+async fn choice_question(store: &MemoryStore, band: &Band, content: &str, correct: &Answer, distractors: &[&Answer], submitted_by: Option<Uuid>) -> Question {
+    QuestionStore::create(store, CreateQuestion {
+        content: content.to_string(),
+        correct_answer_id: Some(correct.id),
+        distractor_ids: if distractors.is_empty() { None } else { Some(distractors.iter().map(|answer| answer.id).collect()) },
+        band_id: band.id,
+        kind: None,
+        max_edit_distance: None,
+        numeric_value: None,
+        numeric_tolerance: None,
+        numeric_curve: None,
+        media_id: None,
+        clip_start_ms: None,
+        clip_end_ms: None,
+        hint: None,
+        explanation: None,
+        source: None,
+        tags: None,
+        force: None,
+    }, submitted_by).await.unwrap()
+}
+
+#[actix_rt::test]
+async fn test_bands() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+
+    let response = execute("{ bands { id name ownerId } }", &context(&store, None)).await;
+
+    assert_eq!(response["data"]["bands"], json!([
+        { "id": radiohead.id.to_string(), "name": "Radiohead", "ownerId": owner.id.to_string() }
+    ]));
+}
+
+#[actix_rt::test]
+async fn test_band_not_found() {
+    let store = MemoryStore::new();
+
+    let query = format!("{{ band(id: \"{}\") {{ name }} }}", Uuid::new_v4());
+    let response = execute(&query, &context(&store, None)).await;
+
+    assert_eq!(error_message(&response), "The requested item was not found");
+}
+
+#[actix_rt::test]
+async fn test_answers_of_band() {
+    let store = MemoryStore::new();
+    let (_, radiohead) = band(&store, "Radiohead").await;
+    let (_, blur) = band(&store, "Blur").await;
+    answer(&store, &radiohead, "OK Computer").await;
+    answer(&store, &radiohead, "Kid A").await;
+    answer(&store, &blur, "Parklife").await;
+
+    let query = format!("{{ answers(bandId: \"{}\") {{ content }} }}", radiohead.id);
+    let response = execute(&query, &context(&store, None)).await;
+
+    assert_eq!(response["data"]["answers"], json!([{ "content": "Kid A" }, { "content": "OK Computer" }]));
+}
+
+#[actix_rt::test]
+async fn test_question_hides_answers() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+    let first = answer(&store, &radiohead, "OK Computer").await;
+    let second = answer(&store, &radiohead, "Kid A").await;
+    // Players see the choices by id, which puts the correct answer last
+    let (wrong, correct) = if first.id < second.id { (first, second) } else { (second, first) };
+    let question = choice_question(&store, &radiohead, "Which album has Paranoid Android?", &correct, &[&wrong], None).await;
+
+    let query = format!("{{ question(id: \"{}\") {{ content correctAnswerId choices {{ answerId position }} }} }}", question.id);
+
+    let response = execute(&query, &context(&store, None)).await;
+    assert_eq!(response["data"]["question"], json!({
+        "content": "Which album has Paranoid Android?",
+        "correctAnswerId": null,
+        "choices": [
+            { "answerId": wrong.id.to_string(), "position": 0 },
+            { "answerId": correct.id.to_string(), "position": 1 },
+        ],
+    }));
+
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    assert_eq!(response["data"]["question"]["correctAnswerId"], json!(correct.id.to_string()));
+    let stored: Vec<Uuid> = QuestionStore::choices(&*store, question.id).await.unwrap()
+        .iter()
+        .map(|choice| choice.answer_id)
+        .collect();
+    let shown: Vec<Uuid> = response["data"]["question"]["choices"].as_array().unwrap()
+        .iter()
+        .map(|choice| Uuid::parse_str(choice["answerId"].as_str().unwrap()).unwrap())
+        .collect();
+    assert_eq!(shown, stored, "Authors should see the stored order");
+}
+
+#[actix_rt::test]
+async fn test_check_answer_needs_login() {
+    let store = MemoryStore::new();
+
+    let query = format!("{{ checkAnswer(attemptId: \"{}\", input: {{ position: 0, text: \"Kid A\" }}) {{ accepted }} }}", Uuid::new_v4());
+    let response = execute(&query, &context(&store, None)).await;
+
+    assert_eq!(error_message(&response), "You must be logged in to do that");
+}
+
+#[actix_rt::test]
+async fn test_moderation_queue() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+    let fan = store.add_user("fan", false);
+    let ok_computer = answer(&store, &radiohead, "OK Computer").await;
+    let kid_a = answer(&store, &radiohead, "Kid A").await;
+    let submitted = choice_question(&store, &radiohead, "Which album has Idioteque?", &kid_a, &[&ok_computer], Some(fan.id)).await;
+
+    let query = format!("{{ moderationQueue(bandId: \"{}\") {{ id submittedBy }} }}", radiohead.id);
+
+    let response = execute(&query, &context(&store, None)).await;
+    assert_eq!(error_message(&response), "You must be logged in to do that");
+
+    let response = execute(&query, &context(&store, Some(fan.id))).await;
+    assert_eq!(error_message(&response), "You are not allowed to do that");
+
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    assert_eq!(response["data"]["moderationQueue"], json!([
+        { "id": submitted.id.to_string(), "submittedBy": fan.id.to_string() }
+    ]));
+}
+
+#[actix_rt::test]
+async fn test_similar_questions_hide_pending_ones() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+    let fan = store.add_user("fan", false);
+    let other_fan = store.add_user("other fan", false);
+    let ok_computer = answer(&store, &radiohead, "OK Computer").await;
+    let kid_a = answer(&store, &radiohead, "Kid A").await;
+    let pending = choice_question(&store, &radiohead, "Which album has Idioteque?", &kid_a, &[&ok_computer], Some(fan.id)).await;
+
+    // Another fan isn't told about a question still waiting for moderation
+    choice_question(&store, &radiohead, "Which album has Idioteque?", &kid_a, &[&ok_computer], Some(other_fan.id)).await;
+
+    let query = format!("{{ similarQuestions(bandId: \"{}\", content: \"Which album has Idioteque?\") {{ id }} }}", radiohead.id);
+
+    let response = execute(&query, &context(&store, Some(fan.id))).await;
+    assert_eq!(error_message(&response), "You are not allowed to do that");
+
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    let similar = response["data"]["similarQuestions"].as_array().unwrap();
+    assert_eq!(similar.len(), 2);
+    assert_eq!(similar[0]["id"], json!(pending.id.to_string()));
+}
+
+#[actix_rt::test]
+async fn test_reject_question_notifies_submitter() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+    let fan = store.add_user("fan", false);
+    let ok_computer = answer(&store, &radiohead, "OK Computer").await;
+    let kid_a = answer(&store, &radiohead, "Kid A").await;
+    let submitted = choice_question(&store, &radiohead, "Which album has Idioteque?", &kid_a, &[&ok_computer], Some(fan.id)).await;
+
+    let query = format!("mutation {{ rejectQuestion(questionId: \"{}\", reason: \"Duplicate.\") {{ status moderationReason }} }}", submitted.id);
+
+    let response = execute(&query, &context(&store, Some(fan.id))).await;
+    assert_eq!(error_message(&response), "You are not allowed to do that");
+
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    assert_eq!(response["data"]["rejectQuestion"], json!({ "status": "REJECTED", "moderationReason": "Duplicate." }));
+
+    let response = execute("{ notifications { kind message } }", &context(&store, Some(fan.id))).await;
+    assert_eq!(response["data"]["notifications"], json!([
+        { "kind": "QUESTION_REJECTED", "message": "Your question \"Which album has Idioteque?\" was rejected: Duplicate." }
+    ]));
+
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    assert_eq!(error_message(&response), "question isn't waiting for moderation.");
+}
+
+#[actix_rt::test]
+async fn test_tag_question() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+    let ok_computer = answer(&store, &radiohead, "OK Computer").await;
+    let kid_a = answer(&store, &radiohead, "Kid A").await;
+    let tagged = choice_question(&store, &radiohead, "Which album has Idioteque?", &kid_a, &[&ok_computer], None).await;
+    choice_question(&store, &radiohead, "Which album has Karma Police?", &ok_computer, &[&kid_a], None).await;
+
+    let query = format!("mutation {{ tagQuestion(questionId: \"{}\", tags: [\" Lyrics \", \"2000s\"]) {{ name kind }} }}", tagged.id);
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    assert_eq!(response["data"]["tagQuestion"], json!([
+        { "name": "2000s", "kind": "ERA" },
+        { "name": "lyrics", "kind": "TOPIC" },
+    ]));
+
+    let query = format!("{{ questions(bandId: \"{}\", tags: [\"LYRICS\", \"2000s\"]) {{ id }} }}", radiohead.id);
+    let response = execute(&query, &context(&store, None)).await;
+    assert_eq!(response["data"]["questions"], json!([{ "id": tagged.id.to_string() }]));
+
+    let query = format!("mutation {{ tagQuestion(questionId: \"{}\", tags: [\"lyrics\"], kind: ALBUM) {{ name }} }}", tagged.id);
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    assert_eq!(error_message(&response), "tag \"lyrics\" is already a topic tag.");
+}
+
+#[actix_rt::test]
+async fn test_create_question_with_media() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+    let kid_a = answer(&store, &radiohead, "Kid A").await;
+    let media = MediaStore::create(&*store, CreateMedia {
+        key: "idioteque.mp3".to_string(),
+        content_type: "audio/mpeg".to_string(),
+        size_bytes: 1024,
+        uploaded_by: owner.id,
+    }).await.unwrap();
+
+    let mutation = |content: &str, media_id: Uuid| format!(
+        "mutation {{ createQuestion(input: {{ content: \"{}\", correctAnswerId: \"{}\", bandId: \"{}\", kind: FREE_TEXT, mediaId: \"{}\" }}) {{ content media {{ id }} }} }}",
+        content, kid_a.id, radiohead.id, media_id,
+    );
+
+    let response = execute(&mutation("Which album is this from?", media.id), &context(&store, Some(owner.id))).await;
+    assert_eq!(response["data"]["createQuestion"], json!({
+        "content": "Which album is this from?",
+        "media": { "id": media.id.to_string() },
+    }));
+
+    let response = execute(&mutation("And this one?", Uuid::new_v4()), &context(&store, Some(owner.id))).await;
+    assert_eq!(
+        error_message(&response),
+        format!("band with id {}, or the referenced answer or media, doesn't exist.", radiohead.id),
+    );
+}
+
+#[actix_rt::test]
+async fn test_play_quiz() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+    let fan = store.add_user("fan", false);
+    let ok_computer = answer(&store, &radiohead, "OK Computer").await;
+    let kid_a = answer(&store, &radiohead, "Kid A").await;
+    let amnesiac = answer(&store, &radiohead, "Amnesiac").await;
+    let question = choice_question(&store, &radiohead, "Which album has Idioteque?", &kid_a, &[&ok_computer, &amnesiac], None).await;
+
+    let query = format!(
+        "mutation {{ createQuiz(input: {{ bandId: \"{}\", title: \" Kid A \", questionIds: [\"{}\"] }}) {{ id title status version }} }}",
+        radiohead.id, question.id,
+    );
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    let quiz = &response["data"]["createQuiz"];
+    assert_eq!((&quiz["title"], &quiz["status"], &quiz["version"]), (&json!("Kid A"), &json!("DRAFT"), &json!(1)));
+    let quiz_id = quiz["id"].as_str().unwrap().to_string();
+
+    let start = format!("mutation {{ startQuiz(quizId: \"{}\") {{ id questionCount }} }}", quiz_id);
+    let response = execute(&start, &context(&store, Some(fan.id))).await;
+    assert_eq!(error_message(&response), "only published quizzes can be played.");
+
+    let query = format!("mutation {{ setQuizStatus(quizId: \"{}\", status: PUBLISHED) {{ status version }} }}", quiz_id);
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    assert_eq!(response["data"]["setQuizStatus"], json!({ "status": "PUBLISHED", "version": 2 }));
+
+    let admin = store.add_user("admin", true);
+    let query = format!("{{ auditLog(filter: {{ targetId: \"{}\" }}) {{ action }} }}", quiz_id);
+    let response = execute(&query, &context(&store, Some(admin.id))).await;
+    assert_eq!(response["data"]["auditLog"], json!([{ "action": "UPDATE" }, { "action": "CREATE" }]));
+
+    let response = execute(&start, &context(&store, Some(fan.id))).await;
+    assert_eq!(response["data"]["startQuiz"]["questionCount"], json!(1));
+    let attempt_id = response["data"]["startQuiz"]["id"].as_str().unwrap().to_string();
+
+    let query = format!("mutation {{ nextQuestion(attemptId: \"{}\") {{ position content choices {{ token content }} }} }}", attempt_id);
+    let response = execute(&query, &context(&store, Some(fan.id))).await;
+    let served = &response["data"]["nextQuestion"];
+    assert_eq!(served["content"], json!("Which album has Idioteque?"));
+    let mut contents: Vec<&str> = served["choices"].as_array().unwrap().iter().map(|choice| choice["content"].as_str().unwrap()).collect();
+    contents.sort();
+    assert_eq!(contents, vec!["Amnesiac", "Kid A", "OK Computer"]);
+    let token = served["choices"].as_array().unwrap().iter()
+        .find(|choice| choice["content"] == json!("Kid A"))
+        .map(|choice| choice["token"].as_str().unwrap().to_string())
+        .unwrap();
+
+    let answer = format!(
+        "mutation {{ submitAttemptAnswer(attemptId: \"{}\", input: {{ position: 0, choiceToken: \"{}\" }}) {{ accepted }} }}",
+        attempt_id, token,
+    );
+    let response = execute(&answer, &context(&store, Some(fan.id))).await;
+    assert_eq!(response["data"]["submitAttemptAnswer"], json!({ "accepted": true }));
+
+    let response = execute(&answer, &context(&store, Some(fan.id))).await;
+    assert_eq!(error_message(&response), "question was already answered in this attempt.");
+
+    let query = format!("mutation {{ finishAttempt(attemptId: \"{}\") {{ answeredCount finishedAt }} }}", attempt_id);
+    let response = execute(&query, &context(&store, Some(fan.id))).await;
+    assert_eq!(response["data"]["finishAttempt"]["answeredCount"], json!(1));
+    assert!(response["data"]["finishAttempt"]["finishedAt"].is_string());
+
+    let query = format!("mutation {{ deleteQuiz(quizId: \"{}\") }}", quiz_id);
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    assert_eq!(error_message(&response), "quiz has been played, archive it instead.");
+}
+
+#[actix_rt::test]
+async fn test_play_question_without_distractors() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+    let fan = store.add_user("fan", false);
+    let kid_a = answer(&store, &radiohead, "Kid A").await;
+    let question = choice_question(&store, &radiohead, "Which album has Idioteque?", &kid_a, &[], None).await;
+
+    let quiz = QuizStore::create(&*store, CreateQuiz {
+        band_id: radiohead.id,
+        title: "Kid A".to_string(),
+        description: None,
+        time_limit_seconds: None,
+        seconds_per_question: None,
+        fifty_fifty_lifelines: None,
+        hint_lifelines: None,
+        scoring_rules: None,
+        question_ids: Some(vec![question.id]),
+    }, owner.id, None).await.unwrap();
+    QuizStore::set_status(&*store, &quiz, QuizStatus::Published).await.unwrap();
+
+    let start = format!("mutation {{ startQuiz(quizId: \"{}\") {{ id }} }}", quiz.id);
+    let response = execute(&start, &context(&store, Some(fan.id))).await;
+    let attempt_id = response["data"]["startQuiz"]["id"].as_str().unwrap().to_string();
+
+    let query = format!("mutation {{ nextQuestion(attemptId: \"{}\") {{ choices {{ token content }} }} }}", attempt_id);
+    let response = execute(&query, &context(&store, Some(fan.id))).await;
+    let choices = response["data"]["nextQuestion"]["choices"].as_array().unwrap();
+    assert_eq!(choices.len(), 1, "The correct answer should be dealt on its own");
+    assert_eq!(choices[0]["content"], json!("Kid A"));
+
+    let answer = format!(
+        "mutation {{ submitAttemptAnswer(attemptId: \"{}\", input: {{ position: 0, choiceToken: {} }}) {{ accepted }} }}",
+        attempt_id, choices[0]["token"],
+    );
+    let response = execute(&answer, &context(&store, Some(fan.id))).await;
+    assert_eq!(response["data"]["submitAttemptAnswer"], json!({ "accepted": true }));
+}
+
+#[actix_rt::test]
+async fn test_stale_version_conflicts() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+    let ok_computer = answer(&store, &radiohead, "OK Computer").await;
+    let kid_a = answer(&store, &radiohead, "Kid A").await;
+    let question = choice_question(&store, &radiohead, "Which album has Idioteque?", &kid_a, &[&ok_computer], None).await;
+
+    let update = |content: &str| format!(
+        "mutation {{ updateQuestion(input: {{ id: \"{}\", version: 1, content: \"{}\" }}) {{ content version }} }}",
+        question.id, content,
+    );
+
+    let response = execute(&update("Which album opens with Everything in Its Right Place?"), &context(&store, Some(owner.id))).await;
+    assert_eq!(response["data"]["updateQuestion"], json!({ "content": "Which album opens with Everything in Its Right Place?", "version": 2 }));
+
+    let response = execute(&update("Which album closes with Motion Picture Soundtrack?"), &context(&store, Some(owner.id))).await;
+    assert_eq!(error_message(&response), "question was changed since, it's now at version 2.");
+    let stored = QuestionStore::get(&*store, question.id).await.unwrap();
+    assert_eq!(stored.content, "Which album opens with Everything in Its Right Place?");
+
+    let quiz = QuizStore::create(&*store, CreateQuiz {
+        band_id: radiohead.id,
+        title: "Kid A".to_string(),
+        description: None,
+        time_limit_seconds: None,
+        seconds_per_question: None,
+        fifty_fifty_lifelines: None,
+        hint_lifelines: None,
+        scoring_rules: None,
+        question_ids: None,
+    }, owner.id, None).await.unwrap();
+
+    let update = |title: &str| format!("mutation {{ updateQuiz(input: {{ id: \"{}\", version: 1, title: \"{}\" }}) {{ title version }} }}", quiz.id, title);
+
+    let response = execute(&update("Kid A Mnesia"), &context(&store, Some(owner.id))).await;
+    assert_eq!(response["data"]["updateQuiz"], json!({ "title": "Kid A Mnesia", "version": 2 }));
+
+    let response = execute(&update("Amnesiac"), &context(&store, Some(owner.id))).await;
+    assert_eq!(error_message(&response), "quiz was changed since version 1.");
+    assert_eq!(QuizStore::get(&*store, quiz.id).await.unwrap().title, "Kid A Mnesia");
+}
+
+#[actix_rt::test]
+async fn test_update_quiz_scoring_rules() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+
+    let query = format!(
+        "mutation {{ createQuiz(input: {{ bandId: \"{}\", title: \"Kid A\", scoringRules: {{ pointsPerQuestion: 5 }} }}) {{ id scoringRules {{ id pointsPerQuestion }} }} }}",
+        radiohead.id,
+    );
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    let quiz = &response["data"]["createQuiz"];
+    let rules_id = quiz["scoringRules"]["id"].clone();
+
+    let update = |version: i32, fields: &str| format!(
+        "mutation {{ updateQuiz(input: {{ id: {}, version: {}, {} }}) {{ scoringRules {{ id pointsPerQuestion }} }} }}",
+        quiz["id"], version, fields,
+    );
+
+    let response = execute(&update(1, "title: \"Kid A Mnesia\""), &context(&store, Some(owner.id))).await;
+    assert_eq!(response["data"]["updateQuiz"]["scoringRules"], json!({ "id": rules_id, "pointsPerQuestion": 5 }), "Rules left out should be kept");
+
+    let response = execute(&update(2, "scoringRules: { pointsPerQuestion: 20 }"), &context(&store, Some(owner.id))).await;
+    assert_eq!(response["data"]["updateQuiz"]["scoringRules"], json!({ "id": rules_id, "pointsPerQuestion": 20 }), "The quiz's rules should be rewritten in place");
+}
+
+#[actix_rt::test]
+async fn test_reports_hide_question() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+    let admin = store.add_user("admin", true);
+    let ok_computer = answer(&store, &radiohead, "OK Computer").await;
+    let kid_a = answer(&store, &radiohead, "Kid A").await;
+    let question = choice_question(&store, &radiohead, "Which album has Idioteque?", &kid_a, &[&ok_computer], None).await;
+
+    let report = format!("mutation {{ reportContent(targetType: QUESTION, targetId: \"{}\", reason: WRONG_ANSWER) {{ status }} }}", question.id);
+    let visible = format!("{{ question(id: \"{}\") {{ hiddenAt }} }}", question.id);
+
+    for number in 0..HIDE_THRESHOLD {
+        let response = execute(&visible, &context(&store, None)).await;
+        assert_eq!(response["data"]["question"], json!({ "hiddenAt": null }), "Hidden after {} reports", number);
+
+        let fan = store.add_user(&format!("fan {}", number), false);
+        let response = execute(&report, &context(&store, Some(fan.id))).await;
+        assert_eq!(response["data"]["reportContent"], json!({ "status": "OPEN" }));
+    }
+
+    let response = execute(&visible, &context(&store, None)).await;
+    assert_eq!(error_message(&response), "The requested item was not found");
+    let response = execute(&visible, &context(&store, Some(owner.id))).await;
+    assert!(response["data"]["question"]["hiddenAt"].is_string(), "Authors should still see the hidden question");
+
+    let query = format!("{{ auditLog(filter: {{ targetId: \"{}\" }}) {{ action }} }}", question.id);
+    let response = execute(&query, &context(&store, Some(admin.id))).await;
+    assert_eq!(response["data"]["auditLog"], json!([{ "action": "HIDE" }]), "Only the report that hid the question should be audited");
+}
+
+#[actix_rt::test]
+async fn test_delete_and_restore_question() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+    let admin = store.add_user("admin", true);
+    let ok_computer = answer(&store, &radiohead, "OK Computer").await;
+    let kid_a = answer(&store, &radiohead, "Kid A").await;
+    let deleted = choice_question(&store, &radiohead, "Which album has Idioteque?", &kid_a, &[&ok_computer], None).await;
+    let kept = choice_question(&store, &radiohead, "Which album has Karma Police?", &ok_computer, &[&kid_a], None).await;
+    let query = format!("mutation {{ tagQuestion(questionId: \"{}\", tags: [\"lyrics\"]) {{ name }} }}", deleted.id);
+    execute(&query, &context(&store, Some(owner.id))).await;
+
+    let query = format!("mutation {{ deleteQuestion(id: \"{}\") {{ deletedAt }} }}", deleted.id);
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    assert!(response["data"]["deleteQuestion"]["deletedAt"].is_string());
+
+    let query = format!("{{ question(id: \"{}\") {{ id }} }}", deleted.id);
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    assert_eq!(error_message(&response), "The requested item was not found");
+    let response = execute("{ questions { id } }", &context(&store, None)).await;
+    assert_eq!(response["data"]["questions"], json!([{ "id": kept.id.to_string() }]));
+    let response = execute("{ questions(tags: [\"lyrics\"]) { id } }", &context(&store, None)).await;
+    assert_eq!(response["data"]["questions"], json!([]));
+
+    let restore = format!("mutation {{ restoreQuestion(id: \"{}\") {{ deletedAt }} }}", deleted.id);
+    let response = execute(&restore, &context(&store, Some(owner.id))).await;
+    assert_eq!(error_message(&response), "You are not allowed to do that");
+
+    let response = execute(&restore, &context(&store, Some(admin.id))).await;
+    assert_eq!(response["data"]["restoreQuestion"], json!({ "deletedAt": null }));
+    let response = execute("{ questions(tags: [\"lyrics\"]) { id } }", &context(&store, None)).await;
+    assert_eq!(response["data"]["questions"], json!([{ "id": deleted.id.to_string() }]));
+
+    let response = execute(&restore, &context(&store, Some(admin.id))).await;
+    assert_eq!(error_message(&response), "question isn't deleted, or its band is.");
+}
+
+#[actix_rt::test]
+async fn test_delete_and_restore_band() {
+    let store = MemoryStore::new();
+    let (owner, radiohead) = band(&store, "Radiohead").await;
+    band(&store, "Blur").await;
+    let admin = store.add_user("admin", true);
+    let ok_computer = answer(&store, &radiohead, "OK Computer").await;
+    let kid_a = answer(&store, &radiohead, "Kid A").await;
+    let question = choice_question(&store, &radiohead, "Which album has Idioteque?", &kid_a, &[&ok_computer], None).await;
+
+    let query = format!("mutation {{ deleteBand(id: \"{}\") {{ name }} }}", radiohead.id);
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    assert_eq!(response["data"]["deleteBand"], json!({ "name": "Radiohead" }));
+
+    let response = execute("{ bands { name } }", &context(&store, None)).await;
+    assert_eq!(response["data"]["bands"], json!([{ "name": "Blur" }]));
+    let query = format!("{{ band(id: \"{}\") {{ name }} }}", radiohead.id);
+    let response = execute(&query, &context(&store, Some(owner.id))).await;
+    assert_eq!(error_message(&response), "The requested item was not found");
+    let response = execute("{ questions { id } }", &context(&store, None)).await;
+    assert_eq!(response["data"]["questions"], json!([]), "The band's questions should be deleted with it");
+
+    let query = format!("mutation {{ restoreQuestion(id: \"{}\") {{ id }} }}", question.id);
+    let response = execute(&query, &context(&store, Some(admin.id))).await;
+    assert_eq!(error_message(&response), "question isn't deleted, or its band is.");
+
+    let query = format!("mutation {{ restoreBand(id: \"{}\") {{ name }} }}", radiohead.id);
+    let response = execute(&query, &context(&store, Some(admin.id))).await;
+    assert_eq!(response["data"]["restoreBand"], json!({ "name": "Radiohead" }));
+    let response = execute("{ questions { id } }", &context(&store, None)).await;
+    assert_eq!(response["data"]["questions"], json!([{ "id": question.id.to_string() }]), "Restoring the band should restore its questions");
+}
+
+#[actix_rt::test]
+async fn test_delete_and_restore_user() {
+    let store = MemoryStore::new();
+    let fan = store.add_user("fan", false);
+    let other = store.add_user("other", false);
+    let admin = store.add_user("admin", true);
+
+    let query = format!("mutation {{ deleteUser(id: \"{}\") {{ username }} }}", fan.id);
+    let response = execute(&query, &context(&store, Some(other.id))).await;
+    assert_eq!(error_message(&response), "You are not allowed to do that");
+
+    let response = execute(&query, &context(&store, Some(fan.id))).await;
+    assert_eq!(response["data"]["deleteUser"], json!({ "username": "fan" }));
+
+    let query = format!("{{ user(id: \"{}\") {{ username }} }}", fan.id);
+    let response = execute(&query, &context(&store, Some(admin.id))).await;
+    assert_eq!(error_message(&response), "The requested item was not found");
+    let response = execute("{ users { username } }", &context(&store, None)).await;
+    assert_eq!(response["data"]["users"], json!([{ "username": "other" }, { "username": "admin" }]));
+
+    let query = format!("mutation {{ restoreUser(id: \"{}\") {{ username }} }}", fan.id);
+    let response = execute(&query, &context(&store, Some(admin.id))).await;
+    assert_eq!(response["data"]["restoreUser"], json!({ "username": "fan" }));
+
+    let response = execute(&query, &context(&store, Some(admin.id))).await;
+    assert_eq!(error_message(&response), "user isn't deleted.");
+}
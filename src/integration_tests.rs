@@ -1,10 +1,32 @@
 /// Integration Tests
 
-use crate::config::Config;
-use crate::handlers::app_config;
+use crate::avatar::AvatarService;
+use crate::config::{Config, HashingService};
+use crate::handlers::{app_config, graphql::{create_schema, Context}};
+use crate::models::{
+    answer::CreateAnswer,
+    band::CreateBand,
+    question::{CreateQuestion, QuestionKind},
+    quiz::{CreateQuiz, QuizStatus},
+    user::CreateUser,
+};
+use crate::purge::purge_deleted;
+use crate::repositories::{
+    answer::AnswerRepository,
+    band::BandRepository,
+    question::QuestionRepository,
+    quiz::QuizRepository,
+    stores::{AnswerStore, BandStore, PgStores, QuestionStore, QuizStore, UserStore},
+    user::UserRepository,
+};
+use crate::storage::local::LocalStorage;
 use actix_web::{test, App};
 use deadpool_postgres::Pool;
+use juniper::http::GraphQLRequest;
 use lazy_static::lazy_static;
+use serde_json::Value;
+use std::{env, sync::Arc};
+use uuid::Uuid;
 
 /// Holds the configuration and connection pool for tests
 struct TestConfig {
@@ -22,6 +44,99 @@ lazy_static! {
     };
 }
 
+/// A Postgres backed context for `user_id`, as the GraphQL handler builds it
+fn context(user_id: Uuid) -> Context {
+    Context {
+        db: Arc::new(CONFIG.pool.clone()).into(),
+        stores: Arc::new(PgStores),
+        hashing: Arc::new(HashingService::new("secret".to_string())),
+        storage: Arc::new(LocalStorage::new(env::temp_dir(), "http://localhost:8080/files".to_string())),
+        avatars: Arc::new(AvatarService::new("http://localhost:8080".to_string())),
+        user_id: Some(user_id),
+        request_id: Uuid::new_v4(),
+        ip: None,
+    }
+}
+
+async fn execute(query: &str, context: &Context) -> Value {
+    let schema = create_schema();
+    let request = GraphQLRequest::new(query.to_string(), None, None);
+    let response = request.execute(&schema, context).await;
+
+    serde_json::to_value(&response).unwrap()
+}
+
+/// A published quiz with one choice question and a fan to play it, returns the fan's, the quiz's and the question's ids
+async fn published_quiz(suffix: &str) -> (Uuid, Uuid, Uuid) {
+    let pool = Arc::new(CONFIG.pool.clone());
+    let users = UserRepository::new(pool.clone());
+    let answers = AnswerRepository::new(pool.clone());
+    let quizzes = QuizRepository::new(pool.clone());
+    let hashing = Arc::new(HashingService::new("secret".to_string()));
+
+    let create_user = |name: &str| CreateUser {
+        username: format!("{} {}", name, suffix),
+        email: format!("{}-{}@example.com", name, suffix),
+        password: "password".to_string(),
+        bio: None,
+        image: None,
+    };
+    let owner = users.create(create_user("owner"), hashing.clone()).await.unwrap();
+    let fan = users.create(create_user("fan"), hashing).await.unwrap();
+    let band = BandRepository::new(pool.clone()).create(CreateBand { name: format!("Radiohead {}", suffix) }, owner.id).await.unwrap();
+
+    let mut answer_ids = vec![];
+    for content in &["Kid A", "OK Computer", "Amnesiac"] {
+        let answer = answers.create(CreateAnswer { band_id: band.id, content: content.to_string(), aliases: None }).await.unwrap();
+        answer_ids.push(answer.id);
+    }
+    let question = QuestionRepository::new(pool.clone()).create(CreateQuestion {
+        content: format!("Which album has Idioteque? {}", suffix),
+        correct_answer_id: Some(answer_ids[0]),
+        distractor_ids: Some(answer_ids[1..].to_vec()),
+        band_id: band.id,
+        kind: None,
+        max_edit_distance: None,
+        numeric_value: None,
+        numeric_tolerance: None,
+        numeric_curve: None,
+        media_id: None,
+        clip_start_ms: None,
+        clip_end_ms: None,
+        hint: None,
+        explanation: None,
+        source: None,
+        tags: None,
+        force: Some(true),
+    }, None).await.unwrap();
+
+    let quiz = quizzes.create(CreateQuiz {
+        band_id: band.id,
+        title: "Kid A".to_string(),
+        description: None,
+        time_limit_seconds: None,
+        seconds_per_question: None,
+        fifty_fifty_lifelines: None,
+        hint_lifelines: None,
+        scoring_rules: None,
+        question_ids: Some(vec![question.id]),
+    }, owner.id, None).await.unwrap();
+    quizzes.set_status(&quiz, QuizStatus::Published).await.unwrap();
+
+    (fan.id, quiz.id, question.id)
+}
+
+/// Makes every `operation` on `table` matching `condition` fail until the returned statement is run
+async fn fail_on(name: &str, operation: &str, table: &str, condition: &str) -> String {
+    let client = CONFIG.pool.get().await.unwrap();
+    client.batch_execute(&format!("
+        create function {name}() returns trigger as $$ begin raise exception 'failing on purpose'; end $$ language plpgsql;
+        create trigger {name} before {operation} on {table} for each row when ({condition}) execute procedure {name}();
+    ", name = name, operation = operation, table = table, condition = condition)).await.unwrap();
+
+    format!("drop trigger {name} on {table}; drop function {name}();", name = name, table = table)
+}
+
 #[actix_rt::test]
 async fn test_health() {
     let app = App::new().data(CONFIG.pool.clone()).configure(app_config);
@@ -34,3 +149,174 @@ async fn test_health() {
 
     assert_eq!(res.status(), 200, "GET / should return 200");
 }
+
+#[actix_rt::test]
+async fn test_purge_deleted() {
+    let pool = Arc::new(CONFIG.pool.clone());
+    let users = UserRepository::new(pool.clone());
+    let bands = BandRepository::new(pool.clone());
+    let questions = QuestionRepository::new(pool.clone());
+    let hashing = Arc::new(HashingService::new("secret".to_string()));
+    // The database outlives the test, so names have to be unique
+    let suffix = Uuid::new_v4().to_simple().to_string();
+
+    let create_user = |name: &str| CreateUser {
+        username: format!("{} {}", name, suffix),
+        email: format!("{}-{}@example.com", name, suffix),
+        password: "password".to_string(),
+        bio: None,
+        image: None,
+    };
+    let owner = users.create(create_user("owner"), hashing.clone()).await.unwrap();
+    let fan = users.create(create_user("fan"), hashing.clone()).await.unwrap();
+
+    let mut question_ids = vec![];
+    let mut band_ids = vec![];
+    for name in &["Radiohead", "Blur"] {
+        let band = bands.create(CreateBand { name: format!("{} {}", name, suffix) }, owner.id).await.unwrap();
+        let answer = AnswerRepository::new(pool.clone()).create(CreateAnswer { band_id: band.id, content: "Kid A".to_string(), aliases: None }).await.unwrap();
+        let question = questions.create(CreateQuestion {
+            content: "Which album has Idioteque?".to_string(),
+            correct_answer_id: Some(answer.id),
+            distractor_ids: None,
+            band_id: band.id,
+            kind: Some(QuestionKind::FreeText),
+            max_edit_distance: None,
+            numeric_value: None,
+            numeric_tolerance: None,
+            numeric_curve: None,
+            media_id: None,
+            clip_start_ms: None,
+            clip_end_ms: None,
+            hint: None,
+            explanation: None,
+            source: None,
+            tags: None,
+            force: Some(true),
+        }, None).await.unwrap();
+
+        bands.soft_delete(band.id).await.unwrap();
+        band_ids.push(band.id);
+        question_ids.push(question.id);
+    }
+    users.soft_delete(fan.id).await.unwrap();
+
+    // Only the first band, its question and the fan are past the retention period
+    let client = pool.get().await.unwrap();
+    client.execute("update bands set deleted_at = deleted_at - interval '31 days' where id = $1", &[&band_ids[0]]).await.unwrap();
+    client.execute("update questions set deleted_at = deleted_at - interval '31 days' where band_id = $1", &[&band_ids[0]]).await.unwrap();
+    client.execute("update users set deleted_at = deleted_at - interval '31 days' where id = $1", &[&fan.id]).await.unwrap();
+
+    let report = purge_deleted(pool.clone(), 30).await.unwrap();
+    assert!(report.questions >= 1 && report.bands >= 1 && report.users >= 1, "Purged {:?}", report);
+
+    let count = |table: &str, id: Uuid| {
+        let client = &client;
+        let query = format!("select count(*) from {} where id = $1", table);
+        async move { client.query_one(query.as_str(), &[&id]).await.unwrap().get::<_, i64>(0) }
+    };
+    assert_eq!(count("questions", question_ids[0]).await, 0, "The old question should be purged");
+    assert_eq!(count("bands", band_ids[0]).await, 0, "Its band should follow in the same run");
+    assert_eq!(count("users", fan.id).await, 0, "The old account should be purged");
+    assert_eq!(count("questions", question_ids[1]).await, 1, "A recently deleted question should be kept");
+    assert_eq!(count("bands", band_ids[1]).await, 1, "A recently deleted band should be kept");
+    assert_eq!(count("users", owner.id).await, 1, "Users who aren't deleted should be kept");
+
+    let restored = bands.restore(band_ids[1]).await.unwrap();
+    assert!(restored.deleted_at.is_none());
+    assert!(questions.get(question_ids[1]).await.is_ok(), "Restoring the band should restore its question");
+}
+
+#[actix_rt::test]
+async fn test_scope_answers_to_bands_migration() {
+    // Replays the migration against the tables as they were before it, in a schema of its own
+    let client = CONFIG.pool.get().await.unwrap();
+    client.batch_execute("
+        begin;
+        create schema scope_answers_test;
+        set local search_path to scope_answers_test, public;
+
+        create table bands (id uuid primary key, created_at timestamp not null);
+        create table answers (
+            id uuid primary key,
+            content varchar not null unique,
+            created_at timestamp not null default current_timestamp,
+            updated_at timestamp not null default current_timestamp
+        );
+        create table answer_aliases (
+            id uuid default uuid_generate_v4() primary key,
+            answer_id uuid not null references answers(id),
+            content varchar not null,
+            created_at timestamp not null default current_timestamp,
+            updated_at timestamp not null default current_timestamp
+        );
+        create table questions (id uuid primary key, band_id uuid not null, correct_answer_id uuid null);
+        create table question_choices (question_id uuid not null, answer_id uuid not null, match_answer_id uuid null);
+
+        insert into bands values
+            ('00000000-0000-0000-0000-00000000000a', '2020-01-01'),
+            ('00000000-0000-0000-0000-00000000000b', '2020-02-01');
+        insert into answers (id, content) values ('00000000-0000-0000-0000-000000000001', 'Kid A');
+        insert into answer_aliases (answer_id, content) values ('00000000-0000-0000-0000-000000000001', 'KidA');
+        insert into questions values
+            ('00000000-0000-0000-0000-0000000000a1', '00000000-0000-0000-0000-00000000000a', '00000000-0000-0000-0000-000000000001'),
+            ('00000000-0000-0000-0000-0000000000b1', '00000000-0000-0000-0000-00000000000b', '00000000-0000-0000-0000-000000000001');
+    ").await.unwrap();
+
+    client.batch_execute(include_str!("../migrations/2020-05-13-000000_scope_answers_to_bands/up.sql")).await.unwrap();
+
+    let rows = client.query("
+        select q.band_id, a.id, a.band_id, (select count(*) from answer_aliases al where al.answer_id = a.id)
+        from questions q join answers a on a.id = q.correct_answer_id
+        order by q.band_id
+    ", &[]).await.unwrap();
+    assert_eq!(rows.len(), 2);
+    for row in &rows {
+        assert_eq!(row.get::<_, Option<Uuid>>(2), Some(row.get::<_, Uuid>(0)), "Each question should point at its own band's answer");
+        assert_eq!(row.get::<_, i64>(3), 1, "Each copy should keep the aliases");
+    }
+    assert_eq!(rows[0].get::<_, Uuid>(1), Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap(), "The oldest band should keep the original row");
+    assert_ne!(rows[0].get::<_, Uuid>(1), rows[1].get::<_, Uuid>(1), "The other band should get a copy");
+
+    client.batch_execute("rollback").await.unwrap();
+}
+
+#[actix_rt::test]
+async fn test_start_quiz_rolls_back() {
+    let suffix = Uuid::new_v4().to_simple().to_string();
+    let (fan_id, quiz_id, question_id) = published_quiz(&suffix).await;
+
+    let cleanup = fail_on(&format!("fail_start_{}", suffix), "insert", "attempt_questions", &format!("new.question_id = '{}'", question_id)).await;
+    let query = format!("mutation {{ startQuiz(quizId: \"{}\") {{ id }} }}", quiz_id);
+    let response = execute(&query, &context(fan_id)).await;
+
+    let client = CONFIG.pool.get().await.unwrap();
+    client.batch_execute(&cleanup).await.unwrap();
+    assert!(response["errors"].is_array(), "Starting the quiz should fail");
+    let attempts: i64 = client.query_one("select count(*) from attempts where user_id = $1", &[&fan_id]).await.unwrap().get(0);
+    assert_eq!(attempts, 0, "No attempt should be left without its questions");
+}
+
+#[actix_rt::test]
+async fn test_use_lifeline_rolls_back() {
+    let suffix = Uuid::new_v4().to_simple().to_string();
+    let (fan_id, quiz_id, _) = published_quiz(&suffix).await;
+
+    let query = format!("mutation {{ startQuiz(quizId: \"{}\") {{ id }} }}", quiz_id);
+    let response = execute(&query, &context(fan_id)).await;
+    let attempt_id = response["data"]["startQuiz"]["id"].as_str().unwrap().to_string();
+    let query = format!("mutation {{ nextQuestion(attemptId: \"{}\") {{ position }} }}", attempt_id);
+    execute(&query, &context(fan_id)).await;
+
+    // Eliminating the choices fails after the lifeline was spent
+    let cleanup = fail_on(&format!("fail_lifeline_{}", suffix), "update", "attempt_choices", &format!("new.attempt_id = '{}'", attempt_id)).await;
+    let query = format!("mutation {{ useLifeline(attemptId: \"{}\", position: 0, kind: FIFTY_FIFTY) {{ position }} }}", attempt_id);
+    let response = execute(&query, &context(fan_id)).await;
+
+    let client = CONFIG.pool.get().await.unwrap();
+    client.batch_execute(&cleanup).await.unwrap();
+    assert!(response["errors"].is_array(), "Using the lifeline should fail");
+    let attempt_id = Uuid::parse_str(&attempt_id).unwrap();
+    let lifelines: i64 = client.query_one("select count(*) from attempt_lifelines where attempt_id = $1", &[&attempt_id]).await.unwrap().get(0);
+    assert_eq!(lifelines, 0, "The lifeline should be given back");
+}
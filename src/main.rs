@@ -1,21 +1,43 @@
+mod avatar;
+mod bulk;
+mod cli;
 mod config;
 mod errors;
+mod grading;
 mod handlers;
 mod models;
+mod moderation;
+mod play;
+mod purge;
+mod reports;
 mod repositories;
+mod revisions;
+mod storage;
 
 use crate::config::Config;
 use crate::handlers::app_config;
 use actix_cors::Cors;
 use actix_web::{http::header, http::Method, middleware, App, HttpServer};
 use slog_scope::info;
+use std::sync::Arc;
 
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
     let config = Config::from_env().unwrap();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return cli::run(&config, &args).await;
+    }
+
     let pool = config.configure_pool();
     let hashing = config.hashing_service();
+    let storage = config.storage();
+    let upload_settings = config.upload_settings();
+    let proxy_settings = config.proxy_settings();
+    let avatars = config.avatar_service();
+
+    purge::spawn(Arc::new(pool.clone()), config.retention_settings());
 
     let host = config.server.host;
     let port = config.server.port;
@@ -38,6 +60,10 @@ async fn main() -> std::io::Result<()> {
             .wrap(middleware::Logger::default())
             .data(hashing.clone())
             .data(pool.clone())
+            .data(storage.clone())
+            .data(upload_settings.clone())
+            .data(proxy_settings.clone())
+            .data(avatars.clone())
             .configure(app_config)
     })
     .bind(server_addr)?
@@ -4,16 +4,37 @@ use uuid::Uuid;
 use tokio_pg_mapper_derive::PostgresMapper;
 use juniper::{GraphQLObject, GraphQLInputObject};
 
-#[derive(Clone, Serialize, Deserialize, PostgresMapper, GraphQLObject)]
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
 #[pg_mapper(table="answers")]
 pub struct Answer {
     pub id: Uuid,
     pub content: String,
+    /// Only answers nothing used before they were scoped to bands have none
+    pub band_id: Option<Uuid>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
 
 #[derive(GraphQLInputObject)]
 pub struct CreateAnswer {
+    pub band_id: Uuid,
     pub content: String,
-}
\ No newline at end of file
+    pub aliases: Option<Vec<String>>,
+}
+
+/// Alternative spelling accepted for free-text questions
+#[derive(Clone, Serialize, Deserialize, PostgresMapper, GraphQLObject)]
+#[pg_mapper(table="answer_aliases")]
+pub struct AnswerAlias {
+    pub id: Uuid,
+    pub answer_id: Uuid,
+    pub content: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(GraphQLInputObject)]
+pub struct CreateAnswerAlias {
+    pub answer_id: Uuid,
+    pub content: String,
+}
@@ -0,0 +1,150 @@
+/// Attempt model
+/// One play of a quiz, or of randomly picked questions, by a user
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tokio_pg_mapper_derive::PostgresMapper;
+use juniper::{GraphQLObject, GraphQLInputObject};
+use crate::models::{lifeline::LifelineKind, question::{Question, QuestionKind}};
+
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
+#[pg_mapper(table="attempts")]
+pub struct Attempt {
+    pub id: Uuid,
+    /// None for attempts on randomly picked questions
+    pub quiz_id: Option<Uuid>,
+    pub band_id: Uuid,
+    pub user_id: Uuid,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub score: f64,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// None for the default rules
+    pub scoring_rules_id: Option<Uuid>,
+    /// Why the attempt looks implausible, empty for most attempts
+    pub flag_reasons: Vec<String>,
+    pub flagged_at: Option<NaiveDateTime>,
+    /// Lifelines the attempt started with
+    pub fifty_fifty_lifelines: i32,
+    pub hint_lifelines: i32,
+}
+
+/// A question served in an attempt, with the player's response once answered
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
+#[pg_mapper(table="attempt_questions")]
+pub struct AttemptQuestion {
+    pub attempt_id: Uuid,
+    pub question_id: Uuid,
+    pub position: i32,
+    pub answer_id: Option<Uuid>,
+    pub answer_text: Option<String>,
+    pub answer_number: Option<f64>,
+    pub answer_ordering: Option<Vec<Uuid>>,
+    pub answer_pair_ids: Option<Vec<Uuid>>,
+    pub answer_pair_match_ids: Option<Vec<Uuid>>,
+    pub accepted: Option<bool>,
+    pub score: Option<f64>,
+    pub answered_at: Option<NaiveDateTime>,
+    /// When the question was first shown, answer times are measured from here
+    pub served_at: Option<NaiveDateTime>,
+}
+
+/// A choice as shown to one player, identified by a token that is only valid
+/// in that attempt so answer ids can't be shared between players
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
+#[pg_mapper(table="attempt_choices")]
+pub struct AttemptChoice {
+    pub token: Uuid,
+    pub attempt_id: Uuid,
+    pub question_id: Uuid,
+    pub answer_id: Uuid,
+    /// "match" for the right-hand side of matching questions
+    pub side: String,
+    pub position: i32,
+    /// Removed by the 50/50 lifeline
+    pub eliminated: bool,
+    /// Joined from answers
+    pub content: String,
+}
+
+#[derive(Clone, GraphQLObject)]
+pub struct ServedChoice {
+    pub token: Uuid,
+    pub content: String,
+}
+
+impl From<&AttemptChoice> for ServedChoice {
+    fn from(choice: &AttemptChoice) -> ServedChoice {
+        ServedChoice { token: choice.token, content: choice.content.clone() }
+    }
+}
+
+/// A question being played, without anything that gives the answer away.
+/// Players refer to it by its position in the attempt, not by its id.
+pub struct ServedQuestion {
+    pub question: Question,
+    pub position: i32,
+    pub served_at: NaiveDateTime,
+    /// Answers after this are rejected
+    pub deadline: Option<NaiveDateTime>,
+    /// Options of choice questions, items of ordering questions or the
+    /// left-hand side of matching questions, in shuffled order
+    pub choices: Vec<ServedChoice>,
+    /// Right-hand side of matching questions
+    pub match_choices: Vec<ServedChoice>,
+    /// The question's hint, once the hint lifeline was used on it
+    pub hint: Option<String>,
+    pub lifelines_used: Vec<LifelineKind>,
+}
+
+/// A question of a finished attempt next to its correct answer
+#[derive(Clone, Debug, PartialEq, GraphQLObject)]
+pub struct ReviewedQuestion {
+    pub question_id: Uuid,
+    pub position: i32,
+    pub content: String,
+    pub kind: QuestionKind,
+    /// The player's answer, items in order for ordering and matching questions,
+    /// empty if the question wasn't answered
+    pub given_answer: Vec<String>,
+    pub correct_answer: Vec<String>,
+    pub accepted: bool,
+    /// Credit earned, from 0 to 1
+    pub score: f64,
+    pub explanation: Option<String>,
+    pub source: Option<String>,
+}
+
+/// An answer during an attempt, referring to choices by their tokens
+#[derive(GraphQLInputObject)]
+pub struct AttemptSubmission {
+    /// Position of the question in the attempt, as served by nextQuestion
+    pub position: i32,
+    /// Chosen option for choice questions
+    pub choice_token: Option<Uuid>,
+    /// Typed answer for free-text questions
+    pub text: Option<String>,
+    /// Entered value for numeric questions
+    pub number: Option<f64>,
+    /// Choice tokens in the submitted order for ordering questions
+    pub ordering: Option<Vec<Uuid>>,
+    /// Submitted pairs for matching questions
+    pub pairs: Option<Vec<TokenPair>>,
+}
+
+#[derive(Clone, Copy, GraphQLInputObject)]
+pub struct TokenPair {
+    pub token: Uuid,
+    pub match_token: Uuid,
+}
+
+/// Random questions from a band instead of a curated quiz
+#[derive(GraphQLInputObject)]
+pub struct StartRandomQuiz {
+    pub band_id: Uuid,
+    /// Only questions carrying all of these tags
+    pub tags: Option<Vec<String>>,
+    pub question_count: Option<i32>,
+}
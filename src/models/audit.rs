@@ -0,0 +1,207 @@
+/// Audit log model
+/// An append-only record of who changed users, bands, questions and answers
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+use uuid::Uuid;
+use tokio_pg_mapper_derive::PostgresMapper;
+use juniper::{GraphQLEnum, GraphQLInputObject};
+use crate::models::report::ReportTargetType;
+
+/// Fields never copied into the log
+const REDACTED_FIELDS: &[&str] = &["password"];
+
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
+#[pg_mapper(table="audit_log")]
+pub struct AuditEntry {
+    pub id: Uuid,
+    /// None for anonymous requests like signing up
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub request_id: Uuid,
+    pub ip: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Approve,
+    Reject,
+    RequestChanges,
+    SuggestEdit,
+    AcceptEdit,
+    RejectEdit,
+    Revert,
+    AddAuthor,
+    Import,
+    /// Hidden after enough reports, or once a report was upheld
+    Hide,
+    /// Shown again after a report was dismissed
+    Show,
+    /// Soft deleted, purged once the retention period is over
+    Delete,
+    Restore,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Approve => "approve",
+            AuditAction::Reject => "reject",
+            AuditAction::RequestChanges => "request_changes",
+            AuditAction::SuggestEdit => "suggest_edit",
+            AuditAction::AcceptEdit => "accept_edit",
+            AuditAction::RejectEdit => "reject_edit",
+            AuditAction::Revert => "revert",
+            AuditAction::AddAuthor => "add_author",
+            AuditAction::Import => "import",
+            AuditAction::Hide => "hide",
+            AuditAction::Show => "show",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+        }
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(action: &str) -> Result<Self, Self::Err> {
+        match action {
+            "create" => Ok(AuditAction::Create),
+            "update" => Ok(AuditAction::Update),
+            "approve" => Ok(AuditAction::Approve),
+            "reject" => Ok(AuditAction::Reject),
+            "request_changes" => Ok(AuditAction::RequestChanges),
+            "suggest_edit" => Ok(AuditAction::SuggestEdit),
+            "accept_edit" => Ok(AuditAction::AcceptEdit),
+            "reject_edit" => Ok(AuditAction::RejectEdit),
+            "revert" => Ok(AuditAction::Revert),
+            "add_author" => Ok(AuditAction::AddAuthor),
+            "import" => Ok(AuditAction::Import),
+            "hide" => Ok(AuditAction::Hide),
+            "show" => Ok(AuditAction::Show),
+            "delete" => Ok(AuditAction::Delete),
+            "restore" => Ok(AuditAction::Restore),
+            _ => Err(format!("unknown audit action {}", action)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum AuditTargetType {
+    User,
+    Band,
+    Question,
+    Answer,
+    Quiz,
+}
+
+impl AuditTargetType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditTargetType::User => "user",
+            AuditTargetType::Band => "band",
+            AuditTargetType::Question => "question",
+            AuditTargetType::Answer => "answer",
+            AuditTargetType::Quiz => "quiz",
+        }
+    }
+}
+
+impl FromStr for AuditTargetType {
+    type Err = String;
+
+    fn from_str(target_type: &str) -> Result<Self, Self::Err> {
+        match target_type {
+            "user" => Ok(AuditTargetType::User),
+            "band" => Ok(AuditTargetType::Band),
+            "question" => Ok(AuditTargetType::Question),
+            "answer" => Ok(AuditTargetType::Answer),
+            "quiz" => Ok(AuditTargetType::Quiz),
+            _ => Err(format!("unknown audit target type {}", target_type)),
+        }
+    }
+}
+
+impl From<ReportTargetType> for AuditTargetType {
+    fn from(target_type: ReportTargetType) -> Self {
+        match target_type {
+            ReportTargetType::Question => AuditTargetType::Question,
+            ReportTargetType::Band => AuditTargetType::Band,
+            ReportTargetType::User => AuditTargetType::User,
+        }
+    }
+}
+
+/// Who did what to which target, and where the request came from
+pub struct NewAuditEntry {
+    pub actor_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub target_type: AuditTargetType,
+    pub target_id: Uuid,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub request_id: Uuid,
+    pub ip: Option<String>,
+}
+
+/// Every field left out matches all entries
+#[derive(Default, GraphQLInputObject)]
+pub struct AuditFilter {
+    pub actor_id: Option<Uuid>,
+    pub action: Option<AuditAction>,
+    pub target_type: Option<AuditTargetType>,
+    pub target_id: Option<Uuid>,
+    pub request_id: Option<Uuid>,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+}
+
+/// JSON of a logged value with secrets like password hashes removed
+pub fn snapshot<T: Serialize>(value: &T) -> Option<String> {
+    let mut value = serde_json::to_value(value).ok()?;
+    if let Value::Object(fields) = &mut value {
+        for field in REDACTED_FIELDS {
+            fields.remove(*field);
+        }
+    }
+
+    Some(value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::snapshot;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Account {
+        username: String,
+        password: String,
+    }
+
+    #[test]
+    fn test_snapshot_redacts_passwords() {
+        let account = Account { username: "thom".to_string(), password: "$argon2i$secret".to_string() };
+
+        let json = snapshot(&account).unwrap();
+        assert_eq!(json, r#"{"username":"thom"}"#);
+    }
+
+    #[test]
+    fn test_snapshot_of_other_values() {
+        assert_eq!(snapshot(&vec!["lyrics", "1990s"]).as_deref(), Some(r#"["lyrics","1990s"]"#));
+    }
+}
@@ -2,7 +2,8 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use tokio_pg_mapper_derive::PostgresMapper;
-use juniper::{GraphQLObject, GraphQLInputObject};
+use juniper::{GraphQLEnum, GraphQLObject, GraphQLInputObject};
+use std::str::FromStr;
 
 #[derive(Clone, Serialize, Deserialize, PostgresMapper, GraphQLObject)]
 #[pg_mapper(table="bands")]
@@ -12,10 +13,44 @@ pub struct Band {
     pub owner_id: Uuid,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    /// Set while the band is hidden from listings after being reported
+    pub hidden_at: Option<NaiveDateTime>,
+    /// Set once deleted, the band is purged after the retention period
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
+/// The logged in user becomes the band's owner
 #[derive(GraphQLInputObject)]
 pub struct CreateBand {
     pub name: String,
-    pub owner_id: Uuid,
-}
\ No newline at end of file
+}
+
+/// What a band member can do besides what fans can. The owner can do everything.
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum BandRole {
+    /// Writes questions and curates quizzes
+    Author,
+    /// Also approves or rejects questions submitted by fans
+    Moderator,
+}
+
+impl BandRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BandRole::Author => "author",
+            BandRole::Moderator => "moderator",
+        }
+    }
+}
+
+impl FromStr for BandRole {
+    type Err = String;
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role {
+            "author" => Ok(BandRole::Author),
+            "moderator" => Ok(BandRole::Moderator),
+            _ => Err(format!("unknown band role {}", role)),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::errors::{AppError, AppErrorType};
+use juniper::GraphQLObject;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Trigram similarity from which questions are flagged as likely duplicates
+pub const DUPLICATE_THRESHOLD: f64 = 0.6;
+
+#[derive(Clone, Debug, GraphQLObject)]
+pub struct SimilarQuestion {
+    pub id: Uuid,
+    pub content: String,
+    /// Trigram similarity between 0 and 1
+    pub similarity: f64,
+}
+
+/// Creating a question that looks like `similar` ones fails unless forced
+pub fn duplicate_error(similar: Vec<SimilarQuestion>) -> AppError {
+    let matches: Vec<String> = similar.iter()
+        .map(|question| format!("\"{}\" ({}, {:.0}% similar)", question.content, question.id, question.similarity * 100.0))
+        .collect();
+
+    AppError {
+        cause: None,
+        message: Some(format!("question looks like a duplicate of {}. Set force to create it anyway.", matches.join(", "))),
+        error_type: AppErrorType::DuplicateError(similar)
+    }
+}
+
+/// Two questions of the same band that look alike
+#[derive(Clone, Debug)]
+pub struct DuplicatePair {
+    pub band_id: Uuid,
+    pub first: SimilarQuestion,
+    pub second: SimilarQuestion,
+    pub similarity: f64,
+}
+
+/// Questions linked by pairwise similarity, each with its best match in the cluster
+#[derive(Clone, Debug, GraphQLObject)]
+pub struct DuplicateCluster {
+    pub band_id: Uuid,
+    pub questions: Vec<SimilarQuestion>,
+}
+
+fn root(parents: &mut HashMap<Uuid, Uuid>, id: Uuid) -> Uuid {
+    let parent = *parents.entry(id).or_insert(id);
+    if parent == id {
+        id
+    } else {
+        let root_id = root(parents, parent);
+        parents.insert(id, root_id);
+        root_id
+    }
+}
+
+/// Groups pairs into clusters of transitively similar questions, largest first
+pub fn clusters(pairs: &[DuplicatePair]) -> Vec<DuplicateCluster> {
+    let mut parents: HashMap<Uuid, Uuid> = HashMap::new();
+    for pair in pairs {
+        let first = root(&mut parents, pair.first.id);
+        let second = root(&mut parents, pair.second.id);
+        if first != second {
+            parents.insert(second, first);
+        }
+    }
+
+    let mut members: HashMap<Uuid, (Uuid, HashMap<Uuid, SimilarQuestion>)> = HashMap::new();
+    for pair in pairs {
+        let cluster_id = root(&mut parents, pair.first.id);
+        let (_, questions) = members.entry(cluster_id).or_insert_with(|| (pair.band_id, HashMap::new()));
+
+        for question in &[&pair.first, &pair.second] {
+            let best = questions.entry(question.id).or_insert_with(|| SimilarQuestion {
+                id: question.id,
+                content: question.content.clone(),
+                similarity: 0.0,
+            });
+            best.similarity = best.similarity.max(pair.similarity);
+        }
+    }
+
+    let mut clusters: Vec<DuplicateCluster> = members
+        .values()
+        .map(|(band_id, questions)| {
+            let mut questions: Vec<SimilarQuestion> = questions.values().cloned().collect();
+            questions.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap().then(a.content.cmp(&b.content)));
+            DuplicateCluster { band_id: *band_id, questions }
+        })
+        .collect();
+
+    clusters.sort_by(|a, b| b.questions.len().cmp(&a.questions.len()).then(a.questions[0].content.cmp(&b.questions[0].content)));
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{clusters, DuplicatePair, SimilarQuestion};
+    use uuid::Uuid;
+
+    fn question(content: &str) -> SimilarQuestion {
+        SimilarQuestion { id: Uuid::new_v4(), content: content.to_string(), similarity: 0.0 }
+    }
+
+    fn pair(band_id: Uuid, first: &SimilarQuestion, second: &SimilarQuestion, similarity: f64) -> DuplicatePair {
+        DuplicatePair { band_id, first: first.clone(), second: second.clone(), similarity }
+    }
+
+    #[test]
+    fn test_clusters_are_transitive() {
+        let band_id = Uuid::new_v4();
+        let a = question("Who sang Yellow Submarine?");
+        let b = question("Who sings Yellow Submarine?");
+        let c = question("Who sings 'Yellow Submarine'");
+        let d = question("When was Abbey Road released?");
+        let e = question("When was Abbey Road released");
+
+        let found = clusters(&[
+            pair(band_id, &a, &b, 0.8),
+            pair(band_id, &b, &c, 0.9),
+            pair(band_id, &d, &e, 0.95),
+        ]);
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].questions.len(), 3, "Largest cluster should come first");
+        assert_eq!(found[0].questions[0].similarity, 0.9);
+        assert_eq!(found[0].questions[2].id, a.id);
+        assert_eq!(found[0].questions[2].similarity, 0.8);
+        assert_eq!(found[1].questions.len(), 2);
+    }
+
+    #[test]
+    fn test_no_pairs() {
+        assert!(clusters(&[]).is_empty());
+    }
+
+}
@@ -0,0 +1,34 @@
+/// Grade model
+/// A player's answer to a question and the result of grading it
+
+use uuid::Uuid;
+use juniper::{GraphQLObject, GraphQLInputObject};
+use crate::models::question::MatchingPair;
+
+#[derive(GraphQLInputObject)]
+pub struct AnswerSubmission {
+    pub question_id: Uuid,
+    /// Chosen answer for choice questions
+    pub answer_id: Option<Uuid>,
+    /// Typed answer for free-text questions
+    pub text: Option<String>,
+    /// Entered value for numeric questions
+    pub number: Option<f64>,
+    /// Answer ids in the submitted order for ordering questions
+    pub ordering: Option<Vec<Uuid>>,
+    /// Submitted pairs for matching questions
+    pub pairs: Option<Vec<MatchingPair>>,
+}
+
+#[derive(Clone, Debug, PartialEq, GraphQLObject)]
+pub struct Grade {
+    pub question_id: Uuid,
+    /// Whether the submission earned full credit
+    pub accepted: bool,
+    /// Credit earned, from 0 to 1
+    pub score: f64,
+    /// Correct answer or alias the submission matched, as stored
+    pub matched: Option<String>,
+    /// Edit distance between the normalized submission and the match
+    pub distance: Option<i32>,
+}
@@ -0,0 +1,61 @@
+/// Lifeline model
+/// Help a player can call on during an attempt, a limited number of times
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+use tokio_pg_mapper_derive::PostgresMapper;
+use juniper::{GraphQLEnum, GraphQLObject};
+
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
+#[pg_mapper(table="attempt_lifelines")]
+pub struct Lifeline {
+    pub attempt_id: Uuid,
+    pub question_id: Uuid,
+    pub kind: String,
+    pub used_at: NaiveDateTime,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum LifelineKind {
+    /// Removes half of the wrong options of a choice question
+    FiftyFifty,
+    /// Shows the hint the author wrote for the question
+    Hint,
+}
+
+impl LifelineKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LifelineKind::FiftyFifty => "fifty_fifty",
+            LifelineKind::Hint => "hint",
+        }
+    }
+}
+
+impl FromStr for LifelineKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "fifty_fifty" => Ok(LifelineKind::FiftyFifty),
+            "hint" => Ok(LifelineKind::Hint),
+            _ => Err(format!("unknown lifeline kind {}", kind)),
+        }
+    }
+}
+
+impl Lifeline {
+    pub fn lifeline_kind(&self) -> Option<LifelineKind> {
+        self.kind.parse().ok()
+    }
+}
+
+/// How many lifelines of a kind are left in an attempt
+#[derive(Clone, GraphQLObject)]
+pub struct LifelineBudget {
+    pub kind: LifelineKind,
+    pub total: i32,
+    pub remaining: i32,
+}
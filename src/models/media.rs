@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tokio_pg_mapper_derive::PostgresMapper;
+use juniper::GraphQLEnum;
+
+/// An uploaded file, stored under `key` in the configured storage backend
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
+#[pg_mapper(table="media")]
+pub struct Media {
+    pub id: Uuid,
+    pub key: String,
+    pub content_type: String,
+    pub size_bytes: i32,
+    pub uploaded_by: Uuid,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum MediaKind {
+    Image,
+    Audio,
+}
+
+impl Media {
+    pub fn media_kind(&self) -> MediaKind {
+        if self.content_type.starts_with("audio/") {
+            MediaKind::Audio
+        } else {
+            MediaKind::Image
+        }
+    }
+}
+
+pub struct CreateMedia {
+    pub key: String,
+    pub content_type: String,
+    pub size_bytes: i32,
+    pub uploaded_by: Uuid,
+}
@@ -1,4 +1,20 @@
 pub mod user;
 pub mod answer;
 pub mod question;
-pub mod band;
\ No newline at end of file
+pub mod question_choice;
+pub mod band;
+pub mod session;
+pub mod review_state;
+pub mod grade;
+pub mod media;
+pub mod tag;
+pub mod search;
+pub mod duplicate;
+pub mod quiz;
+pub mod attempt;
+pub mod scoring_rules;
+pub mod lifeline;
+pub mod notification;
+pub mod revision;
+pub mod report;
+pub mod audit;
\ No newline at end of file
@@ -0,0 +1,58 @@
+/// Notification model
+/// Tells a user something happened to their content
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+use tokio_pg_mapper_derive::PostgresMapper;
+use juniper::GraphQLEnum;
+
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
+#[pg_mapper(table="notifications")]
+pub struct Notification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub kind: String,
+    pub message: String,
+    /// Question the notification is about, if any
+    pub question_id: Option<Uuid>,
+    pub read_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum NotificationKind {
+    QuestionApproved,
+    QuestionRejected,
+    QuestionChangesRequested,
+    EditAccepted,
+    EditRejected,
+}
+
+impl NotificationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::QuestionApproved => "question_approved",
+            NotificationKind::QuestionRejected => "question_rejected",
+            NotificationKind::QuestionChangesRequested => "question_changes_requested",
+            NotificationKind::EditAccepted => "edit_accepted",
+            NotificationKind::EditRejected => "edit_rejected",
+        }
+    }
+}
+
+impl FromStr for NotificationKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "question_approved" => Ok(NotificationKind::QuestionApproved),
+            "question_rejected" => Ok(NotificationKind::QuestionRejected),
+            "question_changes_requested" => Ok(NotificationKind::QuestionChangesRequested),
+            "edit_accepted" => Ok(NotificationKind::EditAccepted),
+            "edit_rejected" => Ok(NotificationKind::EditRejected),
+            _ => Err(format!("unknown notification kind {}", kind)),
+        }
+    }
+}
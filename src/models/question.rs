@@ -2,23 +2,283 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use tokio_pg_mapper_derive::PostgresMapper;
-use juniper::{GraphQLInputObject, GraphQLObject};
+use juniper::{GraphQLEnum, GraphQLInputObject};
+use std::str::FromStr;
 
-#[derive(Clone, Serialize, Deserialize, PostgresMapper, GraphQLObject)]
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
 #[pg_mapper(table="questions")]
 pub struct Question {
     pub id: Uuid,
     pub content: String,
     
-    pub correct_answer_id: Uuid,
+    pub correct_answer_id: Option<Uuid>,
     pub band_id: Uuid,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub kind: String,
+    pub max_edit_distance: i32,
+    pub numeric_value: Option<f64>,
+    pub numeric_tolerance: f64,
+    pub numeric_curve: String,
+    pub scoring_rule: String,
+    pub media_id: Option<Uuid>,
+    pub clip_start_ms: Option<i32>,
+    pub clip_end_ms: Option<i32>,
+    /// Shown by the hint lifeline
+    pub hint: Option<String>,
+    /// Why the correct answer is correct, shown when reviewing an attempt
+    pub explanation: Option<String>,
+    /// Where the answer can be checked, like a liner note or an interview
+    pub source: Option<String>,
+    pub status: String,
+    /// Fan who submitted the question, None for questions written by authors
+    pub submitted_by: Option<Uuid>,
+    /// Why the question was rejected or needs changes
+    pub moderation_reason: Option<String>,
+    pub moderated_by: Option<Uuid>,
+    pub moderated_at: Option<NaiveDateTime>,
+    /// Set while the question is hidden from players after being reported
+    pub hidden_at: Option<NaiveDateTime>,
+    /// Set once deleted, the question is purged after the retention period
+    pub deleted_at: Option<NaiveDateTime>,
+    /// Incremented by every update
+    pub version: i32,
+}
+
+/// Fans' submissions wait for a moderator, only approved questions are played
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum QuestionStatus {
+    Pending,
+    Approved,
+    Rejected,
+    /// Sent back to the submitter with the changes to make
+    ChangesRequested,
+}
+
+impl QuestionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestionStatus::Pending => "pending",
+            QuestionStatus::Approved => "approved",
+            QuestionStatus::Rejected => "rejected",
+            QuestionStatus::ChangesRequested => "changes_requested",
+        }
+    }
+}
+
+impl FromStr for QuestionStatus {
+    type Err = String;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        match status {
+            "pending" => Ok(QuestionStatus::Pending),
+            "approved" => Ok(QuestionStatus::Approved),
+            "rejected" => Ok(QuestionStatus::Rejected),
+            "changes_requested" => Ok(QuestionStatus::ChangesRequested),
+            _ => Err(format!("unknown question status {}", status)),
+        }
+    }
+}
+
+impl Question {
+    pub fn question_status(&self) -> QuestionStatus {
+        self.status.parse().unwrap_or(QuestionStatus::Pending)
+    }
+}
+
+/// How a question is answered and graded
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum QuestionKind {
+    /// Pick the correct answer by id
+    Choice,
+    /// Type the answer, matched against the correct answer and its aliases
+    FreeText,
+    /// Enter a number or year, near misses earn partial credit
+    Numeric,
+    /// Put the question's choices in order
+    Ordering,
+    /// Pair each choice with its match
+    Matching,
+}
+
+impl QuestionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestionKind::Choice => "choice",
+            QuestionKind::FreeText => "free_text",
+            QuestionKind::Numeric => "numeric",
+            QuestionKind::Ordering => "ordering",
+            QuestionKind::Matching => "matching",
+        }
+    }
+}
+
+impl FromStr for QuestionKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "choice" => Ok(QuestionKind::Choice),
+            "free_text" => Ok(QuestionKind::FreeText),
+            "numeric" => Ok(QuestionKind::Numeric),
+            "ordering" => Ok(QuestionKind::Ordering),
+            "matching" => Ok(QuestionKind::Matching),
+            _ => Err(format!("unknown question kind {}", kind)),
+        }
+    }
+}
+
+/// How credit falls off for numeric answers within the tolerance
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum NumericCurve {
+    /// Credit decreases linearly with the distance from the exact value
+    Linear,
+    /// Full credit anywhere within the tolerance
+    Step,
+}
+
+impl NumericCurve {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NumericCurve::Linear => "linear",
+            NumericCurve::Step => "step",
+        }
+    }
+}
+
+impl FromStr for NumericCurve {
+    type Err = String;
+
+    fn from_str(curve: &str) -> Result<Self, Self::Err> {
+        match curve {
+            "linear" => Ok(NumericCurve::Linear),
+            "step" => Ok(NumericCurve::Step),
+            _ => Err(format!("unknown numeric curve {}", curve)),
+        }
+    }
+}
+
+/// Partial credit for ordering and matching questions
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum ScoringRule {
+    /// Full credit only when every item is right
+    AllOrNothing,
+    /// Credit for each item in the right position or pair
+    PerItem,
+    /// Credit for each pair of items in the right relative order (ordering only)
+    Pairwise,
+}
+
+impl ScoringRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScoringRule::AllOrNothing => "all_or_nothing",
+            ScoringRule::PerItem => "per_item",
+            ScoringRule::Pairwise => "pairwise",
+        }
+    }
+}
+
+impl FromStr for ScoringRule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        match rule {
+            "all_or_nothing" => Ok(ScoringRule::AllOrNothing),
+            "per_item" => Ok(ScoringRule::PerItem),
+            "pairwise" => Ok(ScoringRule::Pairwise),
+            _ => Err(format!("unknown scoring rule {}", rule)),
+        }
+    }
 }
 
 #[derive(GraphQLInputObject)]
 pub struct CreateQuestion {
     pub content: String,
-    pub correct_answer_id: Uuid,
+    pub correct_answer_id: Option<Uuid>,
+    /// Wrong options offered alongside the correct answer of choice questions
+    pub distractor_ids: Option<Vec<Uuid>>,
+    pub band_id: Uuid,
+    pub kind: Option<QuestionKind>,
+    pub max_edit_distance: Option<i32>,
+    pub numeric_value: Option<f64>,
+    pub numeric_tolerance: Option<f64>,
+    pub numeric_curve: Option<NumericCurve>,
+    /// Uploaded image or audio clip shown with the question
+    pub media_id: Option<Uuid>,
+    pub clip_start_ms: Option<i32>,
+    pub clip_end_ms: Option<i32>,
+    /// Revealed to players who use the hint lifeline
+    pub hint: Option<String>,
+    /// Shown with the correct answer once an attempt is finished
+    pub explanation: Option<String>,
+    pub source: Option<String>,
+    /// Tag names like "lyrics" or "1990s"
+    pub tags: Option<Vec<String>>,
+    /// Create the question even if the band has a similar one
+    pub force: Option<bool>,
+}
+
+
+#[derive(GraphQLInputObject)]
+pub struct CreateOrderingQuestion {
+    pub content: String,
     pub band_id: Uuid,
-}
\ No newline at end of file
+    pub media_id: Option<Uuid>,
+    /// Answers in their correct order
+    pub answer_ids: Vec<Uuid>,
+    pub scoring_rule: Option<ScoringRule>,
+    pub hint: Option<String>,
+    pub explanation: Option<String>,
+    pub source: Option<String>,
+    /// Create the question even if the band has a similar one
+    pub force: Option<bool>,
+}
+
+#[derive(GraphQLInputObject)]
+pub struct CreateMatchingQuestion {
+    pub content: String,
+    pub band_id: Uuid,
+    pub media_id: Option<Uuid>,
+    pub pairs: Vec<MatchingPair>,
+    pub scoring_rule: Option<ScoringRule>,
+    pub hint: Option<String>,
+    pub explanation: Option<String>,
+    pub source: Option<String>,
+    /// Create the question even if the band has a similar one
+    pub force: Option<bool>,
+}
+
+/// An answer and the answer it should be matched with
+#[derive(Clone, Copy, GraphQLInputObject)]
+pub struct MatchingPair {
+    pub answer_id: Uuid,
+    pub match_answer_id: Uuid,
+}
+/// Fields left out are kept as they are. A question's kind can't be changed.
+#[derive(GraphQLInputObject)]
+pub struct UpdateQuestion {
+    pub id: Uuid,
+    /// The version the edit was made on. The update fails with a conflict
+    /// when the question was changed since.
+    pub version: i32,
+    pub content: Option<String>,
+    pub correct_answer_id: Option<Uuid>,
+    /// Replaces the distractors of choice questions
+    pub distractor_ids: Option<Vec<Uuid>>,
+    /// Replaces the items of ordering questions, in their correct order
+    pub answer_ids: Option<Vec<Uuid>>,
+    /// Replaces the pairs of matching questions
+    pub pairs: Option<Vec<MatchingPair>>,
+    pub max_edit_distance: Option<i32>,
+    pub numeric_value: Option<f64>,
+    pub numeric_tolerance: Option<f64>,
+    pub numeric_curve: Option<NumericCurve>,
+    pub scoring_rule: Option<ScoringRule>,
+    pub media_id: Option<Uuid>,
+    pub clip_start_ms: Option<i32>,
+    pub clip_end_ms: Option<i32>,
+    pub hint: Option<String>,
+    pub explanation: Option<String>,
+    pub source: Option<String>,
+}
@@ -0,0 +1,94 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tokio_pg_mapper_derive::PostgresMapper;
+use juniper::GraphQLObject;
+
+/// An option of a choice question, or an item of an ordering or matching question
+#[derive(Clone, Serialize, Deserialize, PostgresMapper, GraphQLObject)]
+#[pg_mapper(table="question_choices")]
+pub struct QuestionChoice {
+    pub id: Uuid,
+    pub question_id: Uuid,
+    pub answer_id: Uuid,
+    /// Correct position for ordering questions, display order otherwise
+    pub position: i32,
+    /// Answer this choice pairs with in matching questions
+    pub match_answer_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// The answers in random order, so the correct answer of a choice question
+/// isn't always stored first
+pub fn shuffled(mut answer_ids: Vec<Uuid>) -> Vec<Uuid> {
+    answer_ids.sort_by_cached_key(|_| Uuid::new_v4());
+    answer_ids
+}
+
+/// The choices as shown outside the band's authors: sorted by answer id
+/// instead of the stored order, which is the correct one for ordering
+/// questions, and without the pairs of matching questions
+pub fn without_answers(mut choices: Vec<QuestionChoice>) -> Vec<QuestionChoice> {
+    choices.sort_by_key(|choice| choice.answer_id);
+
+    choices
+        .into_iter()
+        .enumerate()
+        .map(|(position, choice)| QuestionChoice {
+            position: position as i32,
+            match_answer_id: None,
+            ..choice
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{shuffled, without_answers, QuestionChoice};
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn choice(position: i32, match_answer_id: Option<Uuid>) -> QuestionChoice {
+        let now = Utc::now().naive_utc();
+        QuestionChoice {
+            id: Uuid::new_v4(),
+            question_id: Uuid::nil(),
+            answer_id: Uuid::new_v4(),
+            position,
+            match_answer_id,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_shuffled() {
+        let correct = Uuid::new_v4();
+        let answer_ids = vec![correct, Uuid::new_v4(), Uuid::new_v4()];
+
+        let firsts: Vec<Uuid> = (0..50).map(|_| shuffled(answer_ids.clone())[0]).collect();
+
+        assert!(firsts.iter().any(|first| *first != correct), "The correct answer shouldn't always come first");
+        let mut sorted = shuffled(answer_ids.clone());
+        sorted.sort();
+        let mut expected = answer_ids;
+        expected.sort();
+        assert_eq!(sorted, expected, "Shuffling should keep every answer");
+    }
+
+    #[test]
+    fn test_without_answers() {
+        let choices = vec![choice(0, Some(Uuid::new_v4())), choice(1, Some(Uuid::new_v4())), choice(2, None)];
+        let mut answer_ids: Vec<Uuid> = choices.iter().map(|choice| choice.answer_id).collect();
+        answer_ids.sort();
+
+        let shown = without_answers(choices);
+
+        assert_eq!(shown.iter().map(|choice| choice.answer_id).collect::<Vec<Uuid>>(), answer_ids);
+        assert_eq!(shown.iter().map(|choice| choice.position).collect::<Vec<i32>>(), vec![0, 1, 2]);
+        assert!(shown.iter().all(|choice| choice.match_answer_id.is_none()));
+    }
+
+}
@@ -0,0 +1,102 @@
+/// Quiz model
+/// A curated, ordered set of a band's questions
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+use tokio_pg_mapper_derive::PostgresMapper;
+use juniper::{GraphQLEnum, GraphQLInputObject};
+use crate::models::scoring_rules::ScoringRulesInput;
+
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
+#[pg_mapper(table="quizzes")]
+pub struct Quiz {
+    pub id: Uuid,
+    pub band_id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub status: String,
+    pub time_limit_seconds: Option<i32>,
+    pub created_by: Uuid,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// None for the default rules
+    pub scoring_rules_id: Option<Uuid>,
+    /// Time to answer each question, on top of the quiz's time limit
+    pub seconds_per_question: Option<i32>,
+    /// Lifelines each attempt gets
+    pub fifty_fifty_lifelines: i32,
+    pub hint_lifelines: i32,
+    /// Incremented by every update
+    pub version: i32,
+}
+
+/// Only published quizzes can be played, archived ones are kept for their attempts
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum QuizStatus {
+    Draft,
+    Published,
+    Archived,
+}
+
+impl QuizStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuizStatus::Draft => "draft",
+            QuizStatus::Published => "published",
+            QuizStatus::Archived => "archived",
+        }
+    }
+}
+
+impl FromStr for QuizStatus {
+    type Err = String;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        match status {
+            "draft" => Ok(QuizStatus::Draft),
+            "published" => Ok(QuizStatus::Published),
+            "archived" => Ok(QuizStatus::Archived),
+            _ => Err(format!("unknown quiz status {}", status)),
+        }
+    }
+}
+
+impl Quiz {
+    pub fn quiz_status(&self) -> QuizStatus {
+        self.status.parse().unwrap_or(QuizStatus::Draft)
+    }
+}
+
+#[derive(GraphQLInputObject)]
+pub struct CreateQuiz {
+    pub band_id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub time_limit_seconds: Option<i32>,
+    pub seconds_per_question: Option<i32>,
+    /// Lifelines each attempt gets, one of each by default
+    pub fifty_fifty_lifelines: Option<i32>,
+    pub hint_lifelines: Option<i32>,
+    pub scoring_rules: Option<ScoringRulesInput>,
+    /// Questions in the order they are played
+    pub question_ids: Option<Vec<Uuid>>,
+}
+
+/// Fields left out are kept as they are
+#[derive(GraphQLInputObject)]
+pub struct UpdateQuiz {
+    pub id: Uuid,
+    /// The version the edit was made on. The update fails with a conflict
+    /// when the quiz was changed since.
+    pub version: i32,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub time_limit_seconds: Option<i32>,
+    pub seconds_per_question: Option<i32>,
+    pub fifty_fifty_lifelines: Option<i32>,
+    pub hint_lifelines: Option<i32>,
+    /// Replaces the rules for attempts started from now on
+    pub scoring_rules: Option<ScoringRulesInput>,
+}
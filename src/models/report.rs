@@ -0,0 +1,139 @@
+/// Report model
+/// Fans flag questions, bands and users as wrong or offensive. Content with
+/// enough open reports is hidden until someone reviews it.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+use tokio_pg_mapper_derive::PostgresMapper;
+use juniper::GraphQLEnum;
+
+/// Open reports from different users that hide the content pending review
+pub const HIDE_THRESHOLD: i32 = 3;
+
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
+#[pg_mapper(table="reports")]
+pub struct Report {
+    pub id: Uuid,
+    pub reporter_id: Uuid,
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub reason: String,
+    pub note: Option<String>,
+    pub status: String,
+    pub resolved_by: Option<Uuid>,
+    pub resolved_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl Report {
+    pub fn report_target_type(&self) -> ReportTargetType {
+        self.target_type.parse().unwrap_or(ReportTargetType::Question)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum ReportTargetType {
+    Question,
+    Band,
+    User,
+}
+
+impl ReportTargetType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportTargetType::Question => "question",
+            ReportTargetType::Band => "band",
+            ReportTargetType::User => "user",
+        }
+    }
+
+    /// Table holding the reported rows
+    pub fn table(&self) -> &'static str {
+        match self {
+            ReportTargetType::Question => "questions",
+            ReportTargetType::Band => "bands",
+            ReportTargetType::User => "users",
+        }
+    }
+}
+
+impl FromStr for ReportTargetType {
+    type Err = String;
+
+    fn from_str(target_type: &str) -> Result<Self, Self::Err> {
+        match target_type {
+            "question" => Ok(ReportTargetType::Question),
+            "band" => Ok(ReportTargetType::Band),
+            "user" => Ok(ReportTargetType::User),
+            _ => Err(format!("unknown report target type {}", target_type)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum ReportReason {
+    /// The question's answer is wrong
+    WrongAnswer,
+    Offensive,
+    Spam,
+    Other,
+}
+
+impl ReportReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportReason::WrongAnswer => "wrong_answer",
+            ReportReason::Offensive => "offensive",
+            ReportReason::Spam => "spam",
+            ReportReason::Other => "other",
+        }
+    }
+}
+
+impl FromStr for ReportReason {
+    type Err = String;
+
+    fn from_str(reason: &str) -> Result<Self, Self::Err> {
+        match reason {
+            "wrong_answer" => Ok(ReportReason::WrongAnswer),
+            "offensive" => Ok(ReportReason::Offensive),
+            "spam" => Ok(ReportReason::Spam),
+            "other" => Ok(ReportReason::Other),
+            _ => Err(format!("unknown report reason {}", reason)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum ReportStatus {
+    Open,
+    /// The content was fine and is shown again
+    Dismissed,
+    /// The content stays hidden
+    Upheld,
+}
+
+impl ReportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportStatus::Open => "open",
+            ReportStatus::Dismissed => "dismissed",
+            ReportStatus::Upheld => "upheld",
+        }
+    }
+}
+
+impl FromStr for ReportStatus {
+    type Err = String;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        match status {
+            "open" => Ok(ReportStatus::Open),
+            "dismissed" => Ok(ReportStatus::Dismissed),
+            "upheld" => Ok(ReportStatus::Upheld),
+            _ => Err(format!("unknown report status {}", status)),
+        }
+    }
+}
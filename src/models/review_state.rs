@@ -0,0 +1,122 @@
+/// Review state model
+/// Per-user SM-2 schedule for a single question in practice mode
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tokio_pg_mapper_derive::PostgresMapper;
+use juniper::GraphQLObject;
+
+pub const MIN_GRADE: i32 = 0;
+pub const MAX_GRADE: i32 = 5;
+const PASSING_GRADE: i32 = 3;
+const DEFAULT_EASE_FACTOR: f64 = 2.5;
+const MIN_EASE_FACTOR: f64 = 1.3;
+
+#[derive(Clone, Serialize, Deserialize, PostgresMapper, GraphQLObject)]
+#[pg_mapper(table="review_states")]
+pub struct ReviewState {
+    pub user_id: Uuid,
+    pub question_id: Uuid,
+    pub repetitions: i32,
+    pub interval_days: i32,
+    pub ease_factor: f64,
+    pub due_at: NaiveDateTime,
+    pub last_grade: Option<i32>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl ReviewState {
+    /// State for a question the user has never reviewed
+    pub fn new(user_id: Uuid, question_id: Uuid, now: NaiveDateTime) -> ReviewState {
+        ReviewState {
+            user_id,
+            question_id,
+            repetitions: 0,
+            interval_days: 0,
+            ease_factor: DEFAULT_EASE_FACTOR,
+            due_at: now,
+            last_grade: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Applies an SM-2 review with a grade from 0 (blackout) to 5 (perfect recall)
+    pub fn review(&self, grade: i32, now: NaiveDateTime) -> ReviewState {
+        let (repetitions, interval_days) = if grade >= PASSING_GRADE {
+            let interval = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval_days as f64 * self.ease_factor).round() as i32,
+            };
+            (self.repetitions + 1, interval)
+        } else {
+            (0, 1)
+        };
+
+        let miss = (MAX_GRADE - grade) as f64;
+        let ease_factor = (self.ease_factor + (0.1 - miss * (0.08 + miss * 0.02)))
+            .max(MIN_EASE_FACTOR);
+
+        ReviewState {
+            repetitions,
+            interval_days,
+            ease_factor,
+            due_at: now + Duration::days(interval_days as i64),
+            last_grade: Some(grade),
+            updated_at: now,
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::ReviewState;
+    use chrono::{NaiveDate, NaiveDateTime};
+    use uuid::Uuid;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd(2020, 4, 1).and_hms(12, 0, 0)
+    }
+
+    #[test]
+    fn test_intervals_grow_on_recall() {
+        let state = ReviewState::new(Uuid::new_v4(), Uuid::new_v4(), now());
+
+        let first = state.review(5, now());
+        let second = first.review(5, now());
+        let third = second.review(5, now());
+
+        assert_eq!(first.interval_days, 1, "First recall should be due tomorrow");
+        assert_eq!(second.interval_days, 6, "Second recall should be due in 6 days");
+        assert_eq!(third.interval_days, 16, "Later intervals should scale by the ease factor");
+        assert_eq!(third.repetitions, 3);
+    }
+
+    #[test]
+    fn test_lapse_resets_repetitions() {
+        let state = ReviewState::new(Uuid::new_v4(), Uuid::new_v4(), now())
+            .review(5, now())
+            .review(5, now())
+            .review(1, now());
+
+        assert_eq!(state.repetitions, 0, "A failed review should restart the schedule");
+        assert_eq!(state.interval_days, 1);
+        assert_eq!(state.due_at, now() + chrono::Duration::days(1));
+    }
+
+    #[test]
+    fn test_ease_factor_has_floor() {
+        let mut state = ReviewState::new(Uuid::new_v4(), Uuid::new_v4(), now());
+        for _ in 0..10 {
+            state = state.review(0, now());
+        }
+
+        assert!((state.ease_factor - 1.3).abs() < 1e-9, "Ease factor should not drop below 1.3");
+    }
+
+}
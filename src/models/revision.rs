@@ -0,0 +1,478 @@
+/// Question revision model
+/// Every saved version of a question is kept as a snapshot of its fields and
+/// choices. Comparing consecutive snapshots shows what each edit changed.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, str::FromStr};
+use uuid::Uuid;
+use tokio_pg_mapper_derive::PostgresMapper;
+use juniper::{GraphQLEnum, GraphQLObject};
+use crate::errors::{AppError, AppErrorType};
+use crate::models::{
+    answer::{Answer, AnswerAlias},
+    question::{Question, QuestionKind, UpdateQuestion},
+    question_choice::QuestionChoice,
+};
+
+/// A question and its choices as they were at one point.
+/// Answer contents are copied in so old revisions stay readable after answers are renamed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QuestionSnapshot {
+    pub content: String,
+    pub kind: String,
+    pub correct_answer_id: Option<Uuid>,
+    pub correct_answer: Option<String>,
+    pub choices: Vec<SnapshotChoice>,
+    pub max_edit_distance: i32,
+    pub numeric_value: Option<f64>,
+    pub numeric_tolerance: f64,
+    pub numeric_curve: String,
+    pub scoring_rule: String,
+    pub media_id: Option<Uuid>,
+    pub clip_start_ms: Option<i32>,
+    pub clip_end_ms: Option<i32>,
+    pub hint: Option<String>,
+    pub explanation: Option<String>,
+    pub source: Option<String>,
+    /// Accepted spellings of the correct answer, None in revisions saved before they were kept
+    #[serde(default)]
+    pub aliases: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotChoice {
+    pub answer_id: Uuid,
+    pub content: String,
+    pub match_answer_id: Option<Uuid>,
+    pub match_content: Option<String>,
+}
+
+impl SnapshotChoice {
+    fn new(answer_id: Uuid, match_answer_id: Option<Uuid>) -> SnapshotChoice {
+        SnapshotChoice { answer_id, content: String::new(), match_answer_id, match_content: None }
+    }
+}
+
+fn corrupt_snapshot(err: serde_json::Error) -> AppError {
+    AppError {
+        cause: Some(err.to_string()),
+        message: Some("Error reading question snapshot.".to_string()),
+        error_type: AppErrorType::DbError,
+    }
+}
+
+impl QuestionSnapshot {
+    /// The question as stored, without answer contents
+    pub fn new(question: &Question, choices: &[QuestionChoice]) -> QuestionSnapshot {
+        QuestionSnapshot {
+            content: question.content.clone(),
+            kind: question.kind.clone(),
+            correct_answer_id: question.correct_answer_id,
+            correct_answer: None,
+            choices: choices.iter().map(|choice| SnapshotChoice::new(choice.answer_id, choice.match_answer_id)).collect(),
+            max_edit_distance: question.max_edit_distance,
+            numeric_value: question.numeric_value,
+            numeric_tolerance: question.numeric_tolerance,
+            numeric_curve: question.numeric_curve.clone(),
+            scoring_rule: question.scoring_rule.clone(),
+            media_id: question.media_id,
+            clip_start_ms: question.clip_start_ms,
+            clip_end_ms: question.clip_end_ms,
+            hint: question.hint.clone(),
+            explanation: question.explanation.clone(),
+            source: question.source.clone(),
+            aliases: None,
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<QuestionSnapshot, AppError> {
+        serde_json::from_str(json).map_err(corrupt_snapshot)
+    }
+
+    pub fn to_json(&self) -> Result<String, AppError> {
+        serde_json::to_string(self).map_err(corrupt_snapshot)
+    }
+
+    /// Every answer the snapshot refers to
+    pub fn answer_ids(&self) -> Vec<Uuid> {
+        let mut ids: Vec<Uuid> = self.correct_answer_id.iter().cloned()
+            .chain(self.choices.iter().flat_map(|choice| Some(choice.answer_id).into_iter().chain(choice.match_answer_id)))
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    /// Copies in the contents of the answers the snapshot refers to
+    pub fn with_contents(mut self, answers: &[Answer]) -> QuestionSnapshot {
+        let contents: HashMap<Uuid, &str> = answers.iter().map(|answer| (answer.id, answer.content.as_str())).collect();
+        let content = |id: Uuid| contents.get(&id).map(|content| content.to_string());
+
+        self.correct_answer = self.correct_answer_id.and_then(content);
+        for choice in self.choices.iter_mut() {
+            choice.content = content(choice.answer_id).unwrap_or_default();
+            choice.match_content = choice.match_answer_id.and_then(content);
+        }
+        self
+    }
+
+    /// Copies in the aliases of the correct answer
+    pub fn with_aliases(mut self, aliases: &[AnswerAlias]) -> QuestionSnapshot {
+        let mut contents: Vec<String> = aliases.iter().map(|alias| alias.content.clone()).collect();
+        contents.sort();
+        self.aliases = Some(contents);
+        self
+    }
+
+    /// The snapshot with the update's fields applied. Answer contents of new choices are left empty.
+    pub fn with_update(&self, input: &UpdateQuestion) -> QuestionSnapshot {
+        let correct_answer_id = input.correct_answer_id.or(self.correct_answer_id);
+
+        let choices = match self.kind.parse::<QuestionKind>() {
+            Ok(QuestionKind::Choice) if input.distractor_ids.is_some() || (input.correct_answer_id.is_some() && !self.choices.is_empty()) => {
+                let distractor_ids = input.distractor_ids.clone().unwrap_or_else(|| self.choices.iter()
+                    .map(|choice| choice.answer_id)
+                    .filter(|id| Some(*id) != self.correct_answer_id)
+                    .collect());
+
+                correct_answer_id.iter()
+                    .chain(distractor_ids.iter())
+                    .map(|id| SnapshotChoice::new(*id, None))
+                    .collect()
+            },
+            Ok(QuestionKind::Ordering) if input.answer_ids.is_some() =>
+                input.answer_ids.iter().flatten().map(|id| SnapshotChoice::new(*id, None)).collect(),
+            Ok(QuestionKind::Matching) if input.pairs.is_some() =>
+                input.pairs.iter().flatten().map(|pair| SnapshotChoice::new(pair.answer_id, Some(pair.match_answer_id))).collect(),
+            _ => self.choices.clone(),
+        };
+
+        QuestionSnapshot {
+            content: input.content.as_deref().map(str::trim).map(String::from).unwrap_or_else(|| self.content.clone()),
+            kind: self.kind.clone(),
+            correct_answer_id,
+            correct_answer: if correct_answer_id == self.correct_answer_id { self.correct_answer.clone() } else { None },
+            choices,
+            max_edit_distance: input.max_edit_distance.unwrap_or(self.max_edit_distance),
+            numeric_value: input.numeric_value.or(self.numeric_value),
+            numeric_tolerance: input.numeric_tolerance.unwrap_or(self.numeric_tolerance),
+            numeric_curve: input.numeric_curve.map(|curve| curve.as_str().to_string()).unwrap_or_else(|| self.numeric_curve.clone()),
+            scoring_rule: input.scoring_rule.map(|rule| rule.as_str().to_string()).unwrap_or_else(|| self.scoring_rule.clone()),
+            media_id: input.media_id.or(self.media_id),
+            clip_start_ms: input.clip_start_ms.or(self.clip_start_ms),
+            clip_end_ms: input.clip_end_ms.or(self.clip_end_ms),
+            hint: input.hint.clone().or_else(|| self.hint.clone()),
+            explanation: input.explanation.clone().or_else(|| self.explanation.clone()),
+            source: input.source.clone().or_else(|| self.source.clone()),
+            aliases: if correct_answer_id == self.correct_answer_id { self.aliases.clone() } else { None },
+        }
+    }
+
+    /// Fields as shown in diffs, named like the GraphQL fields of `Question`
+    fn fields(&self) -> Vec<(&'static str, Option<String>)> {
+        let choices = self.choices.iter()
+            .map(|choice| match &choice.match_content {
+                Some(match_content) => format!("{} → {}", choice.content, match_content),
+                None => choice.content.clone(),
+            })
+            .collect::<Vec<String>>();
+
+        vec![
+            ("content", Some(self.content.clone())),
+            ("correctAnswer", self.correct_answer.clone().or_else(|| self.correct_answer_id.map(|id| id.to_string()))),
+            ("aliases", self.aliases.as_ref().filter(|aliases| !aliases.is_empty()).map(|aliases| aliases.join(", "))),
+            ("choices", if choices.is_empty() { None } else { Some(choices.join(", ")) }),
+            ("maxEditDistance", Some(self.max_edit_distance.to_string())),
+            ("numericValue", self.numeric_value.map(|value| value.to_string())),
+            ("numericTolerance", Some(self.numeric_tolerance.to_string())),
+            ("numericCurve", Some(self.numeric_curve.clone())),
+            ("scoringRule", Some(self.scoring_rule.clone())),
+            ("mediaId", self.media_id.map(|id| id.to_string())),
+            ("clipStartMs", self.clip_start_ms.map(|ms| ms.to_string())),
+            ("clipEndMs", self.clip_end_ms.map(|ms| ms.to_string())),
+            ("hint", self.hint.clone()),
+            ("explanation", self.explanation.clone()),
+            ("source", self.source.clone()),
+        ]
+    }
+}
+
+/// A field an edit changed
+#[derive(Clone, Debug, PartialEq, GraphQLObject)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// Fields that differ between two snapshots. Without a `before`, every field that is set counts as changed.
+pub fn diff(before: Option<&QuestionSnapshot>, after: &QuestionSnapshot) -> Vec<FieldChange> {
+    let before_fields = before.map(QuestionSnapshot::fields).unwrap_or_default();
+
+    after.fields().into_iter()
+        .filter_map(|(field, after)| {
+            let before = before_fields.iter()
+                .find(|(name, _)| *name == field)
+                .and_then(|(_, value)| value.clone());
+
+            if before == after {
+                None
+            } else {
+                Some(FieldChange { field: field.to_string(), before, after })
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
+#[pg_mapper(table="question_revisions")]
+pub struct QuestionRevision {
+    pub id: Uuid,
+    pub question_id: Uuid,
+    /// Counts up from 1 for each question
+    pub number: i32,
+    /// None for the state a question was in before revisions were kept
+    pub author_id: Option<Uuid>,
+    pub snapshot: String,
+    /// Number of the revision this one restored
+    pub reverted_from: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+/// A revision and what it changed from the one before
+#[derive(Clone, GraphQLObject)]
+pub struct Revision {
+    pub number: i32,
+    pub author_id: Option<Uuid>,
+    pub reverted_from: Option<i32>,
+    pub changes: Vec<FieldChange>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Pairs each revision with its predecessor to list what changed, oldest first
+pub fn revision_history(revisions: &[QuestionRevision]) -> Result<Vec<Revision>, AppError> {
+    let snapshots = revisions.iter()
+        .map(|revision| QuestionSnapshot::from_json(&revision.snapshot))
+        .collect::<Result<Vec<QuestionSnapshot>, AppError>>()?;
+
+    Ok(revisions.iter().enumerate()
+        .map(|(index, revision)| Revision {
+            number: revision.number,
+            author_id: revision.author_id,
+            reverted_from: revision.reverted_from,
+            changes: diff(index.checked_sub(1).map(|previous| &snapshots[previous]), &snapshots[index]),
+            created_at: revision.created_at,
+        })
+        .collect())
+}
+
+/// An edit a fan proposed for a band's authors to accept or reject
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
+#[pg_mapper(table="question_edit_suggestions")]
+pub struct EditSuggestion {
+    pub id: Uuid,
+    pub question_id: Uuid,
+    pub suggested_by: Uuid,
+    pub snapshot: String,
+    /// Revision the edit was made against
+    pub base_revision: i32,
+    pub note: Option<String>,
+    pub status: String,
+    /// Why the suggestion was rejected
+    pub reason: Option<String>,
+    pub decided_by: Option<Uuid>,
+    pub decided_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl EditSuggestion {
+    pub fn suggestion_status(&self) -> SuggestionStatus {
+        self.status.parse().unwrap_or(SuggestionStatus::Pending)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum SuggestionStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+impl SuggestionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuggestionStatus::Pending => "pending",
+            SuggestionStatus::Accepted => "accepted",
+            SuggestionStatus::Rejected => "rejected",
+        }
+    }
+}
+
+impl FromStr for SuggestionStatus {
+    type Err = String;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        match status {
+            "pending" => Ok(SuggestionStatus::Pending),
+            "accepted" => Ok(SuggestionStatus::Accepted),
+            "rejected" => Ok(SuggestionStatus::Rejected),
+            _ => Err(format!("unknown suggestion status {}", status)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{diff, QuestionSnapshot, SnapshotChoice};
+    use crate::models::{answer::AnswerAlias, question::{MatchingPair, UpdateQuestion}};
+    use uuid::Uuid;
+
+    fn snapshot(kind: &str, correct_answer_id: Option<Uuid>, choices: Vec<SnapshotChoice>) -> QuestionSnapshot {
+        QuestionSnapshot {
+            content: "Which album came first?".to_string(),
+            kind: kind.to_string(),
+            correct_answer_id,
+            correct_answer: correct_answer_id.map(|_| "Kid A".to_string()),
+            choices,
+            max_edit_distance: 1,
+            numeric_value: None,
+            numeric_tolerance: 0.0,
+            numeric_curve: "linear".to_string(),
+            scoring_rule: "all_or_nothing".to_string(),
+            media_id: None,
+            clip_start_ms: None,
+            clip_end_ms: None,
+            hint: None,
+            explanation: None,
+            source: None,
+            aliases: None,
+        }
+    }
+
+    fn choice(content: &str) -> SnapshotChoice {
+        SnapshotChoice { answer_id: Uuid::new_v4(), content: content.to_string(), match_answer_id: None, match_content: None }
+    }
+
+    fn update(id: Uuid) -> UpdateQuestion {
+        UpdateQuestion {
+            id,
+            version: 1,
+            content: None,
+            correct_answer_id: None,
+            distractor_ids: None,
+            answer_ids: None,
+            pairs: None,
+            max_edit_distance: None,
+            numeric_value: None,
+            numeric_tolerance: None,
+            numeric_curve: None,
+            scoring_rule: None,
+            media_id: None,
+            clip_start_ms: None,
+            clip_end_ms: None,
+            hint: None,
+            explanation: None,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_lists_changed_fields() {
+        let before = snapshot("free_text", Some(Uuid::new_v4()), vec![]);
+        let mut after = before.clone();
+        after.content = "Which album came last?".to_string();
+        after.hint = Some("It's not OK Computer".to_string());
+
+        let changes = diff(Some(&before), &after);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].field, "content");
+        assert_eq!(changes[0].before.as_deref(), Some("Which album came first?"));
+        assert_eq!(changes[1].field, "hint");
+        assert_eq!(changes[1].before, None);
+
+        assert!(diff(Some(&after), &after).is_empty());
+    }
+
+    #[test]
+    fn test_diff_without_previous_revision() {
+        let first = snapshot("choice", Some(Uuid::new_v4()), vec![choice("Kid A"), choice("Amnesiac")]);
+        let fields: Vec<String> = diff(None, &first).into_iter().map(|change| change.field).collect();
+
+        assert!(fields.contains(&"content".to_string()));
+        assert!(fields.contains(&"choices".to_string()));
+        assert!(!fields.contains(&"hint".to_string()));
+    }
+
+    #[test]
+    fn test_diff_shows_aliases() {
+        let now = chrono::Utc::now().naive_utc();
+        let alias = |content: &str| AnswerAlias {
+            id: Uuid::new_v4(),
+            answer_id: Uuid::nil(),
+            content: content.to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        let before = snapshot("free_text", Some(Uuid::new_v4()), vec![]).with_aliases(&[alias("Sgt Pepper")]);
+        let after = before.clone().with_aliases(&[alias("Sgt Pepper"), alias("Sgt. Pepper's")]);
+
+        let changes = diff(Some(&before), &after);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "aliases");
+        assert_eq!(changes[0].after.as_deref(), Some("Sgt Pepper, Sgt. Pepper's"));
+    }
+
+    #[test]
+    fn test_diff_shows_matching_pairs() {
+        let mut pair = choice("Thom Yorke");
+        pair.match_answer_id = Some(Uuid::new_v4());
+        pair.match_content = Some("vocals".to_string());
+
+        let changes = diff(Some(&snapshot("matching", None, vec![])), &snapshot("matching", None, vec![pair]));
+        assert_eq!(changes[0].after.as_deref(), Some("Thom Yorke → vocals"));
+    }
+
+    #[test]
+    fn test_update_keeps_missing_fields() {
+        let before = snapshot("free_text", Some(Uuid::new_v4()), vec![]);
+        let mut input = update(Uuid::new_v4());
+        input.content = Some("  Which album came last? ".to_string());
+
+        let after = before.with_update(&input);
+        assert_eq!(after.content, "Which album came last?");
+        assert_eq!(after.correct_answer, before.correct_answer);
+        assert_eq!(after.max_edit_distance, before.max_edit_distance);
+    }
+
+    #[test]
+    fn test_update_replaces_correct_choice() {
+        let correct = choice("Kid A");
+        let distractor = choice("Amnesiac");
+        let before = snapshot("choice", Some(correct.answer_id), vec![correct, distractor.clone()]);
+
+        let new_correct = Uuid::new_v4();
+        let mut input = update(Uuid::new_v4());
+        input.correct_answer_id = Some(new_correct);
+
+        let after = before.with_update(&input);
+        let ids: Vec<Uuid> = after.choices.iter().map(|choice| choice.answer_id).collect();
+        assert_eq!(ids, vec![new_correct, distractor.answer_id]);
+        assert_eq!(after.correct_answer, None, "Content of the new answer isn't known yet");
+    }
+
+    #[test]
+    fn test_update_replaces_pairs() {
+        let before = snapshot("matching", None, vec![choice("Thom Yorke"), choice("Jonny Greenwood")]);
+        let pair = MatchingPair { answer_id: Uuid::new_v4(), match_answer_id: Uuid::new_v4() };
+        let mut input = update(Uuid::new_v4());
+        input.pairs = Some(vec![pair]);
+        input.distractor_ids = Some(vec![Uuid::new_v4()]);
+
+        let after = before.with_update(&input);
+        assert_eq!(after.choices.len(), 1);
+        assert_eq!(after.choices[0].match_answer_id, Some(pair.match_answer_id));
+    }
+}
@@ -0,0 +1,45 @@
+/// Scoring rules model
+/// Rows are never changed once created, so attempts can always be rescored
+/// with the rules they were played under
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tokio_pg_mapper_derive::PostgresMapper;
+use juniper::{GraphQLObject, GraphQLInputObject};
+
+#[derive(Clone, Serialize, Deserialize, PostgresMapper, GraphQLObject)]
+#[pg_mapper(table="scoring_rules")]
+pub struct ScoringRules {
+    pub id: Uuid,
+    pub points_per_question: i32,
+    /// Extra points for an instant correct answer, decaying to nothing at `speedBonusSeconds`
+    pub speed_bonus_points: i32,
+    pub speed_bonus_seconds: i32,
+    /// Points taken off for a wrong answer
+    pub wrong_answer_penalty: i32,
+    /// Multiplier added for every correct answer in a row after the first
+    pub streak_bonus: f64,
+    pub max_streak_multiplier: f64,
+    pub partial_credit: bool,
+    pub created_at: NaiveDateTime,
+    /// Fraction of a question's points given up for using the 50/50 lifeline on it
+    pub fifty_fifty_cost: f64,
+    /// Fraction of a question's points given up for using the hint lifeline on it
+    pub hint_cost: f64,
+}
+
+/// Fields left out take their defaults: 10 points per question, no bonuses or
+/// penalties, and half or a quarter of the points for using 50/50 or a hint
+#[derive(GraphQLInputObject)]
+pub struct ScoringRulesInput {
+    pub points_per_question: Option<i32>,
+    pub speed_bonus_points: Option<i32>,
+    pub speed_bonus_seconds: Option<i32>,
+    pub wrong_answer_penalty: Option<i32>,
+    pub streak_bonus: Option<f64>,
+    pub max_streak_multiplier: Option<f64>,
+    pub partial_credit: Option<bool>,
+    pub fifty_fifty_cost: Option<f64>,
+    pub hint_cost: Option<f64>,
+}
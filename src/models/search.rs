@@ -0,0 +1,66 @@
+use juniper::{GraphQLEnum, GraphQLObject};
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum SearchType {
+    Band,
+    Question,
+    User,
+}
+
+impl SearchType {
+    pub fn all() -> Vec<SearchType> {
+        vec![SearchType::Band, SearchType::Question, SearchType::User]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchType::Band => "band",
+            SearchType::Question => "question",
+            SearchType::User => "user",
+        }
+    }
+}
+
+impl FromStr for SearchType {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "band" => Ok(SearchType::Band),
+            "question" => Ok(SearchType::Question),
+            "user" => Ok(SearchType::User),
+            _ => Err(format!("unknown search type {}", kind)),
+        }
+    }
+}
+
+#[derive(Clone, GraphQLObject)]
+pub struct SearchHit {
+    pub kind: SearchType,
+    pub id: Uuid,
+    /// Band name, question content or username
+    pub title: String,
+    /// The title, HTML-escaped, with matched words wrapped in `<b>` tags
+    pub snippet: String,
+    pub rank: f64,
+}
+
+#[derive(Clone, GraphQLObject)]
+pub struct SearchEdge {
+    pub cursor: String,
+    pub node: SearchHit,
+}
+
+#[derive(Clone, GraphQLObject)]
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub end_cursor: Option<String>,
+}
+
+#[derive(Clone, GraphQLObject)]
+pub struct SearchConnection {
+    pub edges: Vec<SearchEdge>,
+    pub page_info: PageInfo,
+}
@@ -0,0 +1,20 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tokio_pg_mapper_derive::PostgresMapper;
+use juniper::{GraphQLObject, GraphQLInputObject};
+
+#[derive(Clone, Serialize, Deserialize, PostgresMapper, GraphQLObject)]
+#[pg_mapper(table="sessions")]
+pub struct Session {
+    pub token: Uuid,
+    pub user_id: Uuid,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(GraphQLInputObject)]
+pub struct Login {
+    pub username: String,
+    pub password: String,
+}
@@ -0,0 +1,119 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+use tokio_pg_mapper_derive::PostgresMapper;
+use juniper::{GraphQLEnum, GraphQLObject};
+
+#[derive(Clone, Serialize, Deserialize, PostgresMapper)]
+#[pg_mapper(table="tags")]
+pub struct Tag {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub kind: String,
+}
+
+/// What a tag describes, so clients can group e.g. eras apart from topics
+#[derive(Clone, Copy, Debug, PartialEq, GraphQLEnum)]
+pub enum TagKind {
+    /// What the question is about, like "lyrics" or "members"
+    Topic,
+    /// A decade or year, like "1990s"
+    Era,
+    Album,
+}
+
+impl TagKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TagKind::Topic => "topic",
+            TagKind::Era => "era",
+            TagKind::Album => "album",
+        }
+    }
+
+    /// Guesses the kind of a normalized tag name: decades and years are eras,
+    /// anything else a topic
+    pub fn infer(name: &str) -> TagKind {
+        let digits = name.trim_end_matches('s');
+        let is_era = (name.starts_with('\'') && digits.len() == 3 && digits[1..].chars().all(|c| c.is_ascii_digit()))
+            || ((digits.len() == 2 || digits.len() == 4) && digits.chars().all(|c| c.is_ascii_digit()));
+
+        if is_era {
+            TagKind::Era
+        } else {
+            TagKind::Topic
+        }
+    }
+}
+
+impl FromStr for TagKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "topic" => Ok(TagKind::Topic),
+            "era" => Ok(TagKind::Era),
+            "album" => Ok(TagKind::Album),
+            _ => Err(format!("unknown tag kind {}", kind)),
+        }
+    }
+}
+
+/// How many questions carry a tag, and how the logged in user does on them
+#[derive(Clone, GraphQLObject)]
+pub struct TagStats {
+    pub tag_id: Uuid,
+    pub name: String,
+    pub kind: TagKind,
+    pub question_count: i32,
+    /// Questions with this tag the user has reviewed
+    pub reviewed_count: i32,
+    /// Average of the user's last review grades, from 0 to 5
+    pub average_grade: Option<f64>,
+}
+
+/// Tags are compared case-insensitively and without surrounding whitespace
+pub fn normalize_tag(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Normalizes and dedups tag names, dropping empty ones
+pub fn normalize_tags(names: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = names.iter()
+        .map(|name| normalize_tag(name))
+        .filter(|name| !name.is_empty())
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{normalize_tags, TagKind};
+
+    #[test]
+    fn test_infer_kind() {
+        assert_eq!(TagKind::infer("1990s"), TagKind::Era);
+        assert_eq!(TagKind::infer("80s"), TagKind::Era);
+        assert_eq!(TagKind::infer("'80s"), TagKind::Era);
+        assert_eq!(TagKind::infer("1969"), TagKind::Era);
+        assert_eq!(TagKind::infer("lyrics"), TagKind::Topic);
+        assert_eq!(TagKind::infer("1999 tour"), TagKind::Topic);
+        assert_eq!(TagKind::infer("s"), TagKind::Topic);
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let names = vec![" Lyrics ".to_string(), "lyrics".to_string(), "".to_string(), "The  Wall".to_string()];
+        assert_eq!(normalize_tags(&names), vec!["lyrics".to_string(), "the wall".to_string()]);
+    }
+
+}
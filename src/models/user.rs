@@ -18,6 +18,11 @@ pub struct User {
     pub image: Option<String>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub is_admin: bool,
+    /// Set while the profile is hidden after being reported
+    pub hidden_at: Option<NaiveDateTime>,
+    /// Set once deleted, the account is purged after the retention period
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
 #[derive(GraphQLInputObject)]
@@ -0,0 +1,133 @@
+/// Moderation
+/// Approves, rejects or sends back questions fans submitted to bands, and
+/// lets the submitter know what was decided
+
+use crate::errors::{AppError, AppErrorType};
+use crate::models::{
+    notification::NotificationKind,
+    question::{Question, QuestionStatus},
+};
+use crate::repositories::stores::{NotificationStore, QuestionStore};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// What the submitter is told about the decision
+pub fn notification_for(question: &Question, reason: Option<&str>) -> Option<(NotificationKind, String)> {
+    let because = reason.map(|reason| format!(": {}", reason.trim_end_matches('.'))).unwrap_or_default();
+
+    match question.question_status() {
+        QuestionStatus::Approved => Some((
+            NotificationKind::QuestionApproved,
+            format!("Your question \"{}\" was approved.", question.content),
+        )),
+        QuestionStatus::Rejected => Some((
+            NotificationKind::QuestionRejected,
+            format!("Your question \"{}\" was rejected{}.", question.content, because),
+        )),
+        QuestionStatus::ChangesRequested => Some((
+            NotificationKind::QuestionChangesRequested,
+            format!("Your question \"{}\" needs changes{}.", question.content, because),
+        )),
+        QuestionStatus::Pending => None,
+    }
+}
+
+pub struct ModerationService {
+    pub questions: Arc<dyn QuestionStore>,
+    pub notifications: Arc<dyn NotificationStore>,
+}
+
+impl ModerationService {
+    /// Decides on a pending question. Rejecting it or requesting changes needs a reason.
+    pub async fn moderate(&self, question_id: Uuid, moderator_id: Uuid, status: QuestionStatus, reason: Option<String>) -> Result<Question, AppError> {
+        let reason = reason.map(|reason| reason.trim().to_string()).filter(|reason| !reason.is_empty());
+        if status == QuestionStatus::Pending {
+            return Err(AppError {
+                cause: None,
+                message: Some("a question can't be moderated back to pending.".to_string()),
+                error_type: AppErrorType::InvalidField
+            });
+        }
+        if status != QuestionStatus::Approved && reason.is_none() {
+            return Err(AppError {
+                cause: None,
+                message: Some("a reason is required.".to_string()),
+                error_type: AppErrorType::InvalidField
+            });
+        }
+
+        let question = self.questions.moderate(question_id, status, reason.as_deref(), moderator_id).await?;
+
+        if let (Some(submitted_by), Some((kind, message))) = (question.submitted_by, notification_for(&question, reason.as_deref())) {
+            self.notifications.create(submitted_by, kind, &message, Some(question.id)).await?;
+        }
+
+        Ok(question)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::notification_for;
+    use crate::models::{notification::NotificationKind, question::{Question, QuestionStatus}};
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn question(status: QuestionStatus) -> Question {
+        let now = Utc::now().naive_utc();
+        Question {
+            id: Uuid::new_v4(),
+            content: "Who produced OK Computer?".to_string(),
+            correct_answer_id: Some(Uuid::new_v4()),
+            band_id: Uuid::new_v4(),
+            created_at: now,
+            updated_at: now,
+            kind: "choice".to_string(),
+            max_edit_distance: 1,
+            numeric_value: None,
+            numeric_tolerance: 0.0,
+            numeric_curve: "linear".to_string(),
+            scoring_rule: "all_or_nothing".to_string(),
+            media_id: None,
+            clip_start_ms: None,
+            clip_end_ms: None,
+            hint: None,
+            explanation: None,
+            source: None,
+            status: status.as_str().to_string(),
+            submitted_by: Some(Uuid::new_v4()),
+            moderation_reason: None,
+            moderated_by: None,
+            moderated_at: None,
+            hidden_at: None,
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn test_approved_notification() {
+        let (kind, message) = notification_for(&question(QuestionStatus::Approved), None).unwrap();
+
+        assert_eq!(kind, NotificationKind::QuestionApproved);
+        assert_eq!(message, "Your question \"Who produced OK Computer?\" was approved.");
+    }
+
+    #[test]
+    fn test_notification_includes_reason() {
+        let (kind, message) = notification_for(&question(QuestionStatus::Rejected), Some("It was Nigel Godrich.")).unwrap();
+        assert_eq!(kind, NotificationKind::QuestionRejected);
+        assert_eq!(message, "Your question \"Who produced OK Computer?\" was rejected: It was Nigel Godrich.", "The reason's own period should be dropped");
+
+        let (kind, message) = notification_for(&question(QuestionStatus::ChangesRequested), Some("add the year")).unwrap();
+        assert_eq!(kind, NotificationKind::QuestionChangesRequested);
+        assert_eq!(message, "Your question \"Who produced OK Computer?\" needs changes: add the year.");
+    }
+
+    #[test]
+    fn test_no_notification_while_pending() {
+        assert!(notification_for(&question(QuestionStatus::Pending), None).is_none());
+    }
+
+}
@@ -0,0 +1,153 @@
+/// Anti-cheat checks
+/// Looks at the server-side answer times of a finished attempt for patterns
+/// no honest player produces. Flags only queue the attempt for review, the
+/// score stands until an author looks at it.
+
+use crate::grading::scoring::Response;
+
+/// Reading a question and picking an answer takes at least this long
+pub const MIN_PLAUSIBLE_SECONDS: f64 = 1.0;
+/// Correct answers under `MIN_PLAUSIBLE_SECONDS` tolerated as lucky taps
+pub const MAX_INSTANT_CORRECT: usize = 2;
+/// Answers needed before the timing spread says anything
+pub const MIN_UNIFORM_ANSWERS: usize = 5;
+/// Standard deviation in seconds below which answer times look scripted
+pub const MAX_UNIFORM_DEVIATION: f64 = 0.25;
+/// Fraction of the population's median time that counts as implausibly fast
+pub const FAST_MEDIAN_RATIO: f64 = 0.25;
+/// Accuracy from which a fast attempt is suspicious rather than careless
+pub const HIGH_ACCURACY: f64 = 0.9;
+
+fn median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let middle = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        Some((sorted[middle - 1] + sorted[middle]) / 2.0)
+    } else {
+        Some(sorted[middle])
+    }
+}
+
+fn standard_deviation(values: &[f64]) -> f64 {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / values.len() as f64;
+
+    variance.sqrt()
+}
+
+/// Reasons the attempt looks implausible, empty when it looks fine.
+/// `population_median` is the median answer time of other players on the same questions.
+pub fn flags(responses: &[Response], population_median: Option<f64>) -> Vec<String> {
+    let answered: Vec<(f64, f64)> = responses.iter()
+        .filter_map(|response| match (response.credit, response.seconds) {
+            (Some(credit), Some(seconds)) => Some((credit, seconds)),
+            _ => None,
+        })
+        .collect();
+    if answered.is_empty() {
+        return vec![];
+    }
+
+    let mut reasons = vec![];
+
+    let instant_correct = answered.iter()
+        .filter(|(credit, seconds)| *credit >= 1.0 && *seconds < MIN_PLAUSIBLE_SECONDS)
+        .count();
+    if instant_correct > MAX_INSTANT_CORRECT {
+        reasons.push(format!("{} correct answers in under {}s", instant_correct, MIN_PLAUSIBLE_SECONDS));
+    }
+
+    let seconds: Vec<f64> = answered.iter().map(|(_, seconds)| *seconds).collect();
+    let perfect = answered.iter().all(|(credit, _)| *credit >= 1.0);
+    if perfect && answered.len() >= MIN_UNIFORM_ANSWERS && standard_deviation(&seconds) < MAX_UNIFORM_DEVIATION {
+        reasons.push(format!("perfect run with near-identical answer times ({:.2}s spread)", standard_deviation(&seconds)));
+    }
+
+    let accuracy = answered.iter().map(|(credit, _)| credit).sum::<f64>() / answered.len() as f64;
+    if let (Some(own), Some(population)) = (median(&seconds), population_median) {
+        if accuracy >= HIGH_ACCURACY && own < population * FAST_MEDIAN_RATIO {
+            reasons.push(format!(
+                "median answer time {:.1}s against {:.1}s for other players at {:.0}% accuracy",
+                own, population, accuracy * 100.0
+            ));
+        }
+    }
+
+    reasons
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{flags, median};
+    use crate::grading::scoring::Response;
+    use uuid::Uuid;
+
+    fn response(credit: Option<f64>, seconds: Option<f64>) -> Response {
+        Response { question_id: Uuid::new_v4(), credit, seconds, fifty_fifty: false, hint: false }
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[]), None);
+        assert_eq!(median(&[3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&[4.0, 1.0, 2.0, 3.0]), Some(2.5));
+    }
+
+    #[test]
+    fn test_honest_attempt() {
+        let responses = vec![
+            response(Some(1.0), Some(4.2)),
+            response(Some(0.0), Some(9.8)),
+            response(Some(1.0), Some(0.8)),
+            response(Some(1.0), Some(6.1)),
+            response(Some(1.0), Some(3.3)),
+            response(None, None),
+        ];
+
+        assert!(flags(&responses, Some(5.0)).is_empty());
+    }
+
+    #[test]
+    fn test_instant_correct_answers() {
+        let responses = vec![
+            response(Some(1.0), Some(0.3)),
+            response(Some(1.0), Some(0.4)),
+            response(Some(1.0), Some(0.2)),
+            response(Some(0.0), Some(0.1)),
+        ];
+
+        let reasons = flags(&responses, None);
+        assert_eq!(reasons.len(), 1);
+        assert!(reasons[0].starts_with("3 correct answers"));
+    }
+
+    #[test]
+    fn test_uniform_perfect_run() {
+        let responses: Vec<_> = (0..6).map(|i| response(Some(1.0), Some(4.0 + i as f64 * 0.05))).collect();
+        assert_eq!(flags(&responses, None).len(), 1);
+
+        let mut with_miss = responses;
+        with_miss.push(response(Some(0.0), Some(4.0)));
+        assert!(flags(&with_miss, None).is_empty(), "Uniform times alone shouldn't flag an imperfect run");
+    }
+
+    #[test]
+    fn test_fast_against_population() {
+        let responses = vec![
+            response(Some(1.0), Some(1.5)),
+            response(Some(1.0), Some(2.5)),
+            response(Some(1.0), Some(1.2)),
+        ];
+
+        assert_eq!(flags(&responses, Some(12.0)).len(), 1);
+        assert!(flags(&responses, Some(4.0)).is_empty());
+        assert!(flags(&responses, None).is_empty());
+    }
+}
@@ -0,0 +1,470 @@
+/// Playing quizzes
+/// Starts attempts, serves their questions, grades the answers given during
+/// them and totals the score. All timing is taken from the server's clock.
+
+pub mod anti_cheat;
+pub mod review;
+
+use crate::errors::{AppError, AppErrorType};
+use crate::grading::{self, scoring::{self, Response, Rules, ScoreBreakdown}};
+use crate::models::{
+    attempt::{
+        Attempt, AttemptChoice, AttemptQuestion, AttemptSubmission, ReviewedQuestion, ServedChoice, ServedQuestion,
+        StartRandomQuiz,
+    },
+    grade::{AnswerSubmission, Grade},
+    lifeline::{LifelineBudget, LifelineKind},
+    question::{MatchingPair, QuestionKind},
+    quiz::{Quiz, QuizStatus},
+};
+use crate::repositories::stores::{AnswerStore, AttemptStore, QuestionStore, QuizStore, ScoringRulesStore};
+use chrono::{Duration, NaiveDateTime};
+use std::{collections::HashMap, sync::Arc};
+use uuid::Uuid;
+
+pub const DEFAULT_QUESTION_COUNT: i32 = 10;
+pub const MAX_QUESTION_COUNT: i32 = 50;
+/// Allowance for network latency when checking deadlines
+pub const GRACE_SECONDS: i64 = 2;
+
+fn invalid_attempt(message: &str) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(message.to_string()),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+/// Translates the choice tokens of a submission for `question_id` back to
+/// answer ids. Tokens from other questions or attempts are rejected.
+pub fn resolve_tokens(question_id: Uuid, submission: &AttemptSubmission, choices: &[AttemptChoice]) -> Result<AnswerSubmission, AppError> {
+    let answer_id = |token: Uuid, side: &str| {
+        choices.iter()
+            .find(|choice| choice.token == token && choice.side == side)
+            .map(|choice| choice.answer_id)
+            .ok_or_else(|| invalid_attempt(&format!("unknown choice token {}.", token)))
+    };
+
+    let ordering = match &submission.ordering {
+        Some(tokens) => Some(tokens.iter()
+            .map(|token| answer_id(*token, "answer"))
+            .collect::<Result<Vec<Uuid>, AppError>>()?),
+        None => None,
+    };
+
+    let pairs = match &submission.pairs {
+        Some(pairs) => Some(pairs.iter()
+            .map(|pair| -> Result<MatchingPair, AppError> {
+                Ok(MatchingPair {
+                    answer_id: answer_id(pair.token, "answer")?,
+                    match_answer_id: answer_id(pair.match_token, "match")?,
+                })
+            })
+            .collect::<Result<Vec<MatchingPair>, AppError>>()?),
+        None => None,
+    };
+
+    Ok(AnswerSubmission {
+        question_id,
+        answer_id: submission.choice_token.map(|token| answer_id(token, "answer")).transpose()?,
+        text: submission.text.clone(),
+        number: submission.number,
+        ordering,
+        pairs,
+    })
+}
+
+pub struct AttemptService {
+    pub answers: Arc<dyn AnswerStore>,
+    pub attempts: Arc<dyn AttemptStore>,
+    pub quizzes: Arc<dyn QuizStore>,
+    pub questions: Arc<dyn QuestionStore>,
+    pub scoring_rules: Arc<dyn ScoringRulesStore>,
+}
+
+impl AttemptService {
+    pub async fn start_quiz(&self, quiz_id: Uuid, user_id: Uuid) -> Result<Attempt, AppError> {
+        let quiz = self.quizzes.get(quiz_id).await?;
+        if quiz.quiz_status() != QuizStatus::Published {
+            return Err(invalid_attempt("only published quizzes can be played."));
+        }
+
+        self.attempts.start_quiz(&quiz, user_id).await
+    }
+
+    pub async fn start_random(&self, input: StartRandomQuiz, user_id: Uuid) -> Result<Attempt, AppError> {
+        let count = input.question_count.unwrap_or(DEFAULT_QUESTION_COUNT);
+        if !(1..=MAX_QUESTION_COUNT).contains(&count) {
+            return Err(invalid_attempt(&format!("question_count must be between 1 and {}.", MAX_QUESTION_COUNT)));
+        }
+
+        self.attempts.start_random(input.band_id, user_id, &input.tags.unwrap_or_default(), count).await
+    }
+
+    /// The attempt, if it belongs to the user
+    pub async fn owned(&self, attempt_id: Uuid, user_id: Uuid) -> Result<Attempt, AppError> {
+        let attempt = self.attempts.get(attempt_id).await?;
+        if attempt.user_id == user_id {
+            Ok(attempt)
+        } else {
+            Err(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::ForbiddenError
+            })
+        }
+    }
+
+    pub async fn quiz(&self, attempt: &Attempt) -> Result<Option<Quiz>, AppError> {
+        match attempt.quiz_id {
+            Some(quiz_id) => Ok(Some(self.quizzes.get(quiz_id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// When answers stop being accepted, for quizzes with a time limit
+    pub async fn deadline(&self, attempt: &Attempt) -> Result<Option<NaiveDateTime>, AppError> {
+        Ok(self.quiz(attempt).await?
+            .and_then(|quiz| quiz.time_limit_seconds)
+            .map(|seconds| attempt.started_at + Duration::seconds(seconds as i64)))
+    }
+
+    /// When answers to a question served at `served_at` stop being accepted,
+    /// the earlier of the quiz's time limit and its time per question
+    fn question_deadline(attempt: &Attempt, quiz: Option<&Quiz>, served_at: NaiveDateTime) -> Option<NaiveDateTime> {
+        let quiz = quiz?;
+        let attempt_deadline = quiz.time_limit_seconds
+            .map(|seconds| attempt.started_at + Duration::seconds(seconds as i64));
+        let question_deadline = quiz.seconds_per_question
+            .map(|seconds| served_at + Duration::seconds(seconds as i64));
+
+        match (attempt_deadline, question_deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// `now` comes from the store, the clock questions are served and answered by
+    fn is_past(deadline: Option<NaiveDateTime>, now: NaiveDateTime) -> bool {
+        deadline.map_or(false, |deadline| now > deadline + Duration::seconds(GRACE_SECONDS))
+    }
+
+    /// Serves the first question that is neither answered nor timed out, or
+    /// None once there are no questions left. Until it is answered the same
+    /// question comes back, with the same choices and the same deadline.
+    pub async fn next_question(&self, attempt_id: Uuid, user_id: Uuid) -> Result<Option<ServedQuestion>, AppError> {
+        let attempt = self.owned(attempt_id, user_id).await?;
+        if attempt.finished_at.is_some() {
+            return Err(invalid_attempt("attempt is already finished."));
+        }
+
+        let quiz = self.quiz(&attempt).await?;
+        let now = self.attempts.now().await?;
+        let next = self.attempts.questions(attempt.id).await?
+            .into_iter()
+            .find(|question| question.answered_at.is_none() && !question.served_at.map_or(false, |served_at| {
+                Self::is_past(Self::question_deadline(&attempt, quiz.as_ref(), served_at), now)
+            }));
+
+        let next = match next {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+
+        let served = self.attempts.serve(attempt.id, next.question_id).await?;
+
+        Ok(Some(self.served_question(&attempt, quiz.as_ref(), &served).await?))
+    }
+
+    async fn served_question(&self, attempt: &Attempt, quiz: Option<&Quiz>, served: &AttemptQuestion) -> Result<ServedQuestion, AppError> {
+        let served_at = served.served_at
+            .ok_or_else(|| invalid_attempt("question hasn't been served yet."))?;
+        let question = self.questions.get_any(served.question_id).await?;
+        let choices: Vec<AttemptChoice> = self.attempts.choices(attempt.id, served.question_id).await?
+            .into_iter()
+            .filter(|choice| !choice.eliminated)
+            .collect();
+        let lifelines_used: Vec<LifelineKind> = self.attempts.lifelines(attempt.id).await?
+            .iter()
+            .filter(|lifeline| lifeline.question_id == served.question_id)
+            .filter_map(|lifeline| lifeline.lifeline_kind())
+            .collect();
+
+        Ok(ServedQuestion {
+            hint: if lifelines_used.contains(&LifelineKind::Hint) { question.hint.clone() } else { None },
+            question,
+            position: served.position,
+            served_at,
+            deadline: Self::question_deadline(attempt, quiz, served_at),
+            choices: choices.iter().filter(|choice| choice.side == "answer").map(ServedChoice::from).collect(),
+            match_choices: choices.iter().filter(|choice| choice.side == "match").map(ServedChoice::from).collect(),
+            lifelines_used,
+        })
+    }
+
+    /// The attempt's question at `position`
+    async fn attempt_question(&self, attempt: &Attempt, position: i32) -> Result<AttemptQuestion, AppError> {
+        self.attempts.questions(attempt.id).await?
+            .into_iter()
+            .find(|question| question.position == position)
+            .ok_or_else(|| invalid_attempt("question is not part of this attempt."))
+    }
+
+    /// The served question at `position`, as long as it can still be answered
+    async fn open_question(&self, attempt: &Attempt, position: i32) -> Result<(AttemptQuestion, Option<Quiz>), AppError> {
+        if attempt.finished_at.is_some() {
+            return Err(invalid_attempt("attempt is already finished."));
+        }
+
+        let served = self.attempt_question(attempt, position).await?;
+        let served_at = served.served_at
+            .ok_or_else(|| invalid_attempt("question hasn't been served yet."))?;
+        if served.answered_at.is_some() {
+            return Err(invalid_attempt("question was already answered in this attempt."));
+        }
+
+        let quiz = self.quiz(attempt).await?;
+        if Self::is_past(Self::question_deadline(attempt, quiz.as_ref(), served_at), self.attempts.now().await?) {
+            return Err(invalid_attempt("time is up for this question."));
+        }
+
+        Ok((served, quiz))
+    }
+
+    pub async fn submit(&self, attempt_id: Uuid, user_id: Uuid, submission: AttemptSubmission) -> Result<Grade, AppError> {
+        let attempt = self.owned(attempt_id, user_id).await?;
+        let (served, _) = self.open_question(&attempt, submission.position).await?;
+
+        let choices: Vec<AttemptChoice> = self.attempts.choices(attempt.id, served.question_id).await?
+            .into_iter()
+            .filter(|choice| !choice.eliminated)
+            .collect();
+        let submission = resolve_tokens(served.question_id, &submission, &choices)?;
+
+        let question = self.questions.get_any(submission.question_id).await?;
+        let key = self.questions.answer_key(&question).await?;
+        let grade = grading::grade(&question, &key, &submission)?;
+
+        self.attempts.record_answer(attempt.id, &submission, &grade).await?;
+
+        Ok(grade)
+    }
+
+    /// Grades another answer to a question of a finished attempt without
+    /// recording it, to see what else would have counted
+    pub async fn check(&self, attempt_id: Uuid, user_id: Uuid, submission: AttemptSubmission) -> Result<Grade, AppError> {
+        let attempt = self.owned(attempt_id, user_id).await?;
+        if attempt.finished_at.is_none() {
+            return Err(invalid_attempt("answers can be checked once the attempt is finished."));
+        }
+
+        let served = self.attempt_question(&attempt, submission.position).await?;
+        let choices = self.attempts.choices(attempt.id, served.question_id).await?;
+        let submission = resolve_tokens(served.question_id, &submission, &choices)?;
+
+        let question = self.questions.get_any(served.question_id).await?;
+        let key = self.questions.answer_key(&question).await?;
+        grading::grade(&question, &key, &submission)
+    }
+
+    /// Uses a lifeline on the question being played and serves it again with
+    /// its options narrowed down or its hint shown
+    /// Run in a transaction, concurrent requests then wait on the attempt's lock
+    /// instead of both spending the last lifeline
+    pub async fn use_lifeline(&self, attempt_id: Uuid, user_id: Uuid, position: i32, kind: LifelineKind) -> Result<ServedQuestion, AppError> {
+        let attempt = self.owned(attempt_id, user_id).await?;
+        self.attempts.lock(attempt.id).await?;
+        let (served, quiz) = self.open_question(&attempt, position).await?;
+        let question_id = served.question_id;
+        let question = self.questions.get_any(question_id).await?;
+        let options = self.attempts.choices(attempt.id, question_id).await?
+            .iter()
+            .filter(|choice| choice.side == "answer")
+            .count() as i64;
+
+        let budget = match kind {
+            LifelineKind::FiftyFifty => {
+                if question.kind.parse::<QuestionKind>().ok() != Some(QuestionKind::Choice) || options < 3 {
+                    return Err(invalid_attempt("50/50 needs a choice question with at least three options."));
+                }
+                attempt.fifty_fifty_lifelines
+            },
+            LifelineKind::Hint => {
+                if question.hint.as_ref().map_or(true, |hint| hint.trim().is_empty()) {
+                    return Err(invalid_attempt("this question has no hint."));
+                }
+                attempt.hint_lifelines
+            },
+        };
+
+        let correct_answer_id = match kind {
+            LifelineKind::FiftyFifty => Some(question.correct_answer_id
+                .ok_or_else(|| invalid_attempt("question has no correct answer."))?),
+            LifelineKind::Hint => None,
+        };
+
+        // Spending the lifeline and eliminating choices share the attempt's lock and transaction
+        self.attempts.use_lifeline(attempt.id, question_id, kind, budget).await?;
+        if let Some(correct_answer_id) = correct_answer_id {
+            self.attempts.eliminate_choices(attempt.id, question_id, correct_answer_id, options / 2).await?;
+        }
+
+        self.served_question(&attempt, quiz.as_ref(), &served).await
+    }
+
+    /// Lifelines left in the attempt, by kind
+    pub async fn lifeline_budgets(&self, attempt: &Attempt) -> Result<Vec<LifelineBudget>, AppError> {
+        let used = self.attempts.lifelines(attempt.id).await?;
+
+        Ok([(LifelineKind::FiftyFifty, attempt.fifty_fifty_lifelines), (LifelineKind::Hint, attempt.hint_lifelines)]
+            .iter()
+            .map(|(kind, total)| {
+                let used_count = used.iter().filter(|lifeline| lifeline.lifeline_kind() == Some(*kind)).count() as i32;
+                LifelineBudget { kind: *kind, total: *total, remaining: (total - used_count).max(0) }
+            })
+            .collect())
+    }
+
+    pub async fn rules(&self, attempt: &Attempt) -> Result<Rules, AppError> {
+        match attempt.scoring_rules_id {
+            Some(scoring_rules_id) => Ok(Rules::from(&self.scoring_rules.get(scoring_rules_id).await?)),
+            None => Ok(Rules::default()),
+        }
+    }
+
+    /// The stored responses, each timed from serving to answering
+    async fn responses(&self, attempt: &Attempt) -> Result<Vec<Response>, AppError> {
+        let lifelines = self.attempts.lifelines(attempt.id).await?;
+        let used = |question_id: Uuid, kind: LifelineKind| lifelines.iter()
+            .any(|lifeline| lifeline.question_id == question_id && lifeline.lifeline_kind() == Some(kind));
+
+        Ok(self.attempts.questions(attempt.id).await?
+            .iter()
+            .map(|question| Response {
+                question_id: question.question_id,
+                credit: question.score,
+                seconds: match (question.served_at, question.answered_at) {
+                    (Some(served), Some(answered)) => Some((answered - served).num_milliseconds() as f64 / 1000.0),
+                    _ => None,
+                },
+                fifty_fifty: used(question.question_id, LifelineKind::FiftyFifty),
+                hint: used(question.question_id, LifelineKind::Hint),
+            })
+            .collect())
+    }
+
+    /// Rescores the attempt from its stored responses
+    pub async fn breakdown(&self, attempt: &Attempt) -> Result<ScoreBreakdown, AppError> {
+        let responses = self.responses(attempt).await?;
+
+        Ok(scoring::score(&self.rules(attempt).await?, &responses))
+    }
+
+    /// Every question of a finished attempt with the player's answer, the
+    /// correct answer and why it's correct
+    pub async fn review(&self, attempt_id: Uuid, user_id: Uuid) -> Result<Vec<ReviewedQuestion>, AppError> {
+        let attempt = self.owned(attempt_id, user_id).await?;
+        if attempt.finished_at.is_none() {
+            return Err(invalid_attempt("the review is available once the attempt is finished."));
+        }
+
+        let mut reviewed = Vec::new();
+        for served in self.attempts.questions(attempt.id).await? {
+            let question = self.questions.get_any(served.question_id).await?;
+            let key = self.questions.answer_key(&question).await?;
+            let contents: HashMap<Uuid, String> = self.answers.get_many(&review::answer_ids(&served, &key)).await?
+                .into_iter()
+                .map(|answer| (answer.id, answer.content))
+                .collect();
+            let kind = question.kind.parse().unwrap_or(QuestionKind::Choice);
+
+            reviewed.push(ReviewedQuestion {
+                question_id: question.id,
+                position: served.position,
+                kind,
+                given_answer: review::given_answer(kind, &served, &contents),
+                correct_answer: review::correct_answer(kind, question.numeric_value, &key, &contents),
+                accepted: served.accepted.unwrap_or(false),
+                score: served.score.unwrap_or(0.0),
+                content: question.content,
+                explanation: question.explanation,
+                source: question.source,
+            });
+        }
+
+        Ok(reviewed)
+    }
+
+    /// Ends the attempt, unanswered questions earning nothing, and flags it
+    /// for review if its timing looks implausible
+    pub async fn finish(&self, attempt_id: Uuid, user_id: Uuid) -> Result<Attempt, AppError> {
+        let attempt = self.owned(attempt_id, user_id).await?;
+        let responses = self.responses(&attempt).await?;
+        let breakdown = scoring::score(&self.rules(&attempt).await?, &responses);
+
+        let population_median = self.attempts.median_answer_seconds(attempt.band_id, attempt.quiz_id).await?;
+        let flag_reasons = anti_cheat::flags(&responses, population_median);
+
+        self.attempts.finish(attempt.id, breakdown.total, &flag_reasons).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::resolve_tokens;
+    use crate::models::attempt::{AttemptChoice, AttemptSubmission, TokenPair};
+    use uuid::Uuid;
+
+    fn choice(question_id: Uuid, side: &str, position: i32) -> AttemptChoice {
+        AttemptChoice {
+            token: Uuid::new_v4(),
+            attempt_id: Uuid::nil(),
+            question_id,
+            answer_id: Uuid::new_v4(),
+            side: side.to_string(),
+            position,
+            eliminated: false,
+            content: String::new(),
+        }
+    }
+
+    fn submission() -> AttemptSubmission {
+        AttemptSubmission { position: 0, choice_token: None, text: None, number: None, ordering: None, pairs: None }
+    }
+
+    #[test]
+    fn test_resolve_tokens() {
+        let question_id = Uuid::new_v4();
+        let choices = vec![choice(question_id, "answer", 0), choice(question_id, "answer", 1), choice(question_id, "match", 0)];
+
+        let resolved = resolve_tokens(question_id, &AttemptSubmission {
+            choice_token: Some(choices[1].token),
+            ordering: Some(vec![choices[1].token, choices[0].token]),
+            pairs: Some(vec![TokenPair { token: choices[0].token, match_token: choices[2].token }]),
+            ..submission()
+        }, &choices).unwrap();
+
+        assert_eq!(resolved.question_id, question_id);
+        assert_eq!(resolved.answer_id, Some(choices[1].answer_id));
+        assert_eq!(resolved.ordering, Some(vec![choices[1].answer_id, choices[0].answer_id]));
+        let pairs = resolved.pairs.unwrap();
+        assert_eq!(pairs[0].answer_id, choices[0].answer_id);
+        assert_eq!(pairs[0].match_answer_id, choices[2].answer_id);
+    }
+
+    #[test]
+    fn test_resolve_rejects_foreign_tokens() {
+        let question_id = Uuid::new_v4();
+        let choices = vec![choice(question_id, "answer", 0), choice(question_id, "match", 0)];
+
+        let unknown = AttemptSubmission { choice_token: Some(Uuid::new_v4()), ..submission() };
+        assert!(resolve_tokens(question_id, &unknown, &choices).is_err());
+
+        let answer_ids_are_not_tokens = AttemptSubmission { choice_token: Some(choices[0].answer_id), ..submission() };
+        assert!(resolve_tokens(question_id, &answer_ids_are_not_tokens, &choices).is_err());
+
+        let wrong_side = AttemptSubmission { choice_token: Some(choices[1].token), ..submission() };
+        assert!(resolve_tokens(question_id, &wrong_side, &choices).is_err());
+    }
+}
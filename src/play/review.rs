@@ -0,0 +1,151 @@
+/// Attempt review
+/// Puts what a player answered next to the correct answer, as text
+
+use crate::grading::AnswerKey;
+use crate::models::{attempt::AttemptQuestion, question::QuestionKind};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+fn content(contents: &HashMap<Uuid, String>, id: Uuid) -> String {
+    contents.get(&id).cloned().unwrap_or_else(|| id.to_string())
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn pairs(contents: &HashMap<Uuid, String>, pairs: impl Iterator<Item = (Uuid, Uuid)>) -> Vec<String> {
+    pairs
+        .map(|(answer_id, match_answer_id)| format!("{} → {}", content(contents, answer_id), content(contents, match_answer_id)))
+        .collect()
+}
+
+/// What the player answered, empty if they didn't
+pub fn given_answer(kind: QuestionKind, served: &AttemptQuestion, contents: &HashMap<Uuid, String>) -> Vec<String> {
+    match kind {
+        QuestionKind::Choice => served.answer_id.iter().map(|id| content(contents, *id)).collect(),
+        QuestionKind::FreeText => served.answer_text.iter().cloned().collect(),
+        QuestionKind::Numeric => served.answer_number.iter().map(|value| format_number(*value)).collect(),
+        QuestionKind::Ordering => served.answer_ordering.iter().flatten().map(|id| content(contents, *id)).collect(),
+        QuestionKind::Matching => match (&served.answer_pair_ids, &served.answer_pair_match_ids) {
+            (Some(ids), Some(match_ids)) => pairs(contents, ids.iter().cloned().zip(match_ids.iter().cloned())),
+            _ => vec![],
+        },
+    }
+}
+
+pub fn correct_answer(kind: QuestionKind, numeric_value: Option<f64>, key: &AnswerKey, contents: &HashMap<Uuid, String>) -> Vec<String> {
+    match kind {
+        QuestionKind::Choice | QuestionKind::FreeText => key.correct_answer.iter().map(|answer| answer.content.clone()).collect(),
+        QuestionKind::Numeric => numeric_value.iter().map(|value| format_number(*value)).collect(),
+        QuestionKind::Ordering => {
+            let mut choices: Vec<_> = key.choices.iter().collect();
+            choices.sort_by_key(|choice| choice.position);
+            choices.iter().map(|choice| content(contents, choice.answer_id)).collect()
+        },
+        QuestionKind::Matching => pairs(contents, key.choices.iter()
+            .filter_map(|choice| choice.match_answer_id.map(|match_answer_id| (choice.answer_id, match_answer_id)))),
+    }
+}
+
+/// Ids of every answer `given_answer` and `correct_answer` need the content of
+pub fn answer_ids(served: &AttemptQuestion, key: &AnswerKey) -> Vec<Uuid> {
+    served.answer_id.iter()
+        .chain(served.answer_ordering.iter().flatten())
+        .chain(served.answer_pair_ids.iter().flatten())
+        .chain(served.answer_pair_match_ids.iter().flatten())
+        .cloned()
+        .chain(key.choices.iter().flat_map(|choice| Some(choice.answer_id).into_iter().chain(choice.match_answer_id)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{answer_ids, correct_answer, given_answer};
+    use crate::grading::AnswerKey;
+    use crate::models::{attempt::AttemptQuestion, question::QuestionKind, question_choice::QuestionChoice};
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn served() -> AttemptQuestion {
+        AttemptQuestion {
+            attempt_id: Uuid::new_v4(),
+            question_id: Uuid::new_v4(),
+            position: 0,
+            answer_id: None,
+            answer_text: None,
+            answer_number: None,
+            answer_ordering: None,
+            answer_pair_ids: None,
+            answer_pair_match_ids: None,
+            accepted: None,
+            score: None,
+            answered_at: None,
+            served_at: None,
+        }
+    }
+
+    fn choice(answer_id: Uuid, position: i32, match_answer_id: Option<Uuid>) -> QuestionChoice {
+        let now = Utc::now().naive_utc();
+        QuestionChoice {
+            id: Uuid::new_v4(),
+            question_id: Uuid::nil(),
+            answer_id,
+            position,
+            match_answer_id,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_numeric_answers() {
+        let key = AnswerKey { correct_answer: None, aliases: vec![], choices: vec![] };
+        let answered = AttemptQuestion { answer_number: Some(1991.0), ..served() };
+
+        assert_eq!(given_answer(QuestionKind::Numeric, &answered, &HashMap::new()), vec!["1991"]);
+        assert_eq!(correct_answer(QuestionKind::Numeric, Some(3.5), &key, &HashMap::new()), vec!["3.5"]);
+        assert!(given_answer(QuestionKind::Numeric, &served(), &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_ordering_answers() {
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let contents: HashMap<Uuid, String> = vec![(first, "Nevermind".to_string()), (second, "In Utero".to_string())]
+            .into_iter()
+            .collect();
+        let key = AnswerKey { correct_answer: None, aliases: vec![], choices: vec![choice(second, 1, None), choice(first, 0, None)] };
+        let answered = AttemptQuestion { answer_ordering: Some(vec![second, first]), ..served() };
+
+        assert_eq!(given_answer(QuestionKind::Ordering, &answered, &contents), vec!["In Utero", "Nevermind"]);
+        assert_eq!(correct_answer(QuestionKind::Ordering, None, &key, &contents), vec!["Nevermind", "In Utero"]);
+    }
+
+    #[test]
+    fn test_matching_answers() {
+        let ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let contents: HashMap<Uuid, String> = ids.iter().zip(&["Kurt", "Dave", "guitar", "drums"])
+            .map(|(id, content)| (*id, content.to_string()))
+            .collect();
+        let key = AnswerKey { correct_answer: None, aliases: vec![], choices: vec![choice(ids[0], 0, Some(ids[2])), choice(ids[1], 1, Some(ids[3]))] };
+        let answered = AttemptQuestion {
+            answer_pair_ids: Some(vec![ids[0], ids[1]]),
+            answer_pair_match_ids: Some(vec![ids[3], ids[2]]),
+            ..served()
+        };
+
+        assert_eq!(given_answer(QuestionKind::Matching, &answered, &contents), vec!["Kurt → drums", "Dave → guitar"]);
+        assert_eq!(correct_answer(QuestionKind::Matching, None, &key, &contents), vec!["Kurt → guitar", "Dave → drums"]);
+
+        let mut needed = answer_ids(&answered, &key);
+        needed.sort();
+        needed.dedup();
+        assert_eq!(needed.len(), 4);
+    }
+}
@@ -0,0 +1,43 @@
+/// Purge
+/// Permanently removes bands, questions and users once they've been deleted
+/// for longer than the retention period
+
+use crate::config::RetentionSettings;
+use crate::errors::AppError;
+use crate::repositories::{band::BandRepository, question::QuestionRepository, user::UserRepository};
+use deadpool_postgres::Pool;
+use slog_scope::{error, info};
+use std::{sync::Arc, time::Duration};
+
+#[derive(Debug, Default)]
+pub struct PurgeReport {
+    pub questions: u64,
+    pub bands: u64,
+    pub users: u64,
+}
+
+/// Questions go first so their bands can follow in the same run
+pub async fn purge_deleted(pool: Arc<Pool>, retention_days: i32) -> Result<PurgeReport, AppError> {
+    let questions = QuestionRepository::new(pool.clone()).purge_deleted(retention_days).await?;
+    let bands = BandRepository::new(pool.clone()).purge_deleted(retention_days).await?;
+    let users = UserRepository::new(pool).purge_deleted(retention_days).await?;
+
+    Ok(PurgeReport { questions, bands, users })
+}
+
+/// Purges in the background every `interval_minutes` while the server runs
+pub fn spawn(pool: Arc<Pool>, settings: RetentionSettings) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(settings.interval_minutes * 60));
+        loop {
+            interval.tick().await;
+            match purge_deleted(pool.clone(), settings.deleted_days).await {
+                Ok(report) => info!(
+                    "Purged {} questions, {} bands and {} users",
+                    report.questions, report.bands, report.users
+                ),
+                Err(err) => error!("Error purging deleted content {}", err.message()),
+            }
+        }
+    });
+}
@@ -0,0 +1,182 @@
+/// Reports
+/// Takes fans' reports of wrong or offensive content, hides content once
+/// enough of them are open, and closes them when someone reviews it
+
+use crate::errors::{AppError, AppErrorType};
+use crate::models::report::{Report, ReportReason, ReportStatus, ReportTargetType, HIDE_THRESHOLD};
+use crate::repositories::stores::{BandStore, QuestionStore, ReportStore, UserStore};
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn invalid_report(message: &str) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(message.to_string()),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub struct ReportService {
+    pub reports: Arc<dyn ReportStore>,
+    pub questions: Arc<dyn QuestionStore>,
+    pub bands: Arc<dyn BandStore>,
+    pub users: Arc<dyn UserStore>,
+}
+
+impl ReportService {
+    /// Band whose owner and moderators handle reports on the target, None for users
+    pub async fn band_of(&self, target_type: ReportTargetType, target_id: Uuid) -> Result<Option<Uuid>, AppError> {
+        match target_type {
+            ReportTargetType::Question => Ok(Some(self.questions.get(target_id).await?.band_id)),
+            ReportTargetType::Band => Ok(Some(self.bands.get(target_id).await?.id)),
+            ReportTargetType::User => Ok(None),
+        }
+    }
+
+    async fn is_hidden(&self, target_type: ReportTargetType, target_id: Uuid) -> Result<bool, AppError> {
+        Ok(match target_type {
+            ReportTargetType::Question => self.questions.get(target_id).await?.hidden_at.is_some(),
+            ReportTargetType::Band => self.bands.get(target_id).await?.hidden_at.is_some(),
+            ReportTargetType::User => self.users.get(target_id).await?.hidden_at.is_some(),
+        })
+    }
+
+    /// Reporting the same target again updates the reporter's open report.
+    /// Also returns whether this report is the one that hid the target.
+    pub async fn report(&self, reporter_id: Uuid, target_type: ReportTargetType, target_id: Uuid, reason: ReportReason, note: Option<String>) -> Result<(Report, bool), AppError> {
+        if target_type == ReportTargetType::User {
+            if target_id == reporter_id {
+                return Err(invalid_report("you can't report yourself."));
+            }
+            self.users.get(target_id).await?;
+        } else {
+            self.band_of(target_type, target_id).await?;
+        }
+
+        let note = note.map(|note| note.trim().to_string()).filter(|note| !note.is_empty());
+        let report = self.reports.create(reporter_id, target_type, target_id, reason, note.as_deref()).await?;
+
+        let hide = self.reports.open_count(target_type, target_id).await? >= HIDE_THRESHOLD
+            && !self.is_hidden(target_type, target_id).await?;
+        if hide {
+            self.reports.set_hidden(target_type, target_id, true).await?;
+        }
+
+        Ok((report, hide))
+    }
+
+    /// Closes every open report on the report's target. Dismissing shows the
+    /// content again, upholding keeps it hidden.
+    pub async fn resolve(&self, report_id: Uuid, resolved_by: Uuid, status: ReportStatus) -> Result<Vec<Report>, AppError> {
+        if status == ReportStatus::Open {
+            return Err(invalid_report("a report can't be resolved as open."));
+        }
+
+        let report = self.reports.get(report_id).await?;
+        if report.status != ReportStatus::Open.as_str() {
+            return Err(invalid_report("report was already resolved."));
+        }
+
+        let target_type = report.report_target_type();
+        self.reports.set_hidden(target_type, report.target_id, status == ReportStatus::Upheld).await?;
+        self.reports.resolve(target_type, report.target_id, status, resolved_by).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::ReportService;
+    use crate::models::{band::{Band, CreateBand}, report::{ReportReason, ReportStatus, ReportTargetType, HIDE_THRESHOLD}};
+    use crate::repositories::{memory::MemoryStore, stores::{BandStore, ReportStore}};
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    fn service(store: &Arc<MemoryStore>) -> ReportService {
+        ReportService {
+            reports: store.clone(),
+            questions: store.clone(),
+            bands: store.clone(),
+            users: store.clone(),
+        }
+    }
+
+    async fn band(store: &MemoryStore) -> Band {
+        let owner = store.add_user("owner", false);
+        BandStore::create(store, CreateBand { name: "Radiohead".to_string() }, owner.id).await.unwrap()
+    }
+
+    async fn report(service: &ReportService, reporter_id: Uuid, band: &Band) -> bool {
+        let (_, hidden) = service.report(reporter_id, ReportTargetType::Band, band.id, ReportReason::Offensive, None).await.unwrap();
+        hidden
+    }
+
+    #[actix_rt::test]
+    async fn test_reporting_again_updates_report() {
+        let store = MemoryStore::new();
+        let service = service(&store);
+        let band = band(&store).await;
+        let fan = store.add_user("fan", false);
+
+        let (first, _) = service.report(fan.id, ReportTargetType::Band, band.id, ReportReason::WrongAnswer, None).await.unwrap();
+        let (second, _) = service.report(fan.id, ReportTargetType::Band, band.id, ReportReason::Offensive, Some("  ".to_string())).await.unwrap();
+
+        assert_eq!(second.id, first.id);
+        assert_eq!(second.reason, "offensive");
+        assert_eq!(second.note, None, "A blank note should be dropped");
+        assert_eq!(ReportStore::open_count(&*store, ReportTargetType::Band, band.id).await.unwrap(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_hides_at_threshold() {
+        let store = MemoryStore::new();
+        let service = service(&store);
+        let band = band(&store).await;
+
+        for number in 1..HIDE_THRESHOLD {
+            let fan = store.add_user(&format!("fan {}", number), false);
+            assert!(!report(&service, fan.id, &band).await);
+        }
+        assert!(BandStore::get(&*store, band.id).await.unwrap().hidden_at.is_none());
+
+        let fan = store.add_user("last fan", false);
+        assert!(report(&service, fan.id, &band).await, "The report reaching the threshold should hide the band");
+        assert!(BandStore::get(&*store, band.id).await.unwrap().hidden_at.is_some());
+
+        let fan = store.add_user("late fan", false);
+        assert!(!report(&service, fan.id, &band).await, "Only the report that hid the band should say so");
+    }
+
+    #[actix_rt::test]
+    async fn test_cant_report_yourself() {
+        let store = MemoryStore::new();
+        let fan = store.add_user("fan", false);
+
+        let error = service(&store).report(fan.id, ReportTargetType::User, fan.id, ReportReason::Spam, None).await.err().unwrap();
+
+        assert_eq!(error.message(), "you can't report yourself.");
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve() {
+        let store = MemoryStore::new();
+        let service = service(&store);
+        let band = band(&store).await;
+        let moderator = store.add_user("moderator", false);
+        let mut reports = vec![];
+        for number in 0..HIDE_THRESHOLD {
+            let fan = store.add_user(&format!("fan {}", number), false);
+            reports.push(service.report(fan.id, ReportTargetType::Band, band.id, ReportReason::Offensive, None).await.unwrap().0);
+        }
+
+        let resolved = service.resolve(reports[0].id, moderator.id, ReportStatus::Dismissed).await.unwrap();
+
+        assert_eq!(resolved.len(), reports.len(), "Every open report on the band should be closed");
+        assert!(resolved.iter().all(|report| report.status == "dismissed" && report.resolved_by == Some(moderator.id)));
+        assert!(BandStore::get(&*store, band.id).await.unwrap().hidden_at.is_none(), "Dismissing should show the band again");
+
+        let error = service.resolve(reports[1].id, moderator.id, ReportStatus::Upheld).await.err().unwrap();
+        assert_eq!(error.message(), "report was already resolved.");
+    }
+
+}
@@ -1,23 +1,58 @@
-use deadpool_postgres::{Pool, Client};
-use std::sync::Arc;
+use crate::repositories::{db::{Db, DbClient}, stores::AnswerStore};
 use slog_scope::error;
-use crate::models::answer::{Answer, CreateAnswer};
+use crate::models::answer::{Answer, CreateAnswer, AnswerAlias, CreateAnswerAlias};
 use tokio_pg_mapper::FromTokioPostgresRow;
 use crate::errors::{AppError, AppErrorType};
 use tokio_postgres::error::{Error, SqlState};
 use uuid::Uuid;
+use async_trait::async_trait;
 
 pub struct AnswerRepository {
-    pool: Arc<Pool>
+    pool: Db
+}
+
+pub(crate) fn answer_exists(content: &str, cause: Option<String>) -> AppError {
+    AppError {
+        cause,
+        message: Some(format!("answer {} already exists for this band.", content)),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub(crate) fn band_missing(band_id: Uuid, cause: Option<String>) -> AppError {
+    AppError {
+        cause,
+        message: Some(format!("band with id {} doesn't exists.", band_id)),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub(crate) fn alias_exists(content: &str, cause: Option<String>) -> AppError {
+    AppError {
+        cause,
+        message: Some(format!("alias {} already exists.", content)),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub(crate) fn answer_missing(answer_id: Uuid, cause: Option<String>) -> AppError {
+    AppError {
+        cause,
+        message: Some(format!("answer with id {} doesn't exists.", answer_id)),
+        error_type: AppErrorType::InvalidField
+    }
 }
 
 impl AnswerRepository {
-    pub fn new(pool: Arc<Pool>) -> AnswerRepository {
-        AnswerRepository { pool }
+    pub fn new(pool: impl Into<Db>) -> AnswerRepository {
+        AnswerRepository { pool: pool.into() }
     }
+}
 
-    pub async fn get(&self, id: Uuid) -> Result<Answer, AppError> {
-        let client: Client = self.pool
+#[async_trait]
+impl AnswerStore for AnswerRepository {
+    async fn get(&self, id: Uuid) -> Result<Answer, AppError> {
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
@@ -45,8 +80,33 @@ impl AnswerRepository {
             })
     }
 
-    pub async fn all(&self) -> Result<Vec<Answer>, AppError> {
-        let client: Client = self.pool
+    async fn get_many(&self, ids: &[Uuid]) -> Result<Vec<Answer>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "get_many");
+                err
+            })?;
+
+        let statement = client.prepare("select * from answers where id = any($1)").await?;
+
+        let answers = client
+            .query(&statement, &[&ids])
+            .await
+            .map_err(|err| {
+                error!("Error getting answers {}", err; "query" => "get_many");
+                err
+            })?
+            .iter()
+            .map(|row| Answer::from_row_ref(row))
+            .collect::<Result<Vec<Answer>, _>>()?;
+
+        Ok(answers)
+    }
+
+    async fn all(&self) -> Result<Vec<Answer>, AppError> {
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
@@ -74,8 +134,37 @@ impl AnswerRepository {
         Ok(answers)
     }
 
-    pub async fn create(&self, input: CreateAnswer) -> Result<Answer, AppError> {
-        let client: Client = self.pool
+    async fn get_for_band(&self, band_id: Uuid) -> Result<Vec<Answer>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "get_for_band");
+                err
+            })?;
+
+        let statement = client.prepare("select * from answers where band_id = $1 order by content").await?;
+
+        let answers = client
+            .query(&statement, &[&band_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting answers. {}", err; "query" => "get_for_band");
+                err
+            })?
+            .iter()
+            .map(|row| Answer::from_row_ref(row))
+            .collect::<Result<Vec<Answer>, _>>()
+            .map_err(|err| {
+                error!("Error getting parsing answers. {}", err; "query" => "get_for_band");
+                err
+            })?;
+
+        Ok(answers)
+    }
+
+    async fn create(&self, input: CreateAnswer) -> Result<Answer, AppError> {
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
@@ -84,23 +173,21 @@ impl AnswerRepository {
             })?;
 
         let statement = client
-            .prepare("insert into answers (content) values ($1) returning *")
+            .prepare("insert into answers (band_id, content) values ($1, $2) returning *")
             .await?;
 
         let answer = client.query(&statement, &[
+            &input.band_id,
             &input.content,
             ])
             .await
             .map_err(|err: Error| {
-                let unique_error = err.code()
-                    .map(|code| code == &SqlState::UNIQUE_VIOLATION);
-
-                match unique_error {
-                    Some(true) => AppError {
-                        cause: Some(err.to_string()),
-                        message: Some("answer already exists.".to_string()),
-                        error_type: AppErrorType::InvalidField
-                        },
+                match err.code() {
+                    Some(code) => match code {
+                        c if c == &SqlState::UNIQUE_VIOLATION => answer_exists(&input.content, Some(err.to_string())),
+                        c if c == &SqlState::FOREIGN_KEY_VIOLATION => band_missing(input.band_id, Some(err.to_string())),
+                        _ => AppError::from(err)
+                    }
                     _ => AppError::from(err)
                 }
             })?
@@ -114,6 +201,170 @@ impl AnswerRepository {
                 error_type: AppErrorType::DbError,
             })?;
 
+        for alias in input.aliases.unwrap_or_default() {
+            self.create_alias(CreateAnswerAlias { answer_id: answer.id, content: alias }).await?;
+        }
+
+        Ok(answer)
+    }
+
+    async fn aliases(&self, answer_id: Uuid) -> Result<Vec<AnswerAlias>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "aliases");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select * from answer_aliases where answer_id = $1 order by created_at")
+            .await?;
+
+        let aliases = client
+            .query(&statement, &[&answer_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting answer aliases. {}", err; "query" => "aliases");
+                err
+            })?
+            .iter()
+            .map(|row| AnswerAlias::from_row_ref(row))
+            .collect::<Result<Vec<AnswerAlias>, _>>()
+            .map_err(|err| {
+                error!("Error getting parsing answer aliases. {}", err; "query" => "aliases");
+                err
+            })?;
+
+        Ok(aliases)
+    }
+
+    async fn create_alias(&self, input: CreateAnswerAlias) -> Result<AnswerAlias, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "create_alias");
+                err
+            })?;
+
+        let statement = client
+            .prepare("insert into answer_aliases (answer_id, content) values ($1, $2) returning *")
+            .await?;
+
+        let alias = client.query(&statement, &[
+                &input.answer_id,
+                &input.content,
+            ])
+            .await
+            .map_err(|err: Error| {
+                match err.code() {
+                    Some(code) => match code {
+                        c if c == &SqlState::UNIQUE_VIOLATION => alias_exists(&input.content, Some(err.to_string())),
+                        c if c == &SqlState::FOREIGN_KEY_VIOLATION => answer_missing(input.answer_id, Some(err.to_string())),
+                        _ => AppError::from(err)
+                    }
+                    _ => AppError::from(err)
+                }
+            })?
+            .iter()
+            .map(|row| AnswerAlias::from_row_ref(row))
+            .collect::<Result<Vec<AnswerAlias>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error creating AnswerAlias.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })?;
+
+        Ok(alias)
+    }
+
+    async fn set_aliases(&self, answer_id: Uuid, contents: &[String]) -> Result<Vec<AnswerAlias>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "set_aliases");
+                err
+            })?;
+
+        let statement = client
+            .prepare("delete from answer_aliases where answer_id = $1 and content <> all($2)")
+            .await?;
+
+        client
+            .execute(&statement, &[&answer_id, &contents])
+            .await
+            .map_err(|err| {
+                error!("Error deleting answer aliases. {}", err; "query" => "set_aliases");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                insert into answer_aliases (answer_id, content)
+                select $1, unnest($2::varchar[])
+                on conflict (answer_id, content) do nothing
+            ")
+            .await?;
+
+        client
+            .execute(&statement, &[&answer_id, &contents])
+            .await
+            .map_err(|err| {
+                error!("Error creating answer aliases. {}", err; "query" => "set_aliases");
+                err
+            })?;
+
+        self.aliases(answer_id).await
+    }
+
+    async fn find_or_create(&self, band_id: Uuid, content: &str) -> Result<(Answer, bool), AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "find_or_create");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                with inserted as (
+                    insert into answers (band_id, content) values ($1, $2)
+                    on conflict (band_id, content) do nothing
+                    returning *
+                )
+                select *, true as created from inserted
+                union all
+                select *, false as created from answers where band_id = $1 and content = $2
+                limit 1
+            ")
+            .await?;
+
+        let answer = client
+            .query(&statement, &[&band_id, &content])
+            .await
+            .map_err(|err: Error| {
+                match err.code() {
+                    Some(code) if code == &SqlState::FOREIGN_KEY_VIOLATION => band_missing(band_id, Some(err.to_string())),
+                    _ => {
+                        error!("Error finding or creating answer. {}", err; "query" => "find_or_create");
+                        AppError::from(err)
+                    }
+                }
+            })?
+            .iter()
+            .map(|row| Ok((Answer::from_row_ref(row)?, row.get("created"))))
+            .collect::<Result<Vec<(Answer, bool)>, tokio_pg_mapper::Error>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error creating Answer.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })?;
+
         Ok(answer)
     }
 }
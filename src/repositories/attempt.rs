@@ -0,0 +1,644 @@
+use crate::repositories::{db::{Db, DbClient}, stores::AttemptStore};
+use slog_scope::error;
+use crate::models::{
+    attempt::{Attempt, AttemptChoice, AttemptQuestion},
+    grade::{AnswerSubmission, Grade},
+    lifeline::{Lifeline, LifelineKind},
+    quiz::Quiz,
+    tag::normalize_tags,
+};
+use tokio_pg_mapper::FromTokioPostgresRow;
+use chrono::NaiveDateTime;
+use crate::errors::{AppError, AppErrorType};
+use uuid::Uuid;
+use async_trait::async_trait;
+
+pub struct AttemptRepository {
+    pool: Db
+}
+
+fn invalid_attempt(message: &str) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(message.to_string()),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub(crate) fn no_matching_questions() -> AppError {
+    invalid_attempt("no questions match these tags.")
+}
+
+pub(crate) fn already_answered() -> AppError {
+    invalid_attempt("question was already answered in this attempt.")
+}
+
+pub(crate) fn not_in_attempt() -> AppError {
+    invalid_attempt("question is not part of this attempt.")
+}
+
+pub(crate) fn no_lifeline_left() -> AppError {
+    invalid_attempt("no lifeline of this kind left for this question.")
+}
+
+pub(crate) fn already_finished() -> AppError {
+    invalid_attempt("attempt is already finished.")
+}
+
+impl AttemptRepository {
+
+    pub fn new(pool: impl Into<Db>) -> AttemptRepository {
+        AttemptRepository { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl AttemptStore for AttemptRepository {
+    async fn get(&self, id: Uuid) -> Result<Attempt, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "get");
+                err
+            })?;
+
+        let statement = client.prepare("select * from attempts where id = $1").await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error getting attempts. {}", err; "query" => "get");
+                err
+            })?
+            .iter()
+            .map(|row| Attempt::from_row_ref(row))
+            .collect::<Result<Vec<Attempt>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+
+    async fn lock(&self, id: Uuid) -> Result<Attempt, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "lock");
+                err
+            })?;
+
+        let statement = client.prepare("select * from attempts where id = $1 for update").await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error locking attempt. {}", err; "query" => "lock");
+                err
+            })?
+            .iter()
+            .map(|row| Attempt::from_row_ref(row))
+            .collect::<Result<Vec<Attempt>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+
+    async fn now(&self) -> Result<NaiveDateTime, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "now");
+                err
+            })?;
+
+        // What current_timestamp is stored as in timestamp columns
+        let statement = client.prepare("select localtimestamp").await?;
+
+        let row = client
+            .query_one(&statement, &[])
+            .await
+            .map_err(|err| {
+                error!("Error getting the time. {}", err; "query" => "now");
+                err
+            })?;
+
+        Ok(row.get(0))
+    }
+
+    async fn start_quiz(&self, quiz: &Quiz, user_id: Uuid) -> Result<Attempt, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "start_quiz");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                with attempt as (
+                    insert into attempts (quiz_id, band_id, user_id, scoring_rules_id, fifty_fifty_lifelines, hint_lifelines)
+                    values ($1, $2, $3, $4, $5, $6)
+                    returning *
+                ), served as (
+                    insert into attempt_questions (attempt_id, question_id, position)
+                    select attempt.id, qq.question_id, qq.position
+                    from attempt, quiz_questions qq
+                    where qq.quiz_id = $1
+                )
+                select * from attempt
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[
+                &quiz.id,
+                &quiz.band_id,
+                &user_id,
+                &quiz.scoring_rules_id,
+                &quiz.fifty_fifty_lifelines,
+                &quiz.hint_lifelines,
+            ])
+            .await
+            .map_err(|err| {
+                error!("Error starting quiz. {}", err; "query" => "start_quiz");
+                err
+            })?
+            .iter()
+            .map(|row| Attempt::from_row_ref(row))
+            .collect::<Result<Vec<Attempt>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error creating Attempt.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })
+    }
+
+    async fn start_random(&self, band_id: Uuid, user_id: Uuid, tags: &[String], count: i32) -> Result<Attempt, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "start_random");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                with picked as (
+                    select id, (row_number() over () - 1)::int4 as position
+                    from (
+                        select q.id from questions q
+                        where q.band_id = $1 and q.status = 'approved' and q.hidden_at is null and q.deleted_at is null
+                            and (
+                                select count(*) from question_tags qt
+                                join tags t on t.id = qt.tag_id
+                                where qt.question_id = q.id and t.name = any($3)
+                            ) = $4
+                        order by random()
+                        limit $5
+                    ) random_questions
+                ), attempt as (
+                    insert into attempts (band_id, user_id)
+                    select $1, $2 where exists (select 1 from picked)
+                    returning *
+                ), served as (
+                    insert into attempt_questions (attempt_id, question_id, position)
+                    select attempt.id, picked.id, picked.position from attempt, picked
+                )
+                select * from attempt
+            ")
+            .await?;
+
+        let tags = normalize_tags(tags);
+        let tag_count = tags.len() as i64;
+
+        client
+            .query(&statement, &[&band_id, &user_id, &tags, &tag_count, &(count.max(0) as i64)])
+            .await
+            .map_err(|err| {
+                error!("Error starting random quiz. {}", err; "query" => "start_random");
+                err
+            })?
+            .iter()
+            .map(|row| Attempt::from_row_ref(row))
+            .collect::<Result<Vec<Attempt>, _>>()?
+            .pop()
+            .ok_or_else(no_matching_questions)
+    }
+
+    async fn questions(&self, attempt_id: Uuid) -> Result<Vec<AttemptQuestion>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "questions");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select * from attempt_questions where attempt_id = $1 order by position")
+            .await?;
+
+        let questions = client
+            .query(&statement, &[&attempt_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting attempt questions. {}", err; "query" => "questions");
+                err
+            })?
+            .iter()
+            .map(|row| AttemptQuestion::from_row_ref(row))
+            .collect::<Result<Vec<AttemptQuestion>, _>>()
+            .map_err(|err| {
+                error!("Error getting parsing attempt questions. {}", err; "query" => "questions");
+                err
+            })?;
+
+        Ok(questions)
+    }
+
+    async fn record_answer(&self, attempt_id: Uuid, submission: &AnswerSubmission, grade: &Grade) -> Result<AttemptQuestion, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "record_answer");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update attempt_questions set
+                    answer_id = $3,
+                    answer_text = $4,
+                    answer_number = $5,
+                    answer_ordering = $6,
+                    answer_pair_ids = $7,
+                    answer_pair_match_ids = $8,
+                    accepted = $9,
+                    score = $10,
+                    answered_at = current_timestamp
+                where attempt_id = $1 and question_id = $2 and answered_at is null
+                returning *
+            ")
+            .await?;
+
+        let pair_ids: Option<Vec<Uuid>> = submission.pairs.as_ref()
+            .map(|pairs| pairs.iter().map(|pair| pair.answer_id).collect());
+        let pair_match_ids: Option<Vec<Uuid>> = submission.pairs.as_ref()
+            .map(|pairs| pairs.iter().map(|pair| pair.match_answer_id).collect());
+
+        client
+            .query(&statement, &[
+                &attempt_id,
+                &submission.question_id,
+                &submission.answer_id,
+                &submission.text,
+                &submission.number,
+                &submission.ordering,
+                &pair_ids,
+                &pair_match_ids,
+                &grade.accepted,
+                &grade.score,
+            ])
+            .await
+            .map_err(|err| {
+                error!("Error recording answer. {}", err; "query" => "record_answer");
+                err
+            })?
+            .iter()
+            .map(|row| AttemptQuestion::from_row_ref(row))
+            .collect::<Result<Vec<AttemptQuestion>, _>>()?
+            .pop()
+            .ok_or_else(already_answered)
+    }
+
+    async fn serve(&self, attempt_id: Uuid, question_id: Uuid) -> Result<AttemptQuestion, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "serve");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update attempt_questions set served_at = coalesce(served_at, current_timestamp)
+                where attempt_id = $1 and question_id = $2
+                returning *
+            ")
+            .await?;
+
+        let served = client
+            .query(&statement, &[&attempt_id, &question_id])
+            .await
+            .map_err(|err| {
+                error!("Error serving question. {}", err; "query" => "serve");
+                err
+            })?
+            .iter()
+            .map(|row| AttemptQuestion::from_row_ref(row))
+            .collect::<Result<Vec<AttemptQuestion>, _>>()?
+            .pop()
+            .ok_or_else(not_in_attempt)?;
+
+        // Choice questions without distractors still get their correct answer dealt, so they can be answered
+        let statement = client
+            .prepare("
+                insert into attempt_choices (attempt_id, question_id, answer_id, side, position)
+                select $1, $2, answer_id, 'answer', (row_number() over (order by random()) - 1)::int4
+                from question_choices where question_id = $2
+                union all
+                select $1, $2, correct_answer_id, 'answer', 0
+                from questions where id = $2 and kind = 'choice' and correct_answer_id is not null
+                    and not exists (select 1 from question_choices where question_id = $2)
+                union all
+                select $1, $2, match_answer_id, 'match', (row_number() over (order by random()) - 1)::int4
+                from question_choices where question_id = $2 and match_answer_id is not null
+                on conflict do nothing
+            ")
+            .await?;
+
+        client
+            .execute(&statement, &[&attempt_id, &question_id])
+            .await
+            .map_err(|err| {
+                error!("Error shuffling choices. {}", err; "query" => "serve");
+                err
+            })?;
+
+        Ok(served)
+    }
+
+    async fn choices(&self, attempt_id: Uuid, question_id: Uuid) -> Result<Vec<AttemptChoice>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "choices");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select c.*, a.content from attempt_choices c
+                join answers a on a.id = c.answer_id
+                where c.attempt_id = $1 and c.question_id = $2
+                order by c.side, c.position
+            ")
+            .await?;
+
+        let choices = client
+            .query(&statement, &[&attempt_id, &question_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting attempt choices. {}", err; "query" => "choices");
+                err
+            })?
+            .iter()
+            .map(|row| AttemptChoice::from_row_ref(row))
+            .collect::<Result<Vec<AttemptChoice>, _>>()?;
+
+        Ok(choices)
+    }
+
+    async fn lifelines(&self, attempt_id: Uuid) -> Result<Vec<Lifeline>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "lifelines");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select * from attempt_lifelines where attempt_id = $1 order by used_at")
+            .await?;
+
+        let lifelines = client
+            .query(&statement, &[&attempt_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting lifelines. {}", err; "query" => "lifelines");
+                err
+            })?
+            .iter()
+            .map(|row| Lifeline::from_row_ref(row))
+            .collect::<Result<Vec<Lifeline>, _>>()?;
+
+        Ok(lifelines)
+    }
+
+    async fn use_lifeline(&self, attempt_id: Uuid, question_id: Uuid, kind: LifelineKind, budget: i32) -> Result<Lifeline, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "use_lifeline");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                insert into attempt_lifelines (attempt_id, question_id, kind)
+                select $1, $2, $3
+                where (select count(*) from attempt_lifelines where attempt_id = $1 and kind = $3) < $4
+                on conflict do nothing
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[&attempt_id, &question_id, &kind.as_str(), &(budget as i64)])
+            .await
+            .map_err(|err| {
+                error!("Error using lifeline. {}", err; "query" => "use_lifeline");
+                err
+            })?
+            .iter()
+            .map(|row| Lifeline::from_row_ref(row))
+            .collect::<Result<Vec<Lifeline>, _>>()?
+            .pop()
+            .ok_or_else(no_lifeline_left)
+    }
+
+    async fn eliminate_choices(&self, attempt_id: Uuid, question_id: Uuid, correct_answer_id: Uuid, count: i64) -> Result<(), AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "eliminate_choices");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update attempt_choices set eliminated = true
+                where token in (
+                    select token from attempt_choices
+                    where attempt_id = $1 and question_id = $2 and side = 'answer'
+                        and answer_id <> $3 and not eliminated
+                    order by random()
+                    limit $4
+                )
+            ")
+            .await?;
+
+        client
+            .execute(&statement, &[&attempt_id, &question_id, &correct_answer_id, &count])
+            .await
+            .map_err(|err| {
+                error!("Error eliminating choices. {}", err; "query" => "eliminate_choices");
+                err
+            })?;
+
+        Ok(())
+    }
+
+    async fn median_answer_seconds(&self, band_id: Uuid, quiz_id: Option<Uuid>) -> Result<Option<f64>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "median_answer_seconds");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select percentile_cont(0.5) within group (
+                    order by extract(epoch from aq.answered_at - aq.served_at)
+                )::float8
+                from attempt_questions aq
+                join attempts a on a.id = aq.attempt_id
+                where a.band_id = $1 and a.quiz_id is not distinct from $2
+                    and a.finished_at is not null
+                    and aq.served_at is not null and aq.answered_at is not null
+            ")
+            .await?;
+
+        let median = client
+            .query(&statement, &[&band_id, &quiz_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting median answer time. {}", err; "query" => "median_answer_seconds");
+                err
+            })?
+            .iter()
+            .map(|row| row.get::<_, Option<f64>>(0))
+            .next()
+            .flatten();
+
+        Ok(median)
+    }
+
+    async fn finish(&self, attempt_id: Uuid, score: f64, flag_reasons: &[String]) -> Result<Attempt, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "finish");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update attempts set
+                    finished_at = current_timestamp,
+                    score = $2,
+                    flag_reasons = $3,
+                    flagged_at = case when cardinality($3::text[]) > 0 then current_timestamp end
+                where id = $1 and finished_at is null
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[&attempt_id, &score, &flag_reasons])
+            .await
+            .map_err(|err| {
+                error!("Error finishing attempt. {}", err; "query" => "finish");
+                err
+            })?
+            .iter()
+            .map(|row| Attempt::from_row_ref(row))
+            .collect::<Result<Vec<Attempt>, _>>()?
+            .pop()
+            .ok_or_else(already_finished)
+    }
+
+    async fn flagged(&self, band_id: Uuid) -> Result<Vec<Attempt>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "flagged");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select * from attempts where band_id = $1 and flagged_at is not null order by flagged_at desc")
+            .await?;
+
+        let attempts = client
+            .query(&statement, &[&band_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting flagged attempts. {}", err; "query" => "flagged");
+                err
+            })?
+            .iter()
+            .map(|row| Attempt::from_row_ref(row))
+            .collect::<Result<Vec<Attempt>, _>>()?;
+
+        Ok(attempts)
+    }
+
+    async fn clear_flag(&self, attempt_id: Uuid) -> Result<Attempt, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "clear_flag");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update attempts set flagged_at = null, flag_reasons = '{}'
+                where id = $1
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[&attempt_id])
+            .await
+            .map_err(|err| {
+                error!("Error clearing attempt flag. {}", err; "query" => "clear_flag");
+                err
+            })?
+            .iter()
+            .map(|row| Attempt::from_row_ref(row))
+            .collect::<Result<Vec<Attempt>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+}
\ No newline at end of file
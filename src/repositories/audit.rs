@@ -0,0 +1,111 @@
+use crate::repositories::{db::{Db, DbClient}, stores::AuditStore};
+use slog_scope::error;
+use crate::models::audit::{AuditEntry, AuditFilter, NewAuditEntry};
+use tokio_pg_mapper::FromTokioPostgresRow;
+use crate::errors::{AppError, AppErrorType};
+use async_trait::async_trait;
+
+pub struct AuditRepository {
+    pool: Db
+}
+
+impl AuditRepository {
+
+    pub fn new(pool: impl Into<Db>) -> AuditRepository {
+        AuditRepository { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl AuditStore for AuditRepository {
+    async fn create(&self, entry: NewAuditEntry) -> Result<AuditEntry, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "create");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                insert into audit_log (actor_id, action, target_type, target_id, before, after, request_id, ip)
+                values ($1, $2, $3, $4, $5, $6, $7, $8)
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[
+                &entry.actor_id,
+                &entry.action.as_str(),
+                &entry.target_type.as_str(),
+                &entry.target_id,
+                &entry.before,
+                &entry.after,
+                &entry.request_id,
+                &entry.ip,
+            ])
+            .await
+            .map_err(|err| {
+                error!("Error creating audit entry. {}", err; "query" => "create");
+                err
+            })?
+            .iter()
+            .map(|row| AuditEntry::from_row_ref(row))
+            .collect::<Result<Vec<AuditEntry>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error creating AuditEntry.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })
+    }
+
+    async fn search(&self, filter: &AuditFilter, limit: i64) -> Result<Vec<AuditEntry>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "search");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select * from audit_log
+                where ($1::uuid is null or actor_id = $1)
+                    and ($2::varchar is null or action = $2)
+                    and ($3::varchar is null or target_type = $3)
+                    and ($4::uuid is null or target_id = $4)
+                    and ($5::uuid is null or request_id = $5)
+                    and ($6::timestamp is null or created_at >= $6)
+                    and ($7::timestamp is null or created_at < $7)
+                order by created_at desc
+                limit $8
+            ")
+            .await?;
+
+        let entries = client
+            .query(&statement, &[
+                &filter.actor_id,
+                &filter.action.map(|action| action.as_str()),
+                &filter.target_type.map(|target_type| target_type.as_str()),
+                &filter.target_id,
+                &filter.request_id,
+                &filter.since,
+                &filter.until,
+                &limit,
+            ])
+            .await
+            .map_err(|err| {
+                error!("Error getting audit log. {}", err; "query" => "search");
+                err
+            })?
+            .iter()
+            .map(|row| AuditEntry::from_row_ref(row))
+            .collect::<Result<Vec<AuditEntry>, _>>()?;
+
+        Ok(entries)
+    }
+}
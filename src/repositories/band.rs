@@ -1,23 +1,88 @@
-use deadpool_postgres::{Pool, Client};
-use std::sync::Arc;
+use crate::repositories::{db::{Db, DbClient}, stores::BandStore};
 use slog_scope::error;
-use crate::models::band::{Band, CreateBand};
+use crate::models::band::{Band, BandRole, CreateBand};
 use tokio_pg_mapper::FromTokioPostgresRow;
 use crate::errors::{AppError, AppErrorType};
 use tokio_postgres::error::{Error, SqlState};
 use uuid::Uuid;
+use async_trait::async_trait;
 
 pub struct BandRepository {
-    pool: Arc<Pool>
+    pool: Db
+}
+
+pub(crate) fn band_exists(name: &str, cause: Option<String>) -> AppError {
+    AppError {
+        cause,
+        message: Some(format!("band {} already exists.", name)),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub(crate) fn user_missing(user_id: Uuid, cause: Option<String>) -> AppError {
+    AppError {
+        cause,
+        message: Some(format!("user with id {} doesn't exists.", user_id)),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub(crate) fn band_not_deleted() -> AppError {
+    AppError {
+        cause: None,
+        message: Some("band isn't deleted.".to_string()),
+        error_type: AppErrorType::NotFoundError
+    }
 }
 
 impl BandRepository {
-    pub fn new(pool: Arc<Pool>) -> BandRepository {
-        BandRepository { pool }
+    pub fn new(pool: impl Into<Db>) -> BandRepository {
+        BandRepository { pool: pool.into() }
+    }
+
+    /// Removes bands deleted longer ago than the retention period along with their answers,
+    /// once nothing else refers to them
+    pub async fn purge_deleted(&self, retention_days: i32) -> Result<u64, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "purge_deleted");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                with purged as (
+                    select b.id from bands b
+                    where b.deleted_at < current_timestamp - make_interval(days => $1)
+                        and not exists (select 1 from questions where band_id = b.id)
+                        and not exists (select 1 from quizzes where band_id = b.id)
+                        and not exists (select 1 from attempts where band_id = b.id)
+                ),
+                answers as (
+                    delete from answers where band_id in (select id from purged)
+                )
+                delete from bands where id in (select id from purged)
+            ")
+            .await?;
+
+        let count = client
+            .execute(&statement, &[&retention_days])
+            .await
+            .map_err(|err| {
+                error!("Error purging bands. {}", err; "query" => "purge_deleted");
+                err
+            })?;
+
+        Ok(count)
     }
+}
 
-    pub async fn get(&self, id: Uuid) -> Result<Band, AppError> {
-        let client: Client = self.pool
+#[async_trait]
+impl BandStore for BandRepository {
+    async fn get(&self, id: Uuid) -> Result<Band, AppError> {
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
@@ -25,7 +90,7 @@ impl BandRepository {
                 err
             })?;
 
-        let statement = client.prepare("select * from bands where id = $1").await?;
+        let statement = client.prepare("select * from bands where id = $1 and deleted_at is null").await?;
             
         client 
             .query(&statement, &[&id])
@@ -45,8 +110,8 @@ impl BandRepository {
             })
     }
 
-    pub async fn all(&self) -> Result<Vec<Band>, AppError> {
-        let client: Client = self.pool
+    async fn all(&self) -> Result<Vec<Band>, AppError> {
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
@@ -54,7 +119,7 @@ impl BandRepository {
                 err
             })?;
 
-        let statement = client.prepare("select * from bands").await?;
+        let statement = client.prepare("select * from bands where hidden_at is null and deleted_at is null").await?;
 
         let bands = client
             .query(&statement, &[])
@@ -74,8 +139,8 @@ impl BandRepository {
         Ok(bands)
     }
 
-    pub async fn create(&self, input: CreateBand) -> Result<Band, AppError> {
-        let client: Client = self.pool
+    async fn create(&self, input: CreateBand, owner_id: Uuid) -> Result<Band, AppError> {
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
@@ -84,11 +149,19 @@ impl BandRepository {
             })?;
 
         let statement = client
-            .prepare("insert into bands (content) values ($1) returning *")
+            .prepare("
+                with band as (
+                    insert into bands (name, owner_id) values ($1, $2) returning *
+                ), author as (
+                    insert into band_authors (band_id, user_id) select id, owner_id from band
+                )
+                select * from band
+            ")
             .await?;
 
         let band = client.query(&statement, &[
-            &input.content,
+            &input.name,
+            &owner_id,
             ])
             .await
             .map_err(|err: Error| {
@@ -96,11 +169,7 @@ impl BandRepository {
                     .map(|code| code == &SqlState::UNIQUE_VIOLATION);
 
                 match unique_error {
-                    Some(true) => AppError {
-                        cause: Some(err.to_string()),
-                        message: Some("band already exists.".to_string()),
-                        error_type: AppErrorType::InvalidField
-                        },
+                    Some(true) => band_exists(&input.name, Some(err.to_string())),
                     _ => AppError::from(err)
                 }
             })?
@@ -116,4 +185,159 @@ impl BandRepository {
 
         Ok(band)
     }
+
+    async fn is_author(&self, band_id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "is_author");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select exists (select 1 from band_authors where band_id = $1 and user_id = $2)")
+            .await?;
+
+        let row = client
+            .query_one(&statement, &[&band_id, &user_id])
+            .await
+            .map_err(|err| {
+                error!("Error checking band author. {}", err; "query" => "is_author");
+                err
+            })?;
+
+        Ok(row.get(0))
+    }
+
+    async fn is_moderator(&self, band_id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "is_moderator");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select exists (select 1 from bands where id = $1 and owner_id = $2)
+                    or exists (select 1 from band_authors where band_id = $1 and user_id = $2 and role = 'moderator')
+            ")
+            .await?;
+
+        let row = client
+            .query_one(&statement, &[&band_id, &user_id])
+            .await
+            .map_err(|err| {
+                error!("Error checking band moderator. {}", err; "query" => "is_moderator");
+                err
+            })?;
+
+        Ok(row.get(0))
+    }
+
+    async fn add_author(&self, band_id: Uuid, user_id: Uuid, role: BandRole) -> Result<(), AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "add_author");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                insert into band_authors (band_id, user_id, role) values ($1, $2, $3)
+                on conflict (band_id, user_id) do update set role = excluded.role
+            ")
+            .await?;
+
+        client
+            .execute(&statement, &[&band_id, &user_id, &role.as_str()])
+            .await
+            .map_err(|err: Error| {
+                match err.code() {
+                    Some(code) if code == &SqlState::FOREIGN_KEY_VIOLATION => user_missing(user_id, Some(err.to_string())),
+                    _ => AppError::from(err)
+                }
+            })?;
+
+        Ok(())
+    }
+
+    async fn soft_delete(&self, id: Uuid) -> Result<Band, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "soft_delete");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                with deleted_questions as (
+                    update questions set deleted_at = current_timestamp
+                    where band_id = $1 and deleted_at is null
+                )
+                update bands set deleted_at = current_timestamp
+                where id = $1 and deleted_at is null
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error deleting band. {}", err; "query" => "soft_delete");
+                err
+            })?
+            .iter()
+            .map(|row| Band::from_row_ref(row))
+            .collect::<Result<Vec<Band>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Band, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "restore");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                with restored_questions as (
+                    update questions q set deleted_at = null
+                    from bands b
+                    where b.id = $1 and q.band_id = b.id and q.deleted_at = b.deleted_at
+                )
+                update bands set deleted_at = null
+                where id = $1 and deleted_at is not null
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error restoring band. {}", err; "query" => "restore");
+                err
+            })?
+            .iter()
+            .map(|row| Band::from_row_ref(row))
+            .collect::<Result<Vec<Band>, _>>()?
+            .pop()
+            .ok_or_else(band_not_deleted)
+    }
 }
@@ -0,0 +1,194 @@
+use deadpool_postgres::{Client, Pool, PoolError};
+use std::{future::Future, ops::Deref, sync::Arc};
+use slog_scope::error;
+use crate::errors::AppError;
+
+/// Where repositories get their client: a fresh one from the pool for every
+/// query, or the one client a transaction runs on
+#[derive(Clone)]
+pub enum Db {
+    Pool(Arc<Pool>),
+    Transaction(Arc<Client>),
+}
+
+impl From<Arc<Pool>> for Db {
+    fn from(pool: Arc<Pool>) -> Self {
+        Db::Pool(pool)
+    }
+}
+
+impl Db {
+    pub async fn get(&self) -> Result<DbClient, PoolError> {
+        match self {
+            Db::Pool(pool) => Ok(DbClient::Pooled(pool.get().await?)),
+            Db::Transaction(client) => Ok(DbClient::Shared(client.clone())),
+        }
+    }
+}
+
+pub enum DbClient {
+    Pooled(Client),
+    Shared(Arc<Client>),
+}
+
+impl Deref for DbClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        match self {
+            DbClient::Pooled(client) => client,
+            DbClient::Shared(client) => client,
+        }
+    }
+}
+
+/// Rolls back a transaction dropped before it finished, like a request that
+/// was cancelled, so its client goes back to the pool clean
+struct RollbackOnDrop(Option<Arc<Client>>);
+
+impl RollbackOnDrop {
+    fn disarm(mut self) {
+        self.0.take();
+    }
+}
+
+impl Drop for RollbackOnDrop {
+    fn drop(&mut self) {
+        if let Some(client) = self.0.take() {
+            actix_rt::spawn(async move {
+                if let Err(err) = client.batch_execute("rollback").await {
+                    error!("Error rolling back transaction {}", err; "query" => "transaction");
+                }
+            });
+        }
+    }
+}
+
+/// Runs `work` in one transaction: repositories built on the `Db` it's given
+/// share a client. Commits when `work` returns Ok and rolls back on an AppError.
+/// Inside another transaction `work` joins it instead.
+pub async fn transaction<T, F, Fut>(db: &Db, work: F) -> Result<T, AppError>
+where
+    F: FnOnce(Db) -> Fut,
+    Fut: Future<Output = Result<T, AppError>>,
+{
+    let pool = match db {
+        Db::Pool(pool) => pool,
+        Db::Transaction(_) => return work(db.clone()).await,
+    };
+
+    let client = pool
+        .get()
+        .await
+        .map_err(|err| {
+            error!("Error getting client {}", err; "query" => "transaction");
+            err
+        })?;
+    client.batch_execute("begin").await?;
+
+    let client = Arc::new(client);
+    let guard = RollbackOnDrop(Some(client.clone()));
+    let result = work(Db::Transaction(client.clone())).await;
+    guard.disarm();
+
+    match result {
+        Ok(value) => {
+            client.batch_execute("commit").await?;
+            Ok(value)
+        },
+        Err(err) => {
+            if let Err(rollback_err) = client.batch_execute("rollback").await {
+                error!("Error rolling back transaction {}", rollback_err; "query" => "transaction");
+            }
+            Err(err)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{transaction, Db};
+    use crate::config::Config;
+    use crate::errors::{AppError, AppErrorType};
+    use futures::future::{self, Either};
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    fn db() -> Db {
+        Db::from(Arc::new(Config::from_env().unwrap().configure_pool()))
+    }
+
+    async fn table_exists(db: &Db, table: &str) -> bool {
+        let client = db.get().await.unwrap();
+        let row = client.query_one("select to_regclass($1) is not null", &[&table]).await.unwrap();
+        row.get(0)
+    }
+
+    #[actix_rt::test]
+    async fn test_commits_on_ok() {
+        let db = db();
+        let table = format!("transaction_test_{}", Uuid::new_v4().to_simple());
+
+        let create = format!("create table {} (id uuid)", table);
+        transaction(&db, |db| async move {
+            db.get().await?.batch_execute(&create).await?;
+            Ok(())
+        }).await.unwrap();
+
+        assert!(table_exists(&db, &table).await, "Work that returns Ok should be committed");
+        db.get().await.unwrap().batch_execute(&format!("drop table {}", table)).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_rolls_back_on_error() {
+        let db = db();
+        let table = format!("transaction_test_{}", Uuid::new_v4().to_simple());
+
+        let create = format!("create table {} (id uuid)", table);
+        let result: Result<(), AppError> = transaction(&db, |db| async move {
+            db.get().await?.batch_execute(&create).await?;
+            Err(AppError {
+                message: Some("failed".to_string()),
+                cause: None,
+                error_type: AppErrorType::InvalidField,
+            })
+        }).await;
+
+        assert!(result.is_err());
+        assert!(!table_exists(&db, &table).await, "Work that fails should be rolled back");
+    }
+
+    #[actix_rt::test]
+    async fn test_rolls_back_when_dropped() {
+        let db = db();
+        let lock = Uuid::new_v4().as_u128() as i64;
+
+        let (started, on_started) = futures::channel::oneshot::channel();
+        let work = transaction(&db, |db| async move {
+            db.get().await?.execute("select pg_advisory_xact_lock($1)", &[&lock]).await?;
+            started.send(()).unwrap();
+            future::pending::<Result<(), AppError>>().await
+        });
+
+        match future::select(Box::pin(work), on_started).await {
+            Either::Right((_, work)) => drop(work),
+            Either::Left(_) => panic!("transaction shouldn't finish"),
+        }
+
+        // The rollback is spawned, give it a moment to release the lock
+        let mut released = false;
+        for _ in 0..50 {
+            let client = db.get().await.unwrap();
+            released = client.query_one("select pg_try_advisory_lock($1)", &[&lock]).await.unwrap().get(0);
+            if released {
+                client.execute("select pg_advisory_unlock($1)", &[&lock]).await.unwrap();
+                break;
+            }
+            actix_rt::time::delay_for(std::time::Duration::from_millis(20)).await;
+        }
+
+        assert!(released, "A dropped transaction should be rolled back");
+    }
+
+}
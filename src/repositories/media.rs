@@ -0,0 +1,83 @@
+use crate::repositories::{db::{Db, DbClient}, stores::MediaStore};
+use slog_scope::error;
+use crate::models::media::{Media, CreateMedia};
+use tokio_pg_mapper::FromTokioPostgresRow;
+use crate::errors::{AppError, AppErrorType};
+use uuid::Uuid;
+use async_trait::async_trait;
+
+pub struct MediaRepository {
+    pool: Db
+}
+
+impl MediaRepository {
+
+    pub fn new(pool: impl Into<Db>) -> MediaRepository {
+        MediaRepository { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl MediaStore for MediaRepository {
+    async fn get(&self, id: Uuid) -> Result<Media, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "get");
+                err
+            })?;
+
+        let statement = client.prepare("select * from media where id = $1").await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error getting media. {}", err; "query" => "get");
+                err
+            })?
+            .iter()
+            .map(|row| Media::from_row_ref(row))
+            .collect::<Result<Vec<Media>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+
+    async fn create(&self, input: CreateMedia) -> Result<Media, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "create");
+                err
+            })?;
+
+        let statement = client
+            .prepare("insert into media (key, content_type, size_bytes, uploaded_by) values ($1, $2, $3, $4) returning *")
+            .await?;
+
+        let media = client.query(&statement, &[
+                &input.key,
+                &input.content_type,
+                &input.size_bytes,
+                &input.uploaded_by,
+            ])
+            .await?
+            .iter()
+            .map(|row| Media::from_row_ref(row))
+            .collect::<Result<Vec<Media>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error creating Media.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })?;
+
+        Ok(media)
+    }
+}
@@ -0,0 +1,1943 @@
+use crate::repositories::{
+    db::Db,
+    user::{user_exists, user_not_deleted},
+    answer::{alias_exists, answer_exists, answer_missing, band_missing},
+    question::{
+        band_answer_missing, edit_references_missing, invalid_question, question_changed, question_not_changes_requested,
+        question_not_deleted, question_not_pending, question_references_missing, validate_choices, validate_question,
+        validate_snapshot,
+    },
+    band::{band_exists, band_not_deleted, user_missing},
+    session::invalid_login,
+    review_state::{question_missing, validate_grade},
+    tag::tag_kind_conflict,
+    search::{connection, decode_cursor, highlight, prefix_query, validate_page_size},
+    quiz::{
+        quiz_changed, quiz_played, unplayable_questions, unpublishable_quiz, validate_lifelines, validate_question_ids,
+        validate_settings,
+    },
+    attempt::{already_answered, already_finished, no_lifeline_left, no_matching_questions, not_in_attempt},
+    scoring_rules::{validate_rules, with_defaults},
+    revision::{no_revision, suggestion_decided},
+    stores::{
+        Stores, UserStore, AnswerStore, QuestionStore, BandStore, SessionStore, ReviewStateStore, MediaStore, TagStore,
+        SearchStore, QuizStore, AttemptStore, ScoringRulesStore, NotificationStore, RevisionStore, ReportStore, AuditStore,
+    },
+};
+use crate::models::{
+    user::{User, CreateUser},
+    answer::{Answer, CreateAnswer, AnswerAlias, CreateAnswerAlias},
+    question::{
+        Question, CreateQuestion, CreateOrderingQuestion, CreateMatchingQuestion,
+        QuestionKind, QuestionStatus, NumericCurve, ScoringRule,
+    },
+    question_choice::{shuffled, QuestionChoice},
+    revision::{EditSuggestion, QuestionRevision, QuestionSnapshot, SuggestionStatus},
+    duplicate::{duplicate_error, DuplicatePair, SimilarQuestion, DUPLICATE_THRESHOLD},
+    band::{Band, BandRole, CreateBand},
+    session::{Session, Login},
+    review_state::ReviewState,
+    media::{Media, CreateMedia},
+    tag::{Tag, TagKind, TagStats, normalize_tags},
+    search::{SearchConnection, SearchHit, SearchType},
+    quiz::{Quiz, QuizStatus, CreateQuiz, UpdateQuiz},
+    attempt::{Attempt, AttemptChoice, AttemptQuestion},
+    grade::{AnswerSubmission, Grade},
+    lifeline::{Lifeline, LifelineKind},
+    scoring_rules::{ScoringRules, ScoringRulesInput},
+    notification::{Notification, NotificationKind},
+    report::{Report, ReportReason, ReportStatus, ReportTargetType},
+    audit::{AuditEntry, AuditFilter, NewAuditEntry},
+};
+use crate::{config::HashingService, errors::{AppError, AppErrorType}, grading::AnswerKey};
+use chrono::{Duration, NaiveDateTime, Utc};
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+use async_trait::async_trait;
+
+/// Every table kept in memory, so the schema can be exercised in tests
+/// without Postgres. Writes behave like the repositories' queries and
+/// constraints, except that transactions aren't rolled back, only questions
+/// with the same content count as similar and search only matches word
+/// prefixes, without highlighting them.
+#[derive(Default)]
+pub struct MemoryStore {
+    tables: Mutex<Tables>,
+}
+
+#[derive(Default)]
+struct Tables {
+    users: Vec<User>,
+    sessions: Vec<Session>,
+    answers: Vec<Answer>,
+    aliases: Vec<AnswerAlias>,
+    bands: Vec<Band>,
+    /// Band, user and role of every band author
+    band_authors: Vec<(Uuid, Uuid, BandRole)>,
+    questions: Vec<Question>,
+    choices: Vec<QuestionChoice>,
+    review_states: Vec<ReviewState>,
+    media: Vec<Media>,
+    tags: Vec<Tag>,
+    /// Question and tag ids
+    question_tags: Vec<(Uuid, Uuid)>,
+    quizzes: Vec<Quiz>,
+    /// Quiz and question ids, in the order the quiz plays them
+    quiz_questions: Vec<(Uuid, Uuid)>,
+    attempts: Vec<Attempt>,
+    attempt_questions: Vec<AttemptQuestion>,
+    /// Dealt choices, with the content left to be joined from answers
+    attempt_choices: Vec<AttemptChoice>,
+    lifelines: Vec<Lifeline>,
+    scoring_rules: Vec<ScoringRules>,
+    notifications: Vec<Notification>,
+    revisions: Vec<QuestionRevision>,
+    suggestions: Vec<EditSuggestion>,
+    reports: Vec<Report>,
+    audit_log: Vec<AuditEntry>,
+}
+
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+fn not_found() -> AppError {
+    AppError {
+        cause: None,
+        message: None,
+        error_type: AppErrorType::NotFoundError
+    }
+}
+
+/// What the update triggers do to every changed question
+fn touch(question: &mut Question) {
+    question.updated_at = now();
+    question.version += 1;
+}
+
+/// The same for quizzes
+fn touch_quiz(quiz: &mut Quiz) {
+    quiz.updated_at = now();
+    quiz.version += 1;
+}
+
+/// Stands in for trigram similarity: 1 for the same content, ignoring case, 0 otherwise
+fn similarity(first: &str, second: &str) -> f64 {
+    if first.trim().to_lowercase() == second.trim().to_lowercase() { 1.0 } else { 0.0 }
+}
+
+/// Stands in for the full-text search: every word of the query has to start a word of the title
+fn matches(query: &str, title: &str) -> bool {
+    let words = |text: &str| -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_lowercase())
+            .collect()
+    };
+    let title_words = words(title);
+
+    words(query).iter().all(|word| title_words.iter().any(|title_word| title_word.starts_with(word.as_str())))
+}
+
+/// Continuous median, like `percentile_cont(0.5)`
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    values.sort_by(|first, second| first.partial_cmp(second).unwrap());
+    let middle = values.len() / 2;
+    if values.len() % 2 == 1 {
+        Some(values[middle])
+    } else {
+        Some((values[middle - 1] + values[middle]) / 2.0)
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> Arc<MemoryStore> {
+        Arc::new(MemoryStore::default())
+    }
+
+    fn tables(&self) -> MutexGuard<Tables> {
+        self.tables.lock().unwrap()
+    }
+
+    /// Adds a user without hashing a password, which takes too long for tests
+    pub fn add_user(&self, username: &str, is_admin: bool) -> User {
+        let user = User {
+            id: Uuid::new_v4(),
+            username: username.to_string(),
+            email: format!("{}@example.com", username),
+            password: String::new(),
+            bio: None,
+            image: None,
+            created_at: now(),
+            updated_at: now(),
+            is_admin,
+            hidden_at: None,
+            deleted_at: None,
+        };
+
+        self.tables().users.push(user.clone());
+        user
+    }
+}
+
+impl Tables {
+    fn question_mut(&mut self, id: Uuid) -> Option<&mut Question> {
+        self.questions.iter_mut().find(|question| question.id == id && question.deleted_at.is_none())
+    }
+
+    fn choices(&self, question_id: Uuid) -> Vec<QuestionChoice> {
+        let mut choices: Vec<QuestionChoice> = self.choices.iter()
+            .filter(|choice| choice.question_id == question_id)
+            .cloned()
+            .collect();
+        choices.sort_by_key(|choice| choice.position);
+        choices
+    }
+
+    fn similar(&self, band_id: Uuid, content: &str, threshold: f64, approved_only: bool) -> Vec<SimilarQuestion> {
+        self.questions.iter()
+            .filter(|question| question.band_id == band_id && question.deleted_at.is_none())
+            .filter(|question| !approved_only || (question.status == QuestionStatus::Approved.as_str() && question.hidden_at.is_none()))
+            .map(|question| SimilarQuestion {
+                id: question.id,
+                content: question.content.clone(),
+                similarity: similarity(&question.content, content),
+            })
+            .filter(|question| question.similarity >= threshold)
+            .take(5)
+            .collect()
+    }
+
+    /// Questions can only use their own band's answers
+    fn check_band_answers(&self, band_id: Uuid, answer_ids: &[Uuid]) -> Result<(), AppError> {
+        match answer_ids.iter().find(|id| !self.answers.iter().any(|answer| answer.id == **id && answer.band_id == Some(band_id))) {
+            Some(id) => Err(band_answer_missing(*id, band_id)),
+            None => Ok(()),
+        }
+    }
+
+    fn media_exists(&self, media_id: Option<Uuid>) -> bool {
+        media_id.map_or(true, |id| self.media.iter().any(|media| media.id == id))
+    }
+
+    fn insert(&mut self, input: CreateQuestion, scoring_rule: ScoringRule, submitted_by: Option<Uuid>) -> Result<Question, AppError> {
+        if !input.force.unwrap_or(false) {
+            let similar = self.similar(input.band_id, &input.content, DUPLICATE_THRESHOLD, submitted_by.is_some());
+            if !similar.is_empty() {
+                return Err(duplicate_error(similar));
+            }
+        }
+
+        if !self.bands.iter().any(|band| band.id == input.band_id) || !self.media_exists(input.media_id) {
+            return Err(question_references_missing(input.band_id, None));
+        }
+
+        let status = if submitted_by.is_some() { QuestionStatus::Pending } else { QuestionStatus::Approved };
+        let question = Question {
+            id: Uuid::new_v4(),
+            content: input.content,
+            correct_answer_id: input.correct_answer_id,
+            band_id: input.band_id,
+            created_at: now(),
+            updated_at: now(),
+            kind: input.kind.unwrap_or(QuestionKind::Choice).as_str().to_string(),
+            max_edit_distance: input.max_edit_distance.unwrap_or(1),
+            numeric_value: input.numeric_value,
+            numeric_tolerance: input.numeric_tolerance.unwrap_or(0.0),
+            numeric_curve: input.numeric_curve.unwrap_or(NumericCurve::Linear).as_str().to_string(),
+            scoring_rule: scoring_rule.as_str().to_string(),
+            media_id: input.media_id,
+            clip_start_ms: input.clip_start_ms,
+            clip_end_ms: input.clip_end_ms,
+            hint: input.hint,
+            explanation: input.explanation,
+            source: input.source,
+            status: status.as_str().to_string(),
+            submitted_by,
+            moderation_reason: None,
+            moderated_by: None,
+            moderated_at: None,
+            hidden_at: None,
+            deleted_at: None,
+            version: 1,
+        };
+
+        self.questions.push(question.clone());
+        Ok(question)
+    }
+
+    fn insert_choice(&mut self, question_id: Uuid, answer_id: Uuid, position: i32, match_answer_id: Option<Uuid>) {
+        self.choices.push(QuestionChoice {
+            id: Uuid::new_v4(),
+            question_id,
+            answer_id,
+            position,
+            match_answer_id,
+            created_at: now(),
+            updated_at: now(),
+        });
+    }
+
+    fn create_alias(&mut self, input: CreateAnswerAlias) -> Result<AnswerAlias, AppError> {
+        if !self.answers.iter().any(|answer| answer.id == input.answer_id) {
+            return Err(answer_missing(input.answer_id, None));
+        }
+        if self.aliases.iter().any(|alias| alias.answer_id == input.answer_id && alias.content == input.content) {
+            return Err(alias_exists(&input.content, None));
+        }
+
+        let alias = AnswerAlias {
+            id: Uuid::new_v4(),
+            answer_id: input.answer_id,
+            content: input.content,
+            created_at: now(),
+            updated_at: now(),
+        };
+
+        self.aliases.push(alias.clone());
+        Ok(alias)
+    }
+
+    fn insert_answer(&mut self, band_id: Uuid, content: &str) -> Result<Answer, AppError> {
+        if !self.bands.iter().any(|band| band.id == band_id) {
+            return Err(band_missing(band_id, None));
+        }
+
+        let answer = Answer {
+            id: Uuid::new_v4(),
+            content: content.to_string(),
+            band_id: Some(band_id),
+            created_at: now(),
+            updated_at: now(),
+        };
+
+        self.answers.push(answer.clone());
+        Ok(answer)
+    }
+
+    /// Whether the question carries every one of the normalized `tags`
+    fn has_tags(&self, question_id: Uuid, tags: &[String]) -> bool {
+        tags.iter().all(|name| self.question_tags.iter().any(|(question, tag)| {
+            *question == question_id && self.tags.iter().any(|stored| stored.id == *tag && stored.name == *name)
+        }))
+    }
+
+    /// Approved questions of the band that are neither hidden nor deleted
+    fn playable(&self, band_id: Uuid) -> impl Iterator<Item = &Question> {
+        self.questions.iter().filter(move |question| {
+            question.band_id == band_id
+                && question.status == QuestionStatus::Approved.as_str()
+                && question.hidden_at.is_none()
+                && question.deleted_at.is_none()
+        })
+    }
+
+    fn quiz_questions(&self, quiz_id: Uuid) -> Vec<Question> {
+        self.quiz_questions.iter()
+            .filter(|(quiz, _)| *quiz == quiz_id)
+            .filter_map(|(_, question_id)| self.questions.iter().find(|question| question.id == *question_id && question.deleted_at.is_none()))
+            .cloned()
+            .collect()
+    }
+
+    fn check_quiz_questions(&self, band_id: Uuid, question_ids: &[Uuid]) -> Result<(), AppError> {
+        validate_question_ids(question_ids)?;
+
+        if question_ids.iter().all(|id| self.playable(band_id).any(|question| question.id == *id)) {
+            Ok(())
+        } else {
+            Err(unplayable_questions())
+        }
+    }
+
+    fn replace_quiz_questions(&mut self, quiz_id: Uuid, question_ids: &[Uuid]) {
+        self.quiz_questions.retain(|(quiz, _)| *quiz != quiz_id);
+        self.quiz_questions.extend(question_ids.iter().map(|question_id| (quiz_id, *question_id)));
+    }
+
+    /// Quizzes pass on their rules and lifelines, random attempts get the defaults
+    fn insert_attempt(&mut self, quiz: Option<&Quiz>, band_id: Uuid, user_id: Uuid, question_ids: Vec<Uuid>) -> Attempt {
+        let attempt = Attempt {
+            id: Uuid::new_v4(),
+            quiz_id: quiz.map(|quiz| quiz.id),
+            band_id,
+            user_id,
+            started_at: now(),
+            finished_at: None,
+            score: 0.0,
+            created_at: now(),
+            updated_at: now(),
+            scoring_rules_id: quiz.and_then(|quiz| quiz.scoring_rules_id),
+            flag_reasons: vec![],
+            flagged_at: None,
+            fifty_fifty_lifelines: quiz.map_or(1, |quiz| quiz.fifty_fifty_lifelines),
+            hint_lifelines: quiz.map_or(1, |quiz| quiz.hint_lifelines),
+        };
+
+        for (position, question_id) in question_ids.into_iter().enumerate() {
+            self.attempt_questions.push(AttemptQuestion {
+                attempt_id: attempt.id,
+                question_id,
+                position: position as i32,
+                answer_id: None,
+                answer_text: None,
+                answer_number: None,
+                answer_ordering: None,
+                answer_pair_ids: None,
+                answer_pair_match_ids: None,
+                accepted: None,
+                score: None,
+                answered_at: None,
+                served_at: None,
+            });
+        }
+
+        self.attempts.push(attempt.clone());
+        attempt
+    }
+
+    fn attempt_mut(&mut self, id: Uuid) -> Option<&mut Attempt> {
+        self.attempts.iter_mut().find(|attempt| attempt.id == id)
+    }
+
+    fn deal_choices(&mut self, attempt_id: Uuid, question_id: Uuid, side: &str, answer_ids: Vec<Uuid>) {
+        let dealt = self.attempt_choices.iter()
+            .any(|choice| choice.attempt_id == attempt_id && choice.question_id == question_id && choice.side == side);
+        if dealt {
+            return;
+        }
+
+        for (position, answer_id) in shuffled(answer_ids).into_iter().enumerate() {
+            self.attempt_choices.push(AttemptChoice {
+                token: Uuid::new_v4(),
+                attempt_id,
+                question_id,
+                answer_id,
+                side: side.to_string(),
+                position: position as i32,
+                eliminated: false,
+                content: String::new(),
+            });
+        }
+    }
+}
+
+#[async_trait]
+impl UserStore for MemoryStore {
+    async fn get(&self, id: Uuid) -> Result<User, AppError> {
+        self.tables().users.iter()
+            .find(|user| user.id == id && user.deleted_at.is_none())
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    async fn all(&self) -> Result<Vec<User>, AppError> {
+        Ok(self.tables().users.iter()
+            .filter(|user| user.hidden_at.is_none() && user.deleted_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn create(&self, input: CreateUser, hashing: Arc<HashingService>) -> Result<User, AppError> {
+        let password = hashing.hash(input.password).await?;
+
+        let mut tables = self.tables();
+        if tables.users.iter().any(|user| user.username == input.username || user.email == input.email) {
+            return Err(user_exists(None));
+        }
+
+        let user = User {
+            id: Uuid::new_v4(),
+            username: input.username,
+            email: input.email,
+            password,
+            bio: input.bio,
+            image: input.image,
+            created_at: now(),
+            updated_at: now(),
+            is_admin: false,
+            hidden_at: None,
+            deleted_at: None,
+        };
+
+        tables.users.push(user.clone());
+        Ok(user)
+    }
+
+    async fn soft_delete(&self, id: Uuid) -> Result<User, AppError> {
+        let mut tables = self.tables();
+        tables.sessions.retain(|session| session.user_id != id);
+
+        let user = tables.users.iter_mut()
+            .find(|user| user.id == id && user.deleted_at.is_none())
+            .ok_or_else(not_found)?;
+
+        user.deleted_at = Some(now());
+        user.updated_at = now();
+        Ok(user.clone())
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<User, AppError> {
+        let mut tables = self.tables();
+        let user = tables.users.iter_mut()
+            .find(|user| user.id == id && user.deleted_at.is_some())
+            .ok_or_else(user_not_deleted)?;
+
+        user.deleted_at = None;
+        user.updated_at = now();
+        Ok(user.clone())
+    }
+}
+
+#[async_trait]
+impl AnswerStore for MemoryStore {
+    async fn get(&self, id: Uuid) -> Result<Answer, AppError> {
+        self.tables().answers.iter()
+            .find(|answer| answer.id == id)
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    async fn get_many(&self, ids: &[Uuid]) -> Result<Vec<Answer>, AppError> {
+        Ok(self.tables().answers.iter()
+            .filter(|answer| ids.contains(&answer.id))
+            .cloned()
+            .collect())
+    }
+
+    async fn all(&self) -> Result<Vec<Answer>, AppError> {
+        Ok(self.tables().answers.clone())
+    }
+
+    async fn get_for_band(&self, band_id: Uuid) -> Result<Vec<Answer>, AppError> {
+        let mut answers: Vec<Answer> = self.tables().answers.iter()
+            .filter(|answer| answer.band_id == Some(band_id))
+            .cloned()
+            .collect();
+        answers.sort_by(|first, second| first.content.cmp(&second.content));
+        Ok(answers)
+    }
+
+    async fn create(&self, input: CreateAnswer) -> Result<Answer, AppError> {
+        let mut tables = self.tables();
+        if tables.answers.iter().any(|answer| answer.band_id == Some(input.band_id) && answer.content == input.content) {
+            return Err(answer_exists(&input.content, None));
+        }
+
+        let answer = tables.insert_answer(input.band_id, &input.content)?;
+        for alias in input.aliases.unwrap_or_default() {
+            tables.create_alias(CreateAnswerAlias { answer_id: answer.id, content: alias })?;
+        }
+
+        Ok(answer)
+    }
+
+    async fn aliases(&self, answer_id: Uuid) -> Result<Vec<AnswerAlias>, AppError> {
+        Ok(self.tables().aliases.iter()
+            .filter(|alias| alias.answer_id == answer_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn create_alias(&self, input: CreateAnswerAlias) -> Result<AnswerAlias, AppError> {
+        self.tables().create_alias(input)
+    }
+
+    async fn set_aliases(&self, answer_id: Uuid, contents: &[String]) -> Result<Vec<AnswerAlias>, AppError> {
+        let mut tables = self.tables();
+        tables.aliases.retain(|alias| alias.answer_id != answer_id || contents.contains(&alias.content));
+
+        for content in contents {
+            if !tables.aliases.iter().any(|alias| alias.answer_id == answer_id && alias.content == *content) {
+                tables.create_alias(CreateAnswerAlias { answer_id, content: content.clone() })?;
+            }
+        }
+
+        Ok(tables.aliases.iter()
+            .filter(|alias| alias.answer_id == answer_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn find_or_create(&self, band_id: Uuid, content: &str) -> Result<(Answer, bool), AppError> {
+        let mut tables = self.tables();
+        let existing = tables.answers.iter()
+            .find(|answer| answer.band_id == Some(band_id) && answer.content == content)
+            .cloned();
+
+        match existing {
+            Some(answer) => Ok((answer, false)),
+            None => Ok((tables.insert_answer(band_id, content)?, true)),
+        }
+    }
+}
+
+#[async_trait]
+impl QuestionStore for MemoryStore {
+    async fn get(&self, id: Uuid) -> Result<Question, AppError> {
+        self.tables().questions.iter()
+            .find(|question| question.id == id && question.deleted_at.is_none())
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    async fn get_any(&self, id: Uuid) -> Result<Question, AppError> {
+        self.tables().questions.iter()
+            .find(|question| question.id == id)
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    async fn all(&self) -> Result<Vec<Question>, AppError> {
+        Ok(self.tables().questions.iter()
+            .filter(|question| question.question_status() == QuestionStatus::Approved && question.hidden_at.is_none() && question.deleted_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn find(&self, band_id: Option<Uuid>, tags: &[String]) -> Result<Vec<Question>, AppError> {
+        let tags = normalize_tags(tags);
+        let tables = self.tables();
+
+        Ok(tables.questions.iter()
+            .filter(|question| band_id.map_or(true, |band_id| question.band_id == band_id))
+            .filter(|question| question.status == QuestionStatus::Approved.as_str())
+            .filter(|question| question.hidden_at.is_none() && question.deleted_at.is_none())
+            .filter(|question| tables.has_tags(question.id, &tags))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_for_band(&self, band_id: Uuid) -> Result<Vec<Question>, AppError> {
+        Ok(self.tables().questions.iter()
+            .filter(|question| question.band_id == band_id && question.deleted_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn with_correct_answer(&self, answer_id: Uuid) -> Result<Vec<Question>, AppError> {
+        Ok(self.tables().questions.iter()
+            .filter(|question| question.correct_answer_id == Some(answer_id) && question.deleted_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn create(&self, input: CreateQuestion, submitted_by: Option<Uuid>) -> Result<Question, AppError> {
+        validate_question(&input)?;
+
+        let options: Vec<Uuid> = match &input.distractor_ids {
+            Some(distractor_ids) => input.correct_answer_id.iter()
+                .chain(distractor_ids.iter())
+                .cloned()
+                .collect(),
+            None => vec![],
+        };
+        if !options.is_empty() {
+            validate_choices(&options)?;
+        }
+
+        let mut tables = self.tables();
+        let answer_ids: Vec<Uuid> = input.correct_answer_id.iter().chain(options.iter()).cloned().collect();
+        tables.check_band_answers(input.band_id, &answer_ids)?;
+
+        let question = tables.insert(input, ScoringRule::AllOrNothing, submitted_by)?;
+
+        for (position, answer_id) in shuffled(options).into_iter().enumerate() {
+            tables.insert_choice(question.id, answer_id, position as i32, None);
+        }
+
+        Ok(question)
+    }
+
+    async fn create_ordering(&self, input: CreateOrderingQuestion, submitted_by: Option<Uuid>) -> Result<Question, AppError> {
+        let scoring_rule = input.scoring_rule.unwrap_or(ScoringRule::PerItem);
+        validate_choices(&input.answer_ids)?;
+
+        let mut tables = self.tables();
+        tables.check_band_answers(input.band_id, &input.answer_ids)?;
+
+        let question = tables.insert(CreateQuestion {
+            content: input.content,
+            correct_answer_id: None,
+            distractor_ids: None,
+            band_id: input.band_id,
+            kind: Some(QuestionKind::Ordering),
+            max_edit_distance: None,
+            numeric_value: None,
+            numeric_tolerance: None,
+            numeric_curve: None,
+            media_id: input.media_id,
+            clip_start_ms: None,
+            clip_end_ms: None,
+            hint: input.hint,
+            explanation: input.explanation,
+            source: input.source,
+            tags: None,
+            force: input.force,
+        }, scoring_rule, submitted_by)?;
+
+        for (position, answer_id) in input.answer_ids.iter().enumerate() {
+            tables.insert_choice(question.id, *answer_id, position as i32, None);
+        }
+
+        Ok(question)
+    }
+
+    async fn create_matching(&self, input: CreateMatchingQuestion, submitted_by: Option<Uuid>) -> Result<Question, AppError> {
+        let scoring_rule = input.scoring_rule.unwrap_or(ScoringRule::PerItem);
+        if scoring_rule == ScoringRule::Pairwise {
+            return Err(invalid_question("pairwise scoring only applies to ordering questions."));
+        }
+
+        let answer_ids: Vec<Uuid> = input.pairs.iter().map(|pair| pair.answer_id).collect();
+        let match_answer_ids: Vec<Uuid> = input.pairs.iter().map(|pair| pair.match_answer_id).collect();
+        validate_choices(&answer_ids)?;
+        validate_choices(&match_answer_ids)?;
+
+        let mut tables = self.tables();
+        tables.check_band_answers(input.band_id, &answer_ids)?;
+        tables.check_band_answers(input.band_id, &match_answer_ids)?;
+
+        let question = tables.insert(CreateQuestion {
+            content: input.content,
+            correct_answer_id: None,
+            distractor_ids: None,
+            band_id: input.band_id,
+            kind: Some(QuestionKind::Matching),
+            max_edit_distance: None,
+            numeric_value: None,
+            numeric_tolerance: None,
+            numeric_curve: None,
+            media_id: input.media_id,
+            clip_start_ms: None,
+            clip_end_ms: None,
+            hint: input.hint,
+            explanation: input.explanation,
+            source: input.source,
+            tags: None,
+            force: input.force,
+        }, scoring_rule, submitted_by)?;
+
+        for (position, pair) in input.pairs.iter().enumerate() {
+            tables.insert_choice(question.id, pair.answer_id, position as i32, Some(pair.match_answer_id));
+        }
+
+        Ok(question)
+    }
+
+    async fn similar(&self, band_id: Uuid, content: &str, threshold: f64, approved_only: bool) -> Result<Vec<SimilarQuestion>, AppError> {
+        Ok(self.tables().similar(band_id, content, threshold, approved_only))
+    }
+
+    async fn duplicate_pairs(&self, band_id: Option<Uuid>, threshold: f64) -> Result<Vec<DuplicatePair>, AppError> {
+        let tables = self.tables();
+        let questions: Vec<&Question> = tables.questions.iter()
+            .filter(|question| band_id.map_or(true, |band_id| question.band_id == band_id) && question.deleted_at.is_none())
+            .collect();
+
+        let mut pairs = vec![];
+        for first in &questions {
+            for second in questions.iter().filter(|second| second.band_id == first.band_id && first.id < second.id) {
+                let similarity = similarity(&first.content, &second.content);
+                if similarity >= threshold {
+                    pairs.push(DuplicatePair {
+                        band_id: first.band_id,
+                        first: SimilarQuestion { id: first.id, content: first.content.clone(), similarity },
+                        second: SimilarQuestion { id: second.id, content: second.content.clone(), similarity },
+                        similarity,
+                    });
+                }
+            }
+        }
+
+        Ok(pairs)
+    }
+
+    async fn moderation_queue(&self, band_id: Uuid) -> Result<Vec<Question>, AppError> {
+        Ok(self.tables().questions.iter()
+            .filter(|question| question.band_id == band_id && question.deleted_at.is_none())
+            .filter(|question| question.status == QuestionStatus::Pending.as_str())
+            .cloned()
+            .collect())
+    }
+
+    async fn moderate(&self, question_id: Uuid, status: QuestionStatus, reason: Option<&str>, moderated_by: Uuid) -> Result<Question, AppError> {
+        let mut tables = self.tables();
+        let question = tables.questions.iter_mut()
+            .find(|question| question.id == question_id && question.status == QuestionStatus::Pending.as_str())
+            .ok_or_else(question_not_pending)?;
+
+        question.status = status.as_str().to_string();
+        question.moderation_reason = reason.map(String::from);
+        question.moderated_by = Some(moderated_by);
+        question.moderated_at = Some(now());
+        touch(question);
+        Ok(question.clone())
+    }
+
+    async fn answer_key(&self, question: &Question) -> Result<AnswerKey, AppError> {
+        let tables = self.tables();
+        let correct_answer = tables.answers.iter()
+            .find(|answer| Some(answer.id) == question.correct_answer_id)
+            .cloned();
+        let aliases = tables.aliases.iter()
+            .filter(|alias| Some(alias.answer_id) == question.correct_answer_id)
+            .map(|alias| alias.content.clone())
+            .collect();
+
+        Ok(AnswerKey { correct_answer, aliases, choices: tables.choices(question.id) })
+    }
+
+    async fn choices(&self, question_id: Uuid) -> Result<Vec<QuestionChoice>, AppError> {
+        Ok(self.tables().choices(question_id))
+    }
+
+    async fn snapshot(&self, question: &Question) -> Result<QuestionSnapshot, AppError> {
+        Ok(QuestionSnapshot::new(question, &self.tables().choices(question.id)))
+    }
+
+    async fn validate_edit(&self, band_id: Uuid, snapshot: &QuestionSnapshot) -> Result<(), AppError> {
+        validate_snapshot(snapshot)?;
+        self.tables().check_band_answers(band_id, &snapshot.answer_ids())
+    }
+
+    async fn apply(&self, question: &Question, snapshot: &QuestionSnapshot, version: i32) -> Result<Question, AppError> {
+        validate_snapshot(snapshot)?;
+
+        let mut tables = self.tables();
+        tables.check_band_answers(question.band_id, &snapshot.answer_ids())?;
+        if !tables.media_exists(snapshot.media_id) {
+            return Err(edit_references_missing(None));
+        }
+
+        let updated = tables.questions.iter_mut()
+            .find(|stored| stored.id == question.id && stored.version == version)
+            .ok_or_else(question_changed)?;
+
+        updated.content = snapshot.content.clone();
+        updated.correct_answer_id = snapshot.correct_answer_id;
+        updated.max_edit_distance = snapshot.max_edit_distance;
+        updated.numeric_value = snapshot.numeric_value;
+        updated.numeric_tolerance = snapshot.numeric_tolerance;
+        updated.numeric_curve = snapshot.numeric_curve.clone();
+        updated.scoring_rule = snapshot.scoring_rule.clone();
+        updated.media_id = snapshot.media_id;
+        updated.clip_start_ms = snapshot.clip_start_ms;
+        updated.clip_end_ms = snapshot.clip_end_ms;
+        updated.hint = snapshot.hint.clone();
+        updated.explanation = snapshot.explanation.clone();
+        updated.source = snapshot.source.clone();
+        touch(updated);
+        let updated = updated.clone();
+
+        tables.choices.retain(|choice| choice.question_id != question.id);
+        for (position, choice) in snapshot.choices.iter().enumerate() {
+            tables.insert_choice(question.id, choice.answer_id, position as i32, choice.match_answer_id);
+        }
+
+        Ok(updated)
+    }
+
+    async fn resubmit(&self, question_id: Uuid) -> Result<Question, AppError> {
+        let mut tables = self.tables();
+        let question = tables.questions.iter_mut()
+            .find(|question| question.id == question_id && question.status == QuestionStatus::ChangesRequested.as_str())
+            .ok_or_else(question_not_changes_requested)?;
+
+        question.status = QuestionStatus::Pending.as_str().to_string();
+        touch(question);
+        Ok(question.clone())
+    }
+
+    async fn soft_delete(&self, id: Uuid) -> Result<Question, AppError> {
+        let mut tables = self.tables();
+        let question = tables.question_mut(id).ok_or_else(not_found)?;
+
+        question.deleted_at = Some(now());
+        touch(question);
+        Ok(question.clone())
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Question, AppError> {
+        let mut tables = self.tables();
+        let deleted_bands: Vec<Uuid> = tables.bands.iter()
+            .filter(|band| band.deleted_at.is_some())
+            .map(|band| band.id)
+            .collect();
+
+        let question = tables.questions.iter_mut()
+            .find(|question| question.id == id && question.deleted_at.is_some() && !deleted_bands.contains(&question.band_id))
+            .ok_or_else(question_not_deleted)?;
+
+        question.deleted_at = None;
+        touch(question);
+        Ok(question.clone())
+    }
+}
+
+#[async_trait]
+impl BandStore for MemoryStore {
+    async fn get(&self, id: Uuid) -> Result<Band, AppError> {
+        self.tables().bands.iter()
+            .find(|band| band.id == id && band.deleted_at.is_none())
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    async fn all(&self) -> Result<Vec<Band>, AppError> {
+        Ok(self.tables().bands.iter()
+            .filter(|band| band.hidden_at.is_none() && band.deleted_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn create(&self, input: CreateBand, owner_id: Uuid) -> Result<Band, AppError> {
+        let mut tables = self.tables();
+        if tables.bands.iter().any(|band| band.name == input.name) {
+            return Err(band_exists(&input.name, None));
+        }
+
+        let band = Band {
+            id: Uuid::new_v4(),
+            name: input.name,
+            owner_id,
+            created_at: now(),
+            updated_at: now(),
+            hidden_at: None,
+            deleted_at: None,
+        };
+
+        tables.bands.push(band.clone());
+        tables.band_authors.push((band.id, owner_id, BandRole::Author));
+        Ok(band)
+    }
+
+    async fn is_author(&self, band_id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+        Ok(self.tables().band_authors.iter().any(|(band, user, _)| *band == band_id && *user == user_id))
+    }
+
+    async fn is_moderator(&self, band_id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+        let tables = self.tables();
+        Ok(
+            tables.bands.iter().any(|band| band.id == band_id && band.owner_id == user_id)
+                || tables.band_authors.iter().any(|(band, user, role)| *band == band_id && *user == user_id && *role == BandRole::Moderator)
+        )
+    }
+
+    async fn add_author(&self, band_id: Uuid, user_id: Uuid, role: BandRole) -> Result<(), AppError> {
+        let mut tables = self.tables();
+        if !tables.users.iter().any(|user| user.id == user_id) {
+            return Err(user_missing(user_id, None));
+        }
+
+        tables.band_authors.retain(|(band, user, _)| !(*band == band_id && *user == user_id));
+        tables.band_authors.push((band_id, user_id, role));
+        Ok(())
+    }
+
+    async fn soft_delete(&self, id: Uuid) -> Result<Band, AppError> {
+        let mut tables = self.tables();
+        let deleted_at = now();
+        let band = tables.bands.iter_mut()
+            .find(|band| band.id == id && band.deleted_at.is_none())
+            .ok_or_else(not_found)?;
+
+        band.deleted_at = Some(deleted_at);
+        band.updated_at = deleted_at;
+        let band = band.clone();
+
+        for question in tables.questions.iter_mut().filter(|question| question.band_id == id && question.deleted_at.is_none()) {
+            question.deleted_at = Some(deleted_at);
+            touch(question);
+        }
+
+        Ok(band)
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Band, AppError> {
+        let mut tables = self.tables();
+        let band = tables.bands.iter_mut()
+            .find(|band| band.id == id && band.deleted_at.is_some())
+            .ok_or_else(band_not_deleted)?;
+
+        let deleted_at = band.deleted_at.take();
+        band.updated_at = now();
+        let band = band.clone();
+
+        for question in tables.questions.iter_mut().filter(|question| question.band_id == id && question.deleted_at == deleted_at) {
+            question.deleted_at = None;
+            touch(question);
+        }
+
+        Ok(band)
+    }
+}
+
+#[async_trait]
+impl SessionStore for MemoryStore {
+    async fn find_user_id(&self, token: Uuid) -> Result<Option<Uuid>, AppError> {
+        let tables = self.tables();
+        Ok(tables.sessions.iter()
+            .find(|session| session.token == token && session.expires_at > now())
+            .map(|session| session.user_id)
+            .filter(|user_id| tables.users.iter().any(|user| user.id == *user_id && user.deleted_at.is_none())))
+    }
+
+    async fn create(&self, input: Login, hashing: Arc<HashingService>) -> Result<Session, AppError> {
+        let user = self.tables().users.iter()
+            .find(|user| user.username == input.username && user.deleted_at.is_none())
+            .cloned()
+            .ok_or_else(invalid_login)?;
+
+        if !hashing.verify(input.password, user.password).await? {
+            return Err(invalid_login());
+        }
+
+        let session = Session {
+            token: Uuid::new_v4(),
+            user_id: user.id,
+            expires_at: now() + Duration::days(30),
+            created_at: now(),
+        };
+
+        self.tables().sessions.push(session.clone());
+        Ok(session)
+    }
+}
+
+#[async_trait]
+impl ReviewStateStore for MemoryStore {
+    async fn practice_queue(&self, user_id: Uuid, band_id: Uuid, limit: i32) -> Result<Vec<Question>, AppError> {
+        let tables = self.tables();
+        let due_at = |question: &Question| tables.review_states.iter()
+            .find(|state| state.user_id == user_id && state.question_id == question.id)
+            .map(|state| state.due_at);
+
+        let mut questions: Vec<(Option<NaiveDateTime>, &Question)> = tables.playable(band_id)
+            .map(|question| (due_at(question), question))
+            .filter(|(due_at, _)| due_at.map_or(true, |due_at| due_at <= now()))
+            .collect();
+        questions.sort_by_key(|(due_at, question)| (due_at.is_none(), *due_at, question.created_at));
+
+        Ok(questions.into_iter()
+            .take(limit.max(0) as usize)
+            .map(|(_, question)| question.clone())
+            .collect())
+    }
+
+    async fn review(&self, user_id: Uuid, question_id: Uuid, grade: i32) -> Result<ReviewState, AppError> {
+        validate_grade(grade)?;
+
+        let mut tables = self.tables();
+        if !tables.questions.iter().any(|question| question.id == question_id) {
+            return Err(question_missing(question_id, None));
+        }
+
+        let now = now();
+        let state = tables.review_states.iter()
+            .find(|state| state.user_id == user_id && state.question_id == question_id)
+            .cloned()
+            .unwrap_or_else(|| ReviewState::new(user_id, question_id, now))
+            .review(grade, now);
+
+        tables.review_states.retain(|stored| !(stored.user_id == user_id && stored.question_id == question_id));
+        tables.review_states.push(state.clone());
+        Ok(state)
+    }
+}
+
+#[async_trait]
+impl MediaStore for MemoryStore {
+    async fn get(&self, id: Uuid) -> Result<Media, AppError> {
+        self.tables().media.iter()
+            .find(|media| media.id == id)
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    async fn create(&self, input: CreateMedia) -> Result<Media, AppError> {
+        let media = Media {
+            id: Uuid::new_v4(),
+            key: input.key,
+            content_type: input.content_type,
+            size_bytes: input.size_bytes,
+            uploaded_by: input.uploaded_by,
+            created_at: now(),
+            updated_at: now(),
+        };
+
+        self.tables().media.push(media.clone());
+        Ok(media)
+    }
+}
+
+#[async_trait]
+impl TagStore for MemoryStore {
+    async fn for_question(&self, question_id: Uuid) -> Result<Vec<Tag>, AppError> {
+        let tables = self.tables();
+        let mut tags: Vec<Tag> = tables.tags.iter()
+            .filter(|tag| tables.question_tags.contains(&(question_id, tag.id)))
+            .cloned()
+            .collect();
+        tags.sort_by(|first, second| first.name.cmp(&second.name));
+        Ok(tags)
+    }
+
+    async fn all(&self, band_id: Option<Uuid>) -> Result<Vec<Tag>, AppError> {
+        let tables = self.tables();
+        let used = |tag: &Tag| tables.question_tags.iter().any(|(question_id, tag_id)| {
+            *tag_id == tag.id
+                && tables.questions.iter().any(|question| question.id == *question_id && Some(question.band_id) == band_id && question.deleted_at.is_none())
+        });
+
+        let mut tags: Vec<Tag> = tables.tags.iter()
+            .filter(|tag| band_id.is_none() || used(tag))
+            .cloned()
+            .collect();
+        tags.sort_by(|first, second| (&first.kind, &first.name).cmp(&(&second.kind, &second.name)));
+        Ok(tags)
+    }
+
+    async fn tag_question(&self, question_id: Uuid, names: &[String], kind: Option<TagKind>) -> Result<Vec<Tag>, AppError> {
+        let mut tables = self.tables();
+
+        let mut tags = Vec::new();
+        for name in normalize_tags(names) {
+            let tag_kind = kind.unwrap_or_else(|| TagKind::infer(&name));
+
+            let tag = match tables.tags.iter().find(|tag| tag.name == name).cloned() {
+                Some(tag) => tag,
+                None => {
+                    let tag = Tag { id: Uuid::new_v4(), name, created_at: now(), updated_at: now(), kind: tag_kind.as_str().to_string() };
+                    tables.tags.push(tag.clone());
+                    tag
+                },
+            };
+
+            if kind.is_some() && tag.kind != tag_kind.as_str() {
+                return Err(tag_kind_conflict(&tag));
+            }
+
+            if !tables.questions.iter().any(|question| question.id == question_id) {
+                return Err(question_missing(question_id, None));
+            }
+            if !tables.question_tags.contains(&(question_id, tag.id)) {
+                tables.question_tags.push((question_id, tag.id));
+            }
+            tags.push(tag);
+        }
+
+        Ok(tags)
+    }
+
+    async fn untag_question(&self, question_id: Uuid, names: &[String]) -> Result<(), AppError> {
+        let names = normalize_tags(names);
+        let mut tables = self.tables();
+        let tag_ids: Vec<Uuid> = tables.tags.iter()
+            .filter(|tag| names.contains(&tag.name))
+            .map(|tag| tag.id)
+            .collect();
+
+        tables.question_tags.retain(|(question, tag)| !(*question == question_id && tag_ids.contains(tag)));
+        Ok(())
+    }
+
+    async fn stats(&self, band_id: Option<Uuid>, user_id: Option<Uuid>) -> Result<Vec<TagStats>, AppError> {
+        let tables = self.tables();
+
+        let mut stats: Vec<TagStats> = tables.tags.iter()
+            .filter_map(|tag| {
+                let questions: Vec<&Question> = tables.questions.iter()
+                    .filter(|question| tables.question_tags.contains(&(question.id, tag.id)))
+                    .filter(|question| band_id.map_or(true, |band_id| question.band_id == band_id) && question.deleted_at.is_none())
+                    .collect();
+                if questions.is_empty() {
+                    return None;
+                }
+
+                let grades: Vec<i32> = questions.iter()
+                    .filter_map(|question| tables.review_states.iter()
+                        .find(|state| Some(state.user_id) == user_id && state.question_id == question.id)
+                        .and_then(|state| state.last_grade))
+                    .collect();
+
+                Some(TagStats {
+                    tag_id: tag.id,
+                    name: tag.name.clone(),
+                    kind: tag.kind.parse().unwrap_or(TagKind::Topic),
+                    question_count: questions.len() as i32,
+                    reviewed_count: grades.len() as i32,
+                    average_grade: if grades.is_empty() {
+                        None
+                    } else {
+                        Some(grades.iter().sum::<i32>() as f64 / grades.len() as f64)
+                    },
+                })
+            })
+            .collect();
+        stats.sort_by(|first, second| second.question_count.cmp(&first.question_count).then_with(|| first.name.cmp(&second.name)));
+
+        Ok(stats)
+    }
+}
+
+#[async_trait]
+impl SearchStore for MemoryStore {
+    async fn search(&self, query: &str, types: Vec<SearchType>, first: i32, after: Option<String>) -> Result<SearchConnection, AppError> {
+        validate_page_size(first)?;
+
+        let offset = match &after {
+            Some(cursor) => decode_cursor(cursor)? + 1,
+            None => 0,
+        };
+
+        if prefix_query(query).is_none() {
+            return Ok(connection(vec![], first, offset));
+        }
+
+        let tables = self.tables();
+        let hit = |kind: SearchType, id: Uuid, title: &str| SearchHit {
+            kind,
+            id,
+            title: title.to_string(),
+            snippet: highlight(title),
+            rank: 1.0,
+        };
+
+        let mut hits: Vec<SearchHit> = vec![];
+        if types.contains(&SearchType::Band) {
+            hits.extend(tables.bands.iter()
+                .filter(|band| band.hidden_at.is_none() && band.deleted_at.is_none() && matches(query, &band.name))
+                .map(|band| hit(SearchType::Band, band.id, &band.name)));
+        }
+        if types.contains(&SearchType::Question) {
+            hits.extend(tables.questions.iter()
+                .filter(|question| question.status == QuestionStatus::Approved.as_str())
+                .filter(|question| question.hidden_at.is_none() && question.deleted_at.is_none() && matches(query, &question.content))
+                .map(|question| hit(SearchType::Question, question.id, &question.content)));
+        }
+        if types.contains(&SearchType::User) {
+            hits.extend(tables.users.iter()
+                .filter(|user| user.hidden_at.is_none() && user.deleted_at.is_none() && matches(query, &user.username))
+                .map(|user| hit(SearchType::User, user.id, &user.username)));
+        }
+        hits.sort_by(|first, second| (&first.title, first.id).cmp(&(&second.title, second.id)));
+
+        let hits = hits.into_iter()
+            .skip(offset as usize)
+            .take(first as usize + 1)
+            .collect();
+
+        Ok(connection(hits, first, offset))
+    }
+}
+
+#[async_trait]
+impl QuizStore for MemoryStore {
+    async fn get(&self, id: Uuid) -> Result<Quiz, AppError> {
+        self.tables().quizzes.iter()
+            .find(|quiz| quiz.id == id)
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    async fn get_for_band(&self, band_id: Uuid, statuses: &[QuizStatus]) -> Result<Vec<Quiz>, AppError> {
+        Ok(self.tables().quizzes.iter()
+            .rev()
+            .filter(|quiz| quiz.band_id == band_id && statuses.contains(&quiz.quiz_status()))
+            .cloned()
+            .collect())
+    }
+
+    async fn create(&self, input: CreateQuiz, created_by: Uuid, scoring_rules_id: Option<Uuid>) -> Result<Quiz, AppError> {
+        validate_settings(Some(&input.title), input.time_limit_seconds, input.seconds_per_question)?;
+        validate_lifelines(input.fifty_fifty_lifelines, input.hint_lifelines)?;
+
+        let mut tables = self.tables();
+        if !tables.bands.iter().any(|band| band.id == input.band_id) {
+            return Err(band_missing(input.band_id, None));
+        }
+        // Checked before inserting, since nothing is rolled back
+        if let Some(question_ids) = &input.question_ids {
+            tables.check_quiz_questions(input.band_id, question_ids)?;
+        }
+
+        let quiz = Quiz {
+            id: Uuid::new_v4(),
+            band_id: input.band_id,
+            title: input.title.trim().to_string(),
+            description: input.description,
+            status: QuizStatus::Draft.as_str().to_string(),
+            time_limit_seconds: input.time_limit_seconds,
+            created_by,
+            created_at: now(),
+            updated_at: now(),
+            scoring_rules_id,
+            seconds_per_question: input.seconds_per_question,
+            fifty_fifty_lifelines: input.fifty_fifty_lifelines.unwrap_or(1),
+            hint_lifelines: input.hint_lifelines.unwrap_or(1),
+            version: 1,
+        };
+
+        tables.quizzes.push(quiz.clone());
+        if let Some(question_ids) = &input.question_ids {
+            tables.replace_quiz_questions(quiz.id, question_ids);
+        }
+
+        Ok(quiz)
+    }
+
+    async fn update(&self, input: UpdateQuiz, scoring_rules_id: Option<Uuid>) -> Result<Quiz, AppError> {
+        validate_settings(input.title.as_deref(), input.time_limit_seconds, input.seconds_per_question)?;
+        validate_lifelines(input.fifty_fifty_lifelines, input.hint_lifelines)?;
+
+        let mut tables = self.tables();
+        let quiz = tables.quizzes.iter_mut()
+            .find(|quiz| quiz.id == input.id && quiz.version == input.version)
+            .ok_or_else(|| quiz_changed(input.version))?;
+
+        if let Some(title) = &input.title {
+            quiz.title = title.trim().to_string();
+        }
+        quiz.description = input.description.or_else(|| quiz.description.take());
+        quiz.time_limit_seconds = input.time_limit_seconds.or(quiz.time_limit_seconds);
+        quiz.scoring_rules_id = scoring_rules_id.or(quiz.scoring_rules_id);
+        quiz.seconds_per_question = input.seconds_per_question.or(quiz.seconds_per_question);
+        quiz.fifty_fifty_lifelines = input.fifty_fifty_lifelines.unwrap_or(quiz.fifty_fifty_lifelines);
+        quiz.hint_lifelines = input.hint_lifelines.unwrap_or(quiz.hint_lifelines);
+        touch_quiz(quiz);
+        Ok(quiz.clone())
+    }
+
+    async fn set_status(&self, quiz: &Quiz, status: QuizStatus) -> Result<Quiz, AppError> {
+        let mut tables = self.tables();
+        if status == QuizStatus::Published && tables.quiz_questions(quiz.id).is_empty() {
+            return Err(unpublishable_quiz());
+        }
+
+        let stored = tables.quizzes.iter_mut()
+            .find(|stored| stored.id == quiz.id)
+            .ok_or_else(not_found)?;
+
+        stored.status = status.as_str().to_string();
+        touch_quiz(stored);
+        Ok(stored.clone())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), AppError> {
+        let mut tables = self.tables();
+        if tables.attempts.iter().any(|attempt| attempt.quiz_id == Some(id)) {
+            return Err(quiz_played(None));
+        }
+
+        tables.quizzes.retain(|quiz| quiz.id != id);
+        tables.quiz_questions.retain(|(quiz, _)| *quiz != id);
+        Ok(())
+    }
+
+    async fn questions(&self, quiz_id: Uuid) -> Result<Vec<Question>, AppError> {
+        Ok(self.tables().quiz_questions(quiz_id))
+    }
+
+    async fn set_questions(&self, quiz: &Quiz, question_ids: &[Uuid]) -> Result<Vec<Question>, AppError> {
+        let mut tables = self.tables();
+        tables.check_quiz_questions(quiz.band_id, question_ids)?;
+        tables.replace_quiz_questions(quiz.id, question_ids);
+
+        Ok(tables.quiz_questions(quiz.id))
+    }
+}
+
+#[async_trait]
+impl AttemptStore for MemoryStore {
+    async fn get(&self, id: Uuid) -> Result<Attempt, AppError> {
+        self.tables().attempts.iter()
+            .find(|attempt| attempt.id == id)
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    /// The tables' mutex already makes changes one at a time
+    async fn lock(&self, id: Uuid) -> Result<Attempt, AppError> {
+        AttemptStore::get(self, id).await
+    }
+
+    async fn now(&self) -> Result<NaiveDateTime, AppError> {
+        Ok(now())
+    }
+
+    async fn start_quiz(&self, quiz: &Quiz, user_id: Uuid) -> Result<Attempt, AppError> {
+        let mut tables = self.tables();
+        let question_ids: Vec<Uuid> = tables.quiz_questions.iter()
+            .filter(|(quiz_id, _)| *quiz_id == quiz.id)
+            .map(|(_, question_id)| *question_id)
+            .collect();
+
+        Ok(tables.insert_attempt(Some(quiz), quiz.band_id, user_id, question_ids))
+    }
+
+    async fn start_random(&self, band_id: Uuid, user_id: Uuid, tags: &[String], count: i32) -> Result<Attempt, AppError> {
+        let tags = normalize_tags(tags);
+        let mut tables = self.tables();
+
+        let candidates: Vec<Uuid> = tables.playable(band_id)
+            .filter(|question| tables.has_tags(question.id, &tags))
+            .map(|question| question.id)
+            .collect();
+        let picked: Vec<Uuid> = shuffled(candidates).into_iter().take(count.max(0) as usize).collect();
+
+        if picked.is_empty() {
+            return Err(no_matching_questions());
+        }
+
+        Ok(tables.insert_attempt(None, band_id, user_id, picked))
+    }
+
+    async fn questions(&self, attempt_id: Uuid) -> Result<Vec<AttemptQuestion>, AppError> {
+        let mut questions: Vec<AttemptQuestion> = self.tables().attempt_questions.iter()
+            .filter(|question| question.attempt_id == attempt_id)
+            .cloned()
+            .collect();
+        questions.sort_by_key(|question| question.position);
+        Ok(questions)
+    }
+
+    async fn record_answer(&self, attempt_id: Uuid, submission: &AnswerSubmission, grade: &Grade) -> Result<AttemptQuestion, AppError> {
+        let mut tables = self.tables();
+        let question = tables.attempt_questions.iter_mut()
+            .find(|question| question.attempt_id == attempt_id && question.question_id == submission.question_id && question.answered_at.is_none())
+            .ok_or_else(already_answered)?;
+
+        question.answer_id = submission.answer_id;
+        question.answer_text = submission.text.clone();
+        question.answer_number = submission.number;
+        question.answer_ordering = submission.ordering.clone();
+        question.answer_pair_ids = submission.pairs.as_ref().map(|pairs| pairs.iter().map(|pair| pair.answer_id).collect());
+        question.answer_pair_match_ids = submission.pairs.as_ref().map(|pairs| pairs.iter().map(|pair| pair.match_answer_id).collect());
+        question.accepted = Some(grade.accepted);
+        question.score = Some(grade.score);
+        question.answered_at = Some(now());
+        Ok(question.clone())
+    }
+
+    async fn serve(&self, attempt_id: Uuid, question_id: Uuid) -> Result<AttemptQuestion, AppError> {
+        let mut tables = self.tables();
+        let served = tables.attempt_questions.iter_mut()
+            .find(|question| question.attempt_id == attempt_id && question.question_id == question_id)
+            .ok_or_else(not_in_attempt)?;
+
+        served.served_at = served.served_at.or_else(|| Some(now()));
+        let served = served.clone();
+
+        let choices = tables.choices(question_id);
+        let mut answer_ids: Vec<Uuid> = choices.iter().map(|choice| choice.answer_id).collect();
+        // Choice questions without distractors still get their correct answer dealt
+        if answer_ids.is_empty() {
+            answer_ids = tables.questions.iter()
+                .filter(|question| question.id == question_id && question.kind == QuestionKind::Choice.as_str())
+                .filter_map(|question| question.correct_answer_id)
+                .collect();
+        }
+        let match_answer_ids = choices.iter().filter_map(|choice| choice.match_answer_id).collect();
+        tables.deal_choices(attempt_id, question_id, "answer", answer_ids);
+        tables.deal_choices(attempt_id, question_id, "match", match_answer_ids);
+
+        Ok(served)
+    }
+
+    async fn choices(&self, attempt_id: Uuid, question_id: Uuid) -> Result<Vec<AttemptChoice>, AppError> {
+        let tables = self.tables();
+        let mut choices: Vec<AttemptChoice> = tables.attempt_choices.iter()
+            .filter(|choice| choice.attempt_id == attempt_id && choice.question_id == question_id)
+            .filter_map(|choice| tables.answers.iter()
+                .find(|answer| answer.id == choice.answer_id)
+                .map(|answer| AttemptChoice { content: answer.content.clone(), ..choice.clone() }))
+            .collect();
+        choices.sort_by(|first, second| (&first.side, first.position).cmp(&(&second.side, second.position)));
+        Ok(choices)
+    }
+
+    async fn lifelines(&self, attempt_id: Uuid) -> Result<Vec<Lifeline>, AppError> {
+        Ok(self.tables().lifelines.iter()
+            .filter(|lifeline| lifeline.attempt_id == attempt_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn use_lifeline(&self, attempt_id: Uuid, question_id: Uuid, kind: LifelineKind, budget: i32) -> Result<Lifeline, AppError> {
+        let mut tables = self.tables();
+        let used: Vec<&Lifeline> = tables.lifelines.iter()
+            .filter(|lifeline| lifeline.attempt_id == attempt_id && lifeline.kind == kind.as_str())
+            .collect();
+
+        if used.len() >= budget.max(0) as usize || used.iter().any(|lifeline| lifeline.question_id == question_id) {
+            return Err(no_lifeline_left());
+        }
+
+        let lifeline = Lifeline { attempt_id, question_id, kind: kind.as_str().to_string(), used_at: now() };
+        tables.lifelines.push(lifeline.clone());
+        Ok(lifeline)
+    }
+
+    async fn eliminate_choices(&self, attempt_id: Uuid, question_id: Uuid, correct_answer_id: Uuid, count: i64) -> Result<(), AppError> {
+        let mut tables = self.tables();
+        let tokens: Vec<Uuid> = tables.attempt_choices.iter()
+            .filter(|choice| choice.attempt_id == attempt_id && choice.question_id == question_id && choice.side == "answer")
+            .filter(|choice| choice.answer_id != correct_answer_id && !choice.eliminated)
+            .map(|choice| choice.token)
+            .collect();
+        let eliminated: Vec<Uuid> = shuffled(tokens).into_iter().take(count.max(0) as usize).collect();
+
+        for choice in tables.attempt_choices.iter_mut().filter(|choice| eliminated.contains(&choice.token)) {
+            choice.eliminated = true;
+        }
+
+        Ok(())
+    }
+
+    async fn median_answer_seconds(&self, band_id: Uuid, quiz_id: Option<Uuid>) -> Result<Option<f64>, AppError> {
+        let tables = self.tables();
+        let seconds = tables.attempt_questions.iter()
+            .filter(|question| tables.attempts.iter().any(|attempt| {
+                attempt.id == question.attempt_id && attempt.band_id == band_id && attempt.quiz_id == quiz_id && attempt.finished_at.is_some()
+            }))
+            .filter_map(|question| match (question.served_at, question.answered_at) {
+                (Some(served_at), Some(answered_at)) => Some((answered_at - served_at).num_milliseconds() as f64 / 1000.0),
+                _ => None,
+            })
+            .collect();
+
+        Ok(median(seconds))
+    }
+
+    async fn finish(&self, attempt_id: Uuid, score: f64, flag_reasons: &[String]) -> Result<Attempt, AppError> {
+        let mut tables = self.tables();
+        let attempt = tables.attempt_mut(attempt_id)
+            .filter(|attempt| attempt.finished_at.is_none())
+            .ok_or_else(already_finished)?;
+
+        attempt.finished_at = Some(now());
+        attempt.score = score;
+        attempt.flag_reasons = flag_reasons.to_vec();
+        attempt.flagged_at = if flag_reasons.is_empty() { None } else { Some(now()) };
+        attempt.updated_at = now();
+        Ok(attempt.clone())
+    }
+
+    async fn flagged(&self, band_id: Uuid) -> Result<Vec<Attempt>, AppError> {
+        let mut attempts: Vec<Attempt> = self.tables().attempts.iter()
+            .filter(|attempt| attempt.band_id == band_id && attempt.flagged_at.is_some())
+            .cloned()
+            .collect();
+        attempts.sort_by(|first, second| second.flagged_at.cmp(&first.flagged_at));
+        Ok(attempts)
+    }
+
+    async fn clear_flag(&self, attempt_id: Uuid) -> Result<Attempt, AppError> {
+        let mut tables = self.tables();
+        let attempt = tables.attempt_mut(attempt_id).ok_or_else(not_found)?;
+
+        attempt.flagged_at = None;
+        attempt.flag_reasons = vec![];
+        attempt.updated_at = now();
+        Ok(attempt.clone())
+    }
+}
+
+#[async_trait]
+impl ScoringRulesStore for MemoryStore {
+    async fn get(&self, id: Uuid) -> Result<ScoringRules, AppError> {
+        self.tables().scoring_rules.iter()
+            .find(|rules| rules.id == id)
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    async fn create(&self, input: &ScoringRulesInput) -> Result<ScoringRules, AppError> {
+        validate_rules(input)?;
+
+        let rules = with_defaults(input);
+        self.tables().scoring_rules.push(rules.clone());
+        Ok(rules)
+    }
+
+    async fn replace(&self, id: Uuid, input: &ScoringRulesInput) -> Result<ScoringRules, AppError> {
+        validate_rules(input)?;
+
+        let mut tables = self.tables();
+        let played = tables.attempts.iter().any(|attempt| attempt.scoring_rules_id == Some(id));
+        let rules = with_defaults(input);
+        if !played {
+            if let Some(stored) = tables.scoring_rules.iter_mut().find(|stored| stored.id == id) {
+                *stored = ScoringRules { id, created_at: stored.created_at, ..rules };
+                return Ok(stored.clone());
+            }
+        }
+
+        tables.scoring_rules.push(rules.clone());
+        Ok(rules)
+    }
+}
+
+#[async_trait]
+impl NotificationStore for MemoryStore {
+    async fn for_user(&self, user_id: Uuid, unread_only: bool) -> Result<Vec<Notification>, AppError> {
+        Ok(self.tables().notifications.iter()
+            .rev()
+            .filter(|notification| notification.user_id == user_id && (!unread_only || notification.read_at.is_none()))
+            .cloned()
+            .collect())
+    }
+
+    async fn create(&self, user_id: Uuid, kind: NotificationKind, message: &str, question_id: Option<Uuid>) -> Result<Notification, AppError> {
+        let notification = Notification {
+            id: Uuid::new_v4(),
+            user_id,
+            kind: kind.as_str().to_string(),
+            message: message.to_string(),
+            question_id,
+            read_at: None,
+            created_at: now(),
+        };
+
+        self.tables().notifications.push(notification.clone());
+        Ok(notification)
+    }
+
+    async fn mark_read(&self, user_id: Uuid, ids: Option<&[Uuid]>) -> Result<i32, AppError> {
+        let mut tables = self.tables();
+        let mut count = 0;
+        for notification in tables.notifications.iter_mut() {
+            if notification.user_id == user_id && notification.read_at.is_none() && ids.map_or(true, |ids| ids.contains(&notification.id)) {
+                notification.read_at = Some(now());
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+#[async_trait]
+impl RevisionStore for MemoryStore {
+    async fn for_question(&self, question_id: Uuid) -> Result<Vec<QuestionRevision>, AppError> {
+        let mut revisions: Vec<QuestionRevision> = self.tables().revisions.iter()
+            .filter(|revision| revision.question_id == question_id)
+            .cloned()
+            .collect();
+        revisions.sort_by_key(|revision| revision.number);
+        Ok(revisions)
+    }
+
+    async fn get(&self, question_id: Uuid, number: i32) -> Result<QuestionRevision, AppError> {
+        self.tables().revisions.iter()
+            .find(|revision| revision.question_id == question_id && revision.number == number)
+            .cloned()
+            .ok_or_else(|| no_revision(number))
+    }
+
+    async fn latest(&self, question_id: Uuid) -> Result<Option<QuestionRevision>, AppError> {
+        Ok(self.tables().revisions.iter()
+            .filter(|revision| revision.question_id == question_id)
+            .max_by_key(|revision| revision.number)
+            .cloned())
+    }
+
+    async fn create(&self, question_id: Uuid, author_id: Option<Uuid>, snapshot: &QuestionSnapshot, reverted_from: Option<i32>) -> Result<QuestionRevision, AppError> {
+        let snapshot = snapshot.to_json()?;
+
+        let mut tables = self.tables();
+        let number = tables.revisions.iter()
+            .filter(|revision| revision.question_id == question_id)
+            .map(|revision| revision.number)
+            .max()
+            .unwrap_or(0) + 1;
+
+        let revision = QuestionRevision {
+            id: Uuid::new_v4(),
+            question_id,
+            number,
+            author_id,
+            snapshot,
+            reverted_from,
+            created_at: now(),
+        };
+
+        tables.revisions.push(revision.clone());
+        Ok(revision)
+    }
+
+    async fn create_suggestion(&self, question_id: Uuid, suggested_by: Uuid, snapshot: &QuestionSnapshot, base_revision: i32, note: Option<&str>) -> Result<EditSuggestion, AppError> {
+        let suggestion = EditSuggestion {
+            id: Uuid::new_v4(),
+            question_id,
+            suggested_by,
+            snapshot: snapshot.to_json()?,
+            base_revision,
+            note: note.map(String::from),
+            status: SuggestionStatus::Pending.as_str().to_string(),
+            reason: None,
+            decided_by: None,
+            decided_at: None,
+            created_at: now(),
+        };
+
+        self.tables().suggestions.push(suggestion.clone());
+        Ok(suggestion)
+    }
+
+    async fn get_suggestion(&self, id: Uuid) -> Result<EditSuggestion, AppError> {
+        self.tables().suggestions.iter()
+            .find(|suggestion| suggestion.id == id)
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    async fn pending_suggestions(&self, band_id: Uuid) -> Result<Vec<EditSuggestion>, AppError> {
+        let tables = self.tables();
+        Ok(tables.suggestions.iter()
+            .filter(|suggestion| suggestion.status == SuggestionStatus::Pending.as_str())
+            .filter(|suggestion| tables.questions.iter().any(|question| {
+                question.id == suggestion.question_id && question.band_id == band_id && question.deleted_at.is_none()
+            }))
+            .cloned()
+            .collect())
+    }
+
+    async fn decide_suggestion(&self, id: Uuid, status: SuggestionStatus, reason: Option<&str>, decided_by: Uuid) -> Result<EditSuggestion, AppError> {
+        let mut tables = self.tables();
+        let suggestion = tables.suggestions.iter_mut()
+            .find(|suggestion| suggestion.id == id && suggestion.status == SuggestionStatus::Pending.as_str())
+            .ok_or_else(suggestion_decided)?;
+
+        suggestion.status = status.as_str().to_string();
+        suggestion.reason = reason.map(String::from);
+        suggestion.decided_by = Some(decided_by);
+        suggestion.decided_at = Some(now());
+        Ok(suggestion.clone())
+    }
+}
+
+#[async_trait]
+impl ReportStore for MemoryStore {
+    async fn get(&self, id: Uuid) -> Result<Report, AppError> {
+        self.tables().reports.iter()
+            .find(|report| report.id == id)
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    async fn create(&self, reporter_id: Uuid, target_type: ReportTargetType, target_id: Uuid, reason: ReportReason, note: Option<&str>) -> Result<Report, AppError> {
+        let mut tables = self.tables();
+        let open = tables.reports.iter_mut().find(|report| {
+            report.reporter_id == reporter_id
+                && report.target_type == target_type.as_str()
+                && report.target_id == target_id
+                && report.status == ReportStatus::Open.as_str()
+        });
+
+        if let Some(report) = open {
+            report.reason = reason.as_str().to_string();
+            report.note = note.map(String::from);
+            return Ok(report.clone());
+        }
+
+        let report = Report {
+            id: Uuid::new_v4(),
+            reporter_id,
+            target_type: target_type.as_str().to_string(),
+            target_id,
+            reason: reason.as_str().to_string(),
+            note: note.map(String::from),
+            status: ReportStatus::Open.as_str().to_string(),
+            resolved_by: None,
+            resolved_at: None,
+            created_at: now(),
+        };
+
+        tables.reports.push(report.clone());
+        Ok(report)
+    }
+
+    async fn open_count(&self, target_type: ReportTargetType, target_id: Uuid) -> Result<i32, AppError> {
+        let mut reporters: Vec<Uuid> = self.tables().reports.iter()
+            .filter(|report| report.target_type == target_type.as_str() && report.target_id == target_id)
+            .filter(|report| report.status == ReportStatus::Open.as_str())
+            .map(|report| report.reporter_id)
+            .collect();
+        reporters.sort();
+        reporters.dedup();
+        Ok(reporters.len() as i32)
+    }
+
+    async fn queue(&self, band_id: Option<Uuid>) -> Result<Vec<Report>, AppError> {
+        let tables = self.tables();
+        let in_band = |report: &Report| match (band_id, report.report_target_type()) {
+            (None, _) => true,
+            (Some(band_id), ReportTargetType::Band) => report.target_id == band_id,
+            (Some(band_id), ReportTargetType::Question) =>
+                tables.questions.iter().any(|question| question.id == report.target_id && question.band_id == band_id),
+            (Some(_), ReportTargetType::User) => false,
+        };
+
+        Ok(tables.reports.iter()
+            .filter(|report| report.status == ReportStatus::Open.as_str() && in_band(report))
+            .cloned()
+            .collect())
+    }
+
+    async fn resolve(&self, target_type: ReportTargetType, target_id: Uuid, status: ReportStatus, resolved_by: Uuid) -> Result<Vec<Report>, AppError> {
+        let mut tables = self.tables();
+        let mut resolved = vec![];
+        for report in tables.reports.iter_mut() {
+            if report.target_type == target_type.as_str() && report.target_id == target_id && report.status == ReportStatus::Open.as_str() {
+                report.status = status.as_str().to_string();
+                report.resolved_by = Some(resolved_by);
+                report.resolved_at = Some(now());
+                resolved.push(report.clone());
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    async fn set_hidden(&self, target_type: ReportTargetType, target_id: Uuid, hidden: bool) -> Result<(), AppError> {
+        let hidden_at = |current: Option<NaiveDateTime>| if hidden { current.or_else(|| Some(now())) } else { None };
+
+        let mut tables = self.tables();
+        match target_type {
+            ReportTargetType::Question => {
+                for question in tables.questions.iter_mut().filter(|question| question.id == target_id) {
+                    question.hidden_at = hidden_at(question.hidden_at);
+                    touch(question);
+                }
+            },
+            ReportTargetType::Band => {
+                for band in tables.bands.iter_mut().filter(|band| band.id == target_id) {
+                    band.hidden_at = hidden_at(band.hidden_at);
+                    band.updated_at = now();
+                }
+            },
+            ReportTargetType::User => {
+                for user in tables.users.iter_mut().filter(|user| user.id == target_id) {
+                    user.hidden_at = hidden_at(user.hidden_at);
+                    user.updated_at = now();
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AuditStore for MemoryStore {
+    async fn create(&self, entry: NewAuditEntry) -> Result<AuditEntry, AppError> {
+        let entry = AuditEntry {
+            id: Uuid::new_v4(),
+            actor_id: entry.actor_id,
+            action: entry.action.as_str().to_string(),
+            target_type: entry.target_type.as_str().to_string(),
+            target_id: entry.target_id,
+            before: entry.before,
+            after: entry.after,
+            request_id: entry.request_id,
+            ip: entry.ip,
+            created_at: now(),
+        };
+
+        self.tables().audit_log.push(entry.clone());
+        Ok(entry)
+    }
+
+    async fn search(&self, filter: &AuditFilter, limit: i64) -> Result<Vec<AuditEntry>, AppError> {
+        Ok(self.tables().audit_log.iter()
+            .rev()
+            .filter(|entry| filter.actor_id.map_or(true, |actor_id| entry.actor_id == Some(actor_id)))
+            .filter(|entry| filter.action.map_or(true, |action| entry.action == action.as_str()))
+            .filter(|entry| filter.target_type.map_or(true, |target_type| entry.target_type == target_type.as_str()))
+            .filter(|entry| filter.target_id.map_or(true, |target_id| entry.target_id == target_id))
+            .filter(|entry| filter.request_id.map_or(true, |request_id| entry.request_id == request_id))
+            .filter(|entry| filter.since.map_or(true, |since| entry.created_at >= since))
+            .filter(|entry| filter.until.map_or(true, |until| entry.created_at < until))
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+}
+
+/// Every request sees the same store, and transactions share it too
+impl Stores for Arc<MemoryStore> {
+    fn users(&self, _db: &Db) -> Arc<dyn UserStore> {
+        self.clone()
+    }
+
+    fn answers(&self, _db: &Db) -> Arc<dyn AnswerStore> {
+        self.clone()
+    }
+
+    fn questions(&self, _db: &Db) -> Arc<dyn QuestionStore> {
+        self.clone()
+    }
+
+    fn bands(&self, _db: &Db) -> Arc<dyn BandStore> {
+        self.clone()
+    }
+
+    fn sessions(&self, _db: &Db) -> Arc<dyn SessionStore> {
+        self.clone()
+    }
+
+    fn review_states(&self, _db: &Db) -> Arc<dyn ReviewStateStore> {
+        self.clone()
+    }
+
+    fn media(&self, _db: &Db) -> Arc<dyn MediaStore> {
+        self.clone()
+    }
+
+    fn tags(&self, _db: &Db) -> Arc<dyn TagStore> {
+        self.clone()
+    }
+
+    fn search(&self, _db: &Db) -> Arc<dyn SearchStore> {
+        self.clone()
+    }
+
+    fn quizzes(&self, _db: &Db) -> Arc<dyn QuizStore> {
+        self.clone()
+    }
+
+    fn attempts(&self, _db: &Db) -> Arc<dyn AttemptStore> {
+        self.clone()
+    }
+
+    fn scoring_rules(&self, _db: &Db) -> Arc<dyn ScoringRulesStore> {
+        self.clone()
+    }
+
+    fn notifications(&self, _db: &Db) -> Arc<dyn NotificationStore> {
+        self.clone()
+    }
+
+    fn revisions(&self, _db: &Db) -> Arc<dyn RevisionStore> {
+        self.clone()
+    }
+
+    fn reports(&self, _db: &Db) -> Arc<dyn ReportStore> {
+        self.clone()
+    }
+
+    fn audit_log(&self, _db: &Db) -> Arc<dyn AuditStore> {
+        self.clone()
+    }
+
+    /// Memory has no transactions to begin, so writes aren't rolled back
+    fn uses_db(&self) -> bool {
+        false
+    }
+}
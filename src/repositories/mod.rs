@@ -1,3 +1,20 @@
 pub mod user;
 pub mod answer;
-pub mod question;
\ No newline at end of file
+pub mod question;
+pub mod session;
+pub mod review_state;
+pub mod media;
+pub mod tag;
+pub mod search;
+pub mod band;
+pub mod quiz;
+pub mod attempt;
+pub mod scoring_rules;
+pub mod notification;
+pub mod revision;
+pub mod report;
+pub mod audit;
+pub mod db;
+pub mod stores;
+#[cfg(test)]
+pub mod memory;
\ No newline at end of file
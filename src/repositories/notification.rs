@@ -0,0 +1,114 @@
+use crate::repositories::{db::{Db, DbClient}, stores::NotificationStore};
+use slog_scope::error;
+use crate::models::notification::{Notification, NotificationKind};
+use tokio_pg_mapper::FromTokioPostgresRow;
+use crate::errors::{AppError, AppErrorType};
+use uuid::Uuid;
+use async_trait::async_trait;
+
+pub struct NotificationRepository {
+    pool: Db
+}
+
+impl NotificationRepository {
+
+    pub fn new(pool: impl Into<Db>) -> NotificationRepository {
+        NotificationRepository { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl NotificationStore for NotificationRepository {
+    async fn for_user(&self, user_id: Uuid, unread_only: bool) -> Result<Vec<Notification>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "for_user");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select * from notifications
+                where user_id = $1 and (not $2 or read_at is null)
+                order by created_at desc
+            ")
+            .await?;
+
+        let notifications = client
+            .query(&statement, &[&user_id, &unread_only])
+            .await
+            .map_err(|err| {
+                error!("Error getting notifications. {}", err; "query" => "for_user");
+                err
+            })?
+            .iter()
+            .map(|row| Notification::from_row_ref(row))
+            .collect::<Result<Vec<Notification>, _>>()?;
+
+        Ok(notifications)
+    }
+
+    async fn create(&self, user_id: Uuid, kind: NotificationKind, message: &str, question_id: Option<Uuid>) -> Result<Notification, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "create");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                insert into notifications (user_id, kind, message, question_id)
+                values ($1, $2, $3, $4)
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[&user_id, &kind.as_str(), &message, &question_id])
+            .await
+            .map_err(|err| {
+                error!("Error creating notification. {}", err; "query" => "create");
+                err
+            })?
+            .iter()
+            .map(|row| Notification::from_row_ref(row))
+            .collect::<Result<Vec<Notification>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error creating Notification.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })
+    }
+
+    async fn mark_read(&self, user_id: Uuid, ids: Option<&[Uuid]>) -> Result<i32, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "mark_read");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update notifications set read_at = current_timestamp
+                where user_id = $1 and read_at is null and ($2::uuid[] is null or id = any($2))
+            ")
+            .await?;
+
+        let count = client
+            .execute(&statement, &[&user_id, &ids])
+            .await
+            .map_err(|err| {
+                error!("Error marking notifications read. {}", err; "query" => "mark_read");
+                err
+            })?;
+
+        Ok(count as i32)
+    }
+}
@@ -1,7 +1,18 @@
-use deadpool_postgres::{Pool, Client};
-use std::{collections::HashMap, sync::Arc};
+use crate::repositories::{db::{self, Db, DbClient}, stores::QuestionStore};
+use std::collections::HashMap;
 use slog_scope::{error, info};
-use crate::models::question::{Question, CreateQuestion};
+use crate::models::{
+    answer::{Answer, AnswerAlias},
+    question::{
+        Question, CreateQuestion, CreateOrderingQuestion, CreateMatchingQuestion,
+        QuestionKind, QuestionStatus, NumericCurve, ScoringRule,
+    },
+    question_choice::{shuffled, QuestionChoice},
+    revision::QuestionSnapshot,
+    tag::normalize_tags,
+    duplicate::{duplicate_error, DuplicatePair, SimilarQuestion, DUPLICATE_THRESHOLD},
+};
+use crate::grading::AnswerKey;
 use tokio_pg_mapper::FromTokioPostgresRow;
 use crate::errors::{AppError, AppErrorType};
 use tokio_postgres::error::{Error, SqlState};
@@ -10,28 +21,386 @@ use async_trait::async_trait;
 use dataloader::{BatchFn, cached::Loader};
 
 pub struct QuestionRepository {
-    pool: Arc<Pool>,
+    pool: Db,
 }
 
 pub struct QuestionBatcher {
-    pool: Arc<Pool>,
+    pool: Db,
 }
 
 pub type QuestionLoader = Loader<Uuid, Vec<Question>, AppError, QuestionBatcher>;
 
-pub fn get_question_loader(pool: Arc<Pool>) -> QuestionLoader {
-    Loader::new(QuestionBatcher { pool })
+pub(crate) fn invalid_question(message: &str) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(message.to_string()),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub(crate) fn question_references_missing(band_id: Uuid, cause: Option<String>) -> AppError {
+    AppError {
+        cause,
+        message: Some(format!("band with id {}, or the referenced answer or media, doesn't exist.", band_id)),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub(crate) fn edit_references_missing(cause: Option<String>) -> AppError {
+    AppError {
+        cause,
+        message: Some("the referenced answer or media doesn't exist.".to_string()),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub(crate) fn band_answer_missing(answer_id: Uuid, band_id: Uuid) -> AppError {
+    invalid_question(&format!("answer with id {} doesn't exist for band {}.", answer_id, band_id))
+}
+
+/// Someone else changed the question since the version the edit started from
+pub(crate) fn question_changed() -> AppError {
+    AppError {
+        cause: None,
+        message: None,
+        error_type: AppErrorType::ConflictError
+    }
+}
+
+pub(crate) fn question_not_pending() -> AppError {
+    invalid_question("question isn't waiting for moderation.")
+}
+
+pub(crate) fn question_not_changes_requested() -> AppError {
+    invalid_question("question isn't waiting for changes.")
+}
+
+pub(crate) fn question_not_deleted() -> AppError {
+    AppError {
+        cause: None,
+        message: Some("question isn't deleted, or its band is.".to_string()),
+        error_type: AppErrorType::NotFoundError
+    }
+}
+
+/// Checks that the fields needed to grade the question's kind are present
+pub(crate) fn validate_question(input: &CreateQuestion) -> Result<(), AppError> {
+    match input.kind.unwrap_or(QuestionKind::Choice) {
+        QuestionKind::Choice | QuestionKind::FreeText if input.correct_answer_id.is_none() =>
+            Err(invalid_question("correct_answer_id is required for this kind of question.")),
+        QuestionKind::Numeric if input.numeric_value.is_none() =>
+            Err(invalid_question("numeric_value is required for numeric questions.")),
+        QuestionKind::Ordering | QuestionKind::Matching =>
+            Err(invalid_question("use createOrderingQuestion or createMatchingQuestion for this kind of question.")),
+        QuestionKind::FreeText | QuestionKind::Numeric if input.distractor_ids.is_some() =>
+            Err(invalid_question("distractors only apply to choice questions.")),
+        _ if input.max_edit_distance.map_or(false, |distance| distance < 0) =>
+            Err(invalid_question("max_edit_distance must not be negative.")),
+        _ if input.numeric_tolerance.map_or(false, |tolerance| !(tolerance >= 0.0)) =>
+            Err(invalid_question("numeric_tolerance must not be negative.")),
+        _ if input.media_id.is_none() && (input.clip_start_ms.is_some() || input.clip_end_ms.is_some()) =>
+            Err(invalid_question("clip times need an audio media_id.")),
+        _ if input.clip_start_ms.map_or(false, |start| start < 0) =>
+            Err(invalid_question("clip_start_ms must not be negative.")),
+        _ if input.clip_end_ms.map_or(false, |end| end <= input.clip_start_ms.unwrap_or(0)) =>
+            Err(invalid_question("clip_end_ms must be after clip_start_ms.")),
+        _ => Ok(())
+    }
+}
+
+/// Ordering and matching questions, and choice questions with distractors,
+/// need at least two distinct answers
+pub(crate) fn validate_choices(answer_ids: &[Uuid]) -> Result<(), AppError> {
+    let mut distinct = answer_ids.to_vec();
+    distinct.sort();
+    distinct.dedup();
+
+    if answer_ids.len() < 2 {
+        Err(invalid_question("at least two answers are required."))
+    } else if distinct.len() != answer_ids.len() {
+        Err(invalid_question("each answer can only be used once."))
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks an edited question the way creating it would have
+pub(crate) fn validate_snapshot(snapshot: &QuestionSnapshot) -> Result<(), AppError> {
+    let kind = snapshot.kind.parse::<QuestionKind>().unwrap_or(QuestionKind::Choice);
+    let answer_ids: Vec<Uuid> = snapshot.choices.iter().map(|choice| choice.answer_id).collect();
+
+    match kind {
+        QuestionKind::Choice | QuestionKind::FreeText if snapshot.correct_answer_id.is_none() =>
+            return Err(invalid_question("correct_answer_id is required for this kind of question.")),
+        QuestionKind::Numeric if snapshot.numeric_value.is_none() =>
+            return Err(invalid_question("numeric_value is required for numeric questions.")),
+        QuestionKind::Choice if !answer_ids.is_empty() => validate_choices(&answer_ids)?,
+        QuestionKind::Ordering => validate_choices(&answer_ids)?,
+        QuestionKind::Matching => {
+            validate_choices(&answer_ids)?;
+            let match_answer_ids: Vec<Uuid> = snapshot.choices.iter().filter_map(|choice| choice.match_answer_id).collect();
+            if match_answer_ids.len() != answer_ids.len() {
+                return Err(invalid_question("every answer of a matching question needs a match."));
+            }
+            validate_choices(&match_answer_ids)?;
+        },
+        _ => (),
+    }
+
+    match snapshot.scoring_rule.parse::<ScoringRule>() {
+        Ok(ScoringRule::Pairwise) if kind != QuestionKind::Ordering =>
+            return Err(invalid_question("pairwise scoring only applies to ordering questions.")),
+        Ok(ScoringRule::PerItem) if kind != QuestionKind::Ordering && kind != QuestionKind::Matching =>
+            return Err(invalid_question("partial credit only applies to ordering and matching questions.")),
+        _ => (),
+    }
+
+    match kind {
+        _ if snapshot.max_edit_distance < 0 =>
+            Err(invalid_question("max_edit_distance must not be negative.")),
+        _ if !(snapshot.numeric_tolerance >= 0.0) =>
+            Err(invalid_question("numeric_tolerance must not be negative.")),
+        _ if snapshot.media_id.is_none() && (snapshot.clip_start_ms.is_some() || snapshot.clip_end_ms.is_some()) =>
+            Err(invalid_question("clip times need an audio media_id.")),
+        _ if snapshot.clip_start_ms.map_or(false, |start| start < 0) =>
+            Err(invalid_question("clip_start_ms must not be negative.")),
+        _ if snapshot.clip_end_ms.map_or(false, |end| end <= snapshot.clip_start_ms.unwrap_or(0)) =>
+            Err(invalid_question("clip_end_ms must be after clip_start_ms.")),
+        _ => Ok(())
+    }
+}
+
+pub fn get_question_loader(pool: impl Into<Db>) -> QuestionLoader {
+    Loader::new(QuestionBatcher { pool: pool.into() })
         .with_yield_count(100)
 }
 
 impl QuestionRepository {
 
-    pub fn new(pool: Arc<Pool>) -> QuestionRepository {
-        QuestionRepository { pool }
+    pub fn new(pool: impl Into<Db>) -> QuestionRepository {
+        QuestionRepository { pool: pool.into() }
+    }
+
+    async fn insert(&self, input: CreateQuestion, scoring_rule: ScoringRule, submitted_by: Option<Uuid>) -> Result<Question, AppError> {
+        if !input.force.unwrap_or(false) {
+            // Fans submitting a question only get to see questions that are already public
+            let similar = self.similar(input.band_id, &input.content, DUPLICATE_THRESHOLD, submitted_by.is_some()).await?;
+            if !similar.is_empty() {
+                return Err(duplicate_error(similar));
+            }
+        }
+
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "create");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                insert into questions
+                    (content, band_id, correct_answer_id, kind, max_edit_distance, numeric_value, numeric_tolerance, numeric_curve, scoring_rule,
+                     media_id, clip_start_ms, clip_end_ms, hint, explanation, source, status, submitted_by)
+                values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+                returning *
+            ")
+            .await?;
+
+        let kind = input.kind.unwrap_or(QuestionKind::Choice);
+        let max_edit_distance = input.max_edit_distance.unwrap_or(1);
+        let numeric_tolerance = input.numeric_tolerance.unwrap_or(0.0);
+        let numeric_curve = input.numeric_curve.unwrap_or(NumericCurve::Linear);
+        let status = if submitted_by.is_some() { QuestionStatus::Pending } else { QuestionStatus::Approved };
+
+        let question = client.query(&statement, &[
+                &input.content,
+                &input.band_id,
+                &input.correct_answer_id,
+                &kind.as_str(),
+                &max_edit_distance,
+                &input.numeric_value,
+                &numeric_tolerance,
+                &numeric_curve.as_str(),
+                &scoring_rule.as_str(),
+                &input.media_id,
+                &input.clip_start_ms,
+                &input.clip_end_ms,
+                &input.hint,
+                &input.explanation,
+                &input.source,
+                &status.as_str(),
+                &submitted_by,
+            ])
+            .await
+            .map_err(|err: Error| {
+                match err.code() {
+                    Some(code) => match code {
+                        c if c == &SqlState::UNIQUE_VIOLATION => AppError {
+                            cause: Some(err.to_string()),
+                            message: Some(format!("question {} already exists.", &input.content)),
+                            error_type: AppErrorType::InvalidField
+                        },
+                        c if c == &SqlState::FOREIGN_KEY_VIOLATION => question_references_missing(input.band_id, Some(err.to_string())),
+                        c if c == &SqlState::CHECK_VIOLATION => AppError {
+                            cause: Some(err.to_string()),
+                            message: Some("question fields don't match its kind.".to_string()),
+                            error_type: AppErrorType::InvalidField
+                        },
+                        _ => AppError::from(err)
+                    }
+                    _ => AppError::from(err)
+                }
+            })?
+            .iter()
+            .map(|row| Question::from_row_ref(row))
+            .collect::<Result<Vec<Question>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error creating Question.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })?;
+
+        Ok(question)
+    }
+
+    /// Questions can only use their own band's answers
+    /// Inserts the question and its choices, in order, in one transaction
+    async fn insert_with_choices(&self, input: CreateQuestion, scoring_rule: ScoringRule, submitted_by: Option<Uuid>, choices: Vec<(Uuid, Option<Uuid>)>) -> Result<Question, AppError> {
+        db::transaction(&self.pool, |db| async move {
+            let repository = QuestionRepository::new(db);
+            let question = repository.insert(input, scoring_rule, submitted_by).await?;
+
+            for (position, (answer_id, match_answer_id)) in choices.into_iter().enumerate() {
+                repository.insert_choice(question.id, answer_id, position as i32, match_answer_id).await?;
+            }
+
+            Ok(question)
+        }).await
+    }
+
+    async fn check_band_answers(&self, band_id: Uuid, answer_ids: &[Uuid]) -> Result<(), AppError> {
+        if answer_ids.is_empty() {
+            return Ok(());
+        }
+
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "check_band_answers");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select id from answers where band_id = $1 and id = any($2)")
+            .await?;
+
+        let found: Vec<Uuid> = client
+            .query(&statement, &[&band_id, &answer_ids])
+            .await
+            .map_err(|err| {
+                error!("Error getting answers. {}", err; "query" => "check_band_answers");
+                err
+            })?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+
+        match answer_ids.iter().find(|id| !found.contains(id)) {
+            Some(id) => Err(band_answer_missing(*id, band_id)),
+            None => Ok(()),
+        }
+    }
+
+    async fn insert_choice(&self, question_id: Uuid, answer_id: Uuid, position: i32, match_answer_id: Option<Uuid>) -> Result<QuestionChoice, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "insert_choice");
+                err
+            })?;
+
+        let statement = client
+            .prepare("insert into question_choices (question_id, answer_id, position, match_answer_id) values ($1, $2, $3, $4) returning *")
+            .await?;
+
+        let choice = client.query(&statement, &[
+                &question_id,
+                &answer_id,
+                &position,
+                &match_answer_id,
+            ])
+            .await
+            .map_err(|err: Error| {
+                let foreign_key_error = err.code()
+                    .map(|code| code == &SqlState::FOREIGN_KEY_VIOLATION);
+
+                match foreign_key_error {
+                    Some(true) => AppError {
+                        cause: Some(err.to_string()),
+                        message: Some("choice references an answer that doesn't exist.".to_string()),
+                        error_type: AppErrorType::InvalidField
+                        },
+                    _ => AppError::from(err)
+                }
+            })?
+            .iter()
+            .map(|row| QuestionChoice::from_row_ref(row))
+            .collect::<Result<Vec<QuestionChoice>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error creating QuestionChoice.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })?;
+
+        Ok(choice)
+    }
+
+    /// Removes questions deleted longer ago than the retention period. Questions played in attempts are kept for their history.
+    pub async fn purge_deleted(&self, retention_days: i32) -> Result<u64, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "purge_deleted");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                with purged as (
+                    select q.id from questions q
+                    where q.deleted_at < current_timestamp - make_interval(days => $1)
+                        and not exists (select 1 from attempt_questions where question_id = q.id)
+                ),
+                quiz_questions as (
+                    delete from quiz_questions where question_id in (select id from purged)
+                )
+                delete from questions where id in (select id from purged)
+            ")
+            .await?;
+
+        let count = client
+            .execute(&statement, &[&retention_days])
+            .await
+            .map_err(|err| {
+                error!("Error purging questions. {}", err; "query" => "purge_deleted");
+                err
+            })?;
+
+        Ok(count)
     }
+}
 
-    pub async fn get(&self, id: Uuid) -> Result<Question, AppError> {
-        let client: Client = self.pool
+#[async_trait]
+impl QuestionStore for QuestionRepository {
+    async fn get(&self, id: Uuid) -> Result<Question, AppError> {
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
@@ -39,7 +408,7 @@ impl QuestionRepository {
                 err
             })?;
 
-        let statement = client.prepare("select * from questions where id = $1").await?;
+        let statement = client.prepare("select * from questions where id = $1 and deleted_at is null").await?;
 
         client
             .query(&statement, &[&id])
@@ -59,8 +428,8 @@ impl QuestionRepository {
             })
     }
 
-    pub async fn all(&self) -> Result<Vec<Question>, AppError> {
-        let client: Client = self.pool
+    async fn all(&self) -> Result<Vec<Question>, AppError> {
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
@@ -68,7 +437,7 @@ impl QuestionRepository {
                 err
             })?;
 
-        let statement = client.prepare("select * from questions").await?;
+        let statement = client.prepare("select * from questions where status = 'approved' and hidden_at is null and deleted_at is null").await?;
 
         let users = client
             .query(&statement, &[])
@@ -88,9 +457,52 @@ impl QuestionRepository {
         Ok(users)
     }
 
-    #[allow(dead_code)]
-    pub async fn get_for_band(&self, user_id: Uuid) -> Result<Vec<Question>, AppError> {
-        let client: Client = self.pool
+    async fn find(&self, band_id: Option<Uuid>, tags: &[String]) -> Result<Vec<Question>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "find");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select q.* from questions q
+                where ($1::uuid is null or q.band_id = $1)
+                    and q.status = 'approved' and q.hidden_at is null and q.deleted_at is null
+                    and (
+                        select count(*) from question_tags qt
+                        join tags t on t.id = qt.tag_id
+                        where qt.question_id = q.id and t.name = any($2)
+                    ) = $3
+                order by q.created_at
+            ")
+            .await?;
+
+        let tags = normalize_tags(tags);
+        let tag_count = tags.len() as i64;
+
+        let questions = client
+            .query(&statement, &[&band_id, &tags, &tag_count])
+            .await
+            .map_err(|err| {
+                error!("Error getting questions. {}", err; "query" => "find");
+                err
+            })?
+            .iter()
+            .map(|row| Question::from_row_ref(row))
+            .collect::<Result<Vec<Question>, _>>()
+            .map_err(|err| {
+                error!("Error getting parsing questions. {}", err; "query" => "find");
+                err
+            })?;
+
+        Ok(questions)
+    }
+
+    async fn get_for_band(&self, user_id: Uuid) -> Result<Vec<Question>, AppError> {
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
@@ -98,7 +510,7 @@ impl QuestionRepository {
                 err
             })?;
 
-        let statement = client.prepare("select * from questions where band_id = $1").await?;
+        let statement = client.prepare("select * from questions where band_id = $1 and deleted_at is null order by created_at").await?;
 
         let users = client
             .query(&statement, &[&user_id])
@@ -118,40 +530,403 @@ impl QuestionRepository {
         Ok(users)
     }
 
-    pub async fn create(&self, input: CreateQuestion) -> Result<Question, AppError> {
-        let client: Client = self.pool
+    async fn with_correct_answer(&self, answer_id: Uuid) -> Result<Vec<Question>, AppError> {
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
-                error!("Error getting client {}", err; "query" => "create");
+                error!("Error getting client {}", err; "query" => "with_correct_answer");
                 err
             })?;
 
         let statement = client
-            .prepare("insert into questions (author_id, slug, title, description, body) values ($1, $2, $3, $4, $5) returning *")
+            .prepare("select * from questions where correct_answer_id = $1 and deleted_at is null order by created_at")
             .await?;
 
-        let question = client.query(&statement, &[
-                &input.content,
-                &input.band_id,
-                &input.correct_answer_id,
+        let questions = client
+            .query(&statement, &[&answer_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting questions. {}", err; "query" => "with_correct_answer");
+                err
+            })?
+            .iter()
+            .map(|row| Question::from_row_ref(row))
+            .collect::<Result<Vec<Question>, _>>()?;
+
+        Ok(questions)
+    }
+
+    async fn create(&self, input: CreateQuestion, submitted_by: Option<Uuid>) -> Result<Question, AppError> {
+        validate_question(&input)?;
+
+        let options: Vec<Uuid> = match &input.distractor_ids {
+            Some(distractor_ids) => input.correct_answer_id.iter()
+                .chain(distractor_ids.iter())
+                .cloned()
+                .collect(),
+            None => vec![],
+        };
+        if !options.is_empty() {
+            validate_choices(&options)?;
+        }
+
+        let answer_ids: Vec<Uuid> = input.correct_answer_id.iter().chain(options.iter()).cloned().collect();
+        self.check_band_answers(input.band_id, &answer_ids).await?;
+
+        let choices = shuffled(options).into_iter().map(|answer_id| (answer_id, None)).collect();
+        self.insert_with_choices(input, ScoringRule::AllOrNothing, submitted_by, choices).await
+    }
+
+    async fn similar(&self, band_id: Uuid, content: &str, threshold: f64, approved_only: bool) -> Result<Vec<SimilarQuestion>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "similar");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select id, content, similarity(content, $2)::float8 as similarity
+                from questions
+                where band_id = $1 and deleted_at is null and similarity(content, $2)::float8 >= $3
+                    and (not $4 or (status = 'approved' and hidden_at is null))
+                order by similarity desc, content
+                limit 5
+            ")
+            .await?;
+
+        let similar = client
+            .query(&statement, &[&band_id, &content, &threshold, &approved_only])
+            .await
+            .map_err(|err| {
+                error!("Error getting similar questions. {}", err; "query" => "similar");
+                err
+            })?
+            .iter()
+            .map(|row| SimilarQuestion {
+                id: row.get("id"),
+                content: row.get("content"),
+                similarity: row.get("similarity"),
+            })
+            .collect();
+
+        Ok(similar)
+    }
+
+    async fn duplicate_pairs(&self, band_id: Option<Uuid>, threshold: f64) -> Result<Vec<DuplicatePair>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "duplicate_pairs");
+                err
+            })?;
+
+        // Not `%`, its limit would have to be set on the pooled connection and outlive the query
+        let statement = client
+            .prepare("
+                select a.band_id,
+                    a.id as first_id, a.content as first_content,
+                    b.id as second_id, b.content as second_content,
+                    similarity(a.content, b.content)::float8 as similarity
+                from questions a
+                join questions b on b.band_id = a.band_id and a.id < b.id
+                where ($1::uuid is null or a.band_id = $1)
+                    and a.deleted_at is null and b.deleted_at is null
+                    and similarity(a.content, b.content)::float8 >= $2
+            ")
+            .await?;
+
+        let pairs = client
+            .query(&statement, &[&band_id, &threshold])
+            .await
+            .map_err(|err| {
+                error!("Error getting duplicate questions. {}", err; "query" => "duplicate_pairs");
+                err
+            })?
+            .iter()
+            .map(|row| {
+                let similarity: f64 = row.get("similarity");
+                DuplicatePair {
+                    band_id: row.get("band_id"),
+                    first: SimilarQuestion { id: row.get("first_id"), content: row.get("first_content"), similarity },
+                    second: SimilarQuestion { id: row.get("second_id"), content: row.get("second_content"), similarity },
+                    similarity,
+                }
+            })
+            .collect();
+
+        Ok(pairs)
+    }
+
+    async fn moderation_queue(&self, band_id: Uuid) -> Result<Vec<Question>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "moderation_queue");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select * from questions where band_id = $1 and status = 'pending' and deleted_at is null order by created_at")
+            .await?;
+
+        let questions = client
+            .query(&statement, &[&band_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting moderation queue. {}", err; "query" => "moderation_queue");
+                err
+            })?
+            .iter()
+            .map(|row| Question::from_row_ref(row))
+            .collect::<Result<Vec<Question>, _>>()?;
+
+        Ok(questions)
+    }
+
+    async fn moderate(&self, question_id: Uuid, status: QuestionStatus, reason: Option<&str>, moderated_by: Uuid) -> Result<Question, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "moderate");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update questions set
+                    status = $2,
+                    moderation_reason = $3,
+                    moderated_by = $4,
+                    moderated_at = current_timestamp
+                where id = $1 and status = 'pending'
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[&question_id, &status.as_str(), &reason, &moderated_by])
+            .await
+            .map_err(|err| {
+                error!("Error moderating question. {}", err; "query" => "moderate");
+                err
+            })?
+            .iter()
+            .map(|row| Question::from_row_ref(row))
+            .collect::<Result<Vec<Question>, _>>()?
+            .pop()
+            .ok_or_else(question_not_pending)
+    }
+
+    async fn answer_key(&self, question: &Question) -> Result<AnswerKey, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "answer_key");
+                err
+            })?;
+
+        let statement = client.prepare("select * from answers where id = $1").await?;
+
+        let correct_answer = client
+            .query(&statement, &[&question.correct_answer_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting answers. {}", err; "query" => "answer_key");
+                err
+            })?
+            .iter()
+            .map(|row| Answer::from_row_ref(row))
+            .collect::<Result<Vec<Answer>, _>>()?
+            .pop();
+
+        let statement = client.prepare("select * from answer_aliases where answer_id = $1").await?;
+
+        let aliases = client
+            .query(&statement, &[&question.correct_answer_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting answer aliases. {}", err; "query" => "answer_key");
+                err
+            })?
+            .iter()
+            .map(|row| AnswerAlias::from_row_ref(row).map(|alias| alias.content))
+            .collect::<Result<Vec<String>, _>>()?;
+
+        let choices = self.choices(question.id).await?;
+
+        Ok(AnswerKey { correct_answer, aliases, choices })
+    }
+
+    async fn choices(&self, question_id: Uuid) -> Result<Vec<QuestionChoice>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "choices");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select * from question_choices where question_id = $1 order by position")
+            .await?;
+
+        let choices = client
+            .query(&statement, &[&question_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting question choices. {}", err; "query" => "choices");
+                err
+            })?
+            .iter()
+            .map(|row| QuestionChoice::from_row_ref(row))
+            .collect::<Result<Vec<QuestionChoice>, _>>()
+            .map_err(|err| {
+                error!("Error getting parsing question choices. {}", err; "query" => "choices");
+                err
+            })?;
+
+        Ok(choices)
+    }
+
+    async fn create_ordering(&self, input: CreateOrderingQuestion, submitted_by: Option<Uuid>) -> Result<Question, AppError> {
+        let scoring_rule = input.scoring_rule.unwrap_or(ScoringRule::PerItem);
+        validate_choices(&input.answer_ids)?;
+        self.check_band_answers(input.band_id, &input.answer_ids).await?;
+
+        let choices = input.answer_ids.iter().map(|answer_id| (*answer_id, None)).collect();
+        self.insert_with_choices(CreateQuestion {
+            content: input.content,
+            correct_answer_id: None,
+            distractor_ids: None,
+            band_id: input.band_id,
+            kind: Some(QuestionKind::Ordering),
+            max_edit_distance: None,
+            numeric_value: None,
+            numeric_tolerance: None,
+            numeric_curve: None,
+            media_id: input.media_id,
+            clip_start_ms: None,
+            clip_end_ms: None,
+            hint: input.hint,
+            explanation: input.explanation,
+            source: input.source,
+            tags: None,
+            force: input.force,
+        }, scoring_rule, submitted_by, choices).await
+    }
+
+    async fn create_matching(&self, input: CreateMatchingQuestion, submitted_by: Option<Uuid>) -> Result<Question, AppError> {
+        let scoring_rule = input.scoring_rule.unwrap_or(ScoringRule::PerItem);
+        if scoring_rule == ScoringRule::Pairwise {
+            return Err(invalid_question("pairwise scoring only applies to ordering questions."));
+        }
+
+        let answer_ids: Vec<Uuid> = input.pairs.iter().map(|pair| pair.answer_id).collect();
+        let match_answer_ids: Vec<Uuid> = input.pairs.iter().map(|pair| pair.match_answer_id).collect();
+        validate_choices(&answer_ids)?;
+        validate_choices(&match_answer_ids)?;
+        self.check_band_answers(input.band_id, &answer_ids).await?;
+        self.check_band_answers(input.band_id, &match_answer_ids).await?;
+
+        let choices = input.pairs.iter().map(|pair| (pair.answer_id, Some(pair.match_answer_id))).collect();
+        self.insert_with_choices(CreateQuestion {
+            content: input.content,
+            correct_answer_id: None,
+            distractor_ids: None,
+            band_id: input.band_id,
+            kind: Some(QuestionKind::Matching),
+            max_edit_distance: None,
+            numeric_value: None,
+            numeric_tolerance: None,
+            numeric_curve: None,
+            media_id: input.media_id,
+            clip_start_ms: None,
+            clip_end_ms: None,
+            hint: input.hint,
+            explanation: input.explanation,
+            source: input.source,
+            tags: None,
+            force: input.force,
+        }, scoring_rule, submitted_by, choices).await
+    }
+
+    async fn snapshot(&self, question: &Question) -> Result<QuestionSnapshot, AppError> {
+        let choices = self.choices(question.id).await?;
+        Ok(QuestionSnapshot::new(question, &choices))
+    }
+
+    async fn validate_edit(&self, band_id: Uuid, snapshot: &QuestionSnapshot) -> Result<(), AppError> {
+        validate_snapshot(snapshot)?;
+        self.check_band_answers(band_id, &snapshot.answer_ids()).await
+    }
+
+    async fn apply(&self, question: &Question, snapshot: &QuestionSnapshot, version: i32) -> Result<Question, AppError> {
+        self.validate_edit(question.band_id, snapshot).await?;
+
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "apply");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update questions set
+                    content = $2,
+                    correct_answer_id = $3,
+                    max_edit_distance = $4,
+                    numeric_value = $5,
+                    numeric_tolerance = $6,
+                    numeric_curve = $7,
+                    scoring_rule = $8,
+                    media_id = $9,
+                    clip_start_ms = $10,
+                    clip_end_ms = $11,
+                    hint = $12,
+                    explanation = $13,
+                    source = $14
+                where id = $1 and version = $15
+                returning *
+            ")
+            .await?;
+
+        let updated = client
+            .query(&statement, &[
+                &question.id,
+                &snapshot.content,
+                &snapshot.correct_answer_id,
+                &snapshot.max_edit_distance,
+                &snapshot.numeric_value,
+                &snapshot.numeric_tolerance,
+                &snapshot.numeric_curve,
+                &snapshot.scoring_rule,
+                &snapshot.media_id,
+                &snapshot.clip_start_ms,
+                &snapshot.clip_end_ms,
+                &snapshot.hint,
+                &snapshot.explanation,
+                &snapshot.source,
+                &version,
             ])
             .await
             .map_err(|err: Error| {
                 match err.code() {
-                    Some(code) => match code {
-                        c if c == &SqlState::UNIQUE_VIOLATION => AppError {
-                            cause: Some(err.to_string()),
-                            message: Some(format!("question {} already exists.", &input.content)),
-                            error_type: AppErrorType::InvalidField
-                        },
-                        c if c == &SqlState::FOREIGN_KEY_VIOLATION=> AppError {
-                            cause: Some(err.to_string()),
-                            message: Some(format!("band with id {} doesn't exists.", &input.band_id)),
-                            error_type: AppErrorType::InvalidField
-                        },
-                        _ => AppError::from(err)
-                    }
+                    Some(code) if code == &SqlState::FOREIGN_KEY_VIOLATION => edit_references_missing(Some(err.to_string())),
+                    Some(code) if code == &SqlState::CHECK_VIOLATION => AppError {
+                        cause: Some(err.to_string()),
+                        message: Some("question fields don't match its kind.".to_string()),
+                        error_type: AppErrorType::InvalidField
+                    },
                     _ => AppError::from(err)
                 }
             })?
@@ -159,19 +934,156 @@ impl QuestionRepository {
             .map(|row| Question::from_row_ref(row))
             .collect::<Result<Vec<Question>, _>>()?
             .pop()
+            .ok_or_else(question_changed)?;
+
+        let statement = client
+            .prepare("delete from question_choices where question_id = $1")
+            .await?;
+
+        client
+            .execute(&statement, &[&question.id])
+            .await
+            .map_err(|err| {
+                error!("Error deleting question choices. {}", err; "query" => "apply");
+                err
+            })?;
+
+        for (position, choice) in snapshot.choices.iter().enumerate() {
+            self.insert_choice(question.id, choice.answer_id, position as i32, choice.match_answer_id).await?;
+        }
+
+        Ok(updated)
+    }
+
+    async fn resubmit(&self, question_id: Uuid) -> Result<Question, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "resubmit");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update questions set status = 'pending'
+                where id = $1 and status = 'changes_requested'
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[&question_id])
+            .await
+            .map_err(|err| {
+                error!("Error resubmitting question. {}", err; "query" => "resubmit");
+                err
+            })?
+            .iter()
+            .map(|row| Question::from_row_ref(row))
+            .collect::<Result<Vec<Question>, _>>()?
+            .pop()
+            .ok_or_else(question_not_changes_requested)
+    }
+
+    async fn get_any(&self, id: Uuid) -> Result<Question, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "get_any");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select * from questions where id = $1")
+            .await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error getting question. {}", err; "query" => "get_any");
+                err
+            })?
+            .iter()
+            .map(|row| Question::from_row_ref(row))
+            .collect::<Result<Vec<Question>, _>>()?
+            .pop()
             .ok_or(AppError {
-                message: Some("Error creating Question.".to_string()),
-                cause: Some("Unknown error.".to_string()),
-                error_type: AppErrorType::DbError,
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+
+    async fn soft_delete(&self, id: Uuid) -> Result<Question, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "soft_delete");
+                err
             })?;
 
-        Ok(question)
+        let statement = client
+            .prepare("update questions set deleted_at = current_timestamp where id = $1 and deleted_at is null returning *")
+            .await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error deleting question. {}", err; "query" => "soft_delete");
+                err
+            })?
+            .iter()
+            .map(|row| Question::from_row_ref(row))
+            .collect::<Result<Vec<Question>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Question, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "restore");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update questions set deleted_at = null
+                where id = $1 and deleted_at is not null
+                    and not exists (select 1 from bands where id = questions.band_id and deleted_at is not null)
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error restoring question. {}", err; "query" => "restore");
+                err
+            })?
+            .iter()
+            .map(|row| Question::from_row_ref(row))
+            .collect::<Result<Vec<Question>, _>>()?
+            .pop()
+            .ok_or_else(question_not_deleted)
     }
 }
 
 impl QuestionBatcher {
     pub async fn get_questions_by_band_ids(&self, hashmap: &mut HashMap<Uuid, Vec<Question>>, ids: Vec<Uuid>) -> Result<(), AppError> {
-        let client: Client = self.pool
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
@@ -179,7 +1091,7 @@ impl QuestionBatcher {
                 err
             })?;
 
-        let statement = client.prepare("select * from questions where author_id = ANY($1)").await?; 
+        let statement = client.prepare("select * from questions where author_id = ANY($1) and deleted_at is null").await?; 
 
         client
             .query(&statement, &[&ids])
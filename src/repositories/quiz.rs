@@ -0,0 +1,416 @@
+use crate::repositories::{db::{self, Db, DbClient}, answer::band_missing, stores::QuizStore};
+use slog_scope::error;
+use crate::models::{
+    question::Question,
+    quiz::{Quiz, QuizStatus, CreateQuiz, UpdateQuiz},
+};
+use tokio_pg_mapper::FromTokioPostgresRow;
+use crate::errors::{AppError, AppErrorType};
+use tokio_postgres::error::{Error, SqlState};
+use uuid::Uuid;
+use async_trait::async_trait;
+
+pub struct QuizRepository {
+    pool: Db
+}
+
+pub(crate) fn invalid_quiz(message: &str) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(message.to_string()),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub(crate) fn validate_settings(title: Option<&str>, time_limit_seconds: Option<i32>, seconds_per_question: Option<i32>) -> Result<(), AppError> {
+    if title.map_or(false, |title| title.trim().is_empty()) {
+        Err(invalid_quiz("title must not be empty."))
+    } else if time_limit_seconds.map_or(false, |seconds| seconds <= 0) {
+        Err(invalid_quiz("time_limit_seconds must be positive."))
+    } else if seconds_per_question.map_or(false, |seconds| seconds <= 0) {
+        Err(invalid_quiz("seconds_per_question must be positive."))
+    } else {
+        Ok(())
+    }
+}
+
+pub(crate) fn validate_lifelines(fifty_fifty_lifelines: Option<i32>, hint_lifelines: Option<i32>) -> Result<(), AppError> {
+    if fifty_fifty_lifelines.map_or(false, |count| count < 0) || hint_lifelines.map_or(false, |count| count < 0) {
+        Err(invalid_quiz("lifelines must not be negative."))
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks the question ids of a quiz before looking them up
+pub(crate) fn validate_question_ids(question_ids: &[Uuid]) -> Result<(), AppError> {
+    let mut distinct = question_ids.to_vec();
+    distinct.sort();
+    distinct.dedup();
+    if distinct.len() != question_ids.len() {
+        return Err(invalid_quiz("each question can only be used once."));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn unplayable_questions() -> AppError {
+    invalid_quiz("quiz questions must be approved questions of the quiz's band that aren't hidden.")
+}
+
+pub(crate) fn unpublishable_quiz() -> AppError {
+    invalid_quiz("a quiz needs questions to be published.")
+}
+
+pub(crate) fn quiz_played(cause: Option<String>) -> AppError {
+    AppError {
+        cause,
+        message: Some("quiz has been played, archive it instead.".to_string()),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub(crate) fn quiz_changed(version: i32) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(format!("quiz was changed since version {}.", version)),
+        error_type: AppErrorType::ConflictError
+    }
+}
+
+impl QuizRepository {
+
+    pub fn new(pool: impl Into<Db>) -> QuizRepository {
+        QuizRepository { pool: pool.into() }
+    }
+
+    async fn insert(&self, input: &CreateQuiz, created_by: Uuid, scoring_rules_id: Option<Uuid>) -> Result<Quiz, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "create");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                insert into quizzes
+                    (band_id, title, description, time_limit_seconds, scoring_rules_id, created_by, seconds_per_question,
+                     fifty_fifty_lifelines, hint_lifelines)
+                values ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                returning *
+            ")
+            .await?;
+
+        client.query(&statement, &[
+                &input.band_id,
+                &input.title.trim(),
+                &input.description,
+                &input.time_limit_seconds,
+                &scoring_rules_id,
+                &created_by,
+                &input.seconds_per_question,
+                &input.fifty_fifty_lifelines.unwrap_or(1),
+                &input.hint_lifelines.unwrap_or(1),
+            ])
+            .await
+            .map_err(|err: Error| {
+                match err.code() {
+                    Some(code) if code == &SqlState::FOREIGN_KEY_VIOLATION => band_missing(input.band_id, Some(err.to_string())),
+                    _ => AppError::from(err)
+                }
+            })?
+            .iter()
+            .map(|row| Quiz::from_row_ref(row))
+            .collect::<Result<Vec<Quiz>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error creating Quiz.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })
+    }
+
+    /// Swaps the quiz's questions for `question_ids`, which must be playable
+    async fn replace_questions(&self, quiz: &Quiz, question_ids: &[Uuid]) -> Result<(), AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "set_questions");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select count(*) from questions where band_id = $1 and id = any($2) and status = 'approved' and hidden_at is null and deleted_at is null")
+            .await?;
+
+        let found: i64 = client
+            .query_one(&statement, &[&quiz.band_id, &question_ids])
+            .await?
+            .get(0);
+
+        if found != question_ids.len() as i64 {
+            return Err(unplayable_questions());
+        }
+
+        let remove = client.prepare("delete from quiz_questions where quiz_id = $1").await?;
+        client.execute(&remove, &[&quiz.id]).await?;
+
+        let statement = client
+            .prepare("
+                insert into quiz_questions (quiz_id, question_id, position)
+                select $1, question_id, (position - 1)::int4
+                from unnest($2::uuid[]) with ordinality as ids(question_id, position)
+            ")
+            .await?;
+
+        client
+            .execute(&statement, &[&quiz.id, &question_ids])
+            .await
+            .map_err(|err| {
+                error!("Error setting quiz questions. {}", err; "query" => "set_questions");
+                err
+            })?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl QuizStore for QuizRepository {
+    async fn get(&self, id: Uuid) -> Result<Quiz, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "get");
+                err
+            })?;
+
+        let statement = client.prepare("select * from quizzes where id = $1").await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error getting quizzes. {}", err; "query" => "get");
+                err
+            })?
+            .iter()
+            .map(|row| Quiz::from_row_ref(row))
+            .collect::<Result<Vec<Quiz>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+
+    async fn get_for_band(&self, band_id: Uuid, statuses: &[QuizStatus]) -> Result<Vec<Quiz>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "get_for_band");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select * from quizzes where band_id = $1 and status = any($2) order by created_at desc")
+            .await?;
+
+        let statuses: Vec<&str> = statuses.iter().map(|status| status.as_str()).collect();
+
+        let quizzes = client
+            .query(&statement, &[&band_id, &statuses])
+            .await
+            .map_err(|err| {
+                error!("Error getting quizzes. {}", err; "query" => "get_for_band");
+                err
+            })?
+            .iter()
+            .map(|row| Quiz::from_row_ref(row))
+            .collect::<Result<Vec<Quiz>, _>>()
+            .map_err(|err| {
+                error!("Error getting parsing quizzes. {}", err; "query" => "get_for_band");
+                err
+            })?;
+
+        Ok(quizzes)
+    }
+
+    async fn create(&self, input: CreateQuiz, created_by: Uuid, scoring_rules_id: Option<Uuid>) -> Result<Quiz, AppError> {
+        validate_settings(Some(&input.title), input.time_limit_seconds, input.seconds_per_question)?;
+        validate_lifelines(input.fifty_fifty_lifelines, input.hint_lifelines)?;
+
+        db::transaction(&self.pool, |db| async move {
+            let repository = QuizRepository::new(db);
+            let quiz = repository.insert(&input, created_by, scoring_rules_id).await?;
+
+            if let Some(question_ids) = &input.question_ids {
+                repository.set_questions(&quiz, question_ids).await?;
+            }
+
+            Ok(quiz)
+        }).await
+    }
+
+    async fn update(&self, input: UpdateQuiz, scoring_rules_id: Option<Uuid>) -> Result<Quiz, AppError> {
+        validate_settings(input.title.as_deref(), input.time_limit_seconds, input.seconds_per_question)?;
+        validate_lifelines(input.fifty_fifty_lifelines, input.hint_lifelines)?;
+
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "update");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update quizzes set
+                    title = coalesce($2, title),
+                    description = coalesce($3, description),
+                    time_limit_seconds = coalesce($4, time_limit_seconds),
+                    scoring_rules_id = coalesce($5, scoring_rules_id),
+                    seconds_per_question = coalesce($6, seconds_per_question),
+                    fifty_fifty_lifelines = coalesce($7, fifty_fifty_lifelines),
+                    hint_lifelines = coalesce($8, hint_lifelines)
+                where id = $1 and version = $9
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[
+                &input.id,
+                &input.title.as_deref().map(|title| title.trim()),
+                &input.description,
+                &input.time_limit_seconds,
+                &scoring_rules_id,
+                &input.seconds_per_question,
+                &input.fifty_fifty_lifelines,
+                &input.hint_lifelines,
+                &input.version,
+            ])
+            .await
+            .map_err(|err| {
+                error!("Error updating quiz. {}", err; "query" => "update");
+                err
+            })?
+            .iter()
+            .map(|row| Quiz::from_row_ref(row))
+            .collect::<Result<Vec<Quiz>, _>>()?
+            .pop()
+            .ok_or_else(|| quiz_changed(input.version))
+    }
+
+    async fn set_status(&self, quiz: &Quiz, status: QuizStatus) -> Result<Quiz, AppError> {
+        if status == QuizStatus::Published && self.questions(quiz.id).await?.is_empty() {
+            return Err(unpublishable_quiz());
+        }
+
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "set_status");
+                err
+            })?;
+
+        let statement = client
+            .prepare("update quizzes set status = $2 where id = $1 returning *")
+            .await?;
+
+        client
+            .query(&statement, &[&quiz.id, &status.as_str()])
+            .await
+            .map_err(|err| {
+                error!("Error updating quiz status. {}", err; "query" => "set_status");
+                err
+            })?
+            .iter()
+            .map(|row| Quiz::from_row_ref(row))
+            .collect::<Result<Vec<Quiz>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "delete");
+                err
+            })?;
+
+        let statement = client.prepare("delete from quizzes where id = $1").await?;
+
+        client
+            .execute(&statement, &[&id])
+            .await
+            .map_err(|err: Error| {
+                match err.code() {
+                    Some(code) if code == &SqlState::FOREIGN_KEY_VIOLATION => quiz_played(Some(err.to_string())),
+                    _ => AppError::from(err)
+                }
+            })?;
+
+        Ok(())
+    }
+
+    async fn questions(&self, quiz_id: Uuid) -> Result<Vec<Question>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "questions");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select q.* from questions q
+                join quiz_questions qq on qq.question_id = q.id
+                where qq.quiz_id = $1 and q.deleted_at is null
+                order by qq.position
+            ")
+            .await?;
+
+        let questions = client
+            .query(&statement, &[&quiz_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting quiz questions. {}", err; "query" => "questions");
+                err
+            })?
+            .iter()
+            .map(|row| Question::from_row_ref(row))
+            .collect::<Result<Vec<Question>, _>>()
+            .map_err(|err| {
+                error!("Error getting parsing questions. {}", err; "query" => "questions");
+                err
+            })?;
+
+        Ok(questions)
+    }
+
+    async fn set_questions(&self, quiz: &Quiz, question_ids: &[Uuid]) -> Result<Vec<Question>, AppError> {
+        validate_question_ids(question_ids)?;
+
+        db::transaction(&self.pool, |db| async move {
+            QuizRepository::new(db).replace_questions(quiz, question_ids).await
+        }).await?;
+
+        self.questions(quiz.id).await
+    }
+}
@@ -0,0 +1,211 @@
+use crate::repositories::{db::{Db, DbClient}, stores::ReportStore};
+use slog_scope::error;
+use crate::models::report::{Report, ReportReason, ReportStatus, ReportTargetType};
+use tokio_pg_mapper::FromTokioPostgresRow;
+use crate::errors::{AppError, AppErrorType};
+use uuid::Uuid;
+use async_trait::async_trait;
+
+pub struct ReportRepository {
+    pool: Db
+}
+
+impl ReportRepository {
+
+    pub fn new(pool: impl Into<Db>) -> ReportRepository {
+        ReportRepository { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl ReportStore for ReportRepository {
+    async fn get(&self, id: Uuid) -> Result<Report, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "get");
+                err
+            })?;
+
+        let statement = client.prepare("select * from reports where id = $1").await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error getting report. {}", err; "query" => "get");
+                err
+            })?
+            .iter()
+            .map(|row| Report::from_row_ref(row))
+            .collect::<Result<Vec<Report>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+
+    async fn create(&self, reporter_id: Uuid, target_type: ReportTargetType, target_id: Uuid, reason: ReportReason, note: Option<&str>) -> Result<Report, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "create");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                insert into reports (reporter_id, target_type, target_id, reason, note)
+                values ($1, $2, $3, $4, $5)
+                on conflict (reporter_id, target_type, target_id) where status = 'open'
+                do update set reason = excluded.reason, note = excluded.note
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[&reporter_id, &target_type.as_str(), &target_id, &reason.as_str(), &note])
+            .await
+            .map_err(|err| {
+                error!("Error creating report. {}", err; "query" => "create");
+                err
+            })?
+            .iter()
+            .map(|row| Report::from_row_ref(row))
+            .collect::<Result<Vec<Report>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error creating Report.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })
+    }
+
+    async fn open_count(&self, target_type: ReportTargetType, target_id: Uuid) -> Result<i32, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "open_count");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select count(distinct reporter_id)::int4 from reports
+                where target_type = $1 and target_id = $2 and status = 'open'
+            ")
+            .await?;
+
+        let row = client
+            .query_one(&statement, &[&target_type.as_str(), &target_id])
+            .await
+            .map_err(|err| {
+                error!("Error counting reports. {}", err; "query" => "open_count");
+                err
+            })?;
+
+        Ok(row.get(0))
+    }
+
+    async fn queue(&self, band_id: Option<Uuid>) -> Result<Vec<Report>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "queue");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select r.* from reports r
+                where r.status = 'open'
+                    and (
+                        $1::uuid is null
+                        or (r.target_type = 'band' and r.target_id = $1)
+                        or (r.target_type = 'question' and r.target_id in (select id from questions where band_id = $1))
+                    )
+                order by r.created_at
+            ")
+            .await?;
+
+        let reports = client
+            .query(&statement, &[&band_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting reports. {}", err; "query" => "queue");
+                err
+            })?
+            .iter()
+            .map(|row| Report::from_row_ref(row))
+            .collect::<Result<Vec<Report>, _>>()?;
+
+        Ok(reports)
+    }
+
+    async fn resolve(&self, target_type: ReportTargetType, target_id: Uuid, status: ReportStatus, resolved_by: Uuid) -> Result<Vec<Report>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "resolve");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update reports set
+                    status = $3,
+                    resolved_by = $4,
+                    resolved_at = current_timestamp
+                where target_type = $1 and target_id = $2 and status = 'open'
+                returning *
+            ")
+            .await?;
+
+        let reports = client
+            .query(&statement, &[&target_type.as_str(), &target_id, &status.as_str(), &resolved_by])
+            .await
+            .map_err(|err| {
+                error!("Error resolving reports. {}", err; "query" => "resolve");
+                err
+            })?
+            .iter()
+            .map(|row| Report::from_row_ref(row))
+            .collect::<Result<Vec<Report>, _>>()?;
+
+        Ok(reports)
+    }
+
+    async fn set_hidden(&self, target_type: ReportTargetType, target_id: Uuid, hidden: bool) -> Result<(), AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "set_hidden");
+                err
+            })?;
+
+        let statement = client
+            .prepare(&format!(
+                "update {} set hidden_at = case when $2 then coalesce(hidden_at, current_timestamp) end where id = $1",
+                target_type.table()
+            ))
+            .await?;
+
+        client
+            .execute(&statement, &[&target_id, &hidden])
+            .await
+            .map_err(|err| {
+                error!("Error hiding reported content. {}", err; "query" => "set_hidden");
+                err
+            })?;
+
+        Ok(())
+    }
+}
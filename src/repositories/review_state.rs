@@ -0,0 +1,160 @@
+use crate::repositories::{db::{Db, DbClient}, stores::ReviewStateStore};
+use slog_scope::error;
+use crate::models::{question::Question, review_state::{ReviewState, MIN_GRADE, MAX_GRADE}};
+use tokio_pg_mapper::FromTokioPostgresRow;
+use crate::errors::{AppError, AppErrorType};
+use tokio_postgres::error::{Error, SqlState};
+use chrono::Utc;
+use uuid::Uuid;
+use async_trait::async_trait;
+
+pub struct ReviewStateRepository {
+    pool: Db
+}
+
+pub(crate) fn validate_grade(grade: i32) -> Result<(), AppError> {
+    if grade < MIN_GRADE || grade > MAX_GRADE {
+        return Err(AppError {
+            cause: None,
+            message: Some(format!("grade must be between {} and {}.", MIN_GRADE, MAX_GRADE)),
+            error_type: AppErrorType::InvalidField
+        });
+    }
+
+    Ok(())
+}
+
+pub(crate) fn question_missing(question_id: Uuid, cause: Option<String>) -> AppError {
+    AppError {
+        cause,
+        message: Some(format!("question with id {} doesn't exists.", question_id)),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+impl ReviewStateRepository {
+
+    pub fn new(pool: impl Into<Db>) -> ReviewStateRepository {
+        ReviewStateRepository { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl ReviewStateStore for ReviewStateRepository {
+    async fn practice_queue(&self, user_id: Uuid, band_id: Uuid, limit: i32) -> Result<Vec<Question>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "practice_queue");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select q.* from questions q
+                left join review_states r on r.question_id = q.id and r.user_id = $1
+                where q.band_id = $2 and q.status = 'approved' and q.hidden_at is null and q.deleted_at is null and (r.due_at is null or r.due_at <= current_timestamp)
+                order by r.due_at is null, r.due_at, q.created_at
+                limit $3
+            ")
+            .await?;
+
+        let questions = client
+            .query(&statement, &[&user_id, &band_id, &(limit.max(0) as i64)])
+            .await
+            .map_err(|err| {
+                error!("Error getting practice queue. {}", err; "query" => "practice_queue");
+                err
+            })?
+            .iter()
+            .map(|row| Question::from_row_ref(row))
+            .collect::<Result<Vec<Question>, _>>()
+            .map_err(|err| {
+                error!("Error getting parsing questions. {}", err; "query" => "practice_queue");
+                err
+            })?;
+
+        Ok(questions)
+    }
+
+    async fn review(&self, user_id: Uuid, question_id: Uuid, grade: i32) -> Result<ReviewState, AppError> {
+        validate_grade(grade)?;
+
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "review");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select * from review_states where user_id = $1 and question_id = $2")
+            .await?;
+
+        let now = Utc::now().naive_utc();
+
+        let state = client
+            .query(&statement, &[&user_id, &question_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting review states. {}", err; "query" => "review");
+                err
+            })?
+            .iter()
+            .map(|row| ReviewState::from_row_ref(row))
+            .collect::<Result<Vec<ReviewState>, _>>()?
+            .pop()
+            .unwrap_or_else(|| ReviewState::new(user_id, question_id, now))
+            .review(grade, now);
+
+        let statement = client
+            .prepare("
+                insert into review_states
+                    (user_id, question_id, repetitions, interval_days, ease_factor, due_at, last_grade, updated_at)
+                values ($1, $2, $3, $4, $5, $6, $7, $8)
+                on conflict (user_id, question_id) do update set
+                    repetitions = excluded.repetitions,
+                    interval_days = excluded.interval_days,
+                    ease_factor = excluded.ease_factor,
+                    due_at = excluded.due_at,
+                    last_grade = excluded.last_grade,
+                    updated_at = excluded.updated_at
+                returning *
+            ")
+            .await?;
+
+        let state = client.query(&statement, &[
+                &state.user_id,
+                &state.question_id,
+                &state.repetitions,
+                &state.interval_days,
+                &state.ease_factor,
+                &state.due_at,
+                &state.last_grade,
+                &state.updated_at,
+            ])
+            .await
+            .map_err(|err: Error| {
+                let foreign_key_error = err.code()
+                    .map(|code| code == &SqlState::FOREIGN_KEY_VIOLATION);
+
+                match foreign_key_error {
+                    Some(true) => question_missing(question_id, Some(err.to_string())),
+                    _ => AppError::from(err)
+                }
+            })?
+            .iter()
+            .map(|row| ReviewState::from_row_ref(row))
+            .collect::<Result<Vec<ReviewState>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error saving ReviewState.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })?;
+
+        Ok(state)
+    }
+}
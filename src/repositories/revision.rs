@@ -0,0 +1,304 @@
+use crate::repositories::{db::{Db, DbClient}, stores::RevisionStore};
+use slog_scope::error;
+use crate::models::revision::{EditSuggestion, QuestionRevision, QuestionSnapshot, SuggestionStatus};
+use tokio_pg_mapper::FromTokioPostgresRow;
+use crate::errors::{AppError, AppErrorType};
+use uuid::Uuid;
+use async_trait::async_trait;
+
+/// Times a revision number is picked before giving up on concurrent edits
+const MAX_NUMBER_ATTEMPTS: usize = 3;
+
+pub struct RevisionRepository {
+    pool: Db
+}
+
+pub(crate) fn no_revision(number: i32) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(format!("question has no revision {}.", number)),
+        error_type: AppErrorType::NotFoundError
+    }
+}
+
+pub(crate) fn suggestion_decided() -> AppError {
+    AppError {
+        cause: None,
+        message: Some("suggestion was already decided on.".to_string()),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+impl RevisionRepository {
+
+    pub fn new(pool: impl Into<Db>) -> RevisionRepository {
+        RevisionRepository { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl RevisionStore for RevisionRepository {
+    async fn for_question(&self, question_id: Uuid) -> Result<Vec<QuestionRevision>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "for_question");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select * from question_revisions where question_id = $1 order by number")
+            .await?;
+
+        let revisions = client
+            .query(&statement, &[&question_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting question revisions. {}", err; "query" => "for_question");
+                err
+            })?
+            .iter()
+            .map(|row| QuestionRevision::from_row_ref(row))
+            .collect::<Result<Vec<QuestionRevision>, _>>()?;
+
+        Ok(revisions)
+    }
+
+    async fn get(&self, question_id: Uuid, number: i32) -> Result<QuestionRevision, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "get");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select * from question_revisions where question_id = $1 and number = $2")
+            .await?;
+
+        client
+            .query(&statement, &[&question_id, &number])
+            .await
+            .map_err(|err| {
+                error!("Error getting question revision. {}", err; "query" => "get");
+                err
+            })?
+            .iter()
+            .map(|row| QuestionRevision::from_row_ref(row))
+            .collect::<Result<Vec<QuestionRevision>, _>>()?
+            .pop()
+            .ok_or_else(|| no_revision(number))
+    }
+
+    async fn latest(&self, question_id: Uuid) -> Result<Option<QuestionRevision>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "latest");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select * from question_revisions where question_id = $1 order by number desc limit 1")
+            .await?;
+
+        let revision = client
+            .query(&statement, &[&question_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting question revision. {}", err; "query" => "latest");
+                err
+            })?
+            .iter()
+            .map(|row| QuestionRevision::from_row_ref(row))
+            .collect::<Result<Vec<QuestionRevision>, _>>()?
+            .pop();
+
+        Ok(revision)
+    }
+
+    async fn create(&self, question_id: Uuid, author_id: Option<Uuid>, snapshot: &QuestionSnapshot, reverted_from: Option<i32>) -> Result<QuestionRevision, AppError> {
+        let snapshot = snapshot.to_json()?;
+
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "create");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                insert into question_revisions (question_id, number, author_id, snapshot, reverted_from)
+                select $1, coalesce(max(number), 0) + 1, $2, $3, $4
+                from question_revisions
+                where question_id = $1
+                on conflict (question_id, number) do nothing
+                returning *
+            ")
+            .await?;
+
+        for _ in 0..MAX_NUMBER_ATTEMPTS {
+            let revision = client
+                .query(&statement, &[&question_id, &author_id, &snapshot, &reverted_from])
+                .await
+                .map_err(|err| {
+                    error!("Error creating question revision. {}", err; "query" => "create");
+                    err
+                })?
+                .iter()
+                .map(|row| QuestionRevision::from_row_ref(row))
+                .collect::<Result<Vec<QuestionRevision>, _>>()?
+                .pop();
+
+            if let Some(revision) = revision {
+                return Ok(revision);
+            }
+        }
+
+        Err(AppError {
+            cause: None,
+            message: Some("question is being edited by someone else, try again.".to_string()),
+            error_type: AppErrorType::ConflictError
+        })
+    }
+
+    async fn create_suggestion(&self, question_id: Uuid, suggested_by: Uuid, snapshot: &QuestionSnapshot, base_revision: i32, note: Option<&str>) -> Result<EditSuggestion, AppError> {
+        let snapshot = snapshot.to_json()?;
+
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "create_suggestion");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                insert into question_edit_suggestions (question_id, suggested_by, snapshot, base_revision, note)
+                values ($1, $2, $3, $4, $5)
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[&question_id, &suggested_by, &snapshot, &base_revision, &note])
+            .await
+            .map_err(|err| {
+                error!("Error creating edit suggestion. {}", err; "query" => "create_suggestion");
+                err
+            })?
+            .iter()
+            .map(|row| EditSuggestion::from_row_ref(row))
+            .collect::<Result<Vec<EditSuggestion>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error creating EditSuggestion.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })
+    }
+
+    async fn get_suggestion(&self, id: Uuid) -> Result<EditSuggestion, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "get_suggestion");
+                err
+            })?;
+
+        let statement = client
+            .prepare("select * from question_edit_suggestions where id = $1")
+            .await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error getting edit suggestion. {}", err; "query" => "get_suggestion");
+                err
+            })?
+            .iter()
+            .map(|row| EditSuggestion::from_row_ref(row))
+            .collect::<Result<Vec<EditSuggestion>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+
+    async fn pending_suggestions(&self, band_id: Uuid) -> Result<Vec<EditSuggestion>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "pending_suggestions");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select s.* from question_edit_suggestions s
+                join questions q on q.id = s.question_id
+                where q.band_id = $1 and q.deleted_at is null and s.status = 'pending'
+                order by s.created_at
+            ")
+            .await?;
+
+        let suggestions = client
+            .query(&statement, &[&band_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting edit suggestions. {}", err; "query" => "pending_suggestions");
+                err
+            })?
+            .iter()
+            .map(|row| EditSuggestion::from_row_ref(row))
+            .collect::<Result<Vec<EditSuggestion>, _>>()?;
+
+        Ok(suggestions)
+    }
+
+    async fn decide_suggestion(&self, id: Uuid, status: SuggestionStatus, reason: Option<&str>, decided_by: Uuid) -> Result<EditSuggestion, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "decide_suggestion");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update question_edit_suggestions set
+                    status = $2,
+                    reason = $3,
+                    decided_by = $4,
+                    decided_at = current_timestamp
+                where id = $1 and status = 'pending'
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[&id, &status.as_str(), &reason, &decided_by])
+            .await
+            .map_err(|err| {
+                error!("Error deciding edit suggestion. {}", err; "query" => "decide_suggestion");
+                err
+            })?
+            .iter()
+            .map(|row| EditSuggestion::from_row_ref(row))
+            .collect::<Result<Vec<EditSuggestion>, _>>()?
+            .pop()
+            .ok_or_else(suggestion_decided)
+    }
+}
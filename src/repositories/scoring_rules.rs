@@ -0,0 +1,197 @@
+use crate::repositories::{db::{Db, DbClient}, stores::ScoringRulesStore};
+use slog_scope::error;
+use crate::models::scoring_rules::{ScoringRules, ScoringRulesInput};
+use tokio_pg_mapper::FromTokioPostgresRow;
+use crate::errors::{AppError, AppErrorType};
+use chrono::Utc;
+use uuid::Uuid;
+use async_trait::async_trait;
+
+pub struct ScoringRulesRepository {
+    pool: Db
+}
+
+fn invalid_rules(message: &str) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(message.to_string()),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub(crate) fn validate_rules(input: &ScoringRulesInput) -> Result<(), AppError> {
+    let negative = |value: Option<i32>| value.map_or(false, |value| value < 0);
+
+    if negative(input.points_per_question) || negative(input.speed_bonus_points) || negative(input.wrong_answer_penalty) {
+        Err(invalid_rules("points and penalties must not be negative."))
+    } else if negative(input.speed_bonus_seconds) {
+        Err(invalid_rules("speed_bonus_seconds must not be negative."))
+    } else if input.speed_bonus_points.unwrap_or(0) > 0 && input.speed_bonus_seconds.unwrap_or(0) == 0 {
+        Err(invalid_rules("a speed bonus needs speed_bonus_seconds."))
+    } else if input.streak_bonus.map_or(false, |bonus| !(bonus >= 0.0)) {
+        Err(invalid_rules("streak_bonus must not be negative."))
+    } else if input.max_streak_multiplier.map_or(false, |multiplier| !(multiplier >= 1.0)) {
+        Err(invalid_rules("max_streak_multiplier must be at least 1."))
+    } else if [input.fifty_fifty_cost, input.hint_cost].iter().any(|cost| cost.map_or(false, |cost| !(0.0..=1.0).contains(&cost))) {
+        Err(invalid_rules("lifeline costs must be between 0 and 1."))
+    } else {
+        Ok(())
+    }
+}
+
+/// The rules as they'll be stored, with the defaults for fields left out
+pub(crate) fn with_defaults(input: &ScoringRulesInput) -> ScoringRules {
+    ScoringRules {
+        id: Uuid::new_v4(),
+        points_per_question: input.points_per_question.unwrap_or(10),
+        speed_bonus_points: input.speed_bonus_points.unwrap_or(0),
+        speed_bonus_seconds: input.speed_bonus_seconds.unwrap_or(0),
+        wrong_answer_penalty: input.wrong_answer_penalty.unwrap_or(0),
+        streak_bonus: input.streak_bonus.unwrap_or(0.0),
+        max_streak_multiplier: input.max_streak_multiplier.unwrap_or(1.0),
+        partial_credit: input.partial_credit.unwrap_or(true),
+        created_at: Utc::now().naive_utc(),
+        fifty_fifty_cost: input.fifty_fifty_cost.unwrap_or(0.5),
+        hint_cost: input.hint_cost.unwrap_or(0.25),
+    }
+}
+
+impl ScoringRulesRepository {
+
+    pub fn new(pool: impl Into<Db>) -> ScoringRulesRepository {
+        ScoringRulesRepository { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl ScoringRulesStore for ScoringRulesRepository {
+    async fn get(&self, id: Uuid) -> Result<ScoringRules, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "get");
+                err
+            })?;
+
+        let statement = client.prepare("select * from scoring_rules where id = $1").await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error getting scoring rules. {}", err; "query" => "get");
+                err
+            })?
+            .iter()
+            .map(|row| ScoringRules::from_row_ref(row))
+            .collect::<Result<Vec<ScoringRules>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+
+    async fn create(&self, input: &ScoringRulesInput) -> Result<ScoringRules, AppError> {
+        validate_rules(input)?;
+        let rules = with_defaults(input);
+
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "create");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                insert into scoring_rules
+                    (points_per_question, speed_bonus_points, speed_bonus_seconds, wrong_answer_penalty,
+                     streak_bonus, max_streak_multiplier, partial_credit, fifty_fifty_cost, hint_cost)
+                values ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[
+                &rules.points_per_question,
+                &rules.speed_bonus_points,
+                &rules.speed_bonus_seconds,
+                &rules.wrong_answer_penalty,
+                &rules.streak_bonus,
+                &rules.max_streak_multiplier,
+                &rules.partial_credit,
+                &rules.fifty_fifty_cost,
+                &rules.hint_cost,
+            ])
+            .await
+            .map_err(|err| {
+                error!("Error creating scoring rules. {}", err; "query" => "create");
+                err
+            })?
+            .iter()
+            .map(|row| ScoringRules::from_row_ref(row))
+            .collect::<Result<Vec<ScoringRules>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error creating ScoringRules.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })
+    }
+
+    async fn replace(&self, id: Uuid, input: &ScoringRulesInput) -> Result<ScoringRules, AppError> {
+        validate_rules(input)?;
+        let rules = with_defaults(input);
+
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "replace");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                update scoring_rules set
+                    points_per_question = $2, speed_bonus_points = $3, speed_bonus_seconds = $4, wrong_answer_penalty = $5,
+                    streak_bonus = $6, max_streak_multiplier = $7, partial_credit = $8, fifty_fifty_cost = $9, hint_cost = $10
+                where id = $1 and not exists (select 1 from attempts where scoring_rules_id = $1)
+                returning *
+            ")
+            .await?;
+
+        let replaced = client
+            .query(&statement, &[
+                &id,
+                &rules.points_per_question,
+                &rules.speed_bonus_points,
+                &rules.speed_bonus_seconds,
+                &rules.wrong_answer_penalty,
+                &rules.streak_bonus,
+                &rules.max_streak_multiplier,
+                &rules.partial_credit,
+                &rules.fifty_fifty_cost,
+                &rules.hint_cost,
+            ])
+            .await
+            .map_err(|err| {
+                error!("Error replacing scoring rules. {}", err; "query" => "replace");
+                err
+            })?
+            .iter()
+            .map(|row| ScoringRules::from_row_ref(row))
+            .collect::<Result<Vec<ScoringRules>, _>>()?
+            .pop();
+
+        match replaced {
+            Some(rules) => Ok(rules),
+            None => self.create(input).await,
+        }
+    }
+}
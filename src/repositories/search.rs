@@ -0,0 +1,230 @@
+use crate::repositories::{db::{Db, DbClient}, stores::SearchStore};
+use std::str::FromStr;
+use slog_scope::error;
+use crate::models::search::{PageInfo, SearchConnection, SearchEdge, SearchHit, SearchType};
+use crate::errors::{AppError, AppErrorType};
+use async_trait::async_trait;
+
+pub const DEFAULT_PAGE_SIZE: i32 = 10;
+pub const MAX_PAGE_SIZE: i32 = 50;
+
+pub struct SearchRepository {
+    pool: Db
+}
+
+/// Turns free text into a `tsquery` where every word is matched as a prefix,
+/// so "hey ju" finds "Hey Jude". Only letters and digits are kept.
+pub fn prefix_query(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("{}:*", word.to_lowercase()))
+        .collect();
+
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" & "))
+    }
+}
+
+/// Marks `ts_headline` wraps matched words in, control characters so they can't be mistaken for content
+const START_SELECTION: char = '\u{1}';
+const STOP_SELECTION: char = '\u{2}';
+
+/// HTML-escapes a headline and turns its marks into `<b>` tags, so only the
+/// highlighting is markup
+pub fn highlight(headline: &str) -> String {
+    let mut snippet = String::with_capacity(headline.len());
+    for c in headline.chars() {
+        match c {
+            START_SELECTION => snippet.push_str("<b>"),
+            STOP_SELECTION => snippet.push_str("</b>"),
+            '&' => snippet.push_str("&amp;"),
+            '<' => snippet.push_str("&lt;"),
+            '>' => snippet.push_str("&gt;"),
+            '"' => snippet.push_str("&quot;"),
+            '\'' => snippet.push_str("&#39;"),
+            _ => snippet.push(c),
+        }
+    }
+    snippet
+}
+
+pub fn encode_cursor(offset: i64) -> String {
+    hex::encode(format!("search:{}", offset))
+}
+
+pub fn decode_cursor(cursor: &str) -> Result<i64, AppError> {
+    hex::decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|decoded| {
+            let mut parts = decoded.splitn(2, ':');
+            match (parts.next(), parts.next()) {
+                (Some("search"), Some(offset)) => offset.parse::<i64>().ok(),
+                _ => None,
+            }
+        })
+        .filter(|offset| *offset >= 0)
+        .ok_or(AppError {
+            cause: None,
+            message: Some(format!("invalid cursor {}.", cursor)),
+            error_type: AppErrorType::InvalidField
+        })
+}
+
+pub(crate) fn validate_page_size(first: i32) -> Result<(), AppError> {
+    if !(1..=MAX_PAGE_SIZE).contains(&first) {
+        return Err(AppError {
+            cause: None,
+            message: Some(format!("first must be between 1 and {}.", MAX_PAGE_SIZE)),
+            error_type: AppErrorType::InvalidField
+        });
+    }
+
+    Ok(())
+}
+
+/// Pages hits fetched from `offset` with one extra row, which tells whether
+/// there's a next page
+pub(crate) fn connection(mut hits: Vec<SearchHit>, first: i32, offset: i64) -> SearchConnection {
+    let has_next_page = hits.len() > first as usize;
+    hits.truncate(first as usize);
+
+    let edges: Vec<SearchEdge> = hits
+        .into_iter()
+        .enumerate()
+        .map(|(index, node)| SearchEdge { cursor: encode_cursor(offset + index as i64), node })
+        .collect();
+
+    let end_cursor = edges.last().map(|edge| edge.cursor.clone());
+
+    SearchConnection {
+        edges,
+        page_info: PageInfo { has_next_page, end_cursor },
+    }
+}
+
+impl SearchRepository {
+
+    pub fn new(pool: impl Into<Db>) -> SearchRepository {
+        SearchRepository { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl SearchStore for SearchRepository {
+    async fn search(&self, query: &str, types: Vec<SearchType>, first: i32, after: Option<String>) -> Result<SearchConnection, AppError> {
+        validate_page_size(first)?;
+
+        let offset = match &after {
+            Some(cursor) => decode_cursor(cursor)? + 1,
+            None => 0,
+        };
+
+        let ts_query = match prefix_query(query) {
+            Some(ts_query) => ts_query,
+            None => return Ok(SearchConnection {
+                edges: vec![],
+                page_info: PageInfo { has_next_page: false, end_cursor: None },
+            }),
+        };
+
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "search");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select kind, id, title, snippet, rank::float8 as rank from (
+                    select 'band' as kind, id, name as title,
+                        ts_headline('pg_catalog.simple', name, to_tsquery('pg_catalog.simple', $1), $6) as snippet,
+                        ts_rank(search_vector, to_tsquery('pg_catalog.simple', $1)) + word_similarity($2, name) as rank
+                    from bands
+                    where 'band' = any($3) and hidden_at is null and deleted_at is null
+                        and (search_vector @@ to_tsquery('pg_catalog.simple', $1) or $2 <% name)
+                    union all
+                    select 'question', id, content,
+                        ts_headline('pg_catalog.simple', content, to_tsquery('pg_catalog.simple', $1), $6 || ', MaxFragments=2, MaxWords=20, MinWords=5'),
+                        ts_rank(search_vector, to_tsquery('pg_catalog.simple', $1)) + word_similarity($2, content)
+                    from questions
+                    where 'question' = any($3) and status = 'approved' and hidden_at is null and deleted_at is null
+                        and (search_vector @@ to_tsquery('pg_catalog.simple', $1) or $2 <% content)
+                    union all
+                    select 'user', id, username,
+                        ts_headline('pg_catalog.simple', username, to_tsquery('pg_catalog.simple', $1), $6),
+                        ts_rank(search_vector, to_tsquery('pg_catalog.simple', $1)) + word_similarity($2, username)
+                    from users
+                    where 'user' = any($3) and hidden_at is null and deleted_at is null
+                        and (search_vector @@ to_tsquery('pg_catalog.simple', $1) or $2 <% username)
+                ) hits
+                order by rank desc, title, id
+                limit $4 offset $5
+            ")
+            .await?;
+
+        let types: Vec<&str> = types.iter().map(|kind| kind.as_str()).collect();
+        // One extra row tells whether there's a next page
+        let limit = first as i64 + 1;
+        let selection = format!("StartSel={}, StopSel={}", START_SELECTION, STOP_SELECTION);
+
+        let hits = client
+            .query(&statement, &[&ts_query, &query, &types, &limit, &offset, &selection])
+            .await
+            .map_err(|err| {
+                error!("Error searching. {}", err; "query" => "search");
+                err
+            })?
+            .iter()
+            .map(|row| -> Result<SearchHit, AppError> { Ok(SearchHit {
+                kind: SearchType::from_str(row.get("kind")).map_err(|err| AppError {
+                    cause: Some(err),
+                    message: None,
+                    error_type: AppErrorType::DbError
+                })?,
+                id: row.get("id"),
+                title: row.get("title"),
+                snippet: highlight(row.get("snippet")),
+                rank: row.get("rank"),
+            }) })
+            .collect::<Result<Vec<SearchHit>, AppError>>()?;
+
+        Ok(connection(hits, first, offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{decode_cursor, encode_cursor, highlight, prefix_query};
+
+    #[test]
+    fn test_prefix_query() {
+        assert_eq!(prefix_query("Hey Ju"), Some("hey:* & ju:*".to_string()));
+        assert_eq!(prefix_query("AC/DC's 'back' & (black)"), Some("ac:* & dc:* & s:* & back:* & black:*".to_string()));
+        assert_eq!(prefix_query("Motörhead"), Some("motörhead:*".to_string()));
+        assert_eq!(prefix_query(" !:* "), None);
+    }
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(highlight("\u{1}Hey\u{2} Jude"), "<b>Hey</b> Jude");
+        assert_eq!(
+            highlight("<script>\u{1}alert\u{2}('AC/DC & \"Hey\"')</script>"),
+            "&lt;script&gt;<b>alert</b>(&#39;AC/DC &amp; &quot;Hey&quot;&#39;)&lt;/script&gt;",
+        );
+    }
+
+    #[test]
+    fn test_cursor() {
+        assert_eq!(decode_cursor(&encode_cursor(42)).unwrap(), 42);
+        assert!(decode_cursor("42").is_err());
+        assert!(decode_cursor(&hex::encode("search:-1")).is_err());
+    }
+
+}
@@ -0,0 +1,111 @@
+use crate::repositories::{db::{Db, DbClient}, stores::SessionStore};
+use std::sync::Arc;
+use slog_scope::error;
+use crate::models::session::{Session, Login};
+use crate::models::user::User;
+use tokio_pg_mapper::FromTokioPostgresRow;
+use crate::{config::HashingService, errors::{AppError, AppErrorType}};
+use uuid::Uuid;
+use async_trait::async_trait;
+
+pub struct SessionRepository {
+    pool: Db
+}
+
+/// The same error for an unknown username and a wrong password
+pub(crate) fn invalid_login() -> AppError {
+    AppError {
+        cause: None,
+        message: Some("Invalid username or password.".to_string()),
+        error_type: AppErrorType::UnauthorizedError
+    }
+}
+
+impl SessionRepository {
+
+    pub fn new(pool: impl Into<Db>) -> SessionRepository {
+        SessionRepository { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl SessionStore for SessionRepository {
+    async fn find_user_id(&self, token: Uuid) -> Result<Option<Uuid>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "find_user_id");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select s.* from sessions s
+                join users u on u.id = s.user_id
+                where s.token = $1 and s.expires_at > current_timestamp and u.deleted_at is null
+            ")
+            .await?;
+
+        let session = client
+            .query(&statement, &[&token])
+            .await
+            .map_err(|err| {
+                error!("Error getting sessions. {}", err; "query" => "find_user_id");
+                err
+            })?
+            .iter()
+            .map(|row| Session::from_row_ref(row))
+            .collect::<Result<Vec<Session>, _>>()?
+            .pop();
+
+        Ok(session.map(|session| session.user_id))
+    }
+
+    async fn create(&self, input: Login, hashing: Arc<HashingService>) -> Result<Session, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "create");
+                err
+            })?;
+
+        let statement = client.prepare("select * from users where username = $1 and deleted_at is null").await?;
+
+        let user = client
+            .query(&statement, &[&input.username])
+            .await
+            .map_err(|err| {
+                error!("Error getting users. {}", err; "query" => "create");
+                err
+            })?
+            .iter()
+            .map(|row| User::from_row_ref(row))
+            .collect::<Result<Vec<User>, _>>()?
+            .pop()
+            .ok_or_else(invalid_login)?;
+
+        if !hashing.verify(input.password, user.password).await? {
+            return Err(invalid_login());
+        }
+
+        let statement = client
+            .prepare("insert into sessions (user_id) values ($1) returning *")
+            .await?;
+
+        let session = client.query(&statement, &[&user.id])
+            .await?
+            .iter()
+            .map(|row| Session::from_row_ref(row))
+            .collect::<Result<Vec<Session>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                message: Some("Error creating Session.".to_string()),
+                cause: Some("Unknown error.".to_string()),
+                error_type: AppErrorType::DbError,
+            })?;
+
+        Ok(session)
+    }
+}
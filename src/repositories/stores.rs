@@ -0,0 +1,492 @@
+use crate::repositories::{
+    db::Db,
+    user::UserRepository,
+    answer::AnswerRepository,
+    question::QuestionRepository,
+    band::BandRepository,
+    session::SessionRepository,
+    review_state::ReviewStateRepository,
+    media::MediaRepository,
+    tag::TagRepository,
+    search::SearchRepository,
+    quiz::QuizRepository,
+    attempt::AttemptRepository,
+    scoring_rules::ScoringRulesRepository,
+    notification::NotificationRepository,
+    revision::RevisionRepository,
+    report::ReportRepository,
+    audit::AuditRepository,
+};
+use crate::models::{
+    user::{User, CreateUser},
+    answer::{Answer, CreateAnswer, AnswerAlias, CreateAnswerAlias},
+    question::{Question, CreateQuestion, CreateOrderingQuestion, CreateMatchingQuestion, QuestionStatus},
+    question_choice::QuestionChoice,
+    revision::{EditSuggestion, QuestionRevision, QuestionSnapshot, SuggestionStatus},
+    duplicate::{DuplicatePair, SimilarQuestion},
+    band::{Band, BandRole, CreateBand},
+    session::{Session, Login},
+    review_state::ReviewState,
+    media::{Media, CreateMedia},
+    tag::{Tag, TagKind, TagStats},
+    search::{SearchConnection, SearchType},
+    quiz::{Quiz, QuizStatus, CreateQuiz, UpdateQuiz},
+    attempt::{Attempt, AttemptChoice, AttemptQuestion},
+    grade::{AnswerSubmission, Grade},
+    lifeline::{Lifeline, LifelineKind},
+    scoring_rules::{ScoringRules, ScoringRulesInput},
+    notification::{Notification, NotificationKind},
+    report::{Report, ReportReason, ReportStatus, ReportTargetType},
+    audit::{AuditEntry, AuditFilter, NewAuditEntry},
+};
+use crate::{config::HashingService, errors::AppError, grading::AnswerKey};
+use chrono::NaiveDateTime;
+use std::sync::Arc;
+use uuid::Uuid;
+use async_trait::async_trait;
+
+/// Users as `Context` and the services see them
+#[async_trait]
+pub trait UserStore: Send + Sync {
+    async fn get(&self, id: Uuid) -> Result<User, AppError>;
+
+    /// Users that aren't hidden
+    async fn all(&self) -> Result<Vec<User>, AppError>;
+
+    async fn create(&self, input: CreateUser, hashing: Arc<HashingService>) -> Result<User, AppError>;
+
+    /// Hides the account and logs it out everywhere until it's restored or purged
+    async fn soft_delete(&self, id: Uuid) -> Result<User, AppError>;
+
+    async fn restore(&self, id: Uuid) -> Result<User, AppError>;
+}
+
+#[async_trait]
+pub trait AnswerStore: Send + Sync {
+    async fn get(&self, id: Uuid) -> Result<Answer, AppError>;
+
+    /// The answers with these ids, in no particular order
+    async fn get_many(&self, ids: &[Uuid]) -> Result<Vec<Answer>, AppError>;
+
+    async fn all(&self) -> Result<Vec<Answer>, AppError>;
+
+    async fn get_for_band(&self, band_id: Uuid) -> Result<Vec<Answer>, AppError>;
+
+    async fn create(&self, input: CreateAnswer) -> Result<Answer, AppError>;
+
+    async fn aliases(&self, answer_id: Uuid) -> Result<Vec<AnswerAlias>, AppError>;
+
+    async fn create_alias(&self, input: CreateAnswerAlias) -> Result<AnswerAlias, AppError>;
+
+    /// Replaces the answer's aliases with `contents`
+    async fn set_aliases(&self, answer_id: Uuid, contents: &[String]) -> Result<Vec<AnswerAlias>, AppError>;
+
+    /// Returns the band's answer with exactly this content, creating it if
+    /// needed, and whether it was created
+    async fn find_or_create(&self, band_id: Uuid, content: &str) -> Result<(Answer, bool), AppError>;
+}
+
+#[async_trait]
+pub trait QuestionStore: Send + Sync {
+    async fn get(&self, id: Uuid) -> Result<Question, AppError>;
+
+    /// Also finds deleted questions, for attempts that played them
+    async fn get_any(&self, id: Uuid) -> Result<Question, AppError>;
+
+    /// Approved questions that aren't hidden
+    async fn all(&self) -> Result<Vec<Question>, AppError>;
+
+    /// Approved questions of a band, or of every band, carrying all of the given tags
+    async fn find(&self, band_id: Option<Uuid>, tags: &[String]) -> Result<Vec<Question>, AppError>;
+
+    async fn get_for_band(&self, band_id: Uuid) -> Result<Vec<Question>, AppError>;
+
+    /// Questions whose correct answer is `answer_id`
+    async fn with_correct_answer(&self, answer_id: Uuid) -> Result<Vec<Question>, AppError>;
+
+    /// Creates a question, which waits for moderation when a fan `submitted_by` it
+    async fn create(&self, input: CreateQuestion, submitted_by: Option<Uuid>) -> Result<Question, AppError>;
+
+    /// Creates an ordering question, `answer_ids` being the correct order
+    async fn create_ordering(&self, input: CreateOrderingQuestion, submitted_by: Option<Uuid>) -> Result<Question, AppError>;
+
+    async fn create_matching(&self, input: CreateMatchingQuestion, submitted_by: Option<Uuid>) -> Result<Question, AppError>;
+
+    /// The band's questions whose content is at least `threshold` similar, closest first,
+    /// only approved ones that aren't hidden if `approved_only` is set
+    async fn similar(&self, band_id: Uuid, content: &str, threshold: f64, approved_only: bool) -> Result<Vec<SimilarQuestion>, AppError>;
+
+    /// Pairs of questions in the same band, or any band, that are at least `threshold` similar
+    async fn duplicate_pairs(&self, band_id: Option<Uuid>, threshold: f64) -> Result<Vec<DuplicatePair>, AppError>;
+
+    /// Questions submitted to the band that wait for a moderator, oldest first
+    async fn moderation_queue(&self, band_id: Uuid) -> Result<Vec<Question>, AppError>;
+
+    /// Decides on a pending question
+    async fn moderate(&self, question_id: Uuid, status: QuestionStatus, reason: Option<&str>, moderated_by: Uuid) -> Result<Question, AppError>;
+
+    /// Loads the correct answer, its accepted aliases and the question's choices for grading
+    async fn answer_key(&self, question: &Question) -> Result<AnswerKey, AppError>;
+
+    async fn choices(&self, question_id: Uuid) -> Result<Vec<QuestionChoice>, AppError>;
+
+    /// The question and its choices as they are now, without answer contents
+    async fn snapshot(&self, question: &Question) -> Result<QuestionSnapshot, AppError>;
+
+    /// Checks an edit can be applied to a question of the band
+    async fn validate_edit(&self, band_id: Uuid, snapshot: &QuestionSnapshot) -> Result<(), AppError>;
+
+    /// Overwrites the question's fields and choices with the snapshot's, as
+    /// long as the question is still at `version`
+    async fn apply(&self, question: &Question, snapshot: &QuestionSnapshot, version: i32) -> Result<Question, AppError>;
+
+    /// Sends a question back to the moderation queue once its submitter made the requested changes
+    async fn resubmit(&self, question_id: Uuid) -> Result<Question, AppError>;
+
+    /// Hides the question until it's restored or purged
+    async fn soft_delete(&self, id: Uuid) -> Result<Question, AppError>;
+
+    /// Brings back a deleted question unless its band is still deleted
+    async fn restore(&self, id: Uuid) -> Result<Question, AppError>;
+}
+
+#[async_trait]
+pub trait BandStore: Send + Sync {
+    async fn get(&self, id: Uuid) -> Result<Band, AppError>;
+
+    /// Bands that aren't hidden
+    async fn all(&self) -> Result<Vec<Band>, AppError>;
+
+    /// Creates the band with `owner_id` as its owner and first author
+    async fn create(&self, input: CreateBand, owner_id: Uuid) -> Result<Band, AppError>;
+
+    async fn is_author(&self, band_id: Uuid, user_id: Uuid) -> Result<bool, AppError>;
+
+    /// Whether the user is the band's owner or one of its moderators
+    async fn is_moderator(&self, band_id: Uuid, user_id: Uuid) -> Result<bool, AppError>;
+
+    /// Adds the user to the band's authors, or changes their role if they are one
+    async fn add_author(&self, band_id: Uuid, user_id: Uuid, role: BandRole) -> Result<(), AppError>;
+
+    /// Hides the band and its questions until they're restored or purged
+    async fn soft_delete(&self, id: Uuid) -> Result<Band, AppError>;
+
+    /// Brings back a deleted band and the questions deleted along with it
+    async fn restore(&self, id: Uuid) -> Result<Band, AppError>;
+}
+
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    /// Resolves a bearer token to the user it was issued for, ignoring expired sessions
+    async fn find_user_id(&self, token: Uuid) -> Result<Option<Uuid>, AppError>;
+
+    async fn create(&self, input: Login, hashing: Arc<HashingService>) -> Result<Session, AppError>;
+}
+
+#[async_trait]
+pub trait ReviewStateStore: Send + Sync {
+    /// Questions of a band the user should practice next: overdue reviews first,
+    /// oldest due date first, then questions the user has never seen.
+    async fn practice_queue(&self, user_id: Uuid, band_id: Uuid, limit: i32) -> Result<Vec<Question>, AppError>;
+
+    async fn review(&self, user_id: Uuid, question_id: Uuid, grade: i32) -> Result<ReviewState, AppError>;
+}
+
+#[async_trait]
+pub trait MediaStore: Send + Sync {
+    async fn get(&self, id: Uuid) -> Result<Media, AppError>;
+
+    async fn create(&self, input: CreateMedia) -> Result<Media, AppError>;
+}
+
+#[async_trait]
+pub trait TagStore: Send + Sync {
+    async fn for_question(&self, question_id: Uuid) -> Result<Vec<Tag>, AppError>;
+
+    /// Tags used by the band's questions, or all tags
+    async fn all(&self, band_id: Option<Uuid>) -> Result<Vec<Tag>, AppError>;
+
+    /// Links the question to each tag, creating tags that don't exist yet.
+    /// New tags get `kind` or an inferred one. Tags are shared by every band, so
+    /// a `kind` other than an existing tag's is rejected rather than changing it.
+    async fn tag_question(&self, question_id: Uuid, names: &[String], kind: Option<TagKind>) -> Result<Vec<Tag>, AppError>;
+
+    async fn untag_question(&self, question_id: Uuid, names: &[String]) -> Result<(), AppError>;
+
+    /// Question counts per tag, with the user's review progress when there is one
+    async fn stats(&self, band_id: Option<Uuid>, user_id: Option<Uuid>) -> Result<Vec<TagStats>, AppError>;
+}
+
+#[async_trait]
+pub trait SearchStore: Send + Sync {
+    /// Ranks full-text prefix matches together with trigram matches, which
+    /// catch typos the full-text search misses
+    async fn search(&self, query: &str, types: Vec<SearchType>, first: i32, after: Option<String>) -> Result<SearchConnection, AppError>;
+}
+
+#[async_trait]
+pub trait QuizStore: Send + Sync {
+    async fn get(&self, id: Uuid) -> Result<Quiz, AppError>;
+
+    /// The band's quizzes with one of the given statuses, newest first
+    async fn get_for_band(&self, band_id: Uuid, statuses: &[QuizStatus]) -> Result<Vec<Quiz>, AppError>;
+
+    /// Creates a draft quiz, with its questions when given, in one transaction
+    async fn create(&self, input: CreateQuiz, created_by: Uuid, scoring_rules_id: Option<Uuid>) -> Result<Quiz, AppError>;
+
+    /// Updates the given fields, and the scoring rules when `scoring_rules_id` is given.
+    /// Fails with a conflict when the quiz is no longer at the input's version.
+    async fn update(&self, input: UpdateQuiz, scoring_rules_id: Option<Uuid>) -> Result<Quiz, AppError>;
+
+    /// Publishing needs at least one question, and archived quizzes can't be played again
+    async fn set_status(&self, quiz: &Quiz, status: QuizStatus) -> Result<Quiz, AppError>;
+
+    /// Quizzes that have been played can only be archived
+    async fn delete(&self, id: Uuid) -> Result<(), AppError>;
+
+    async fn questions(&self, quiz_id: Uuid) -> Result<Vec<Question>, AppError>;
+
+    /// Replaces the quiz's questions, which must belong to its band, keeping their order
+    async fn set_questions(&self, quiz: &Quiz, question_ids: &[Uuid]) -> Result<Vec<Question>, AppError>;
+}
+
+#[async_trait]
+pub trait AttemptStore: Send + Sync {
+    async fn get(&self, id: Uuid) -> Result<Attempt, AppError>;
+
+    /// The attempt, locked until the transaction ends so that changes to it are made one at a time
+    async fn lock(&self, id: Uuid) -> Result<Attempt, AppError>;
+
+    /// The time attempts are started, served and answered at, so deadlines
+    /// are checked against the same clock
+    async fn now(&self) -> Result<NaiveDateTime, AppError>;
+
+    /// Starts an attempt on the quiz's questions, in the quiz's order and under
+    /// its current scoring rules and lifelines
+    async fn start_quiz(&self, quiz: &Quiz, user_id: Uuid) -> Result<Attempt, AppError>;
+
+    /// Starts an attempt on up to `count` random questions of the band carrying all of `tags`
+    async fn start_random(&self, band_id: Uuid, user_id: Uuid, tags: &[String], count: i32) -> Result<Attempt, AppError>;
+
+    /// Served questions, in the order they are played
+    async fn questions(&self, attempt_id: Uuid) -> Result<Vec<AttemptQuestion>, AppError>;
+
+    /// Stores the response to a served question, which can only be answered once
+    async fn record_answer(&self, attempt_id: Uuid, submission: &AnswerSubmission, grade: &Grade) -> Result<AttemptQuestion, AppError>;
+
+    /// Marks the question as served, once, and deals its choices out in a
+    /// shuffled order with fresh tokens. Serving it again changes nothing.
+    async fn serve(&self, attempt_id: Uuid, question_id: Uuid) -> Result<AttemptQuestion, AppError>;
+
+    /// Choices dealt for a served question, answers first, each side in shown order
+    async fn choices(&self, attempt_id: Uuid, question_id: Uuid) -> Result<Vec<AttemptChoice>, AppError>;
+
+    /// Lifelines used so far, in the order they were used
+    async fn lifelines(&self, attempt_id: Uuid) -> Result<Vec<Lifeline>, AppError>;
+
+    /// Records a lifeline used on a question, as long as fewer than `budget`
+    /// of its kind were used in the attempt and none on this question
+    async fn use_lifeline(&self, attempt_id: Uuid, question_id: Uuid, kind: LifelineKind, budget: i32) -> Result<Lifeline, AppError>;
+
+    /// Removes `count` of the served options that aren't `correct_answer_id`, at random
+    async fn eliminate_choices(&self, attempt_id: Uuid, question_id: Uuid, correct_answer_id: Uuid, count: i64) -> Result<(), AppError>;
+
+    /// Median seconds from serving to answering across finished attempts on
+    /// the same quiz, or on random questions of the band when `quiz_id` is None
+    async fn median_answer_seconds(&self, band_id: Uuid, quiz_id: Option<Uuid>) -> Result<Option<f64>, AppError>;
+
+    /// Ends the attempt, flagging it for review when there are `flag_reasons`
+    async fn finish(&self, attempt_id: Uuid, score: f64, flag_reasons: &[String]) -> Result<Attempt, AppError>;
+
+    /// The band's attempts waiting for review, most recently flagged first
+    async fn flagged(&self, band_id: Uuid) -> Result<Vec<Attempt>, AppError>;
+
+    /// Takes a reviewed attempt off the flagged list
+    async fn clear_flag(&self, attempt_id: Uuid) -> Result<Attempt, AppError>;
+}
+
+#[async_trait]
+pub trait ScoringRulesStore: Send + Sync {
+    async fn get(&self, id: Uuid) -> Result<ScoringRules, AppError>;
+
+    async fn create(&self, input: &ScoringRulesInput) -> Result<ScoringRules, AppError>;
+
+    /// Rewrites the rules with `id`, or stores new ones if attempts were already
+    /// played under them so their scores keep their meaning
+    async fn replace(&self, id: Uuid, input: &ScoringRulesInput) -> Result<ScoringRules, AppError>;
+}
+
+#[async_trait]
+pub trait NotificationStore: Send + Sync {
+    /// The user's notifications, newest first
+    async fn for_user(&self, user_id: Uuid, unread_only: bool) -> Result<Vec<Notification>, AppError>;
+
+    async fn create(&self, user_id: Uuid, kind: NotificationKind, message: &str, question_id: Option<Uuid>) -> Result<Notification, AppError>;
+
+    /// Marks the user's notifications with these ids as read, or all of them
+    /// when `ids` is None. Returns how many were unread.
+    async fn mark_read(&self, user_id: Uuid, ids: Option<&[Uuid]>) -> Result<i32, AppError>;
+}
+
+#[async_trait]
+pub trait RevisionStore: Send + Sync {
+    /// The question's revisions, oldest first
+    async fn for_question(&self, question_id: Uuid) -> Result<Vec<QuestionRevision>, AppError>;
+
+    async fn get(&self, question_id: Uuid, number: i32) -> Result<QuestionRevision, AppError>;
+
+    /// None for questions saved before revisions were kept
+    async fn latest(&self, question_id: Uuid) -> Result<Option<QuestionRevision>, AppError>;
+
+    /// Stores the snapshot as the question's next revision. Two edits saved at
+    /// once can pick the same number, the later one then tries the next.
+    async fn create(&self, question_id: Uuid, author_id: Option<Uuid>, snapshot: &QuestionSnapshot, reverted_from: Option<i32>) -> Result<QuestionRevision, AppError>;
+
+    async fn create_suggestion(&self, question_id: Uuid, suggested_by: Uuid, snapshot: &QuestionSnapshot, base_revision: i32, note: Option<&str>) -> Result<EditSuggestion, AppError>;
+
+    async fn get_suggestion(&self, id: Uuid) -> Result<EditSuggestion, AppError>;
+
+    /// Suggestions for the band's questions waiting for a decision, oldest first
+    async fn pending_suggestions(&self, band_id: Uuid) -> Result<Vec<EditSuggestion>, AppError>;
+
+    /// Accepts or rejects a pending suggestion
+    async fn decide_suggestion(&self, id: Uuid, status: SuggestionStatus, reason: Option<&str>, decided_by: Uuid) -> Result<EditSuggestion, AppError>;
+}
+
+#[async_trait]
+pub trait ReportStore: Send + Sync {
+    async fn get(&self, id: Uuid) -> Result<Report, AppError>;
+
+    /// Reports the target, or updates the reporter's open report on it
+    async fn create(&self, reporter_id: Uuid, target_type: ReportTargetType, target_id: Uuid, reason: ReportReason, note: Option<&str>) -> Result<Report, AppError>;
+
+    /// Number of users with an open report on the target
+    async fn open_count(&self, target_type: ReportTargetType, target_id: Uuid) -> Result<i32, AppError>;
+
+    /// Open reports, oldest first. With a band, only those on the band and its questions.
+    async fn queue(&self, band_id: Option<Uuid>) -> Result<Vec<Report>, AppError>;
+
+    /// Closes every open report on the target. Returns the closed reports.
+    async fn resolve(&self, target_type: ReportTargetType, target_id: Uuid, status: ReportStatus, resolved_by: Uuid) -> Result<Vec<Report>, AppError>;
+
+    /// Hides or shows the reported question, band or user
+    async fn set_hidden(&self, target_type: ReportTargetType, target_id: Uuid, hidden: bool) -> Result<(), AppError>;
+}
+
+#[async_trait]
+pub trait AuditStore: Send + Sync {
+    async fn create(&self, entry: NewAuditEntry) -> Result<AuditEntry, AppError>;
+
+    /// Entries matching the filter, newest first
+    async fn search(&self, filter: &AuditFilter, limit: i64) -> Result<Vec<AuditEntry>, AppError>;
+}
+
+/// Builds the stores a request uses on top of its `Db`, so they join its transactions
+pub trait Stores: Send + Sync {
+    fn users(&self, db: &Db) -> Arc<dyn UserStore>;
+
+    fn answers(&self, db: &Db) -> Arc<dyn AnswerStore>;
+
+    fn questions(&self, db: &Db) -> Arc<dyn QuestionStore>;
+
+    fn bands(&self, db: &Db) -> Arc<dyn BandStore>;
+
+    fn sessions(&self, db: &Db) -> Arc<dyn SessionStore>;
+
+    fn review_states(&self, db: &Db) -> Arc<dyn ReviewStateStore>;
+
+    fn media(&self, db: &Db) -> Arc<dyn MediaStore>;
+
+    fn tags(&self, db: &Db) -> Arc<dyn TagStore>;
+
+    fn search(&self, db: &Db) -> Arc<dyn SearchStore>;
+
+    fn quizzes(&self, db: &Db) -> Arc<dyn QuizStore>;
+
+    fn attempts(&self, db: &Db) -> Arc<dyn AttemptStore>;
+
+    fn scoring_rules(&self, db: &Db) -> Arc<dyn ScoringRulesStore>;
+
+    fn notifications(&self, db: &Db) -> Arc<dyn NotificationStore>;
+
+    fn revisions(&self, db: &Db) -> Arc<dyn RevisionStore>;
+
+    fn reports(&self, db: &Db) -> Arc<dyn ReportStore>;
+
+    fn audit_log(&self, db: &Db) -> Arc<dyn AuditStore>;
+
+    /// Whether the stores keep their data in Postgres, so `Context::transaction`
+    /// has to begin one there for them to share
+    fn uses_db(&self) -> bool {
+        true
+    }
+}
+
+/// The Postgres repositories
+pub struct PgStores;
+
+impl Stores for PgStores {
+    fn users(&self, db: &Db) -> Arc<dyn UserStore> {
+        Arc::new(UserRepository::new(db.clone()))
+    }
+
+    fn answers(&self, db: &Db) -> Arc<dyn AnswerStore> {
+        Arc::new(AnswerRepository::new(db.clone()))
+    }
+
+    fn questions(&self, db: &Db) -> Arc<dyn QuestionStore> {
+        Arc::new(QuestionRepository::new(db.clone()))
+    }
+
+    fn bands(&self, db: &Db) -> Arc<dyn BandStore> {
+        Arc::new(BandRepository::new(db.clone()))
+    }
+
+    fn sessions(&self, db: &Db) -> Arc<dyn SessionStore> {
+        Arc::new(SessionRepository::new(db.clone()))
+    }
+
+    fn review_states(&self, db: &Db) -> Arc<dyn ReviewStateStore> {
+        Arc::new(ReviewStateRepository::new(db.clone()))
+    }
+
+    fn media(&self, db: &Db) -> Arc<dyn MediaStore> {
+        Arc::new(MediaRepository::new(db.clone()))
+    }
+
+    fn tags(&self, db: &Db) -> Arc<dyn TagStore> {
+        Arc::new(TagRepository::new(db.clone()))
+    }
+
+    fn search(&self, db: &Db) -> Arc<dyn SearchStore> {
+        Arc::new(SearchRepository::new(db.clone()))
+    }
+
+    fn quizzes(&self, db: &Db) -> Arc<dyn QuizStore> {
+        Arc::new(QuizRepository::new(db.clone()))
+    }
+
+    fn attempts(&self, db: &Db) -> Arc<dyn AttemptStore> {
+        Arc::new(AttemptRepository::new(db.clone()))
+    }
+
+    fn scoring_rules(&self, db: &Db) -> Arc<dyn ScoringRulesStore> {
+        Arc::new(ScoringRulesRepository::new(db.clone()))
+    }
+
+    fn notifications(&self, db: &Db) -> Arc<dyn NotificationStore> {
+        Arc::new(NotificationRepository::new(db.clone()))
+    }
+
+    fn revisions(&self, db: &Db) -> Arc<dyn RevisionStore> {
+        Arc::new(RevisionRepository::new(db.clone()))
+    }
+
+    fn reports(&self, db: &Db) -> Arc<dyn ReportStore> {
+        Arc::new(ReportRepository::new(db.clone()))
+    }
+
+    fn audit_log(&self, db: &Db) -> Arc<dyn AuditStore> {
+        Arc::new(AuditRepository::new(db.clone()))
+    }
+}
@@ -0,0 +1,237 @@
+use crate::repositories::{db::{Db, DbClient}, review_state::question_missing, stores::TagStore};
+use slog_scope::error;
+use crate::models::tag::{Tag, TagKind, TagStats, normalize_tags};
+use std::str::FromStr;
+use tokio_pg_mapper::FromTokioPostgresRow;
+use crate::errors::{AppError, AppErrorType};
+use tokio_postgres::error::SqlState;
+use uuid::Uuid;
+use async_trait::async_trait;
+
+pub struct TagRepository {
+    pool: Db
+}
+
+/// Tagging with an explicit kind can't change the kind of an existing tag
+pub(crate) fn tag_kind_conflict(tag: &Tag) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(format!("tag \"{}\" is already a {} tag.", tag.name, tag.kind)),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+impl TagRepository {
+
+    pub fn new(pool: impl Into<Db>) -> TagRepository {
+        TagRepository { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl TagStore for TagRepository {
+    async fn for_question(&self, question_id: Uuid) -> Result<Vec<Tag>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "for_question");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select t.* from tags t
+                join question_tags qt on qt.tag_id = t.id
+                where qt.question_id = $1
+                order by t.name
+            ")
+            .await?;
+
+        let tags = client
+            .query(&statement, &[&question_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting tags. {}", err; "query" => "for_question");
+                err
+            })?
+            .iter()
+            .map(|row| Tag::from_row_ref(row))
+            .collect::<Result<Vec<Tag>, _>>()
+            .map_err(|err| {
+                error!("Error getting parsing tags. {}", err; "query" => "for_question");
+                err
+            })?;
+
+        Ok(tags)
+    }
+
+    async fn all(&self, band_id: Option<Uuid>) -> Result<Vec<Tag>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "all");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select t.* from tags t
+                where $1::uuid is null or exists (
+                    select 1 from question_tags qt
+                    join questions q on q.id = qt.question_id
+                    where qt.tag_id = t.id and q.band_id = $1 and q.deleted_at is null
+                )
+                order by t.kind, t.name
+            ")
+            .await?;
+
+        let tags = client
+            .query(&statement, &[&band_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting tags. {}", err; "query" => "all");
+                err
+            })?
+            .iter()
+            .map(|row| Tag::from_row_ref(row))
+            .collect::<Result<Vec<Tag>, _>>()
+            .map_err(|err| {
+                error!("Error getting parsing tags. {}", err; "query" => "all");
+                err
+            })?;
+
+        Ok(tags)
+    }
+
+    async fn tag_question(&self, question_id: Uuid, names: &[String], kind: Option<TagKind>) -> Result<Vec<Tag>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "tag_question");
+                err
+            })?;
+
+        let upsert_tag = client
+            .prepare("
+                insert into tags (name, kind) values ($1, $2)
+                on conflict (name) do update set name = excluded.name
+                returning *
+            ")
+            .await?;
+
+        let link = client
+            .prepare("insert into question_tags (question_id, tag_id) values ($1, $2) on conflict do nothing")
+            .await?;
+
+        let mut tags = Vec::new();
+        for name in normalize_tags(names) {
+            let tag_kind = kind.unwrap_or_else(|| TagKind::infer(&name));
+
+            let tag = client
+                .query(&upsert_tag, &[&name, &tag_kind.as_str()])
+                .await?
+                .iter()
+                .map(|row| Tag::from_row_ref(row))
+                .collect::<Result<Vec<Tag>, _>>()?
+                .pop()
+                .ok_or(AppError {
+                    message: Some("Error creating Tag.".to_string()),
+                    cause: Some("Unknown error.".to_string()),
+                    error_type: AppErrorType::DbError,
+                })?;
+
+            if kind.is_some() && tag.kind != tag_kind.as_str() {
+                return Err(tag_kind_conflict(&tag));
+            }
+
+            client.execute(&link, &[&question_id, &tag.id])
+                .await
+                .map_err(|err| {
+                    match err.code() {
+                        Some(code) if code == &SqlState::FOREIGN_KEY_VIOLATION => question_missing(question_id, Some(err.to_string())),
+                        _ => AppError::from(err)
+                    }
+                })?;
+            tags.push(tag);
+        }
+
+        Ok(tags)
+    }
+
+    async fn untag_question(&self, question_id: Uuid, names: &[String]) -> Result<(), AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "untag_question");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                delete from question_tags qt
+                using tags t
+                where t.id = qt.tag_id and qt.question_id = $1 and t.name = any($2)
+            ")
+            .await?;
+
+        client
+            .execute(&statement, &[&question_id, &normalize_tags(names)])
+            .await
+            .map_err(|err| {
+                error!("Error removing tags. {}", err; "query" => "untag_question");
+                err
+            })?;
+
+        Ok(())
+    }
+
+    async fn stats(&self, band_id: Option<Uuid>, user_id: Option<Uuid>) -> Result<Vec<TagStats>, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "stats");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                select t.id, t.name, t.kind,
+                    count(distinct q.id)::int4 as question_count,
+                    count(rs.last_grade)::int4 as reviewed_count,
+                    avg(rs.last_grade)::float8 as average_grade
+                from tags t
+                join question_tags qt on qt.tag_id = t.id
+                join questions q on q.id = qt.question_id
+                left join review_states rs on rs.question_id = q.id and rs.user_id = $2
+                where ($1::uuid is null or q.band_id = $1) and q.deleted_at is null
+                group by t.id
+                order by question_count desc, t.name
+            ")
+            .await?;
+
+        let stats = client
+            .query(&statement, &[&band_id, &user_id])
+            .await
+            .map_err(|err| {
+                error!("Error getting tag stats. {}", err; "query" => "stats");
+                err
+            })?
+            .iter()
+            .map(|row| TagStats {
+                tag_id: row.get("id"),
+                name: row.get("name"),
+                kind: TagKind::from_str(row.get("kind")).unwrap_or(TagKind::Topic),
+                question_count: row.get("question_count"),
+                reviewed_count: row.get("reviewed_count"),
+                average_grade: row.get("average_grade"),
+            })
+            .collect();
+
+        Ok(stats)
+    }
+}
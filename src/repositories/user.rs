@@ -1,4 +1,4 @@
-use deadpool_postgres::{Pool, Client};
+use crate::repositories::{db::{Db, DbClient}, stores::UserStore};
 use std::sync::Arc;
 use slog_scope::error;
 use crate::models::user::{User, CreateUser};
@@ -6,19 +6,102 @@ use tokio_pg_mapper::FromTokioPostgresRow;
 use crate::{config::HashingService, errors::{AppError, AppErrorType}};
 use tokio_postgres::error::{Error, SqlState};
 use uuid::Uuid;
+use async_trait::async_trait;
 
 pub struct UserRepository {
-    pool: Arc<Pool>
+    pool: Db
+}
+
+pub(crate) fn user_exists(cause: Option<String>) -> AppError {
+    AppError {
+        cause,
+        message: Some("Username or email address already exists.".to_string()),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+pub(crate) fn user_not_deleted() -> AppError {
+    AppError {
+        cause: None,
+        message: Some("user isn't deleted.".to_string()),
+        error_type: AppErrorType::NotFoundError
+    }
 }
 
 impl UserRepository {
 
-    pub fn new(pool: Arc<Pool>) -> UserRepository {
-        UserRepository { pool }
+    pub fn new(pool: impl Into<Db>) -> UserRepository {
+        UserRepository { pool: pool.into() }
+    }
+
+    pub async fn update_image(&self, id: Uuid, image: Option<String>) -> Result<User, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "update_image");
+                err
+            })?;
+
+        let statement = client
+            .prepare("update users set image = $2 where id = $1 and deleted_at is null returning *")
+            .await?;
+
+        client
+            .query(&statement, &[&id, &image])
+            .await
+            .map_err(|err| {
+                error!("Error updating users. {}", err; "query" => "update_image");
+                err
+            })?
+            .iter()
+            .map(|row| User::from_row_ref(row))
+            .collect::<Result<Vec<User>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+
+    /// Removes accounts deleted longer ago than the retention period, once they
+    /// no longer own bands, quizzes or media
+    pub async fn purge_deleted(&self, retention_days: i32) -> Result<u64, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "purge_deleted");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                delete from users u
+                where u.deleted_at < current_timestamp - make_interval(days => $1)
+                    and not exists (select 1 from bands where owner_id = u.id)
+                    and not exists (select 1 from quizzes where created_by = u.id)
+                    and not exists (select 1 from media where uploaded_by = u.id)
+            ")
+            .await?;
+
+        let count = client
+            .execute(&statement, &[&retention_days])
+            .await
+            .map_err(|err| {
+                error!("Error purging users. {}", err; "query" => "purge_deleted");
+                err
+            })?;
+
+        Ok(count)
     }
+}
 
-    pub async fn get(&self, id: Uuid) -> Result<User, AppError> {
-        let client: Client = self.pool
+#[async_trait]
+impl UserStore for UserRepository {
+    async fn get(&self, id: Uuid) -> Result<User, AppError> {
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
@@ -26,7 +109,7 @@ impl UserRepository {
                 err
             })?;
 
-        let statement = client.prepare("select * from users where id = $1").await?;
+        let statement = client.prepare("select * from users where id = $1 and deleted_at is null").await?;
 
         client
             .query(&statement, &[&id])
@@ -46,8 +129,8 @@ impl UserRepository {
             })
     }
 
-    pub async fn all(&self) -> Result<Vec<User>, AppError> {
-        let client: Client = self.pool
+    async fn all(&self) -> Result<Vec<User>, AppError> {
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
@@ -55,7 +138,7 @@ impl UserRepository {
                 err
             })?;
 
-        let statement = client.prepare("select * from users").await?;
+        let statement = client.prepare("select * from users where hidden_at is null and deleted_at is null").await?;
 
         let users = client
             .query(&statement, &[])
@@ -75,8 +158,8 @@ impl UserRepository {
         Ok(users)
     }
 
-    pub async fn create(&self, input: CreateUser, hashing: Arc<HashingService>) -> Result<User, AppError> {
-        let client: Client = self.pool
+    async fn create(&self, input: CreateUser, hashing: Arc<HashingService>) -> Result<User, AppError> {
+        let client: DbClient = self.pool
             .get()
             .await
             .map_err(|err| {
@@ -103,11 +186,7 @@ impl UserRepository {
                     .map(|code| code == &SqlState::UNIQUE_VIOLATION);
 
                 match unique_error {
-                    Some(true) => AppError {
-                            cause: Some(err.to_string()),
-                            message: Some("Username or email address already exists.".to_string()),
-                            error_type: AppErrorType::InvalidField
-                        },
+                    Some(true) => user_exists(Some(err.to_string())),
                     _ => AppError::from(err)
                 }
             })?
@@ -123,4 +202,69 @@ impl UserRepository {
 
         Ok(user)
     }
+
+    async fn soft_delete(&self, id: Uuid) -> Result<User, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "soft_delete");
+                err
+            })?;
+
+        let statement = client
+            .prepare("
+                with sessions as (
+                    delete from sessions where user_id = $1
+                )
+                update users set deleted_at = current_timestamp
+                where id = $1 and deleted_at is null
+                returning *
+            ")
+            .await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error deleting user. {}", err; "query" => "soft_delete");
+                err
+            })?
+            .iter()
+            .map(|row| User::from_row_ref(row))
+            .collect::<Result<Vec<User>, _>>()?
+            .pop()
+            .ok_or(AppError {
+                cause: None,
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            })
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<User, AppError> {
+        let client: DbClient = self.pool
+            .get()
+            .await
+            .map_err(|err| {
+                error!("Error getting client {}", err; "query" => "restore");
+                err
+            })?;
+
+        let statement = client
+            .prepare("update users set deleted_at = null where id = $1 and deleted_at is not null returning *")
+            .await?;
+
+        client
+            .query(&statement, &[&id])
+            .await
+            .map_err(|err| {
+                error!("Error restoring user. {}", err; "query" => "restore");
+                err
+            })?
+            .iter()
+            .map(|row| User::from_row_ref(row))
+            .collect::<Result<Vec<User>, _>>()?
+            .pop()
+            .ok_or_else(user_not_deleted)
+    }
 }
@@ -0,0 +1,190 @@
+/// Question revisions
+/// Keeps every change to a question as a revision, lets fans suggest edits for
+/// the band's authors to accept or reject, and restores earlier revisions
+
+use crate::errors::{AppError, AppErrorType};
+use crate::models::{
+    notification::NotificationKind,
+    question::{Question, QuestionStatus, UpdateQuestion},
+    revision::{EditSuggestion, QuestionRevision, QuestionSnapshot, SuggestionStatus},
+};
+use crate::repositories::stores::{AnswerStore, NotificationStore, QuestionStore, RevisionStore};
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn invalid_edit(message: &str) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(message.to_string()),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+fn edit_conflict(question: &Question) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(format!("question was changed since, it's now at version {}.", question.version)),
+        error_type: AppErrorType::ConflictError
+    }
+}
+
+pub struct RevisionService {
+    pub answers: Arc<dyn AnswerStore>,
+    pub questions: Arc<dyn QuestionStore>,
+    pub revisions: Arc<dyn RevisionStore>,
+    pub notifications: Arc<dyn NotificationStore>,
+}
+
+impl RevisionService {
+    async fn with_contents(&self, snapshot: QuestionSnapshot) -> Result<QuestionSnapshot, AppError> {
+        let answers = self.answers.get_many(&snapshot.answer_ids()).await?;
+        Ok(snapshot.with_contents(&answers))
+    }
+
+    /// Copies in the answer contents and the aliases the correct answer has now
+    async fn with_current_answers(&self, snapshot: QuestionSnapshot) -> Result<QuestionSnapshot, AppError> {
+        let aliases = match snapshot.correct_answer_id {
+            Some(answer_id) => self.answers.aliases(answer_id).await?,
+            None => Vec::new(),
+        };
+
+        Ok(self.with_contents(snapshot).await?.with_aliases(&aliases))
+    }
+
+    /// The question, its choices and the correct answer's aliases as they are now
+    async fn snapshot(&self, question: &Question) -> Result<QuestionSnapshot, AppError> {
+        let snapshot = self.questions.snapshot(question).await?;
+        self.with_current_answers(snapshot).await
+    }
+
+    /// Records the question as it is now as its next revision
+    pub async fn record(&self, question: &Question, author_id: Option<Uuid>) -> Result<QuestionRevision, AppError> {
+        let snapshot = self.snapshot(question).await?;
+        self.revisions.create(question.id, author_id, &snapshot, None).await
+    }
+
+    /// Number of the question's latest revision. Questions saved before
+    /// revisions were kept get their current state recorded first.
+    async fn current_revision(&self, question: &Question) -> Result<i32, AppError> {
+        match self.revisions.latest(question.id).await? {
+            Some(revision) => Ok(revision.number),
+            None => Ok(self.record(question, None).await?.number),
+        }
+    }
+
+    /// Edits a question. Submitters editing a question sent back to them return it to the moderation queue.
+    /// Fails with a conflict when the question is no longer at the input's version.
+    pub async fn update(&self, input: UpdateQuestion, editor_id: Uuid) -> Result<Question, AppError> {
+        let question = self.questions.get(input.id).await?;
+        if input.version != question.version {
+            return Err(edit_conflict(&question));
+        }
+        self.current_revision(&question).await?;
+
+        let before = self.snapshot(&question).await?;
+        let after = self.with_current_answers(before.with_update(&input)).await?;
+        if after == before {
+            return Ok(question);
+        }
+
+        let updated = self.questions.apply(&question, &after, question.version).await?;
+        self.revisions.create(question.id, Some(editor_id), &after, None).await?;
+
+        if updated.submitted_by == Some(editor_id) && updated.question_status() == QuestionStatus::ChangesRequested {
+            return self.questions.resubmit(updated.id).await;
+        }
+
+        Ok(updated)
+    }
+
+    /// Proposes an edit to an approved question for the band's authors to decide on
+    pub async fn suggest(&self, input: UpdateQuestion, suggested_by: Uuid, note: Option<String>) -> Result<EditSuggestion, AppError> {
+        let question = self.questions.get(input.id).await?;
+        if question.question_status() != QuestionStatus::Approved {
+            return Err(invalid_edit("edits can only be suggested for approved questions."));
+        }
+
+        let base_revision = self.current_revision(&question).await?;
+        let before = self.snapshot(&question).await?;
+        let after = self.with_current_answers(before.with_update(&input)).await?;
+        if after == before {
+            return Err(invalid_edit("suggestion doesn't change the question."));
+        }
+        self.questions.validate_edit(question.band_id, &after).await?;
+
+        let note = note.map(|note| note.trim().to_string()).filter(|note| !note.is_empty());
+        self.revisions.create_suggestion(question.id, suggested_by, &after, base_revision, note.as_deref()).await
+    }
+
+    /// Applies a suggested edit, credited to the fan who suggested it
+    pub async fn accept(&self, suggestion_id: Uuid, author_id: Uuid) -> Result<Question, AppError> {
+        let suggestion = self.revisions.get_suggestion(suggestion_id).await?;
+        if suggestion.suggestion_status() != SuggestionStatus::Pending {
+            return Err(invalid_edit("suggestion was already decided on."));
+        }
+
+        let question = self.questions.get(suggestion.question_id).await?;
+        if self.current_revision(&question).await? != suggestion.base_revision {
+            return Err(invalid_edit("question was edited after the suggestion was made."));
+        }
+
+        let snapshot = self.with_current_answers(QuestionSnapshot::from_json(&suggestion.snapshot)?).await?;
+        let updated = self.questions.apply(&question, &snapshot, question.version).await?;
+        self.revisions.create(question.id, Some(suggestion.suggested_by), &snapshot, None).await?;
+        self.revisions.decide_suggestion(suggestion.id, SuggestionStatus::Accepted, None, author_id).await?;
+
+        let message = format!("Your edit to \"{}\" was accepted.", question.content);
+        self.notifications.create(suggestion.suggested_by, NotificationKind::EditAccepted, &message, Some(question.id)).await?;
+
+        Ok(updated)
+    }
+
+    pub async fn reject(&self, suggestion_id: Uuid, author_id: Uuid, reason: String) -> Result<EditSuggestion, AppError> {
+        let reason = reason.trim();
+        if reason.is_empty() {
+            return Err(invalid_edit("a reason is required."));
+        }
+
+        let suggestion = self.revisions.decide_suggestion(suggestion_id, SuggestionStatus::Rejected, Some(reason), author_id).await?;
+        let question = self.questions.get(suggestion.question_id).await?;
+
+        let message = format!("Your edit to \"{}\" was rejected: {}.", question.content, reason.trim_end_matches('.'));
+        self.notifications.create(suggestion.suggested_by, NotificationKind::EditRejected, &message, Some(question.id)).await?;
+
+        Ok(suggestion)
+    }
+
+    /// Restores an earlier revision, recorded as a new revision so the history stays intact.
+    /// Aliases belong to the answer, so restoring them changes them for every question using it.
+    pub async fn revert(&self, question_id: Uuid, number: i32, author_id: Uuid) -> Result<Question, AppError> {
+        let question = self.questions.get(question_id).await?;
+        self.current_revision(&question).await?;
+
+        let revision = self.revisions.get(question.id, number).await?;
+        let mut target = self.with_contents(QuestionSnapshot::from_json(&revision.snapshot)?).await?;
+        if target.aliases.is_none() {
+            target.aliases = self.with_current_answers(target.clone()).await?.aliases;
+        }
+        if target == self.snapshot(&question).await? {
+            return Err(invalid_edit(&format!("question is already as it was at revision {}.", number)));
+        }
+
+        let updated = self.questions.apply(&question, &target, question.version).await?;
+        if let (Some(answer_id), Some(aliases)) = (target.correct_answer_id, &target.aliases) {
+            self.answers.set_aliases(answer_id, aliases).await?;
+        }
+        self.revisions.create(question.id, Some(author_id), &target, Some(number)).await?;
+
+        Ok(updated)
+    }
+
+    /// Records a revision of every question the answer is the correct answer
+    /// of, after its aliases changed
+    pub async fn record_aliases(&self, answer_id: Uuid, author_id: Option<Uuid>) -> Result<(), AppError> {
+        for question in self.questions.with_correct_answer(answer_id).await? {
+            self.record(&question, author_id).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,9 +1,102 @@
+table! {
+    answer_aliases (id) {
+        id -> Uuid,
+        answer_id -> Uuid,
+        content -> Varchar,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 table! {
     answers (id) {
         id -> Uuid,
         content -> Varchar,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        band_id -> Nullable<Uuid>,
+    }
+}
+
+table! {
+    attempt_choices (token) {
+        token -> Uuid,
+        attempt_id -> Uuid,
+        question_id -> Uuid,
+        answer_id -> Uuid,
+        side -> Varchar,
+        position -> Int4,
+        eliminated -> Bool,
+    }
+}
+
+table! {
+    attempt_lifelines (attempt_id, question_id, kind) {
+        attempt_id -> Uuid,
+        question_id -> Uuid,
+        kind -> Varchar,
+        used_at -> Timestamp,
+    }
+}
+
+table! {
+    attempt_questions (attempt_id, question_id) {
+        attempt_id -> Uuid,
+        question_id -> Uuid,
+        position -> Int4,
+        answer_id -> Nullable<Uuid>,
+        answer_text -> Nullable<Varchar>,
+        answer_number -> Nullable<Float8>,
+        answer_ordering -> Nullable<Array<Uuid>>,
+        answer_pair_ids -> Nullable<Array<Uuid>>,
+        answer_pair_match_ids -> Nullable<Array<Uuid>>,
+        accepted -> Nullable<Bool>,
+        score -> Nullable<Float8>,
+        answered_at -> Nullable<Timestamp>,
+        served_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    attempts (id) {
+        id -> Uuid,
+        quiz_id -> Nullable<Uuid>,
+        band_id -> Uuid,
+        user_id -> Uuid,
+        started_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+        score -> Float8,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        scoring_rules_id -> Nullable<Uuid>,
+        flag_reasons -> Array<Text>,
+        flagged_at -> Nullable<Timestamp>,
+        fifty_fifty_lifelines -> Int4,
+        hint_lifelines -> Int4,
+    }
+}
+
+table! {
+    audit_log (id) {
+        id -> Uuid,
+        actor_id -> Nullable<Uuid>,
+        action -> Varchar,
+        target_type -> Varchar,
+        target_id -> Uuid,
+        before -> Nullable<Text>,
+        after -> Nullable<Text>,
+        request_id -> Uuid,
+        ip -> Nullable<Varchar>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    band_authors (band_id, user_id) {
+        band_id -> Uuid,
+        user_id -> Uuid,
+        created_at -> Timestamp,
+        role -> Varchar,
     }
 }
 
@@ -14,6 +107,81 @@ table! {
         owner_id -> Uuid,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        search_vector -> Nullable<Tsvector>,
+        hidden_at -> Nullable<Timestamp>,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    media (id) {
+        id -> Uuid,
+        key -> Varchar,
+        content_type -> Varchar,
+        size_bytes -> Int4,
+        uploaded_by -> Uuid,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    notifications (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        kind -> Varchar,
+        message -> Varchar,
+        question_id -> Nullable<Uuid>,
+        read_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    question_choices (id) {
+        id -> Uuid,
+        question_id -> Uuid,
+        answer_id -> Uuid,
+        position -> Int4,
+        match_answer_id -> Nullable<Uuid>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    question_edit_suggestions (id) {
+        id -> Uuid,
+        question_id -> Uuid,
+        suggested_by -> Uuid,
+        snapshot -> Text,
+        base_revision -> Int4,
+        note -> Nullable<Varchar>,
+        status -> Varchar,
+        reason -> Nullable<Varchar>,
+        decided_by -> Nullable<Uuid>,
+        decided_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    question_revisions (id) {
+        id -> Uuid,
+        question_id -> Uuid,
+        number -> Int4,
+        author_id -> Nullable<Uuid>,
+        snapshot -> Text,
+        reverted_from -> Nullable<Int4>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    question_tags (question_id, tag_id) {
+        question_id -> Uuid,
+        tag_id -> Uuid,
+        created_at -> Timestamp,
     }
 }
 
@@ -21,10 +189,122 @@ table! {
     questions (id) {
         id -> Uuid,
         content -> Varchar,
-        correct_answer_id -> Uuid,
+        correct_answer_id -> Nullable<Uuid>,
         band_id -> Uuid,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        kind -> Varchar,
+        max_edit_distance -> Int4,
+        numeric_value -> Nullable<Float8>,
+        numeric_tolerance -> Float8,
+        numeric_curve -> Varchar,
+        scoring_rule -> Varchar,
+        media_id -> Nullable<Uuid>,
+        clip_start_ms -> Nullable<Int4>,
+        clip_end_ms -> Nullable<Int4>,
+        search_vector -> Nullable<Tsvector>,
+        hint -> Nullable<Varchar>,
+        explanation -> Nullable<Varchar>,
+        source -> Nullable<Varchar>,
+        status -> Varchar,
+        submitted_by -> Nullable<Uuid>,
+        moderation_reason -> Nullable<Varchar>,
+        moderated_by -> Nullable<Uuid>,
+        moderated_at -> Nullable<Timestamp>,
+        hidden_at -> Nullable<Timestamp>,
+        deleted_at -> Nullable<Timestamp>,
+        version -> Int4,
+    }
+}
+
+table! {
+    quiz_questions (quiz_id, question_id) {
+        quiz_id -> Uuid,
+        question_id -> Uuid,
+        position -> Int4,
+    }
+}
+
+table! {
+    quizzes (id) {
+        id -> Uuid,
+        band_id -> Uuid,
+        title -> Varchar,
+        description -> Nullable<Varchar>,
+        status -> Varchar,
+        time_limit_seconds -> Nullable<Int4>,
+        created_by -> Uuid,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        scoring_rules_id -> Nullable<Uuid>,
+        seconds_per_question -> Nullable<Int4>,
+        fifty_fifty_lifelines -> Int4,
+        hint_lifelines -> Int4,
+        version -> Int4,
+    }
+}
+
+table! {
+    reports (id) {
+        id -> Uuid,
+        reporter_id -> Uuid,
+        target_type -> Varchar,
+        target_id -> Uuid,
+        reason -> Varchar,
+        note -> Nullable<Varchar>,
+        status -> Varchar,
+        resolved_by -> Nullable<Uuid>,
+        resolved_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    review_states (user_id, question_id) {
+        user_id -> Uuid,
+        question_id -> Uuid,
+        repetitions -> Int4,
+        interval_days -> Int4,
+        ease_factor -> Float8,
+        due_at -> Timestamp,
+        last_grade -> Nullable<Int4>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    scoring_rules (id) {
+        id -> Uuid,
+        points_per_question -> Int4,
+        speed_bonus_points -> Int4,
+        speed_bonus_seconds -> Int4,
+        wrong_answer_penalty -> Int4,
+        streak_bonus -> Float8,
+        max_streak_multiplier -> Float8,
+        partial_credit -> Bool,
+        created_at -> Timestamp,
+        fifty_fifty_cost -> Float8,
+        hint_cost -> Float8,
+    }
+}
+
+table! {
+    sessions (token) {
+        token -> Uuid,
+        user_id -> Uuid,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    tags (id) {
+        id -> Uuid,
+        name -> Varchar,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        kind -> Varchar,
     }
 }
 
@@ -38,16 +318,67 @@ table! {
         image -> Nullable<Varchar>,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        search_vector -> Nullable<Tsvector>,
+        is_admin -> Bool,
+        hidden_at -> Nullable<Timestamp>,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
+joinable!(answer_aliases -> answers (answer_id));
+joinable!(answers -> bands (band_id));
+joinable!(attempt_choices -> answers (answer_id));
+joinable!(attempt_questions -> attempts (attempt_id));
+joinable!(attempt_questions -> questions (question_id));
+joinable!(attempts -> quizzes (quiz_id));
+joinable!(attempts -> scoring_rules (scoring_rules_id));
+joinable!(attempts -> users (user_id));
+joinable!(band_authors -> bands (band_id));
+joinable!(band_authors -> users (user_id));
 joinable!(bands -> users (owner_id));
+joinable!(media -> users (uploaded_by));
+joinable!(notifications -> questions (question_id));
+joinable!(notifications -> users (user_id));
+joinable!(question_choices -> questions (question_id));
+joinable!(question_edit_suggestions -> questions (question_id));
+joinable!(question_revisions -> questions (question_id));
+joinable!(question_revisions -> users (author_id));
+joinable!(question_tags -> questions (question_id));
+joinable!(question_tags -> tags (tag_id));
 joinable!(questions -> answers (correct_answer_id));
 joinable!(questions -> bands (band_id));
+joinable!(questions -> media (media_id));
+joinable!(quiz_questions -> questions (question_id));
+joinable!(quiz_questions -> quizzes (quiz_id));
+joinable!(quizzes -> bands (band_id));
+joinable!(quizzes -> scoring_rules (scoring_rules_id));
+joinable!(review_states -> questions (question_id));
+joinable!(review_states -> users (user_id));
+joinable!(sessions -> users (user_id));
 
 allow_tables_to_appear_in_same_query!(
+    answer_aliases,
     answers,
+    attempt_choices,
+    attempt_lifelines,
+    attempt_questions,
+    attempts,
+    audit_log,
+    band_authors,
     bands,
+    media,
+    notifications,
+    question_choices,
+    question_edit_suggestions,
+    question_revisions,
+    question_tags,
     questions,
+    quiz_questions,
+    quizzes,
+    reports,
+    review_states,
+    scoring_rules,
+    sessions,
+    tags,
     users,
 );
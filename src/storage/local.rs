@@ -0,0 +1,79 @@
+/// Local filesystem storage
+/// Files live under a root directory and are served back by the app
+
+use crate::errors::{AppError, AppErrorType};
+use crate::storage::Storage;
+use actix_web::{error::BlockingError, web};
+use async_trait::async_trait;
+use std::{fs, io, path::{Component, Path, PathBuf}};
+
+pub struct LocalStorage {
+    root: PathBuf,
+    base_url: String,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf, base_url: String) -> LocalStorage {
+        LocalStorage { root, base_url }
+    }
+
+    /// Resolves a key below the root, refusing keys that could escape it
+    fn path(&self, key: &str) -> Result<PathBuf, AppError> {
+        let relative = Path::new(key);
+        let is_safe = relative.components().all(|component| match component {
+            Component::Normal(_) => true,
+            _ => false,
+        });
+
+        if key.is_empty() || !is_safe {
+            return Err(AppError {
+                cause: Some(format!("unsafe storage key {}", key)),
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            });
+        }
+
+        Ok(self.root.join(relative))
+    }
+}
+
+fn storage_error(err: BlockingError<io::Error>) -> AppError {
+    let error_type = match &err {
+        BlockingError::Error(io_err) if io_err.kind() == io::ErrorKind::NotFound => AppErrorType::NotFoundError,
+        _ => AppErrorType::DbError,
+    };
+
+    AppError {
+        cause: Some(err.to_string()),
+        message: None,
+        error_type
+    }
+}
+
+#[async_trait(?Send)]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, _content_type: &str, bytes: Vec<u8>) -> Result<(), AppError> {
+        let path = self.path(key)?;
+
+        web::block(move || {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, bytes)
+        })
+        .await
+        .map_err(storage_error)
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, AppError> {
+        let path = self.path(key)?;
+
+        web::block(move || fs::read(path))
+            .await
+            .map_err(storage_error)
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.base_url.trim_end_matches('/'), key)
+    }
+}
@@ -0,0 +1,17 @@
+pub mod local;
+pub mod s3;
+pub mod validation;
+
+use crate::errors::AppError;
+use async_trait::async_trait;
+
+/// Where uploaded files are kept. Keys are relative paths like
+/// `media/<uuid>.png`; `url` turns a key into a public link.
+#[async_trait(?Send)]
+pub trait Storage: Send + Sync {
+    async fn put(&self, key: &str, content_type: &str, bytes: Vec<u8>) -> Result<(), AppError>;
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, AppError>;
+
+    fn url(&self, key: &str) -> String;
+}
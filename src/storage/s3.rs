@@ -0,0 +1,286 @@
+/// S3-compatible storage
+/// Path-style requests signed with AWS Signature Version 4, so the same code
+/// works against S3 and local stand-ins like MinIO
+
+use crate::errors::{AppError, AppErrorType};
+use crate::storage::Storage;
+use actix_web::{client::Client, http::StatusCode};
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const SERVICE: &str = "s3";
+
+pub struct S3Storage {
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    public_url: String,
+}
+
+impl S3Storage {
+    pub fn new(endpoint: String, bucket: String, region: String, access_key: String, secret_key: String, public_url: Option<String>) -> S3Storage {
+        let endpoint = endpoint.trim_end_matches('/').to_string();
+        let public_url = public_url.unwrap_or_else(|| format!("{}/{}", endpoint, bucket));
+
+        S3Storage { endpoint, bucket, region, access_key, secret_key, public_url }
+    }
+
+    fn host(&self) -> &str {
+        let without_scheme = self.endpoint
+            .splitn(2, "://")
+            .last()
+            .unwrap_or(&self.endpoint);
+
+        without_scheme.split('/').next().unwrap_or(without_scheme)
+    }
+
+    /// Headers for a signed request on `key`, including `Authorization`
+    fn signed_headers(&self, method: &str, key: &str, payload: &[u8], now: NaiveDateTime) -> Vec<(&'static str, String)> {
+        let payload_hash = hex::encode(Sha256::digest(payload));
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, SERVICE);
+
+        let canonical_request = format!(
+            "{}\n/{}/{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method, self.bucket, key, self.host(), payload_hash, amz_date, payload_hash
+        );
+
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date, scope, hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = signing_key(&self.secret_key, &date, &self.region, SERVICE);
+        let signature = hex::encode(hmac(&signing_key, &string_to_sign));
+
+        vec![
+            ("x-amz-content-sha256", payload_hash),
+            ("x-amz-date", amz_date),
+            ("Authorization", format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                self.access_key, scope, signature
+            )),
+        ]
+    }
+
+    fn object_url(&self, key: &str) -> String {
+        format!("{}/{}/{}", self.endpoint, self.bucket, key)
+    }
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_varkey(key).expect("HMAC accepts keys of any size");
+    mac.input(data.as_bytes());
+    mac.result().code().to_vec()
+}
+
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let date_key = hmac(format!("AWS4{}", secret_key).as_bytes(), date);
+    let region_key = hmac(&date_key, region);
+    let service_key = hmac(&region_key, service);
+    hmac(&service_key, "aws4_request")
+}
+
+fn request_error(cause: String) -> AppError {
+    AppError {
+        cause: Some(cause),
+        message: None,
+        error_type: AppErrorType::DbError
+    }
+}
+
+#[async_trait(?Send)]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, content_type: &str, bytes: Vec<u8>) -> Result<(), AppError> {
+        let mut request = Client::new()
+            .put(self.object_url(key))
+            .header("Content-Type", content_type);
+        for (name, value) in self.signed_headers("PUT", key, &bytes, Utc::now().naive_utc()) {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .send_body(bytes)
+            .await
+            .map_err(|err| request_error(err.to_string()))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(request_error(format!("PUT {} returned {}", key, response.status())))
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, AppError> {
+        let mut request = Client::new().get(self.object_url(key));
+        for (name, value) in self.signed_headers("GET", key, &[], Utc::now().naive_utc()) {
+            request = request.header(name, value);
+        }
+
+        let mut response = request
+            .send()
+            .await
+            .map_err(|err| request_error(err.to_string()))?;
+
+        match response.status() {
+            StatusCode::OK => response
+                .body()
+                .limit(usize::max_value())
+                .await
+                .map(|body| body.to_vec())
+                .map_err(|err| request_error(err.to_string())),
+            StatusCode::NOT_FOUND => Err(AppError {
+                cause: Some(format!("object {} not found", key)),
+                message: None,
+                error_type: AppErrorType::NotFoundError
+            }),
+            status => Err(request_error(format!("GET {} returned {}", key, status))),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_url.trim_end_matches('/'), key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{signing_key, S3Storage};
+    use crate::errors::AppErrorType;
+    use crate::storage::Storage;
+    use actix_web::{test, web, App, HttpRequest, HttpResponse};
+    use chrono::NaiveDate;
+    use std::{collections::HashMap, sync::{Arc, Mutex}};
+
+    /// Content type and bytes of the stand-in's objects, by key
+    type Objects = Arc<Mutex<HashMap<String, (String, Vec<u8>)>>>;
+
+    async fn put_object(req: HttpRequest, body: web::Bytes, objects: web::Data<Objects>) -> HttpResponse {
+        let signed = req.headers().get("Authorization")
+            .map_or(false, |value| value.as_bytes().starts_with(b"AWS4-HMAC-SHA256 Credential=minio/"));
+        if !signed || req.headers().get("x-amz-date").is_none() {
+            return HttpResponse::Forbidden().finish();
+        }
+
+        let key = req.match_info().query("key").to_string();
+        if key.ends_with("broken.png") {
+            return HttpResponse::InternalServerError().finish();
+        }
+
+        let content_type = req.headers().get("Content-Type")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        objects.lock().unwrap().insert(key, (content_type, body.to_vec()));
+        HttpResponse::Ok().finish()
+    }
+
+    async fn get_object(req: HttpRequest, objects: web::Data<Objects>) -> HttpResponse {
+        match objects.lock().unwrap().get(req.match_info().query("key")) {
+            Some((content_type, bytes)) => HttpResponse::Ok().content_type(content_type.as_str()).body(bytes.clone()),
+            None => HttpResponse::NotFound().finish(),
+        }
+    }
+
+    /// A bucket named "media" that checks requests are signed and keeps objects in memory
+    fn stand_in(objects: Objects) -> test::TestServer {
+        test::start(move || App::new()
+            .data(objects.clone())
+            .route("/media/{key:.*}", web::put().to(put_object))
+            .route("/media/{key:.*}", web::get().to(get_object)))
+    }
+
+    fn storage(endpoint: &str, public_url: Option<String>) -> S3Storage {
+        S3Storage::new(
+            endpoint.to_string(),
+            "media".to_string(),
+            "us-east-1".to_string(),
+            "minio".to_string(),
+            "minio-secret".to_string(),
+            public_url,
+        )
+    }
+
+    #[test]
+    fn test_signing_key() {
+        // Example from the AWS Signature Version 4 documentation
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn test_signed_headers_are_stable() {
+        let storage = S3Storage::new(
+            "http://localhost:9000/".to_string(),
+            "media".to_string(),
+            "us-east-1".to_string(),
+            "minio".to_string(),
+            "minio-secret".to_string(),
+            None,
+        );
+        let now = NaiveDate::from_ymd(2020, 4, 29).and_hms(12, 0, 0);
+
+        let first = storage.signed_headers("PUT", "media/a.png", b"bytes", now);
+        let second = storage.signed_headers("PUT", "media/a.png", b"bytes", now);
+        let other = storage.signed_headers("PUT", "media/b.png", b"bytes", now);
+
+        assert_eq!(storage.host(), "localhost:9000");
+        assert_eq!(first, second, "Signing should be deterministic");
+        assert_ne!(first[2], other[2], "Signature should cover the key");
+        assert!(first[2].1.starts_with("AWS4-HMAC-SHA256 Credential=minio/20200429/us-east-1/s3/aws4_request"));
+        assert_eq!(storage.url("media/a.png"), "http://localhost:9000/media/media/a.png");
+    }
+
+    #[actix_rt::test]
+    async fn test_put_and_get() {
+        let objects = Objects::default();
+        let server = stand_in(objects.clone());
+        let storage = storage(&server.url("/"), None);
+
+        storage.put("media/a.png", "image/png", b"png".to_vec()).await.unwrap();
+
+        assert_eq!(objects.lock().unwrap().get("media/a.png"), Some(&("image/png".to_string(), b"png".to_vec())));
+        assert_eq!(storage.get("media/a.png").await.unwrap(), b"png".to_vec());
+    }
+
+    #[actix_rt::test]
+    async fn test_errors() {
+        let server = stand_in(Objects::default());
+        let s3 = storage(&server.url("/"), None);
+
+        let missing = s3.get("media/missing.png").await.unwrap_err();
+        assert!(matches!(missing.error_type, AppErrorType::NotFoundError), "Missing objects should be not found");
+
+        let failed = s3.put("media/broken.png", "image/png", b"png".to_vec()).await.unwrap_err();
+        assert!(matches!(failed.error_type, AppErrorType::DbError));
+        assert_eq!(failed.cause.as_deref(), Some("PUT media/broken.png returned 500 Internal Server Error"));
+
+        let stranger = S3Storage { access_key: "stranger".to_string(), ..storage(&server.url("/"), None) };
+        let refused = stranger.put("media/a.png", "image/png", b"png".to_vec()).await.unwrap_err();
+        assert_eq!(refused.cause.as_deref(), Some("PUT media/a.png returned 403 Forbidden"));
+
+        let unreachable = storage("http://127.0.0.1:1", None);
+        let unreached = unreachable.get("media/a.png").await.unwrap_err();
+        assert!(matches!(unreached.error_type, AppErrorType::DbError), "Connection errors should be reported");
+    }
+
+    #[test]
+    fn test_url() {
+        let storage = storage("http://localhost:9000", Some("https://cdn.example.com/".to_string()));
+
+        assert_eq!(storage.url("media/a.png"), "https://cdn.example.com/media/a.png");
+    }
+
+}
@@ -0,0 +1,93 @@
+/// Upload validation
+/// Checks declared content types against the file's leading bytes
+
+use crate::errors::{AppError, AppErrorType};
+
+/// Content types accepted for question media, with their file extensions
+pub const MEDIA_TYPES: &[(&str, &str)] = &[
+    ("image/jpeg", "jpg"),
+    ("image/png", "png"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+    ("audio/mpeg", "mp3"),
+    ("audio/ogg", "ogg"),
+    ("audio/wav", "wav"),
+];
+
+/// Guesses a content type from magic bytes
+pub fn sniff_content_type(bytes: &[u8]) -> Option<&'static str> {
+    let riff_type = |kind: &[u8]| bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == kind;
+
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if riff_type(b"WEBP") {
+        Some("image/webp")
+    } else if riff_type(b"WAVE") {
+        Some("audio/wav")
+    } else if bytes.starts_with(b"OggS") {
+        Some("audio/ogg")
+    } else if bytes.starts_with(b"ID3") || (bytes.len() >= 2 && bytes[0] == 0xFF && bytes[1] & 0xE0 == 0xE0) {
+        Some("audio/mpeg")
+    } else {
+        None
+    }
+}
+
+fn invalid_upload(message: String) -> AppError {
+    AppError {
+        cause: None,
+        message: Some(message),
+        error_type: AppErrorType::InvalidField
+    }
+}
+
+/// Validates an upload against `allowed` types and `max_bytes`, returning
+/// the file extension to store it with
+pub fn validate_upload(declared: &str, bytes: &[u8], allowed: &[(&str, &'static str)], max_bytes: usize) -> Result<&'static str, AppError> {
+    if bytes.is_empty() {
+        return Err(invalid_upload("file is empty.".to_string()));
+    }
+
+    if bytes.len() > max_bytes {
+        return Err(invalid_upload(format!("file is larger than {} bytes.", max_bytes)));
+    }
+
+    let extension = allowed.iter()
+        .find(|(content_type, _)| *content_type == declared)
+        .map(|(_, extension)| *extension)
+        .ok_or_else(|| invalid_upload(format!("{} files are not supported.", declared)))?;
+
+    match sniff_content_type(bytes) {
+        Some(detected) if detected == declared => Ok(extension),
+        _ => Err(invalid_upload(format!("file contents don't match {}.", declared))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{sniff_content_type, validate_upload, MEDIA_TYPES};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[test]
+    fn test_sniff_content_type() {
+        assert_eq!(sniff_content_type(PNG), Some("image/png"));
+        assert_eq!(sniff_content_type(b"RIFF\0\0\0\0WAVEfmt "), Some("audio/wav"));
+        assert_eq!(sniff_content_type(b"ID3\x03\0"), Some("audio/mpeg"));
+        assert_eq!(sniff_content_type(b"<html>"), None);
+    }
+
+    #[test]
+    fn test_validate_upload() {
+        assert_eq!(validate_upload("image/png", PNG, MEDIA_TYPES, 1024).ok(), Some("png"));
+        assert!(validate_upload("image/png", PNG, MEDIA_TYPES, 4).is_err(), "Oversized files should be rejected");
+        assert!(validate_upload("image/jpeg", PNG, MEDIA_TYPES, 1024).is_err(), "Mismatched contents should be rejected");
+        assert!(validate_upload("text/html", b"<html>", MEDIA_TYPES, 1024).is_err(), "Unsupported types should be rejected");
+    }
+
+}